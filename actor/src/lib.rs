@@ -1,6 +1,7 @@
 use actor_channel::{ActorChannel, ActorSender};
 use an_id::AnId;
 use box_msg_any::BoxMsgAny;
+use protocol_set::ProtocolSet;
 use std::fmt::Debug;
 
 pub type ProcessMsgFn<SM> = fn(&mut SM, context: &dyn ActorContext, BoxMsgAny);
@@ -27,8 +28,11 @@ pub trait ActorContext {
     /// Get Rsp instance id
     fn get_dst_instance_id(&self) -> &AnId;
 
-    /// Clone rsp_tx
-    fn clone_dst_sndr(&self) -> ActorSender;
+    /// Clone rsp_tx, Err if there is no sender for the dst instance id
+    fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>>;
+
+    /// Record that the actor didn't understand msg_id, by default a NOP
+    fn record_unknown_msg(&self, _msg_id: &AnId) {}
 }
 
 pub trait Actor: Send + Debug + Sync {
@@ -75,6 +79,13 @@ pub trait Actor: Send + Debug + Sync {
     fn get_chnl(&self) -> &ActorChannel;
     fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny);
     fn done(&self) -> bool;
+
+    /// The protocols the actor implements, the ActorExecutor checks that
+    /// the messages it dispatches to the actor are received by one of
+    /// them. None if the messages aren't checked.
+    fn get_protocol_set(&self) -> Option<&ProtocolSet> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,175 @@
+use crossbeam_channel::{Receiver, RecvError, Select, SelectTimeoutError};
+use std::time::Instant;
+
+/// Identifies a receiver added to a DynamicSelect. Handles are never
+/// reused, so a handle stays valid until its receiver is removed and
+/// the handles of the other receivers don't change when it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SelectHandle(usize);
+
+impl SelectHandle {
+    /// The handles are 0, 1, 2, ... in the order the receivers are added
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// A set of receivers to select on that can be changed between selects.
+///
+/// A crossbeam Select borrows its receivers, so they can't be removed
+/// or replaced while it's alive. DynamicSelect owns the receivers and
+/// builds a Select each time select is called, selecting is already
+/// linear in the number of receivers so this doesn't change its cost.
+#[derive(Debug)]
+pub struct DynamicSelect<T> {
+    receivers: Vec<(SelectHandle, Receiver<T>)>,
+    positions: Vec<Option<usize>>, // Index of each handle in receivers, None if removed
+}
+
+impl<T> Default for DynamicSelect<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DynamicSelect<T> {
+    pub fn new() -> Self {
+        Self {
+            receivers: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    /// Add a receiver, returns its handle
+    pub fn add(&mut self, receiver: Receiver<T>) -> SelectHandle {
+        let handle = SelectHandle(self.positions.len());
+        self.positions.push(Some(self.receivers.len()));
+        self.receivers.push((handle, receiver));
+        handle
+    }
+
+    /// Remove the receiver, it's returned so the messages in its channel
+    /// can be received. None if the handle was already removed.
+    pub fn remove(&mut self, handle: SelectHandle) -> Option<Receiver<T>> {
+        let position = self.positions.get_mut(handle.0)?.take()?;
+        let (_, receiver) = self.receivers.swap_remove(position);
+        if let Some((moved, _)) = self.receivers.get(position) {
+            self.positions[moved.0] = Some(position);
+        }
+        Some(receiver)
+    }
+
+    /// Replace the receiver keeping its handle, returns the previous
+    /// receiver. None, and receiver isn't added, if the handle was removed.
+    pub fn replace(&mut self, handle: SelectHandle, receiver: Receiver<T>) -> Option<Receiver<T>> {
+        let position = (*self.positions.get(handle.0)?)?;
+        Some(std::mem::replace(&mut self.receivers[position].1, receiver))
+    }
+
+    /// The receiver of handle, None if it was removed
+    pub fn get(&self, handle: SelectHandle) -> Option<&Receiver<T>> {
+        let position = (*self.positions.get(handle.0)?)?;
+        Some(&self.receivers[position].1)
+    }
+
+    /// Number of receivers
+    pub fn len(&self) -> usize {
+        self.receivers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receivers.is_empty()
+    }
+
+    /// Block until one of the receivers has a message, or is
+    /// disconnected, and receive it. Panics if there are no receivers.
+    pub fn select(&self) -> (SelectHandle, Result<T, RecvError>) {
+        let mut sel = self.selector();
+        let oper = sel.select();
+        let (handle, receiver) = &self.receivers[oper.index()];
+        (*handle, oper.recv(receiver))
+    }
+
+    /// Like select but gives up at deadline
+    pub fn select_deadline(
+        &self,
+        deadline: Instant,
+    ) -> Result<(SelectHandle, Result<T, RecvError>), SelectTimeoutError> {
+        let mut sel = self.selector();
+        let oper = sel.select_deadline(deadline)?;
+        let (handle, receiver) = &self.receivers[oper.index()];
+        Ok((*handle, oper.recv(receiver)))
+    }
+
+    // A Select whose operation indexes are the indexes of receivers
+    fn selector(&self) -> Select<'_> {
+        let mut sel = Select::new();
+        for (_, receiver) in &self.receivers {
+            sel.recv(receiver);
+        }
+        sel
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crossbeam_channel::unbounded;
+    use std::time::Duration;
+
+    #[test]
+    fn test_dynamic_select_add_remove_replace() {
+        let mut dyn_sel = DynamicSelect::<u32>::new();
+        let (tx0, rx0) = unbounded();
+        let (tx1, rx1) = unbounded();
+        let (tx2, rx2) = unbounded();
+        let h0 = dyn_sel.add(rx0);
+        let h1 = dyn_sel.add(rx1);
+        let h2 = dyn_sel.add(rx2);
+        assert_eq!((h0.index(), h1.index(), h2.index()), (0, 1, 2));
+        assert_eq!(dyn_sel.len(), 3);
+
+        tx1.send(1).unwrap();
+        assert_eq!(dyn_sel.select(), (h1, Ok(1)));
+
+        // Removing h0 doesn't change the other handles
+        let rx0 = dyn_sel.remove(h0).unwrap();
+        assert!(dyn_sel.remove(h0).is_none());
+        assert!(dyn_sel.get(h0).is_none());
+        tx0.send(0).unwrap();
+        tx2.send(2).unwrap();
+        assert_eq!(dyn_sel.select(), (h2, Ok(2)));
+        assert_eq!(rx0.try_recv(), Ok(0));
+
+        // A replaced receiver keeps its handle
+        let (tx3, rx3) = unbounded();
+        let rx1 = dyn_sel.replace(h1, rx3).unwrap();
+        tx1.send(10).unwrap();
+        tx3.send(3).unwrap();
+        assert_eq!(dyn_sel.select(), (h1, Ok(3)));
+        assert_eq!(rx1.try_recv(), Ok(10));
+        assert!(dyn_sel.replace(h0, rx1).is_none());
+
+        // New handles aren't reused
+        let (_tx4, rx4) = unbounded();
+        assert_eq!(dyn_sel.add(rx4).index(), 3);
+        assert_eq!(dyn_sel.len(), 3);
+
+        // A disconnected receiver is selected with an error
+        drop(tx2);
+        assert_eq!(dyn_sel.select(), (h2, Err(RecvError)));
+    }
+
+    #[test]
+    fn test_dynamic_select_deadline() {
+        let mut dyn_sel = DynamicSelect::<u32>::new();
+        let (tx, rx) = unbounded();
+        let handle = dyn_sel.add(rx);
+
+        let deadline = Instant::now() + Duration::from_millis(10);
+        assert!(dyn_sel.select_deadline(deadline).is_err());
+        tx.send(1).unwrap();
+        assert_eq!(dyn_sel.select_deadline(deadline), Ok((handle, Ok(1))));
+    }
+}
@@ -1,13 +1,77 @@
 use an_id::AnId;
 use box_msg_any::BoxMsgAny;
-use crossbeam_channel::{unbounded, Receiver, Sender};
-use std::cell::UnsafeCell;
+use crossbeam_channel::{unbounded, Receiver, SendError, Sender};
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug},
+    sync::{Arc, OnceLock},
+};
 
-#[derive(Clone, Debug)]
+mod dynamic_select;
+pub use dynamic_select::{DynamicSelect, SelectHandle};
+
+/// Invoked after a message is sent on a channel, lets a receiver that
+/// isn't blocked in recv or a Select, like a task, know there is a message.
+pub type Wakeup = Arc<dyn Fn() + Send + Sync>;
+
+/// Invoked with each message the current thread sends on any channel,
+/// lets an executor see everything its actors send whichever
+/// ActorSender they send it with.
+pub type SendObserver = Arc<dyn Fn(&BoxMsgAny) + Send + Sync>;
+
+thread_local! {
+    static SEND_OBSERVER: RefCell<Option<SendObserver>> = const { RefCell::new(None) };
+}
+
+/// Observe the messages the current thread sends until the returned
+/// guard is dropped, the previous observer is then restored.
+pub fn observe_sends(observer: SendObserver) -> SendObserverGuard {
+    let prev = SEND_OBSERVER.with(|o| o.replace(Some(observer)));
+    SendObserverGuard { prev }
+}
+
+pub struct SendObserverGuard {
+    prev: Option<SendObserver>,
+}
+
+impl Drop for SendObserverGuard {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        SEND_OBSERVER.with(|o| *o.borrow_mut() = prev);
+    }
+}
+
+/// Invoked with each message before it is sent on any channel, lets the
+/// header be completed whichever ActorSender it's sent with, like
+/// continuing the trace of the message being processed.
+pub type SendHook = fn(&mut BoxMsgAny);
+
+static SEND_HOOK: OnceLock<SendHook> = OnceLock::new();
+
+/// Set the hook of every send, only the first hook set is used.
+///
+/// Returns false if a hook was already set.
+pub fn send_hook_set(hook: SendHook) -> bool {
+    SEND_HOOK.set(hook).is_ok()
+}
+
+#[derive(Clone)]
 pub struct ActorSender {
     pub name: String,
     pub dst_instance_id: AnId,
-    pub dst_sndr: Sender<BoxMsgAny>,
+    dst_sndr: Sender<BoxMsgAny>,
+    wakeup: Arc<OnceLock<Wakeup>>, // Shared by the clones of the channel
+}
+
+// The wakeup isn't interesting to display
+impl Debug for ActorSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActorSender")
+            .field("name", &self.name)
+            .field("dst_instance_id", &self.dst_instance_id)
+            .field("dst_sndr", &self.dst_sndr)
+            .finish()
+    }
 }
 
 impl ActorSender {
@@ -16,11 +80,41 @@ impl ActorSender {
             name: name.to_string() + "_chnl_tx",
             dst_instance_id: *instance_id,
             dst_sndr: tx,
+            wakeup: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// A sender on the same channel, sharing its wakeup, for the actor
+    /// name with instance_id. Used when a channel carries the messages of
+    /// other actors, like the link to a peer.
+    pub fn with_dst(&self, name: &str, instance_id: &AnId) -> Self {
+        Self {
+            name: name.to_string() + "_chnl_tx",
+            dst_instance_id: *instance_id,
+            ..self.clone()
         }
     }
 
     pub fn send(&self, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+        self.send_recoverable(msg)?;
+        Ok(())
+    }
+
+    /// Send msg, if the receiver is gone the error holds msg so the
+    /// sender can take it back with into_inner.
+    pub fn send_recoverable(&self, mut msg: BoxMsgAny) -> Result<(), SendError<BoxMsgAny>> {
+        if let Some(hook) = SEND_HOOK.get() {
+            hook(&mut msg);
+        }
+        SEND_OBSERVER.with(|o| {
+            if let Some(observer) = &*o.borrow() {
+                observer(&msg);
+            }
+        });
         self.dst_sndr.send(msg)?;
+        if let Some(wakeup) = self.wakeup.get() {
+            wakeup();
+        }
         Ok(())
     }
 
@@ -63,46 +157,23 @@ impl ActorChannel {
             receiver: ActorReceiver::new(name, rx),
         }
     }
-}
-
-#[derive(Debug)]
-pub struct VecActorChannel(UnsafeCell<Vec<ActorChannel>>);
-
-impl Default for VecActorChannel {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl VecActorChannel {
-    pub fn new() -> Self {
-        Self(UnsafeCell::new(Vec::new()))
-    }
-
-    // Panic's if idx is out of bounds
-    pub fn get(&self, idx: usize) -> &ActorChannel {
-        unsafe {
-            let v = &*self.0.get();
-            &v[idx]
-        }
-    }
 
-    pub fn push(&self, chnl: ActorChannel) {
-        unsafe {
-            let ptr = &mut *self.0.get();
-            ptr.push(chnl);
-        }
+    /// Invoke wakeup after each message sent on the channel, including by
+    /// the clones of its sender. There is only one wakeup per channel,
+    /// returns false if it was already set.
+    pub fn set_wakeup(&self, wakeup: Wakeup) -> bool {
+        self.sender.wakeup.set(wakeup).is_ok()
     }
 
-    pub fn len(&self) -> usize {
-        unsafe {
-            let v = &*self.0.get();
-            v.len()
-        }
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    /// A Notify that is notified after each message sent on the channel,
+    /// a task receives by draining receiver with try_recv and then
+    /// awaiting notified. None if the channel already has a wakeup.
+    #[cfg(feature = "tokio")]
+    pub fn tokio_notify(&self) -> Option<Arc<tokio::sync::Notify>> {
+        let notify = Arc::new(tokio::sync::Notify::new());
+        let wakeup = Arc::clone(&notify);
+        self.set_wakeup(Arc::new(move || wakeup.notify_one()))
+            .then_some(notify)
     }
 }
 
@@ -144,4 +215,100 @@ mod test {
         assert_eq!(recv_msg_1.src_id(), &supervisor_instance_id);
         assert_eq!(recv_msg_1.v, msg_1.v);
     }
+
+    #[test]
+    fn test_actor_channel_wakeup() {
+        let instance_id = AnId::new();
+        let chnl = ActorChannel::new("woken", &instance_id);
+        let sender = chnl.sender.clone();
+
+        // Sending without a wakeup is fine
+        sender
+            .send(Box::new(Msg1::new(&instance_id, &instance_id, 1)))
+            .unwrap();
+
+        let (wakeup_tx, wakeup_rx) = unbounded::<()>();
+        assert!(chnl.set_wakeup(Arc::new(move || wakeup_tx.send(()).unwrap())));
+        assert!(!chnl.set_wakeup(Arc::new(|| ())));
+        assert!(wakeup_rx.try_recv().is_err());
+
+        // The clone made before the wakeup was set invokes it too
+        sender
+            .send(Box::new(Msg1::new(&instance_id, &instance_id, 2)))
+            .unwrap();
+        assert!(wakeup_rx.try_recv().is_ok());
+        assert!(wakeup_rx.try_recv().is_err());
+
+        // As does a sender for another actor on the same channel
+        let other_instance_id = AnId::new();
+        let other = chnl.sender.with_dst("other", &other_instance_id);
+        assert_eq!(other.name, "other_chnl_tx");
+        assert_eq!(other.get_dst_instance_id(), &other_instance_id);
+        other
+            .send(Box::new(Msg1::new(&other_instance_id, &instance_id, 3)))
+            .unwrap();
+        assert!(wakeup_rx.try_recv().is_ok());
+        assert_eq!(chnl.receiver.rx.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_send_recoverable() {
+        let instance_id = AnId::new();
+        let chnl = ActorChannel::new("gone", &instance_id);
+        let sender = chnl.sender.clone();
+        drop(chnl);
+
+        // The receiver is gone so the msg is returned in the error
+        let why = sender
+            .send_recoverable(Box::new(Msg1::new(&instance_id, &instance_id, 1)))
+            .unwrap_err();
+        let msg = why.into_inner().downcast::<Msg1>().unwrap();
+        assert_eq!(msg.v, 1);
+    }
+
+    #[test]
+    fn test_observe_sends() {
+        let instance_id = AnId::new();
+        let chnl = ActorChannel::new("observed", &instance_id);
+        let sender = chnl.sender.clone();
+
+        let (observed_tx, observed_rx) = unbounded::<AnId>();
+        let observer: SendObserver = Arc::new(move |msg: &BoxMsgAny| {
+            observed_tx
+                .send(*MsgHeader::get_msg_id_from_boxed_msg_any(msg))
+                .unwrap();
+        });
+        {
+            let _guard = observe_sends(observer);
+
+            // Every sender used on this thread is observed
+            chnl.sender
+                .send(Box::new(Msg1::new(&instance_id, &instance_id, 1)))
+                .unwrap();
+            sender
+                .send(Box::new(Msg1::new(&instance_id, &instance_id, 2)))
+                .unwrap();
+
+            // But not those used on other threads
+            let other = sender.clone();
+            std::thread::spawn(move || {
+                other
+                    .send(Box::new(Msg1::new(&instance_id, &instance_id, 3)))
+                    .unwrap()
+            })
+            .join()
+            .unwrap();
+        }
+        assert_eq!(
+            observed_rx.try_iter().collect::<Vec<_>>(),
+            [MSG1_ID, MSG1_ID]
+        );
+
+        // Dropping the guard stops observing
+        sender
+            .send(Box::new(Msg1::new(&instance_id, &instance_id, 4)))
+            .unwrap();
+        assert!(observed_rx.try_recv().is_err());
+        assert_eq!(chnl.receiver.rx.try_iter().count(), 4);
+    }
 }
@@ -0,0 +1,64 @@
+//! Messages/sec of the Client/Server echo loop running in an ActorExecutor.
+//!
+//! Run with `cargo bench -p actor_executor --bench echo`, the throughput
+//! is the number of EchoReq/EchoRsp round trips per second.
+use actor_executor::{
+    add_actor_to_actor_executor_blocking, initialize_supervisor_con_mgr_actor_executor_blocking,
+};
+use client::Client;
+use cmd_done_issuer_protocol::CmdDone;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use echo_start_complete_protocol::{EchoComplete, EchoStart};
+use sender_map_by_instance_id::sender_map_get;
+use server::Server;
+
+const PING_COUNT: u64 = 1_000;
+
+fn echo_client_server(c: &mut Criterion) {
+    let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+        initialize_supervisor_con_mgr_actor_executor_blocking();
+
+    let (_, c1_instance_id) = add_actor_to_actor_executor_blocking(
+        Box::new(Client::new("client1")),
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+    let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+        Box::new(Server::new("server1")),
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+    let c1_sndr = sender_map_get(&c1_instance_id).unwrap();
+
+    let mut group = c.benchmark_group("echo");
+    group.throughput(Throughput::Elements(PING_COUNT));
+    group.bench_function("client_server", |b| {
+        b.iter(|| {
+            c1_sndr
+                .send(Box::new(EchoStart::new(
+                    &c1_instance_id,
+                    &supervisor_instance_id,
+                    &s1_instance_id,
+                    PING_COUNT,
+                )))
+                .unwrap();
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            assert!(msg_any.downcast_ref::<EchoComplete>().is_some());
+        })
+    });
+    group.finish();
+
+    sender_map_get(&ae_instance_id)
+        .unwrap()
+        .send(Box::new(CmdDone::new(
+            &ae_instance_id,
+            &supervisor_instance_id,
+        )))
+        .unwrap();
+    ae_join_handle.join().unwrap();
+}
+
+criterion_group!(benches, echo_client_server);
+criterion_main!(benches);
@@ -0,0 +1,726 @@
+//! What ActorExecutor and TokioActorExecutor share. Each implements the
+//! required methods of Executor for the way it runs its actors and the
+//! provided methods handle the executor's messages, supervise the actors
+//! and drive the ordered shutdown, so an actor behaves the same on both.
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use actor::Actor;
+use actor_channel::{ActorReceiver, ActorSender};
+use actor_factory::actor_factory_get;
+use admin_protocol::{
+    AdminActorInfo, AdminExecutorInfo, AdminListActorsReq, AdminListActorsRsp, AdminRemoveActorReq,
+    AdminRemoveActorRsp, AdminRemoveActorStatus,
+};
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use cmd_done_issuee_protocol::CmdDone;
+use cmd_init_issuer_protocol::{CmdInit, CmdReady, CmdReadyStatus};
+use cmd_shutdown_issuer_protocol::{CmdShutdown, CmdShutdownAck};
+use con_mgr_register_actor_protocol::{
+    ConMgrUnregisterActorReq, ConMgrUnregisterActorRsp, ConMgrUnregisterActorStatus,
+};
+use dead_letters::dead_letter_post;
+use metrics_protocol::{MetricsReq, MetricsRsp};
+use msg_tap::{tap_attach, tap_detach};
+use req_shutdown::ReqShutdown;
+use req_spawn_actor::ReqSpawnActor;
+use rsp_shutdown::{RspShutdown, ShutdownSummary};
+use rsp_spawn_actor::{RspSpawnActor, RspSpawnActorStatus};
+use runtime_metrics::metrics_snapshot;
+use sender_map_by_instance_id::{sender_map_insert, sender_map_remove};
+use supervisor_protocol::{
+    ActorFailed, ActorRestarted, ActorStopped, ReqSetSupervisorPolicy, RspSetSupervisorPolicy,
+    SupervisorDirective, SupervisorPolicy, SupervisorStrategy,
+};
+use tap_protocol::{TapDetachReq, TapDetachRsp, TapDetachRspStatus, TapReq, TapRsp, TapRspStatus};
+
+use super::send_msg;
+
+/// How long a shutdown started by CmdDone waits for the actors to
+/// acknowledge their CmdShutdown
+const CMD_DONE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Supervision information for each actor an executor runs
+#[derive(Debug)]
+pub(crate) struct Supervision {
+    pub(crate) supervisor_instance_id: AnId,
+    pub(crate) params: String, // Passed to the actors factory when restarting
+    restarts: VecDeque<Instant>,
+}
+
+impl Supervision {
+    pub(crate) fn new(supervisor_instance_id: &AnId, params: &str) -> Self {
+        Self {
+            supervisor_instance_id: *supervisor_instance_id,
+            params: params.to_owned(),
+            restarts: VecDeque::new(),
+        }
+    }
+
+    // Forget the restarts outside of the policy's window, false if
+    // restarting now would exceed its max_restarts
+    fn may_restart(&mut self, policy: &SupervisorPolicy, now: Instant) -> bool {
+        while let Some(t) = self.restarts.front() {
+            if now.duration_since(*t) > policy.within {
+                self.restarts.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.restarts.len() < policy.max_restarts
+    }
+
+    // The supervision of the restarted instance, which remembers the restarts
+    fn restarted(&mut self, now: Instant) -> Self {
+        let mut restarts = std::mem::take(&mut self.restarts);
+        restarts.push_back(now);
+        Self {
+            supervisor_instance_id: self.supervisor_instance_id,
+            params: self.params.clone(),
+            restarts,
+        }
+    }
+}
+
+// State of a shutdown requested by ReqShutdown
+#[derive(Debug)]
+pub(crate) struct Shutdown {
+    requester_instance_id: AnId,
+    pub(crate) deadline: Instant,
+    pending_acks: Vec<AnId>,    // instance_ids of actors sent CmdShutdown
+    pending_unregisters: usize, // ConMgrUnregisterActorReq's without a response
+    con_mgr_pending: bool,      // ConMgr is one of our actors and hasn't been sent CmdShutdown
+    summary: ShutdownSummary,
+}
+
+// An actor the executor is no longer running
+pub(crate) struct RemovedActor {
+    pub(crate) name: String,
+    pub(crate) actor_id: AnId,
+    pub(crate) supervision: Supervision,
+    pub(crate) receiver: ActorReceiver, // The messages still in its channel
+}
+
+pub(crate) trait Executor {
+    /// Prefixes the log records, "AE" or "TAE"
+    const LOG_TAG: &'static str;
+
+    fn name(&self) -> &str;
+
+    fn instance_id(&self) -> &AnId;
+
+    fn con_mgr_instance_id(&self) -> &AnId;
+
+    fn con_mgr_tx(&self) -> &ActorSender;
+
+    /// Sent the summary of every shutdown, if there is one
+    fn supervisor_instance_id(&self) -> Option<&AnId>;
+
+    fn supervisor_policy(&self) -> &SupervisorPolicy;
+
+    fn set_supervisor_policy(&mut self, policy: SupervisorPolicy);
+
+    fn shutdown_mut(&mut self) -> &mut Option<Shutdown>;
+
+    /// The executor stops once the current message is handled
+    fn set_done(&mut self);
+
+    /// The instance_ids of the actors being run
+    fn actor_instance_ids(&self) -> Vec<AnId>;
+
+    /// Send msg to one of our actors, Err if we aren't running it
+    fn send_to_actor(
+        &self,
+        instance_id: &AnId,
+        msg: BoxMsgAny,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// The name and actor_id of one of our actors
+    fn actor_name_and_id(&self, instance_id: &AnId) -> Option<(&str, &AnId)>;
+
+    fn supervision(&self, instance_id: &AnId) -> Option<&Supervision>;
+
+    fn supervision_mut(&mut self, instance_id: &AnId) -> Option<&mut Supervision>;
+
+    /// Start running the actor, its CmdInit is sent separately
+    fn insert_actor(&mut self, actor: Box<dyn Actor>, supervision: Supervision);
+
+    /// Stop running the actor, None if we weren't
+    fn take_actor(&mut self, instance_id: &AnId) -> Option<RemovedActor>;
+
+    /// The actors we're running for an AdminListActorsRsp
+    fn admin_actor_infos(&self) -> Vec<AdminActorInfo>;
+
+    // Handle the messages of the executor protocols that don't depend on
+    // how the actors are run, returns msg_any if it isn't one of them.
+    fn handle_executor_msg(&mut self, msg_any: BoxMsgAny) -> Option<BoxMsgAny> {
+        let tag = Self::LOG_TAG;
+        if let Some(msg) = msg_any.downcast_ref::<ReqSpawnActor>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            self.spawn_actor(msg);
+        } else if let Some(msg) = msg_any.downcast_ref::<ReqSetSupervisorPolicy>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            self.set_supervisor_policy(msg.policy.clone());
+            send_msg(
+                msg.src_id(),
+                Box::new(RspSetSupervisorPolicy::new(
+                    msg.src_id(),
+                    self.instance_id(),
+                )),
+            );
+        } else if let Some(msg) = msg_any.downcast_ref::<ReqShutdown>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            self.start_shutdown(msg.src_id(), msg.timeout);
+        } else if let Some(msg) = msg_any.downcast_ref::<CmdShutdownAck>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            self.shutdown_acked(msg.src_id());
+        } else if let Some(msg) = msg_any.downcast_ref::<ConMgrUnregisterActorRsp>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            self.shutdown_unregistered(msg);
+        } else if let Some(msg) = msg_any.downcast_ref::<MetricsReq>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            send_msg(
+                msg.src_id(),
+                Box::new(MetricsRsp::new(
+                    msg.src_id(),
+                    self.instance_id(),
+                    metrics_snapshot(),
+                )),
+            );
+        } else if let Some(msg) = msg_any.downcast_ref::<AdminListActorsReq>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            send_msg(
+                msg.src_id(),
+                Box::new(AdminListActorsRsp::new(
+                    msg.src_id(),
+                    self.instance_id(),
+                    &AdminExecutorInfo {
+                        name: self.name().to_owned(),
+                        instance_id: *self.instance_id(),
+                    },
+                    &self.admin_actor_infos(),
+                )),
+            );
+        } else if let Some(msg) = msg_any.downcast_ref::<AdminRemoveActorReq>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            let status = self.admin_remove_actor(&msg.instance_id);
+            send_msg(
+                msg.src_id(),
+                Box::new(AdminRemoveActorRsp::new(
+                    msg.src_id(),
+                    self.instance_id(),
+                    &msg.instance_id,
+                    status,
+                )),
+            );
+        } else if let Some(msg) = msg_any.downcast_ref::<TapReq>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            let (tap_id, status) = match tap_attach(
+                msg.src_id(),
+                msg.filter,
+                msg.sample_every,
+                msg.record_path.as_deref(),
+            ) {
+                Ok(tap_id) => (tap_id, TapRspStatus::Success),
+                Err(why) => (AnId::nil(), TapRspStatus::RecordError(why.to_string())),
+            };
+            send_msg(
+                msg.src_id(),
+                Box::new(TapRsp::new(
+                    msg.src_id(),
+                    self.instance_id(),
+                    &tap_id,
+                    status,
+                )),
+            );
+        } else if let Some(msg) = msg_any.downcast_ref::<TapDetachReq>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            let status = match tap_detach(&msg.tap_id) {
+                Ok(true) => TapDetachRspStatus::Success,
+                Ok(false) => TapDetachRspStatus::TapNotFound,
+                Err(why) => TapDetachRspStatus::RecordError(why.to_string()),
+            };
+            send_msg(
+                msg.src_id(),
+                Box::new(TapDetachRsp::new(
+                    msg.src_id(),
+                    self.instance_id(),
+                    &msg.tap_id,
+                    status,
+                )),
+            );
+        } else if let Some(msg) = msg_any.downcast_ref::<CmdReady>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            self.cmd_ready(msg);
+        } else if let Some(msg) = msg_any.downcast_ref::<CmdDone>() {
+            log::debug!("{tag}:{}: msg={msg:?}", self.name());
+            self.start_shutdown(msg.src_id(), CMD_DONE_SHUTDOWN_TIMEOUT);
+        } else {
+            return Some(msg_any);
+        }
+        None
+    }
+
+    // Create an actor with the factory registered for its actor_id and run it
+    fn spawn_actor(&mut self, msg: &ReqSpawnActor) {
+        let requester_instance_id = *msg.src_id();
+        let (actor_instance_id, status) = match actor_factory_get(&msg.actor_id) {
+            _ if self.shutdown_mut().is_some() => (AnId::nil(), RspSpawnActorStatus::ShuttingDown),
+            None => (AnId::nil(), RspSpawnActorStatus::FactoryNotFound),
+            Some(factory) => match factory(&msg.name, &msg.params) {
+                Err(why) => (
+                    AnId::nil(),
+                    RspSpawnActorStatus::FactoryFailed(why.to_string()),
+                ),
+                Ok(actor) => {
+                    let actor_instance_id = *actor.get_instance_id();
+                    self.insert_actor(actor, Supervision::new(&requester_instance_id, &msg.params));
+                    self.send_cmd_init(&actor_instance_id);
+                    (actor_instance_id, RspSpawnActorStatus::Success)
+                }
+            },
+        };
+        send_msg(
+            &requester_instance_id,
+            Box::new(RspSpawnActor::new(
+                &requester_instance_id,
+                self.instance_id(),
+                &msg.actor_id,
+                &actor_instance_id,
+                status,
+            )),
+        );
+    }
+
+    fn send_cmd_init(&self, instance_id: &AnId) {
+        let msg = Box::new(CmdInit::new(instance_id, self.instance_id()));
+        if let Err(why) = self.send_to_actor(instance_id, msg) {
+            log::warn!(
+                "{}:{}: error sending CmdInit {why}",
+                Self::LOG_TAG,
+                self.name()
+            );
+        }
+    }
+
+    // An actor responded to CmdInit, pass it on to its supervisor
+    fn cmd_ready(&self, msg: &CmdReady) {
+        let actor_instance_id = msg.src_id();
+        let Some(supervision) = self.supervision(actor_instance_id) else {
+            log::warn!(
+                "{}:{}: CmdReady from unknown actor {actor_instance_id}",
+                Self::LOG_TAG,
+                self.name()
+            );
+            return;
+        };
+        if let CmdReadyStatus::RegistrationFailed(reason) = &msg.status {
+            log::error!(
+                "{}:{}: {actor_instance_id} registration failed: {reason}",
+                Self::LOG_TAG,
+                self.name(),
+            );
+        }
+        let supervisor_instance_id = &supervision.supervisor_instance_id;
+        send_msg(
+            supervisor_instance_id,
+            Box::new(CmdReady::new(
+                supervisor_instance_id,
+                actor_instance_id,
+                msg.status.clone(),
+            )),
+        );
+    }
+
+    // Remove the actor, it's no longer sent messages, and tell its supervisor
+    fn stop_actor(&mut self, instance_id: &AnId) {
+        let Some(removed) = self.take_actor(instance_id) else {
+            return;
+        };
+        sender_map_remove(instance_id);
+        log::debug!(
+            "{}:{}: stopped {}",
+            Self::LOG_TAG,
+            self.name(),
+            removed.name
+        );
+        let supervisor_instance_id = &removed.supervision.supervisor_instance_id;
+        send_msg(
+            supervisor_instance_id,
+            Box::new(ActorStopped::new(
+                supervisor_instance_id,
+                self.instance_id(),
+                &removed.actor_id,
+                instance_id,
+            )),
+        );
+    }
+
+    // Stop the actor, without a CmdShutdown, and unregister it from ConMgr
+    fn admin_remove_actor(&mut self, instance_id: &AnId) -> AdminRemoveActorStatus {
+        if instance_id == self.con_mgr_instance_id() {
+            return AdminRemoveActorStatus::NotRemovable;
+        }
+        if self.supervision(instance_id).is_none() {
+            return AdminRemoveActorStatus::NotFound;
+        }
+
+        self.stop_actor(instance_id);
+        self.unregister_actor(instance_id);
+        AdminRemoveActorStatus::Success
+    }
+
+    // Remove an actor that is no longer running from the sender_map and
+    // unregister it from ConMgr, the response is ignored.
+    fn unregister_actor(&self, instance_id: &AnId) {
+        sender_map_remove(instance_id);
+        let msg = Box::new(ConMgrUnregisterActorReq::new(
+            self.con_mgr_instance_id(),
+            self.instance_id(),
+            instance_id,
+        ));
+        if let Err(why) = self.con_mgr_tx().send(msg) {
+            log::warn!(
+                "{}:{}: error sending ConMgrUnregisterActorReq {why}",
+                Self::LOG_TAG,
+                self.name()
+            );
+        }
+    }
+
+    // The actors supervised by supervisor_instance_id
+    fn children(&self, supervisor_instance_id: &AnId) -> Vec<AnId> {
+        self.actor_instance_ids()
+            .into_iter()
+            .filter(|instance_id| {
+                self.supervision(instance_id)
+                    .is_some_and(|s| s.supervisor_instance_id == *supervisor_instance_id)
+            })
+            .collect()
+    }
+
+    // An actor failed, tell its supervisor and apply the supervisor_policy
+    fn actor_failed(&mut self, instance_id: &AnId, reason: &str) {
+        let Some((name, actor_id)) = self.actor_name_and_id(instance_id) else {
+            return;
+        };
+        log::error!("{}:{}: {name} failed: {reason}", Self::LOG_TAG, self.name());
+        let actor_id = *actor_id;
+        let supervisor_instance_id = self
+            .supervision(instance_id)
+            .unwrap()
+            .supervisor_instance_id;
+        send_msg(
+            &supervisor_instance_id,
+            Box::new(ActorFailed::new(
+                &supervisor_instance_id,
+                self.instance_id(),
+                &actor_id,
+                instance_id,
+                reason,
+            )),
+        );
+
+        // The affected actors, siblings have the same supervisor
+        let affected = match self.supervisor_policy().strategy {
+            SupervisorStrategy::OneForOne => vec![*instance_id],
+            SupervisorStrategy::OneForAll => self.children(&supervisor_instance_id),
+        };
+
+        match self.supervisor_policy().directive {
+            SupervisorDirective::Restart => {
+                for instance_id in affected {
+                    self.restart_actor(&instance_id);
+                }
+            }
+            SupervisorDirective::Stop => {
+                for instance_id in affected {
+                    self.stop_actor(&instance_id);
+                }
+            }
+            SupervisorDirective::Escalate => {
+                // Stop all of the supervisor's actors, whatever the strategy,
+                // the supervisor decides what else to do from the ActorFailed
+                for instance_id in self.children(&supervisor_instance_id) {
+                    self.stop_actor(&instance_id);
+                }
+            }
+        }
+    }
+
+    // Replace the actor with a new instance created by the factory registered
+    // for its actor_id. If there is no factory, the factory fails or it has
+    // been restarted too many times it is stopped.
+    fn restart_actor(&mut self, instance_id: &AnId) {
+        let Some((name, actor_id)) = self.actor_name_and_id(instance_id) else {
+            return;
+        };
+        let (name, actor_id) = (name.to_owned(), *actor_id);
+        let Some(factory) = actor_factory_get(&actor_id) else {
+            log::warn!("{}:{}: no factory for {name}", Self::LOG_TAG, self.name());
+            self.stop_actor(instance_id);
+            return;
+        };
+
+        let now = Instant::now();
+        let policy = self.supervisor_policy().clone();
+        let supervision = self.supervision_mut(instance_id).unwrap();
+        if !supervision.may_restart(&policy, now) {
+            log::warn!(
+                "{}:{}: {name} restarted too many times",
+                Self::LOG_TAG,
+                self.name()
+            );
+            self.stop_actor(instance_id);
+            return;
+        }
+        let new_actor = match factory(&name, &supervision.params) {
+            Ok(new_actor) => new_actor,
+            Err(why) => {
+                log::warn!(
+                    "{}:{}: factory failed for {name}: {why}",
+                    Self::LOG_TAG,
+                    self.name()
+                );
+                self.stop_actor(instance_id);
+                return;
+            }
+        };
+        let new_supervision = supervision.restarted(now);
+        let supervisor_instance_id = new_supervision.supervisor_instance_id;
+
+        let old_actor = self.take_actor(instance_id).unwrap();
+        let new_instance_id = *new_actor.get_instance_id();
+        log::debug!(
+            "{}:{}: restart {name} {instance_id} as {new_instance_id}",
+            Self::LOG_TAG,
+            self.name()
+        );
+        self.unregister_actor(instance_id);
+        let new_sndr = new_actor.get_chnl().sender.clone();
+        self.insert_actor(new_actor, new_supervision);
+        sender_map_insert(&new_instance_id, &new_sndr);
+
+        // Initialize the new instance, it registers with ConMgr
+        self.send_cmd_init(&new_instance_id);
+
+        // Forward the messages pending for the old instance, after CmdInit
+        for msg in old_actor.receiver.rx.try_iter() {
+            if let Err(why) = new_sndr.send(msg) {
+                log::warn!(
+                    "{}:{}: error forwarding pending msg {why}",
+                    Self::LOG_TAG,
+                    self.name()
+                );
+            }
+        }
+
+        send_msg(
+            &supervisor_instance_id,
+            Box::new(ActorRestarted::new(
+                &supervisor_instance_id,
+                self.instance_id(),
+                &actor_id,
+                instance_id,
+                &new_instance_id,
+            )),
+        );
+    }
+
+    // Start shutting down, CmdShutdown is sent to all of the actors except
+    // ConMgr which is shutdown after the others have acknowledged so they
+    // can be unregistered.
+    fn start_shutdown(&mut self, requester_instance_id: &AnId, timeout: Duration) {
+        if self.shutdown_mut().is_some() {
+            log::warn!("{}:{}: already shutting down", Self::LOG_TAG, self.name());
+            return;
+        }
+
+        let mut shutdown = Shutdown {
+            requester_instance_id: *requester_instance_id,
+            deadline: Instant::now() + timeout,
+            pending_acks: Vec::new(),
+            pending_unregisters: 0,
+            con_mgr_pending: false,
+            summary: ShutdownSummary::default(),
+        };
+        for instance_id in self.actor_instance_ids() {
+            if instance_id == *self.con_mgr_instance_id() {
+                shutdown.con_mgr_pending = true;
+            } else {
+                self.send_cmd_shutdown(&instance_id);
+                shutdown.pending_acks.push(instance_id);
+            }
+        }
+        *self.shutdown_mut() = Some(shutdown);
+        self.shutdown_progress();
+    }
+
+    fn send_cmd_shutdown(&self, instance_id: &AnId) {
+        let msg = Box::new(CmdShutdown::new(instance_id, self.instance_id()));
+        if let Err(why) = self.send_to_actor(instance_id, msg) {
+            log::warn!(
+                "{}:{}: error sending CmdShutdown {why}",
+                Self::LOG_TAG,
+                self.name()
+            );
+        }
+    }
+
+    // An actor acknowledged CmdShutdown, remove it and unregister it from ConMgr
+    fn shutdown_acked(&mut self, instance_id: &AnId) {
+        let acked = self.shutdown_mut().as_mut().map(|shutdown| {
+            let pos = shutdown
+                .pending_acks
+                .iter()
+                .position(|iid| iid == instance_id);
+            if let Some(pos) = pos {
+                shutdown.pending_acks.remove(pos);
+                shutdown.summary.acked.push(*instance_id);
+            }
+            pos.is_some()
+        });
+        match acked {
+            None => {
+                log::warn!(
+                    "{}:{}: CmdShutdownAck while not shutting down",
+                    Self::LOG_TAG,
+                    self.name()
+                );
+                return;
+            }
+            Some(false) => {
+                log::warn!(
+                    "{}:{}: unexpected CmdShutdownAck from {instance_id}",
+                    Self::LOG_TAG,
+                    self.name()
+                );
+                return;
+            }
+            Some(true) => (),
+        }
+        if instance_id != self.con_mgr_instance_id() {
+            let msg = Box::new(ConMgrUnregisterActorReq::new(
+                self.con_mgr_instance_id(),
+                self.instance_id(),
+                instance_id,
+            ));
+            match self.con_mgr_tx().send(msg) {
+                Ok(_) => {
+                    if let Some(shutdown) = self.shutdown_mut() {
+                        shutdown.pending_unregisters += 1;
+                    }
+                }
+                Err(why) => log::warn!(
+                    "{}:{}: error sending ConMgrUnregisterActorReq {why}",
+                    Self::LOG_TAG,
+                    self.name()
+                ),
+            }
+        }
+        let dropped_msgs = self.shutdown_remove_actor(instance_id);
+        if let Some(shutdown) = self.shutdown_mut() {
+            shutdown.summary.dropped_msgs += dropped_msgs;
+        }
+        self.shutdown_progress();
+    }
+
+    // ConMgr responded to the ConMgrUnregisterActorReq of an acknowledged actor
+    fn shutdown_unregistered(&mut self, msg: &ConMgrUnregisterActorRsp) {
+        if let Some(shutdown) = self.shutdown_mut() {
+            shutdown.pending_unregisters = shutdown.pending_unregisters.saturating_sub(1);
+            if msg.status == ConMgrUnregisterActorStatus::Success {
+                shutdown.summary.unregistered += 1;
+            }
+            self.shutdown_progress();
+        }
+    }
+
+    // Remove a shutdown actor, the messages left in its channel are posted
+    // to the dead letters and their number returned
+    fn shutdown_remove_actor(&mut self, instance_id: &AnId) -> usize {
+        let Some(removed) = self.take_actor(instance_id) else {
+            // Already removed, it was moved or stopped
+            return 0;
+        };
+        let reason = format!("{} was shutdown", removed.name);
+        let mut dropped_msgs = 0;
+        for msg_any in removed.receiver.rx.try_iter() {
+            dead_letter_post(msg_any, &reason);
+            dropped_msgs += 1;
+        }
+        log::debug!(
+            "{}:{}: shutdown {} dropped_msgs={dropped_msgs}",
+            Self::LOG_TAG,
+            self.name(),
+            removed.name
+        );
+        dropped_msgs
+    }
+
+    // When the actors have acknowledged and been unregistered shutdown
+    // ConMgr, when it has acknowledged the shutdown is finished.
+    fn shutdown_progress(&mut self) {
+        let con_mgr_instance_id = *self.con_mgr_instance_id();
+        let Some(shutdown) = self.shutdown_mut() else {
+            return;
+        };
+        if !shutdown.pending_acks.is_empty() || shutdown.pending_unregisters != 0 {
+            return;
+        }
+        if shutdown.con_mgr_pending {
+            shutdown.con_mgr_pending = false;
+            shutdown.pending_acks.push(con_mgr_instance_id);
+            self.send_cmd_shutdown(&con_mgr_instance_id);
+        } else {
+            self.finish_shutdown();
+        }
+    }
+
+    // Actors that haven't acknowledged are timed_out, respond to the
+    // requester and our supervisor with the summary and we're done.
+    fn finish_shutdown(&mut self) {
+        let Some(mut shutdown) = self.shutdown_mut().take() else {
+            return;
+        };
+        let mut timed_out = std::mem::take(&mut shutdown.pending_acks);
+        if shutdown.con_mgr_pending {
+            timed_out.push(*self.con_mgr_instance_id());
+        }
+        for instance_id in timed_out {
+            shutdown.summary.dropped_msgs += self.shutdown_remove_actor(&instance_id);
+            shutdown.summary.timed_out.push(instance_id);
+        }
+
+        log::debug!(
+            "{}:{}: shutdown summary={:?}",
+            Self::LOG_TAG,
+            self.name(),
+            shutdown.summary
+        );
+        if let Some(supervisor_instance_id) = self.supervisor_instance_id() {
+            if *supervisor_instance_id != shutdown.requester_instance_id {
+                send_msg(
+                    supervisor_instance_id,
+                    Box::new(RspShutdown::new(
+                        supervisor_instance_id,
+                        self.instance_id(),
+                        shutdown.summary.clone(),
+                    )),
+                );
+            }
+        }
+        send_msg(
+            &shutdown.requester_instance_id,
+            Box::new(RspShutdown::new(
+                &shutdown.requester_instance_id,
+                self.instance_id(),
+                shutdown.summary,
+            )),
+        );
+        self.set_done();
+    }
+}
@@ -1,26 +1,56 @@
 use std::{
-    collections::HashMap,
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::{hash_map::Entry, HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
+    sync::{atomic::Ordering, Arc},
     thread::{self, JoinHandle},
+    time::Instant,
 };
 
 use actor::{Actor, ActorContext};
-use actor_channel::{ActorChannel, ActorReceiver, ActorSender, VecActorChannel};
+use actor_channel::{
+    observe_sends, send_hook_set, ActorChannel, ActorReceiver, ActorSender, DynamicSelect,
+    SelectHandle,
+};
 
 use actor_executor_protocol::actor_executor_protocol;
+use admin_protocol::{admin_executor_protocol, AdminActorInfo};
 use an_id::{anid, paste, AnId};
 use box_msg_any::BoxMsgAny;
-use cmd_done_issuee_protocol::{cmd_done_issuee_protocol, CmdDone};
-use cmd_init_issuer_protocol::{cmd_init_issuer_protocol, CmdInit};
+use cmd_done_issuee_protocol::cmd_done_issuee_protocol;
+use cmd_init_issuer_protocol::{cmd_init_issuer_protocol, CmdInit, CmdReady, CmdReadyStatus};
+use cmd_shutdown_issuer_protocol::cmd_shutdown_issuer_protocol;
 use con_mgr::ConMgr;
-use con_mgr_query_protocol::con_mgr_query_protocol;
-use con_mgr_register_actor_protocol::con_mgr_register_actor_protocol;
-use crossbeam_channel::Select;
-use msg_header::MsgHeader;
+use con_mgr_query_protocol::con_mgr_query_requester_protocol;
+use con_mgr_register_actor_protocol::con_mgr_registee_actor_protocol;
+use dead_letters::dead_letter_post;
+use id_inventory::register_id;
+use metrics_protocol::metrics_protocol;
+use msg_header::{MsgHeader, TraceContext};
+use msg_replay::{Direction, Recorder};
+use msg_tap::tap_observe;
+use msg_trace::{continue_trace, enter_span};
 use protocol::Protocol;
 use protocol_set::ProtocolSet;
 use req_add_actor::ReqAddActor;
-use rsp_add_actor::RspAddActor;
-use sender_map_by_instance_id::{sender_map_get, sender_map_insert};
+use req_move_actor::ReqMoveActor;
+use rsp_add_actor::{RspAddActor, RspAddActorStatus};
+use rsp_move_actor::{RspMoveActor, RspMoveActorStatus};
+use runtime_metrics::{
+    executor_metrics_register, executor_metrics_unregister, ActorCounters, ExecutorCounters,
+};
+use sender_map_by_instance_id::{
+    sender_map_generation, sender_map_get, sender_map_insert, sender_map_remove, sender_map_replace,
+};
+use supervisor_protocol::{supervisor_protocol, SupervisorPolicy};
+use tap_protocol::{tap_protocol, TapPoint};
+
+mod executor;
+use executor::{Executor, RemovedActor, Shutdown, Supervision};
+
+#[cfg(feature = "tokio")]
+pub mod tokio_executor;
 
 // Helper functions for setting up a cluster local of actors for testing.
 // Someday something like this will be use in "production", but for now
@@ -65,7 +95,7 @@ pub fn add_actor_to_actor_executor_blocking(
     supervisor_instance_id: &AnId,
     supervisor_receiver: &ActorReceiver,
 ) -> (AnId, AnId) {
-    println!("add_actor_to_actor_executor_blocking:+");
+    log::trace!("add_actor_to_actor_executor_blocking:+");
     let msg = Box::new(ReqAddActor::new(
         ae_instance_id,
         supervisor_instance_id,
@@ -75,9 +105,22 @@ pub fn add_actor_to_actor_executor_blocking(
 
     let msg_any = supervisor_receiver.recv().unwrap();
     let msg = msg_any.downcast_ref::<RspAddActor>().unwrap();
+    let ids = (msg.actor_id, msg.actor_instance_id);
+    if msg.status != RspAddActorStatus::Success {
+        log::error!("add_actor_to_actor_executor_blocking: {:?}", msg.status);
+        return ids;
+    }
+
+    // Wait until the actor is initialized and registered with ConMgr
+    let msg_any = supervisor_receiver.recv().unwrap();
+    let msg = msg_any.downcast_ref::<CmdReady>().unwrap();
+    assert_eq!(msg.src_id(), &ids.1);
+    if let CmdReadyStatus::RegistrationFailed(reason) = &msg.status {
+        log::error!("add_actor_to_actor_executor_blocking: {reason}");
+    }
 
-    println!("add_actor_to_actor_executor_blocking:-");
-    (msg.actor_id, msg.actor_instance_id)
+    log::trace!("add_actor_to_actor_executor_blocking:-");
+    ids
 }
 
 // Initialize create supervisor_id, and supervisor_chnl, ConMg and ActorExecutor
@@ -86,7 +129,7 @@ pub fn add_actor_to_actor_executor_blocking(
 // Returns supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, con_mgr_instance_id
 pub fn initialize_supervisor_con_mgr_actor_executor_blocking(
 ) -> (AnId, ActorChannel, JoinHandle<()>, AnId, AnId) {
-    println!("initialize_supervisor_con_mgr_actor_executor_blocking:+");
+    log::trace!("initialize_supervisor_con_mgr_actor_executor_blocking:+");
 
     // Add supervisor to sender_map
     let supervisor_instance_id = AnId::new();
@@ -114,8 +157,10 @@ pub fn initialize_supervisor_con_mgr_actor_executor_blocking(
     let msg_any = supervisor_chnl.receiver.recv().unwrap();
     let _msg = msg_any.downcast_ref::<RspAddActor>().unwrap();
     //println!("initialize_supervisor_con_mgr_actor_executor_blocking: recvd rsp_add_actor={_msg:?}");
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    let _msg = msg_any.downcast_ref::<CmdReady>().unwrap();
 
-    println!("initialize_supervisor_con_mgr_actor_executor_blocking:-");
+    log::trace!("initialize_supervisor_con_mgr_actor_executor_blocking:-");
     (
         supervisor_instance_id,
         supervisor_chnl,
@@ -125,29 +170,134 @@ pub fn initialize_supervisor_con_mgr_actor_executor_blocking(
     )
 }
 
+/// What an ActorExecutor does with a message for an actor whose
+/// ProtocolSet doesn't receive it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtocolEnforcement {
+    /// Log a warning and deliver it anyway
+    Permissive,
+
+    /// Post it to the dead letters instead of delivering it
+    Strict,
+}
+
+impl Default for ProtocolEnforcement {
+    // Strict in debug builds, which includes tests, and permissive in release builds
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            Self::Strict
+        } else {
+            Self::Permissive
+        }
+    }
+}
+
+/// How an ActorExecutor is started, see [`ActorExecutor::start_with_options`]
+#[derive(Debug, Default)]
+pub struct ActorExecutorOptions {
+    /// Records the serializable messages the actors receive and send
+    pub recorder: Option<Arc<Recorder>>,
+
+    /// What is done with messages an actor didn't declare it receives
+    pub protocol_enforcement: ProtocolEnforcement,
+
+    /// Sent the RspShutdown of every shutdown, including those CmdDone starts
+    pub supervisor_instance_id: Option<AnId>,
+}
+
+// The msg_ids an actor declared it receives, None if it has no
+// ProtocolSet in which case every message is delivered.
+fn receive_msg_ids(actor: &dyn Actor) -> Option<HashSet<AnId>> {
+    actor.get_protocol_set().map(ProtocolSet::receive_msg_ids)
+}
+
 #[allow(unused)]
 #[derive(Debug)]
 pub struct ActorExecutor {
     pub name: String,
     pub actor_id: AnId, // TODO: not used yet
     pub instance_id: AnId,
-    pub protocol_set: ProtocolSet, // TODO: not used yet
-    pub vec_actor: Vec<Box<dyn Actor>>,
-    pub vec_actor_chnl: VecActorChannel,
+    pub protocol_set: ProtocolSet,              // TODO: not used yet
+    pub vec_actor: Vec<Option<Box<dyn Actor>>>, // None if the slot is free, it's reused
+    pub supervisor_policy: SupervisorPolicy,
+    vec_supervision: Vec<Supervision>,
+    vec_select_handle: Vec<SelectHandle>,
+    vec_actor_counters: Vec<Option<Arc<ActorCounters>>>,
+    vec_actor_receives: Vec<Option<HashSet<AnId>>>, // See receive_msg_ids
+    actor_idx_by_handle: HashMap<SelectHandle, usize>, // The actor_idx of each actor's receiver
+    selector: DynamicSelect<BoxMsgAny>, // Our receiver and those of the actors we're running
     con_mgr_instance_id: AnId,
     con_mgr_tx: ActorSender,
+    sender_cache: SenderCache,
+    metrics: Arc<ExecutorCounters>,
+    recorder: Option<Arc<Recorder>>, // Records the messages our actors receive and send
+    protocol_enforcement: ProtocolEnforcement,
+    supervisor_instance_id: Option<AnId>, // Sent the summary of every shutdown
+    shutdown: Option<Shutdown>,           // Some while shutting down
     done: bool,
 }
 
-struct Context {
-    ae_sndr: ActorSender,
-    con_mgr_sndr: ActorSender,
-    dst_sndr: ActorSender,
+/// Number of senders a SenderCache holds, it is cleared when full
+const SENDER_CACHE_CAPACITY: usize = 256;
+
+// Cache of the senders of the entities our actors respond to so
+// sender_map_get is only called the first time an instance_id is seen.
+// It is cleared when the sender_map_generation changes, which happens
+// when an actor is restarted or moved or a federated peer is lost.
+#[derive(Debug, Default)]
+struct SenderCache {
+    senders: RefCell<HashMap<AnId, ActorSender>>,
+    generation: Cell<u64>, // sender_map_generation when senders was valid
+}
+
+impl SenderCache {
+    // Invoke f with the sender for instance_id, Err if there is no sender
+    fn with_sender<R>(
+        &self,
+        instance_id: &AnId,
+        f: impl FnOnce(&ActorSender) -> R,
+    ) -> Result<R, Box<dyn std::error::Error>> {
+        let mut map = self.senders.borrow_mut();
+        let generation = sender_map_generation();
+        if self.generation.replace(generation) != generation {
+            map.clear();
+        }
+        if map.len() >= SENDER_CACHE_CAPACITY && !map.contains_key(instance_id) {
+            map.clear();
+        }
+        let sndr = match map.entry(*instance_id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => match sender_map_get(instance_id) {
+                Some(sndr) => e.insert(sndr),
+                None => return Err(format!("No sender for instance_id={instance_id}").into()),
+            },
+        };
+        Ok(f(sndr))
+    }
+
+    fn send(&self, instance_id: &AnId, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self.with_sender(instance_id, |sndr| sndr.send(msg))?;
+        if result.is_err() {
+            // The receiver is gone, forget it in case the instance_id reappears
+            self.senders.borrow_mut().remove(instance_id);
+        }
+        result
+    }
+}
+
+// The dst sender is resolved when it is used so there is no cost
+// for messages that aren't responded to.
+struct Context<'a> {
+    ae_sndr: &'a ActorSender,
+    con_mgr_sndr: &'a ActorSender,
+    dst_id: AnId,
+    sender_cache: &'a SenderCache,
+    unknown_msgs: Cell<u64>,
 }
 
-impl ActorContext for Context {
+impl ActorContext for Context<'_> {
     fn actor_executor_sndr(&self) -> &ActorSender {
-        &self.ae_sndr
+        self.ae_sndr
     }
 
     fn send_con_mgr(&self, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
@@ -159,26 +309,94 @@ impl ActorContext for Context {
     }
 
     fn send_self(&self, _msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
-        println!("ActorExecutor::send_self: Not implemented, just return Ok(())");
+        log::trace!("ActorExecutor::send_self: Not implemented, just return Ok(())");
         Ok(())
     }
 
     fn send_dst(&self, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
-        self.dst_sndr.send(msg)
+        self.sender_cache.send(&self.dst_id, msg)
     }
 
     fn get_dst_instance_id(&self) -> &AnId {
-        self.dst_sndr.get_dst_instance_id()
+        &self.dst_id
+    }
+
+    fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>> {
+        self.sender_cache
+            .with_sender(&self.dst_id, |sndr| sndr.clone())
+    }
+
+    fn record_unknown_msg(&self, _msg_id: &AnId) {
+        self.unknown_msgs.set(self.unknown_msgs.get() + 1);
+    }
+}
+
+// The span an actor processes a message in. With no tracing subscriber
+// installed its creation, enter and exit are log records at trace level
+// because of tracing's "log" feature, so the actor's own log records are
+// bracketed by the span's fields in the output of the log logger.
+fn dispatch_span(actor: &dyn Actor, msg_id: &AnId, trace: Option<&TraceContext>) -> tracing::Span {
+    let span = tracing::trace_span!(
+        "dispatch",
+        actor = actor.get_name(),
+        instance_id = %actor.get_instance_id(),
+        msg_id = %msg_id,
+        trace_id = tracing::field::Empty,
+    );
+    if let Some(trace) = trace {
+        span.record("trace_id", tracing::field::display(&trace.trace_id));
+    }
+    span
+}
+
+// Send RspMoveActor to the entity that requested the move
+fn send_rsp_move_actor(
+    requester_instance_id: &AnId,
+    ae_instance_id: &AnId,
+    actor_instance_id: &AnId,
+    status: RspMoveActorStatus,
+) {
+    let msg_rsp = Box::new(RspMoveActor::new(
+        requester_instance_id,
+        ae_instance_id,
+        actor_instance_id,
+        ae_instance_id,
+        status,
+    ));
+    send_msg(requester_instance_id, msg_rsp);
+}
+
+// Send a message to dst_id, errors are reported but otherwise ignored
+fn send_msg(dst_id: &AnId, msg: BoxMsgAny) {
+    if let Some(sndr) = sender_map_get(dst_id) {
+        if let Err(why) = sndr.send(msg) {
+            log::warn!("send_msg: error sending to dst_id={dst_id} {why}");
+        }
+    } else {
+        log::warn!("send_msg: no sender for dst_id={dst_id}");
     }
+}
 
-    fn clone_dst_sndr(&self) -> ActorSender {
-        self.dst_sndr.clone()
+// Convert a panic payload to a string
+fn panic_reason(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_owned()
     }
 }
 
 // From: https://www.uuidgenerator.net/version4
 const ACTOR_EXECUTOR_ACTOR_ID: AnId = anid!("5c3d6e86-5e19-4ad8-a397-f446bedef1bd");
 const ACTOR_EXECUTOR_PROTOCOL_SET_ID: AnId = anid!("09b50f0f-fb5d-4609-b657-0b1910d1d1dc");
+register_id!(Actor, ACTOR_EXECUTOR_ACTOR_ID, "actor_executor");
+register_id!(
+    ProtocolSet,
+    ACTOR_EXECUTOR_PROTOCOL_SET_ID,
+    "actor_executor_ps"
+);
 
 #[allow(unused)]
 impl ActorExecutor {
@@ -187,6 +405,42 @@ impl ActorExecutor {
     //
     // Returns the ActorExecutor join handle and its instance_id
     pub fn start(name: &str, con_mgr_instance_id: &AnId) -> (JoinHandle<()>, AnId) {
+        Self::start_with_options(name, con_mgr_instance_id, ActorExecutorOptions::default())
+    }
+
+    // Start an ActorExecutor that records the serializable messages its
+    // actors receive and send, see msg_replay.
+    pub fn start_with_recorder(
+        name: &str,
+        con_mgr_instance_id: &AnId,
+        recorder: Option<Arc<Recorder>>,
+    ) -> (JoinHandle<()>, AnId) {
+        let options = ActorExecutorOptions {
+            recorder,
+            ..Default::default()
+        };
+        Self::start_with_options(name, con_mgr_instance_id, options)
+    }
+
+    // Start an ActorExecutor configured by options.
+    //
+    // Panics if two of the ids registered with id_inventory collide.
+    pub fn start_with_options(
+        name: &str,
+        con_mgr_instance_id: &AnId,
+        options: ActorExecutorOptions,
+    ) -> (JoinHandle<()>, AnId) {
+        // Two messages, protocols or actors sharing an id would be confused
+        // by downcasting and dispatch, so refuse to start if there are any
+        if let Err(collisions) = id_inventory::validate_ids_once() {
+            let collisions: Vec<String> = collisions.iter().map(|c| c.to_string()).collect();
+            panic!("AE:{name}::start: {}", collisions.join(" "));
+        }
+
+        // Messages our actors send continue the trace of the message being
+        // processed however they send them
+        send_hook_set(continue_trace);
+
         let ae_iid = AnId::new();
         let ae_chnl = ActorChannel::new(name, &ae_iid);
         sender_map_insert(&ae_iid, &ae_chnl.sender);
@@ -195,21 +449,31 @@ impl ActorExecutor {
         let name = name.to_string();
 
         // Create the ActorExecutor ProtocolSet.
-        println!("AE:{}::start()", name);
+        log::debug!("AE:{}::start()", name);
         let mut pm = HashMap::<AnId, Protocol>::new();
         let ae_protocol = actor_executor_protocol();
         pm.insert(ae_protocol.id, ae_protocol.clone());
-        let con_mgr_reg_actor_protocol = con_mgr_register_actor_protocol();
+        let con_mgr_registee_protocol = con_mgr_registee_actor_protocol();
         pm.insert(
-            con_mgr_reg_actor_protocol.id,
-            con_mgr_reg_actor_protocol.clone(),
+            con_mgr_registee_protocol.id,
+            con_mgr_registee_protocol.clone(),
         );
-        let query_protocol = con_mgr_query_protocol();
+        let query_protocol = con_mgr_query_requester_protocol();
         pm.insert(query_protocol.id, query_protocol.clone());
         let ci_irp = cmd_init_issuer_protocol();
         pm.insert(ci_irp.id, ci_irp.clone());
         let cd_iep = cmd_done_issuee_protocol();
         pm.insert(cd_iep.id, cd_iep.clone());
+        let sp = supervisor_protocol();
+        pm.insert(sp.id, sp.clone());
+        let cs_irp = cmd_shutdown_issuer_protocol();
+        pm.insert(cs_irp.id, cs_irp.clone());
+        let mp = metrics_protocol();
+        pm.insert(mp.id, mp.clone());
+        let tp = tap_protocol();
+        pm.insert(tp.id, tp.clone());
+        let adp = admin_executor_protocol();
+        pm.insert(adp.id, adp.clone());
 
         let ps_name = name.clone() + "_ps";
         let ps = ProtocolSet::new(&ps_name, ACTOR_EXECUTOR_PROTOCOL_SET_ID, pm);
@@ -226,92 +490,196 @@ impl ActorExecutor {
                 instance_id: ae_instance_id,
                 protocol_set: ps,
                 vec_actor: Vec::new(),
-                vec_actor_chnl: VecActorChannel::new(),
+                supervisor_policy: SupervisorPolicy::default(),
+                vec_supervision: Vec::new(),
+                vec_select_handle: Vec::new(),
+                vec_actor_counters: Vec::new(),
+                vec_actor_receives: Vec::new(),
+                actor_idx_by_handle: HashMap::new(),
+                selector: DynamicSelect::new(),
                 con_mgr_instance_id: cm_instance_id,
                 con_mgr_tx: con_mgr_sender,
+                sender_cache: SenderCache::default(),
+                metrics: executor_metrics_register(&name, &ae_instance_id),
+                recorder: options.recorder,
+                protocol_enforcement: options.protocol_enforcement,
+                supervisor_instance_id: options.supervisor_instance_id,
+                shutdown: None,
                 done: false,
             };
-            println!("AE:{}:+", ae.name);
+            log::debug!("AE:{}:+", ae.name);
 
-            let mut selector = Select::new();
-            let oper_idx = selector.recv(&ae_chnl.receiver.rx);
-            assert_eq!(oper_idx, 0);
+            let ae_handle = ae.selector.add(ae_chnl.receiver.rx.clone());
 
             while !ae.done {
-                println!("AE:{}: TOL", ae.name);
-                let oper = selector.select();
-                let oper_idx = oper.index();
+                log::trace!("AE:{}: TOL", ae.name);
+                let (handle, result) = if let Some(shutdown) = &ae.shutdown {
+                    match ae.selector.select_deadline(shutdown.deadline) {
+                        Ok(selected) => selected,
+                        Err(_) => {
+                            log::warn!("AE:{}: shutdown timed out", ae.name);
+                            ae.finish_shutdown();
+                            continue;
+                        }
+                    }
+                } else {
+                    ae.selector.select()
+                };
+                ae.metrics.select_wakeups.fetch_add(1, Ordering::Relaxed);
 
-                if oper_idx == 0 {
-                    println!("AE:{}:self: msg received", ae.name);
+                if handle == ae_handle {
+                    log::trace!("AE:{}:self: msg received", ae.name);
                     // This message is for the AE itself
-                    let result = oper.recv(&ae_chnl.receiver.rx);
                     match result {
                         Err(why) => {
                             // TODO: Error on our selves, is there anything else we need to do?
-                            println!("AE:{}:self: error on recv: {why} `done = true`", ae.name);
+                            log::error!("AE:{}:self: error on recv: {why} `done = true`", ae.name);
                             ae.done = true;
                         }
                         Ok(msg_any) => {
                             // Got our message
-                            println!("AE:{}:self: msg_any={msg_any:?}", ae.name);
+                            log::trace!("AE:{}:self: msg_any={msg_any:?}", ae.name);
                             if msg_any.downcast_ref::<ReqAddActor>().is_some() {
                                 // It is a MsgReqAeAddActor, now downcast to concrete message so we can push it to vec_actor
                                 let msg = msg_any.downcast::<ReqAddActor>().unwrap();
-                                println!("AE:{}:self: msg={msg:?}", ae.name);
+                                log::debug!("AE:{}:self: msg={msg:?}", ae.name);
 
                                 // Get the destination id of the actor requesting the registration
                                 let requester_dst_id = *msg.src_id();
 
-                                // Get the actor's instance_id that is being registered
+                                // Get the actor's id and instance_id that is being registered
+                                let actor_id = *msg.actor.get_actor_id();
                                 let actor_instance_id = *msg.actor.get_instance_id();
 
-                                // Push actor
-                                let actor_idx = ae.vec_actor.len();
-                                ae.vec_actor.push(msg.actor);
-
-                                // Push the actors channel
-                                assert_eq!(ae.vec_actor_chnl.len(), actor_idx);
-                                ae.vec_actor_chnl
-                                    .push(ae.vec_actor[actor_idx].get_chnl().clone());
-
-                                // Get a reference to the actors channel
-                                let chnl = ae.vec_actor_chnl.get(actor_idx);
-
-                                // Add the actors receiver to the selector
-                                println!(
-                                    "AE:{}:self: selector.recv(our_channel.get_recv())",
-                                    ae.name
-                                );
-                                selector.recv(&chnl.receiver.rx);
+                                if ae.shutdown.is_some() {
+                                    log::warn!(
+                                        "AE:{}:self: shutting down, dropping {}",
+                                        ae.name,
+                                        msg.actor.get_name()
+                                    );
+                                    sender_map_remove(&actor_instance_id);
+                                    send_msg(
+                                        &requester_dst_id,
+                                        Box::new(RspAddActor::new(
+                                            &requester_dst_id,
+                                            &ae.instance_id,
+                                            &actor_id,
+                                            &actor_instance_id,
+                                            RspAddActorStatus::ShuttingDown,
+                                        )),
+                                    );
+                                    continue;
+                                }
+
+                                // Add the actor and its receiver to the selector
+                                log::trace!("AE:{}:self: selector.add(actor's receiver)", ae.name);
+                                let chnl = msg.actor.get_chnl().clone();
+                                ae.add_actor(msg.actor, Supervision::new(&requester_dst_id, ""));
 
                                 // Send the response message with their instance_id
-                                let sndr = sender_map_get(&requester_dst_id).unwrap();
                                 let msg_rsp = Box::new(RspAddActor::new(
-                                    sndr.get_dst_instance_id(),
+                                    &requester_dst_id,
                                     &ae.instance_id,
-                                    ae.vec_actor[actor_idx].get_actor_id(),
+                                    &actor_id,
                                     &actor_instance_id,
+                                    RspAddActorStatus::Success,
                                 ));
-                                println!("AE:{}:self: respond with msg={msg_rsp:?}", ae.name);
-                                println!("AE:{}:self: sender={sndr:?}", ae.name);
-                                sndr.send(msg_rsp);
+                                log::trace!("AE:{}:self: respond with msg={msg_rsp:?}", ae.name);
+                                send_msg(&requester_dst_id, msg_rsp);
 
                                 // Issue a CmdInit
                                 let msg =
                                     Box::new(CmdInit::new(&actor_instance_id, &ae.instance_id));
-                                chnl.sender.send(msg).unwrap(); // TODO: Ignore error on release builds so we don't panic?
-
-                                println!(
+                                if let Err(why) = chnl.sender.send(msg) {
+                                    log::warn!(
+                                        "AE:{}:self: error sending CmdInit to {}: {why}",
+                                        ae.name,
+                                        chnl.sender.name
+                                    );
+                                }
+
+                                log::debug!(
                                     "AE:{}:self: added new receiver for {}",
                                     ae.name,
-                                    ae.vec_actor[actor_idx].get_name()
+                                    chnl.sender.name
                                 );
-                            } else if let Some(msg) = msg_any.downcast_ref::<CmdDone>() {
-                                println!("AE:{}:self: msg={msg:?}", ae.name);
-                                ae.done = true;
-                            } else {
-                                println!(
+                            } else if msg_any.downcast_ref::<ReqMoveActor>().is_some() {
+                                let msg = msg_any.downcast::<ReqMoveActor>().unwrap();
+                                log::debug!("AE:{}:self: msg={msg:?}", ae.name);
+                                let requester_instance_id = msg.requester_instance_id;
+                                let actor_instance_id = msg.actor_instance_id;
+                                let dst_ae_instance_id = msg.dst_ae_instance_id;
+
+                                if let Some(actor) = msg.actor {
+                                    // We are the destination, the actor was initialized
+                                    // by the source AE so there is no CmdInit. The
+                                    // requester becomes the actors supervisor. Its
+                                    // sender is replaced so cached senders are dropped.
+                                    sender_map_replace(
+                                        &actor_instance_id,
+                                        &actor.get_chnl().sender,
+                                    );
+                                    ae.add_actor(
+                                        actor,
+                                        Supervision::new(&requester_instance_id, &msg.params),
+                                    );
+                                    send_rsp_move_actor(
+                                        &requester_instance_id,
+                                        &ae.instance_id,
+                                        &actor_instance_id,
+                                        RspMoveActorStatus::Success,
+                                    );
+                                } else {
+                                    // We are the source, find the actor and forward it
+                                    let actor_idx = ae.actor_idx(&actor_instance_id);
+                                    let dst_ae_sndr = sender_map_get(&dst_ae_instance_id);
+                                    let status = match (actor_idx, dst_ae_sndr) {
+                                        (None, _) => Some(RspMoveActorStatus::ActorNotFound),
+                                        (Some(_), _) if dst_ae_instance_id == ae.instance_id => {
+                                            // Moving to ourselves, nothing to do
+                                            Some(RspMoveActorStatus::Success)
+                                        }
+                                        (Some(_), None) => {
+                                            Some(RspMoveActorStatus::DstActorExecutorNotFound)
+                                        }
+                                        (Some(actor_idx), Some(dst_ae_sndr)) => {
+                                            // Stop selecting on the actors receiver, messages
+                                            // that are pending or arrive while the actor is in
+                                            // transit stay in its channel in order.
+                                            let (actor, supervision) = ae.remove_actor(actor_idx);
+                                            let msg = Box::new(ReqMoveActor::new_with_actor(
+                                                &dst_ae_instance_id,
+                                                &ae.instance_id,
+                                                &requester_instance_id,
+                                                &supervision.params,
+                                                actor,
+                                            ));
+                                            match dst_ae_sndr.send_recoverable(msg) {
+                                                // The destination AE will respond
+                                                Ok(_) => None,
+                                                Err(why) => {
+                                                    // Destination is gone, take the actor back
+                                                    let msg = why
+                                                        .into_inner()
+                                                        .downcast::<ReqMoveActor>()
+                                                        .unwrap();
+                                                    ae.add_actor(msg.actor.unwrap(), supervision);
+                                                    Some(RspMoveActorStatus::DstActorExecutorNotFound)
+                                                }
+                                            }
+                                        }
+                                    };
+                                    if let Some(status) = status {
+                                        send_rsp_move_actor(
+                                            &requester_instance_id,
+                                            &ae.instance_id,
+                                            &actor_instance_id,
+                                            status,
+                                        );
+                                    }
+                                }
+                            } else if let Some(msg_any) = ae.handle_executor_msg(msg_any) {
+                                log::warn!(
                                     "AE:{}:self: Uknown msg_id={:?}",
                                     ae.name,
                                     MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any)
@@ -320,66 +688,301 @@ impl ActorExecutor {
                         }
                     }
                 } else {
-                    // This message for one of the actors running in the AE
-                    let actor_idx = oper_idx - 1;
-                    let actor = &mut ae.vec_actor[actor_idx];
-                    println!(
+                    // This message for one of the actors running in the AE, actors
+                    // that were removed are no longer selected so they're found
+                    let Some(&actor_idx) = ae.actor_idx_by_handle.get(&handle) else {
+                        log::warn!(
+                            "AE:{}: no actor for {handle:?}, dropping {result:?}",
+                            ae.name
+                        );
+                        continue;
+                    };
+                    let actor = ae.vec_actor[actor_idx].as_mut().unwrap();
+                    let actor_instance_id = *actor.get_instance_id();
+                    log::trace!(
                         "AE:{}: msg for vec_actor[{actor_idx}] {}",
                         ae.name,
                         actor.get_name(),
                     );
-                    let chnl = ae.vec_actor_chnl.get(actor_idx);
-                    if let Ok(msg_any) = oper.recv(&chnl.receiver.rx).map_err(|why| {
-                        // TODO: What should we do here?
-                        panic!("AE:{}: {} error on recv: {why}", ae.name, actor.get_name())
-                    }) {
-                        println!(
-                            "AE:{}: call process_msg_any[{actor_idx}] {} msg_id={}",
-                            ae.name,
-                            actor.get_name(),
-                            MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any),
-                        );
-                        let src_id = MsgHeader::get_src_id_from_boxed_msg_any(&msg_any);
-                        let rsp_tx = if let Some(sender) = sender_map_get(src_id) {
-                            sender.clone()
-                        } else {
-                            let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
-                            panic!(
-                                "AE:{}: BUG; send_map_get returned None for src_id={src_id:?} with msg_id={msg_id:?}",
-                                ae.name);
-                        };
-                        let context = Context {
-                            // TODO: All this cloning for each msg is slow, we need an array/hash of these Context's
-                            ae_sndr: ae_chnl.sender.clone(),
-                            con_mgr_sndr: ae.con_mgr_tx.clone(),
-                            dst_sndr: rsp_tx,
-                        };
-                        actor.process_msg_any(&context, msg_any);
-                        println!(
-                            "AE:{}: retf process_msg_any[{actor_idx}] {}",
-                            ae.name,
-                            actor.get_name(),
-                        );
-                        if actor.done() {
-                            panic!(
-                                "AE:{}: {} reported done, what to do?",
+                    match result {
+                        Err(why) => {
+                            let reason = format!("error on recv: {why}");
+                            ae.actor_failed(&actor_instance_id, &reason);
+                        }
+                        Ok(msg_any) => {
+                            tap_observe(&TapPoint::Executor(ae.instance_id), &msg_any);
+                            if let Some(recorder) = &ae.recorder {
+                                if let Err(why) = recorder.record_msg(Direction::Inbound, &msg_any)
+                                {
+                                    log::warn!("AE:{}: error recording: {why}", ae.name);
+                                }
+                            }
+                            let msg_id = *MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
+                            let undeclared = ae.vec_actor_receives[actor_idx]
+                                .as_ref()
+                                .is_some_and(|receives| !receives.contains(&msg_id));
+                            if undeclared {
+                                if let Some(ps) = actor.get_protocol_set() {
+                                    let reason = format!(
+                                        "{} didn't declare msg_id={msg_id} in {}",
+                                        actor.get_name(),
+                                        ps.name
+                                    );
+                                    match ae.protocol_enforcement {
+                                        ProtocolEnforcement::Permissive => {
+                                            log::warn!("AE:{}: {reason}", ae.name);
+                                        }
+                                        ProtocolEnforcement::Strict => {
+                                            log::error!("AE:{}: {reason}, rejected", ae.name);
+                                            dead_letter_post(msg_any, &reason);
+                                            continue;
+                                        }
+                                    }
+                                }
+                            }
+                            // Continue the messages trace, if it has one, while it's processed
+                            let trace = MsgHeader::get_trace_from_boxed_msg_any(&msg_any);
+                            let span = dispatch_span(actor.as_ref(), &msg_id, trace);
+                            let _enter = span.enter();
+                            let trace_span = enter_span(
+                                trace,
+                                "dispatch",
+                                actor.get_name(),
+                                actor.get_instance_id(),
+                                &msg_id,
+                            );
+                            log::trace!(
+                                "AE:{}: call process_msg_any[{actor_idx}] {} msg_id={msg_id}",
+                                ae.name,
+                                actor.get_name(),
+                            );
+                            let context = Context {
+                                ae_sndr: &ae_chnl.sender,
+                                con_mgr_sndr: &ae.con_mgr_tx,
+                                dst_id: *MsgHeader::get_src_id_from_boxed_msg_any(&msg_any),
+                                sender_cache: &ae.sender_cache,
+                                unknown_msgs: Cell::new(0),
+                            };
+
+                            // Record what the actor sends however it sends it
+                            let _send_observer = ae
+                                .recorder
+                                .as_ref()
+                                .map(|r| observe_sends(Recorder::outbound_observer(r)));
+
+                            // Isolate panics to the actor so the other actors keep running
+                            let start = Instant::now();
+                            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                                actor.process_msg_any(&context, msg_any)
+                            }));
+                            let elapsed = start.elapsed();
+                            drop(trace_span);
+                            if let Some(counters) = &ae.vec_actor_counters[actor_idx] {
+                                counters.processed(elapsed, context.unknown_msgs.get());
+                            }
+                            log::trace!(
+                                "AE:{}: retf process_msg_any[{actor_idx}] {}",
                                 ae.name,
-                                actor.get_name()
+                                actor.get_name(),
                             );
+                            match result {
+                                Err(payload) => {
+                                    let reason = panic_reason(&*payload);
+                                    ae.actor_failed(&actor_instance_id, &reason);
+                                }
+                                Ok(_) => {
+                                    if actor.done() {
+                                        log::debug!(
+                                            "AE:{}: {} reported done, stopping it",
+                                            ae.name,
+                                            actor.get_name()
+                                        );
+                                        ae.stop_actor(&actor_instance_id);
+                                    }
+                                }
+                            }
                         }
                     }
                 }
             }
 
-            // TODO: Should we be cleaning things up, like telling the Manager?
-            println!("AE:{}:-", ae.name);
+            executor_metrics_unregister(&ae.instance_id);
+            if let Some(Err(why)) = ae.recorder.as_ref().map(|r| r.flush()) {
+                log::warn!("AE:{}: error flushing recorder: {why}", ae.name);
+            }
+            log::debug!("AE:{}:-", ae.name);
         });
 
         (join_handle, ae_iid)
     }
 
+    // Add an actor in the first free slot of vec_actor, or a new one, and
+    // add its receiver to the selector.
+    //
+    // Returns the actor_idx
+    fn add_actor(&mut self, actor: Box<dyn Actor>, supervision: Supervision) -> usize {
+        let handle = self.selector.add(actor.get_chnl().receiver.rx.clone());
+        let counters = self.metrics.actor_add(
+            actor.get_name(),
+            actor.get_instance_id(),
+            &actor.get_chnl().receiver.rx,
+        );
+        let receives = receive_msg_ids(actor.as_ref());
+        let actor_idx = match self.vec_actor.iter().position(Option::is_none) {
+            Some(actor_idx) => {
+                self.vec_actor[actor_idx] = Some(actor);
+                self.vec_supervision[actor_idx] = supervision;
+                self.vec_select_handle[actor_idx] = handle;
+                self.vec_actor_counters[actor_idx] = Some(counters);
+                self.vec_actor_receives[actor_idx] = receives;
+                actor_idx
+            }
+            None => {
+                self.vec_actor.push(Some(actor));
+                self.vec_supervision.push(supervision);
+                self.vec_select_handle.push(handle);
+                self.vec_actor_counters.push(Some(counters));
+                self.vec_actor_receives.push(receives);
+                self.vec_actor.len() - 1
+            }
+        };
+        self.actor_idx_by_handle.insert(handle, actor_idx);
+
+        actor_idx
+    }
+
+    // Remove the actor so it is no longer selected and free its slot,
+    // returns the actor and its supervision. Messages sent to the actor
+    // remain in its channel.
+    fn remove_actor(&mut self, actor_idx: usize) -> (Box<dyn Actor>, Supervision) {
+        let handle = self.vec_select_handle[actor_idx];
+        self.selector.remove(handle);
+        self.actor_idx_by_handle.remove(&handle);
+        let actor = self.vec_actor[actor_idx].take().unwrap();
+        self.vec_actor_counters[actor_idx] = None;
+        self.vec_actor_receives[actor_idx] = None;
+        self.metrics.actor_remove(actor.get_instance_id());
+        let supervision = std::mem::replace(
+            &mut self.vec_supervision[actor_idx],
+            Supervision::new(&AnId::nil(), ""),
+        );
+        (actor, supervision)
+    }
+
+    // The actor_idx of the actor with instance_id, None if we aren't running it
+    fn actor_idx(&self, instance_id: &AnId) -> Option<usize> {
+        self.vec_actor.iter().position(|actor| {
+            actor
+                .as_ref()
+                .is_some_and(|actor| actor.get_instance_id() == instance_id)
+        })
+    }
+}
+
+impl Executor for ActorExecutor {
+    const LOG_TAG: &'static str = "AE";
+
     fn name(&self) -> &str {
-        self.name.as_str()
+        &self.name
+    }
+
+    fn instance_id(&self) -> &AnId {
+        &self.instance_id
+    }
+
+    fn con_mgr_instance_id(&self) -> &AnId {
+        &self.con_mgr_instance_id
+    }
+
+    fn con_mgr_tx(&self) -> &ActorSender {
+        &self.con_mgr_tx
+    }
+
+    fn supervisor_instance_id(&self) -> Option<&AnId> {
+        self.supervisor_instance_id.as_ref()
+    }
+
+    fn supervisor_policy(&self) -> &SupervisorPolicy {
+        &self.supervisor_policy
+    }
+
+    fn set_supervisor_policy(&mut self, policy: SupervisorPolicy) {
+        self.supervisor_policy = policy;
+    }
+
+    fn shutdown_mut(&mut self) -> &mut Option<Shutdown> {
+        &mut self.shutdown
+    }
+
+    fn set_done(&mut self) {
+        self.done = true;
+    }
+
+    fn actor_instance_ids(&self) -> Vec<AnId> {
+        self.vec_actor
+            .iter()
+            .flatten()
+            .map(|actor| *actor.get_instance_id())
+            .collect()
+    }
+
+    fn send_to_actor(
+        &self,
+        instance_id: &AnId,
+        msg: BoxMsgAny,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(actor_idx) = self.actor_idx(instance_id) else {
+            return Err(format!("not running instance_id={instance_id}").into());
+        };
+        self.vec_actor[actor_idx]
+            .as_ref()
+            .unwrap()
+            .get_chnl()
+            .sender
+            .send(msg)
+    }
+
+    fn actor_name_and_id(&self, instance_id: &AnId) -> Option<(&str, &AnId)> {
+        let actor = self.vec_actor[self.actor_idx(instance_id)?].as_ref()?;
+        Some((actor.get_name(), actor.get_actor_id()))
+    }
+
+    fn supervision(&self, instance_id: &AnId) -> Option<&Supervision> {
+        Some(&self.vec_supervision[self.actor_idx(instance_id)?])
+    }
+
+    fn supervision_mut(&mut self, instance_id: &AnId) -> Option<&mut Supervision> {
+        let actor_idx = self.actor_idx(instance_id)?;
+        Some(&mut self.vec_supervision[actor_idx])
+    }
+
+    fn insert_actor(&mut self, actor: Box<dyn Actor>, supervision: Supervision) {
+        self.add_actor(actor, supervision);
+    }
+
+    fn take_actor(&mut self, instance_id: &AnId) -> Option<RemovedActor> {
+        let actor_idx = self.actor_idx(instance_id)?;
+        let (actor, supervision) = self.remove_actor(actor_idx);
+        Some(RemovedActor {
+            name: actor.get_name().to_owned(),
+            actor_id: *actor.get_actor_id(),
+            supervision,
+            receiver: actor.get_chnl().receiver.clone(),
+        })
+    }
+
+    // The actors we're running for an AdminListActorsRsp
+    fn admin_actor_infos(&self) -> Vec<AdminActorInfo> {
+        self.vec_actor
+            .iter()
+            .flatten()
+            .map(|actor| AdminActorInfo {
+                name: actor.get_name().to_owned(),
+                actor_id: *actor.get_actor_id(),
+                instance_id: *actor.get_instance_id(),
+                state: format!("{actor:?}"),
+            })
+            .collect()
     }
 }
 
@@ -387,12 +990,146 @@ impl ActorExecutor {
 mod tests {
     use super::*;
 
+    use actor_factory::actor_factory_insert;
+    use admin_protocol::{AdminRemoveActorReq, AdminRemoveActorRsp, AdminRemoveActorStatus};
     use client::Client;
     use cmd_done_issuer_protocol::CmdDone;
-    use echo_requestee_protocol::{EchoReq, EchoRsp};
+    use echo_requestee_protocol::{EchoReq, EchoRsp, ECHO_REQ_ID, ECHO_RSP_ID};
     use echo_start_complete_protocol::{EchoComplete, EchoStart};
+    use metrics_protocol::{MetricsReq, MetricsRsp};
+    use msg_replay::{read_recording, ReplaySpeed, Replayer};
+    use req_shutdown::ReqShutdown;
+    use req_spawn_actor::ReqSpawnActor;
+    use rsp_shutdown::RspShutdown;
+    use rsp_spawn_actor::{RspSpawnActor, RspSpawnActorStatus};
     use sender_map_by_instance_id::sender_map_get;
-    use server::Server;
+    use server::{Server, SERVER_ACTOR_ID};
+    use std::{sync::Mutex, time::Duration};
+    use supervisor_protocol::{
+        ActorFailed, ActorRestarted, ActorStopped, ReqSetSupervisorPolicy, RspSetSupervisorPolicy,
+        SupervisorDirective, SupervisorStrategy,
+    };
+    use tap_protocol::{
+        TapDetachReq, TapDetachRsp, TapDetachRspStatus, TapFilter, TapMsg, TapReq, TapRsp,
+        TapRspStatus,
+    };
+
+    // An actor that panics when it receives an EchoReq with a counter
+    // of zero otherwise it responds with an EchoRsp
+    #[derive(Debug)]
+    pub(crate) struct Crasher {
+        name: String,
+        instance_id: AnId,
+        chnl: ActorChannel,
+    }
+
+    // From: https://www.uuidgenerator.net/version4
+    pub(crate) const CRASHER_ACTOR_ID: AnId = anid!("605abde2-628a-4118-a120-b4eadda09f23");
+
+    // A Crasher waits for this before panicking, a test holds it to queue
+    // messages behind the one that crashes
+    static CRASH_GATE: Mutex<()> = Mutex::new(());
+
+    impl Crasher {
+        pub(crate) fn new(name: &str) -> Self {
+            let instance_id = AnId::new();
+            let chnl = ActorChannel::new(name, &instance_id);
+            sender_map_insert(&instance_id, &chnl.sender);
+            Self {
+                name: name.to_owned(),
+                instance_id,
+                chnl,
+            }
+        }
+
+        pub(crate) fn factory(
+            name: &str,
+            _params: &str,
+        ) -> Result<Box<dyn Actor>, Box<dyn std::error::Error>> {
+            Ok(Box::new(Self::new(name)))
+        }
+    }
+
+    impl Actor for Crasher {
+        fn get_name(&self) -> &str {
+            &self.name
+        }
+
+        fn get_actor_id(&self) -> &AnId {
+            &CRASHER_ACTOR_ID
+        }
+
+        fn get_instance_id(&self) -> &AnId {
+            &self.instance_id
+        }
+
+        fn get_chnl(&self) -> &ActorChannel {
+            &self.chnl
+        }
+
+        fn process_msg_any(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
+            if let Some(msg) = msg_any.downcast_ref::<EchoReq>() {
+                if msg.counter == 0 {
+                    drop(CRASH_GATE.lock());
+                    panic!("{}: counter == 0", self.name);
+                }
+                let rsp_msg = Box::new(EchoRsp::new(
+                    context.get_dst_instance_id(),
+                    &self.instance_id,
+                    msg.req_timestamp_ns,
+                    msg.counter,
+                ));
+                context.send_dst(rsp_msg).unwrap();
+            } else if let Some(msg) = msg_any.downcast_ref::<CmdInit>() {
+                // Nothing to initialize, ready immediately
+                send_msg(
+                    msg.src_id(),
+                    Box::new(CmdReady::new(
+                        msg.src_id(),
+                        &self.instance_id,
+                        CmdReadyStatus::Ready,
+                    )),
+                );
+            }
+        }
+
+        fn done(&self) -> bool {
+            false
+        }
+    }
+
+    // Create a supervisor channel and add it to the sender_map
+    fn new_supervisor(name: &str) -> (AnId, ActorChannel) {
+        let instance_id = AnId::new();
+        let chnl = ActorChannel::new(name, &instance_id);
+        sender_map_insert(&instance_id, &chnl.sender);
+        (instance_id, chnl)
+    }
+
+    pub(crate) fn set_supervisor_policy_blocking(
+        ae_instance_id: &AnId,
+        supervisor_instance_id: &AnId,
+        supervisor_chnl: &ActorChannel,
+        policy: SupervisorPolicy,
+    ) {
+        sender_map_get(ae_instance_id)
+            .unwrap()
+            .send(Box::new(ReqSetSupervisorPolicy::new(
+                ae_instance_id,
+                supervisor_instance_id,
+                policy,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<RspSetSupervisorPolicy>().is_some());
+    }
+
+    pub(crate) fn send_echo_req(dst_id: &AnId, src_id: &AnId, counter: u64) {
+        sender_map_get(dst_id)
+            .unwrap()
+            .send(Box::new(EchoReq::new(dst_id, src_id, counter)))
+            .unwrap();
+    }
 
     #[test]
     fn test_con_mgr_server() {
@@ -596,4 +1333,994 @@ mod tests {
 
         println!("test_multiple_ae:-");
     }
+
+    #[test]
+    fn test_move_actor() {
+        println!("\ntest_move_actor:+");
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        // Start a second ActorExecutor
+        let (ae2_join_handle, ae2_instance_id) = ActorExecutor::start("ae2", &con_mgr_instance_id);
+        let ae2_sender = sender_map_get(&ae2_instance_id).unwrap();
+
+        // Add Server s1 to the first ActorExecutor
+        let s1 = Box::new(Server::new("server1"));
+        let (_s1_actor_id, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            s1,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let s1_sndr = sender_map_get(&s1_instance_id).unwrap();
+
+        // Interleave EchoReq's with moving s1 to ae2
+        println!("test_move_actor: send EchoReq's and ReqMoveActor");
+        for counter in 1..=5 {
+            s1_sndr
+                .send(Box::new(EchoReq::new(
+                    &s1_instance_id,
+                    &supervisor_instance_id,
+                    counter,
+                )))
+                .unwrap();
+        }
+        ae_sender
+            .send(Box::new(ReqMoveActor::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &s1_instance_id,
+                &ae2_instance_id,
+            )))
+            .unwrap();
+        for counter in 6..=10 {
+            s1_sndr
+                .send(Box::new(EchoReq::new(
+                    &s1_instance_id,
+                    &supervisor_instance_id,
+                    counter,
+                )))
+                .unwrap();
+        }
+
+        // All EchoRsp's must arrive in order plus one RspMoveActor
+        println!("test_move_actor: wait EchoRsp's and RspMoveActor");
+        let mut expected_counter = 1;
+        let mut rsp_move_actor_count = 0;
+        while expected_counter <= 10 || rsp_move_actor_count == 0 {
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            if let Some(msg) = msg_any.downcast_ref::<EchoRsp>() {
+                assert_eq!(msg.counter, expected_counter);
+                expected_counter += 1;
+            } else if let Some(msg) = msg_any.downcast_ref::<RspMoveActor>() {
+                println!("test_move_actor: recv {msg:?}");
+                assert_eq!(msg.status, RspMoveActorStatus::Success);
+                assert_eq!(msg.actor_instance_id, s1_instance_id);
+                assert_eq!(msg.ae_instance_id, ae2_instance_id);
+                rsp_move_actor_count += 1;
+            } else {
+                panic!("test_move_actor: unexpected msg");
+            }
+        }
+        assert_eq!(rsp_move_actor_count, 1);
+
+        // Stop the first ActorExecutor, s1 is running on ae2 so it still responds
+        println!("test_move_actor: send CmdDone to ae");
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<RspShutdown>().is_some());
+
+        s1_sndr
+            .send(Box::new(EchoReq::new(
+                &s1_instance_id,
+                &supervisor_instance_id,
+                11,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg_rsp = msg_any.downcast_ref::<EchoRsp>().unwrap();
+        assert_eq!(msg_rsp.counter, 11);
+
+        println!("test_move_actor: send CmdDone to ae2");
+        ae2_sender
+            .send(Box::new(CmdDone::new(
+                &ae2_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae2_join_handle.join().unwrap();
+
+        println!("test_move_actor:-");
+    }
+
+    #[test]
+    fn test_move_actor_not_found() {
+        println!("\ntest_move_actor_not_found:+");
+
+        let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        // Move an actor that doesn't exist
+        let unknown_instance_id = AnId::new();
+        ae_sender
+            .send(Box::new(ReqMoveActor::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &unknown_instance_id,
+                &AnId::new(),
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg_rsp = msg_any.downcast_ref::<RspMoveActor>().unwrap();
+        println!("test_move_actor_not_found: recv {msg_rsp:?}");
+        assert_eq!(msg_rsp.status, RspMoveActorStatus::ActorNotFound);
+        assert_eq!(msg_rsp.actor_instance_id, unknown_instance_id);
+
+        // Moving it to the same ActorExecutor doesn't find it either
+        ae_sender
+            .send(Box::new(ReqMoveActor::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &unknown_instance_id,
+                &ae_instance_id,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg_rsp = msg_any.downcast_ref::<RspMoveActor>().unwrap();
+        assert_eq!(msg_rsp.status, RspMoveActorStatus::ActorNotFound);
+
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_move_actor_not_found:-");
+    }
+
+    #[test]
+    fn test_supervisor_one_for_one_restart() {
+        println!("\ntest_supervisor_one_for_one_restart:+");
+
+        let (supervisor_instance_id, _supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+        let (sup_instance_id, sup_chnl) = new_supervisor("sup");
+
+        let policy = SupervisorPolicy::new(
+            SupervisorStrategy::OneForOne,
+            SupervisorDirective::Restart,
+            3,
+            Duration::from_secs(60),
+        );
+        actor_factory_insert(&CRASHER_ACTOR_ID, Crasher::factory);
+        set_supervisor_policy_blocking(&ae_instance_id, &sup_instance_id, &sup_chnl, policy);
+
+        let (_, mut c1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Crasher::new("crasher1")),
+            &ae_instance_id,
+            &sup_instance_id,
+            &sup_chnl.receiver,
+        );
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &sup_instance_id,
+            &sup_chnl.receiver,
+        );
+
+        // Crash c1 twice, each time it is restarted with a new instance_id
+        for _ in 0..2 {
+            send_echo_req(&c1_instance_id, &sup_instance_id, 0);
+            let msg_any = sup_chnl.receiver.recv().unwrap();
+            let msg = msg_any.downcast_ref::<ActorFailed>().unwrap();
+            println!("test_supervisor_one_for_one_restart: recv {msg:?}");
+            assert_eq!(msg.instance_id, c1_instance_id);
+            assert!(msg.reason.contains("counter == 0"));
+
+            let msg_any = sup_chnl.receiver.recv().unwrap();
+            let msg = msg_any.downcast_ref::<ActorRestarted>().unwrap();
+            println!("test_supervisor_one_for_one_restart: recv {msg:?}");
+            assert_eq!(msg.actor_id, CRASHER_ACTOR_ID);
+            assert_eq!(msg.old_instance_id, c1_instance_id);
+            assert_ne!(msg.new_instance_id, c1_instance_id);
+            c1_instance_id = msg.new_instance_id;
+
+            // The new instance was initialized
+            let msg_any = sup_chnl.receiver.recv().unwrap();
+            let msg = msg_any.downcast_ref::<CmdReady>().unwrap();
+            assert_eq!(msg.src_id(), &c1_instance_id);
+            assert_eq!(msg.status, CmdReadyStatus::Ready);
+
+            // The new instance and its sibling are working
+            send_echo_req(&c1_instance_id, &sup_instance_id, 1);
+            let msg_any = sup_chnl.receiver.recv().unwrap();
+            assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 1);
+            send_echo_req(&s1_instance_id, &sup_instance_id, 2);
+            let msg_any = sup_chnl.receiver.recv().unwrap();
+            assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 2);
+        }
+
+        // Messages pending when c1 crashes are forwarded to the new instance,
+        // the old instance is no longer in the sender_map
+        let gate = CRASH_GATE.lock().unwrap();
+        send_echo_req(&c1_instance_id, &sup_instance_id, 0);
+        send_echo_req(&c1_instance_id, &sup_instance_id, 4);
+        drop(gate);
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<ActorFailed>().is_some());
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorRestarted>().unwrap();
+        assert!(sender_map_get(&c1_instance_id).is_none());
+        c1_instance_id = msg.new_instance_id;
+
+        // The new instance's CmdReady is passed on by the AE, so it may
+        // arrive before or after the EchoRsp the new instance sends
+        let mut cmd_ready_count = 0;
+        let mut echo_rsp_count = 0;
+        for _ in 0..2 {
+            let msg_any = sup_chnl.receiver.recv().unwrap();
+            if msg_any.downcast_ref::<CmdReady>().is_some() {
+                cmd_ready_count += 1;
+            } else {
+                let msg = msg_any.downcast_ref::<EchoRsp>().unwrap();
+                assert_eq!(msg.counter, 4);
+                assert_eq!(msg.src_id(), &c1_instance_id);
+                echo_rsp_count += 1;
+            }
+        }
+        assert_eq!((cmd_ready_count, echo_rsp_count), (1, 1));
+
+        // The next crash exceeds max_restarts so c1 is stopped
+        send_echo_req(&c1_instance_id, &sup_instance_id, 0);
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<ActorFailed>().is_some());
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorStopped>().unwrap();
+        println!("test_supervisor_one_for_one_restart: recv {msg:?}");
+        assert_eq!(msg.instance_id, c1_instance_id);
+
+        // s1 is still working
+        send_echo_req(&s1_instance_id, &sup_instance_id, 3);
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 3);
+
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_supervisor_one_for_one_restart:-");
+    }
+
+    #[test]
+    fn test_supervisor_one_for_all_stop() {
+        println!("\ntest_supervisor_one_for_all_stop:+");
+
+        let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+        let (sup_instance_id, sup_chnl) = new_supervisor("sup");
+
+        let policy = SupervisorPolicy::new(
+            SupervisorStrategy::OneForAll,
+            SupervisorDirective::Stop,
+            0,
+            Duration::from_secs(60),
+        );
+        set_supervisor_policy_blocking(&ae_instance_id, &sup_instance_id, &sup_chnl, policy);
+
+        let (_, c1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Crasher::new("crasher1")),
+            &ae_instance_id,
+            &sup_instance_id,
+            &sup_chnl.receiver,
+        );
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &sup_instance_id,
+            &sup_chnl.receiver,
+        );
+
+        // A server supervised by someone else isn't a sibling
+        let (_, s2_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server2")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        // Crashing c1 stops c1 and s1
+        send_echo_req(&c1_instance_id, &sup_instance_id, 0);
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorFailed>().unwrap();
+        assert_eq!(msg.instance_id, c1_instance_id);
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorStopped>().unwrap();
+        assert_eq!(msg.instance_id, c1_instance_id);
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorStopped>().unwrap();
+        assert_eq!(msg.instance_id, s1_instance_id);
+
+        // s2 is still working
+        send_echo_req(&s2_instance_id, &supervisor_instance_id, 1);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 1);
+
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_supervisor_one_for_all_stop:-");
+    }
+
+    #[test]
+    fn test_supervisor_escalate() {
+        println!("\ntest_supervisor_escalate:+");
+
+        let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let (sup_instance_id, sup_chnl) = new_supervisor("sup");
+
+        let policy = SupervisorPolicy::new(
+            SupervisorStrategy::OneForOne,
+            SupervisorDirective::Escalate,
+            0,
+            Duration::from_secs(60),
+        );
+        set_supervisor_policy_blocking(&ae_instance_id, &sup_instance_id, &sup_chnl, policy);
+
+        let (_, c1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Crasher::new("crasher1")),
+            &ae_instance_id,
+            &sup_instance_id,
+            &sup_chnl.receiver,
+        );
+
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &sup_instance_id,
+            &sup_chnl.receiver,
+        );
+        let (_, s2_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server2")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        // Crashing c1 stops all of sup's actors, even with OneForOne
+        send_echo_req(&c1_instance_id, &sup_instance_id, 0);
+        let msg_any = sup_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorFailed>().unwrap();
+        assert_eq!(msg.instance_id, c1_instance_id);
+        for instance_id in [c1_instance_id, s1_instance_id] {
+            let msg_any = sup_chnl.receiver.recv().unwrap();
+            let msg = msg_any.downcast_ref::<ActorStopped>().unwrap();
+            assert_eq!(msg.instance_id, instance_id);
+        }
+
+        // s2 has another supervisor so it and the AE are still running
+        send_echo_req(&s2_instance_id, &supervisor_instance_id, 1);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 1);
+
+        sender_map_get(&ae_instance_id)
+            .unwrap()
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_supervisor_escalate:-");
+    }
+
+    #[test]
+    fn test_spawn_actor() {
+        println!("\ntest_spawn_actor:+");
+
+        let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+        actor_factory_insert(&SERVER_ACTOR_ID, Server::factory);
+
+        // Spawn a server and verify it works
+        ae_sender
+            .send(Box::new(ReqSpawnActor::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &SERVER_ACTOR_ID,
+                "server1",
+                "",
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspSpawnActor>().unwrap();
+        println!("test_spawn_actor: recv {msg:?}");
+        assert_eq!(msg.status, RspSpawnActorStatus::Success);
+        assert_eq!(msg.actor_id, SERVER_ACTOR_ID);
+        let s1_instance_id = msg.actor_instance_id;
+
+        // The server registered with ConMgr and is ready
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<CmdReady>().unwrap();
+        println!("test_spawn_actor: recv {msg:?}");
+        assert_eq!(msg.src_id(), &s1_instance_id);
+        assert_eq!(msg.status, CmdReadyStatus::Ready);
+
+        send_echo_req(&s1_instance_id, &supervisor_instance_id, 1);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 1);
+
+        // Spawning an actor without a factory fails
+        let unknown_actor_id = AnId::new();
+        ae_sender
+            .send(Box::new(ReqSpawnActor::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &unknown_actor_id,
+                "unknown",
+                "",
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspSpawnActor>().unwrap();
+        println!("test_spawn_actor: recv {msg:?}");
+        assert_eq!(msg.status, RspSpawnActorStatus::FactoryNotFound);
+        assert_eq!(msg.actor_instance_id, AnId::nil());
+
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_spawn_actor:-");
+    }
+
+    #[test]
+    fn test_shutdown() {
+        println!("\ntest_shutdown:+");
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        ae_sender
+            .send(Box::new(ReqShutdown::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                Duration::from_secs(5),
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspShutdown>().unwrap();
+        println!("test_shutdown: recv {msg:?}");
+
+        // ConMgr is shutdown last
+        assert_eq!(msg.summary.acked, vec![s1_instance_id, con_mgr_instance_id]);
+        assert!(msg.summary.timed_out.is_empty());
+        assert_eq!(msg.summary.unregistered, 1);
+        ae_join_handle.join().unwrap();
+
+        println!("test_shutdown:-");
+    }
+
+    #[test]
+    fn test_cmd_done_shutdown_summary() {
+        println!("\ntest_cmd_done_shutdown_summary:+");
+
+        let supervisor_instance_id = AnId::new();
+        let supervisor_chnl = ActorChannel::new("supervisor", &supervisor_instance_id);
+        sender_map_insert(&supervisor_instance_id, &supervisor_chnl.sender);
+        let issuer_instance_id = AnId::new();
+        let issuer_chnl = ActorChannel::new("issuer", &issuer_instance_id);
+        sender_map_insert(&issuer_instance_id, &issuer_chnl.sender);
+        let con_mgr = Box::new(ConMgr::new("con_mgr"));
+        let con_mgr_instance_id = *con_mgr.get_instance_id();
+
+        let options = ActorExecutorOptions {
+            supervisor_instance_id: Some(supervisor_instance_id),
+            ..Default::default()
+        };
+        let (ae_join_handle, ae_instance_id) =
+            ActorExecutor::start_with_options("ae", &con_mgr_instance_id, options);
+        add_actor_to_actor_executor_blocking(
+            con_mgr,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        sender_map_get(&ae_instance_id)
+            .unwrap()
+            .send(Box::new(CmdDone::new(&ae_instance_id, &issuer_instance_id)))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        // Both the issuer of CmdDone and the supervisor get the summary
+        for receiver in [&issuer_chnl.receiver, &supervisor_chnl.receiver] {
+            let msg_any = receiver.recv().unwrap();
+            let msg = msg_any.downcast_ref::<RspShutdown>().unwrap();
+            assert_eq!(msg.summary.acked, vec![s1_instance_id, con_mgr_instance_id]);
+            assert!(msg.summary.timed_out.is_empty());
+        }
+
+        println!("test_cmd_done_shutdown_summary:-");
+    }
+
+    #[test]
+    fn test_shutdown_timeout() {
+        println!("\ntest_shutdown_timeout:+");
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        // Crasher ignores CmdShutdown
+        let (_, c1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Crasher::new("crasher1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        ae_sender
+            .send(Box::new(ReqShutdown::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                Duration::from_millis(200),
+            )))
+            .unwrap();
+
+        // Actors aren't added once shutdown has started
+        ae_sender
+            .send(Box::new(ReqAddActor::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                Box::new(Server::new("server2")),
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspAddActor>().unwrap();
+        assert_eq!(msg.status, RspAddActorStatus::ShuttingDown);
+        assert!(sender_map_get(&msg.actor_instance_id).is_none());
+
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspShutdown>().unwrap();
+        println!("test_shutdown_timeout: recv {msg:?}");
+
+        // ConMgr is never shutdown because crasher1 didn't acknowledge
+        assert_eq!(msg.summary.acked, vec![s1_instance_id]);
+        assert_eq!(
+            msg.summary.timed_out,
+            vec![c1_instance_id, con_mgr_instance_id]
+        );
+        assert_eq!(msg.summary.unregistered, 1);
+        ae_join_handle.join().unwrap();
+
+        println!("test_shutdown_timeout:-");
+    }
+
+    #[test]
+    fn test_trace_continued() {
+        println!("\ntest_trace_continued:+");
+
+        let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        // Not traced
+        send_echo_req(&s1_instance_id, &supervisor_instance_id, 1);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(MsgHeader::get_trace_from_boxed_msg_any(&msg_any).is_none());
+
+        // The response continues the requests trace
+        let root = TraceContext::new_root();
+        let mut msg = EchoReq::new(&s1_instance_id, &supervisor_instance_id, 2);
+        msg.header.trace = Some(root);
+        sender_map_get(&s1_instance_id)
+            .unwrap()
+            .send(Box::new(msg))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<EchoRsp>().unwrap();
+        println!("test_trace_continued: recv {msg:?}");
+        let trace = msg.header.trace.unwrap();
+        assert_eq!(trace.trace_id, root.trace_id);
+        assert_ne!(trace.span_id, root.span_id);
+
+        // Messages sent with an ActorSender, rather than the Context,
+        // continue the trace too
+        let span = enter_span(
+            Some(&root),
+            "dispatch",
+            "test_trace_continued",
+            &supervisor_instance_id,
+            &AnId::new(),
+        )
+        .unwrap();
+        send_echo_req(&s1_instance_id, &supervisor_instance_id, 3);
+        drop(span);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<EchoRsp>().unwrap();
+        assert_eq!(msg.counter, 3);
+        assert_eq!(msg.header.trace.unwrap().trace_id, root.trace_id);
+
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_trace_continued:-");
+    }
+
+    #[test]
+    fn test_sender_cache() {
+        let sender_cache = SenderCache::default();
+        let instance_id = AnId::new();
+        let chnl = ActorChannel::new("test_sender_cache", &instance_id);
+        sender_map_insert(&instance_id, &chnl.sender);
+
+        sender_cache.send(&instance_id, Box::new(1)).unwrap();
+        assert_eq!(
+            chnl.receiver.recv().unwrap().downcast_ref::<i32>(),
+            Some(&1)
+        );
+
+        // The cached sender is dropped once it's removed from the sender_map
+        sender_map_remove(&instance_id);
+        assert!(sender_cache.send(&instance_id, Box::new(2)).is_err());
+        assert!(sender_cache.with_sender(&instance_id, |_| ()).is_err());
+
+        // The cache doesn't grow beyond its capacity
+        let instance_ids: Vec<AnId> = (0..SENDER_CACHE_CAPACITY * 2)
+            .map(|_| AnId::new())
+            .collect();
+        for instance_id in instance_ids.iter() {
+            sender_map_insert(instance_id, &chnl.sender);
+            sender_cache.with_sender(instance_id, |_| ()).unwrap();
+            assert!(sender_cache.senders.borrow().len() <= SENDER_CACHE_CAPACITY);
+        }
+        for instance_id in instance_ids.iter() {
+            sender_map_remove(instance_id);
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        println!("\ntest_metrics:+");
+
+        let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        // A message the server didn't declare, which is rejected, followed
+        // by echos, the responses guarantee all of them have been processed.
+        sender_map_get(&s1_instance_id)
+            .unwrap()
+            .send(Box::new(MetricsReq::new(
+                &s1_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        for counter in 1..=3 {
+            send_echo_req(&s1_instance_id, &supervisor_instance_id, counter);
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            assert!(msg_any.downcast_ref::<EchoRsp>().is_some());
+        }
+
+        ae_sender
+            .send(Box::new(MetricsReq::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<MetricsRsp>().unwrap();
+        println!("test_metrics: recv {msg:?}");
+        let ae_metrics = msg
+            .snapshot
+            .executors
+            .iter()
+            .find(|e| e.instance_id == ae_instance_id)
+            .unwrap();
+        assert_eq!(ae_metrics.active_actors, 2);
+        assert!(ae_metrics.select_wakeups >= 6);
+        let s1_metrics = ae_metrics
+            .actors
+            .iter()
+            .find(|a| a.instance_id == s1_instance_id)
+            .unwrap();
+        // CmdInit and the EchoReq's, plus ConMgrRegisterActorRsp if it
+        // has arrived, the MetricsReq went to the dead letters
+        assert!(s1_metrics.msgs_processed >= 4);
+        assert_eq!(s1_metrics.unknown_msgs, 0);
+        assert_eq!(s1_metrics.processing_time.count, s1_metrics.msgs_processed);
+        assert_eq!(s1_metrics.mailbox_depth, 0);
+
+        // A removed actor's metrics are removed and its slot is reused
+        ae_sender
+            .send(Box::new(AdminRemoveActorReq::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &s1_instance_id,
+            )))
+            .unwrap();
+        loop {
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            if let Some(msg) = msg_any.downcast_ref::<AdminRemoveActorRsp>() {
+                assert_eq!(msg.status, AdminRemoveActorStatus::Success);
+                break;
+            }
+        }
+        let (_, s2_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server2")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        send_echo_req(&s2_instance_id, &supervisor_instance_id, 1);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<EchoRsp>().is_some());
+        ae_sender
+            .send(Box::new(MetricsReq::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<MetricsRsp>().unwrap();
+        let ae_metrics = msg
+            .snapshot
+            .executors
+            .iter()
+            .find(|e| e.instance_id == ae_instance_id)
+            .unwrap();
+        assert_eq!(ae_metrics.active_actors, 2);
+        assert_eq!(ae_metrics.actors.len(), 2);
+        assert!(!ae_metrics
+            .actors
+            .iter()
+            .any(|a| a.instance_id == s1_instance_id));
+        assert!(ae_metrics
+            .actors
+            .iter()
+            .any(|a| a.instance_id == s2_instance_id));
+
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_metrics:-");
+    }
+
+    #[test]
+    fn test_tap() {
+        println!("\ntest_tap:+");
+
+        let (supervisor_instance_id, supervisor_chnl, ae_join_handle, ae_instance_id, _) =
+            initialize_supervisor_con_mgr_actor_executor_blocking();
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        // The supervisor observes the messages to server1
+        ae_sender
+            .send(Box::new(TapReq::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                TapFilter::Instance(s1_instance_id),
+                1,
+                None,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<TapRsp>().unwrap();
+        assert_eq!(msg.status, TapRspStatus::Success);
+        let tap_id = msg.tap_id;
+
+        // The EchoReq is tapped before server1 processes it, server1's
+        // registration messages may also be tapped if they're late
+        send_echo_req(&s1_instance_id, &supervisor_instance_id, 1);
+        let mut tapped_echo_req = false;
+        loop {
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            if msg_any.downcast_ref::<EchoRsp>().is_some() {
+                break;
+            }
+            let msg = msg_any.downcast_ref::<TapMsg>().unwrap();
+            println!("test_tap: recv {msg:?}");
+            assert_eq!(msg.tap_id, tap_id);
+            assert_eq!(msg.point, TapPoint::Executor(ae_instance_id));
+            assert!(
+                msg.msg_header.dst_id == s1_instance_id || msg.msg_header.src_id == s1_instance_id
+            );
+            tapped_echo_req |= msg.msg_header.msg_id == ECHO_REQ_ID;
+        }
+        assert!(tapped_echo_req);
+
+        ae_sender
+            .send(Box::new(TapDetachReq::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &tap_id,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<TapDetachRsp>().unwrap();
+        assert_eq!(msg.status, TapDetachRspStatus::Success);
+
+        // Detached so only the EchoRsp is received
+        send_echo_req(&s1_instance_id, &supervisor_instance_id, 2);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<EchoRsp>().is_some());
+
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+
+        println!("test_tap:-");
+    }
+
+    #[test]
+    fn test_record_replay() {
+        println!("\ntest_record_replay:+");
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        // Record a server running in a second ActorExecutor
+        let path =
+            std::env::temp_dir().join(format!("ae_test_record_replay-{}.rec", std::process::id()));
+        let recorder = Arc::new(Recorder::create(&path).unwrap());
+        recorder.add_msg_id_to_serde_json_buf(ECHO_REQ_ID, EchoReq::to_serde_json_buf_ref);
+        recorder.add_msg_id_to_serde_json_buf(ECHO_RSP_ID, EchoRsp::to_serde_json_buf_ref);
+        let (ae2_join_handle, ae2_instance_id) = ActorExecutor::start_with_recorder(
+            "ae2",
+            &con_mgr_instance_id,
+            Some(Arc::clone(&recorder)),
+        );
+        let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+            Box::new(Server::new("server1")),
+            &ae2_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        for counter in 1..=3 {
+            send_echo_req(&s1_instance_id, &supervisor_instance_id, counter);
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            assert!(msg_any.downcast_ref::<EchoRsp>().is_some());
+        }
+        for (instance_id, join_handle) in [
+            (ae2_instance_id, ae2_join_handle),
+            (ae_instance_id, ae_join_handle),
+        ] {
+            sender_map_get(&instance_id)
+                .unwrap()
+                .send(Box::new(CmdDone::new(
+                    &instance_id,
+                    &supervisor_instance_id,
+                )))
+                .unwrap();
+            join_handle.join().unwrap();
+        }
+
+        // Replay into a new server, only the response timestamps differ
+        let frames = read_recording(&path).unwrap();
+        assert_eq!(frames.len(), 6);
+        let mut replayer = Replayer::new(ReplaySpeed::Unpaced);
+        replayer.add_msg_id(
+            ECHO_REQ_ID,
+            EchoReq::from_serde_json_buf,
+            EchoReq::to_serde_json_buf_ref,
+        );
+        replayer.add_msg_id(
+            ECHO_RSP_ID,
+            EchoRsp::from_serde_json_buf,
+            EchoRsp::to_serde_json_buf_ref,
+        );
+        replayer.ignore_field("/rsp_timestamp_ns");
+        let report = replayer.replay(&mut Server::new("server2"), &frames, &s1_instance_id);
+        println!("test_record_replay: {report}");
+        assert_eq!(report.replayed, 3);
+        assert_eq!(report.matched, 3);
+        assert!(report.is_ok());
+        std::fs::remove_file(&path).unwrap();
+
+        println!("test_record_replay:-");
+    }
 }
@@ -0,0 +1,918 @@
+//! An executor running its actors on a tokio runtime, enabled by the
+//! `tokio` feature.
+//!
+//! Each actor is a task, so the actors of all of the executors share the
+//! runtime's thread pool rather than each ActorExecutor having a thread.
+//! The actors keep their crossbeam channels and are unchanged, a task is
+//! woken by the wakeup of its actor's channel.
+//!
+//! The executor's messages, supervision, metrics, taps and recording are
+//! those of ActorExecutor, see [`Executor`]. A task reports the end of its
+//! actor, failed or done, to the executor which then applies the
+//! supervisor_policy or stops it. Actors can be moved here with
+//! ReqMoveActor but not away, a task owns its actor.
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
+    sync::{atomic::Ordering, Arc},
+    time::Instant,
+};
+
+use actor::Actor;
+use actor_channel::{observe_sends, send_hook_set, ActorChannel, ActorSender};
+use admin_protocol::AdminActorInfo;
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use dead_letters::dead_letter_post;
+use msg_header::MsgHeader;
+use msg_replay::{Direction, Recorder};
+use msg_tap::tap_observe;
+use msg_trace::{continue_trace, enter_span};
+use req_add_actor::ReqAddActor;
+use req_move_actor::ReqMoveActor;
+use rsp_add_actor::{RspAddActor, RspAddActorStatus};
+use rsp_move_actor::RspMoveActorStatus;
+use runtime_metrics::{
+    executor_metrics_register, executor_metrics_unregister, ActorCounters, ExecutorCounters,
+};
+use sender_map_by_instance_id::{
+    sender_map_get, sender_map_insert, sender_map_remove, sender_map_replace,
+};
+use supervisor_protocol::SupervisorPolicy;
+use tap_protocol::TapPoint;
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc, Notify},
+    task::JoinHandle,
+};
+
+use super::{
+    dispatch_span,
+    executor::{Executor, RemovedActor, Shutdown, Supervision},
+    panic_reason, receive_msg_ids, send_msg, send_rsp_move_actor, ActorExecutorOptions, Context,
+    ProtocolEnforcement, SenderCache,
+};
+
+/// Number of messages an actor's task or the executor handles before
+/// yielding, so a busy actor doesn't starve the others on its worker
+const MSGS_PER_YIELD: usize = 64;
+
+/// Notified when a message is sent on chnl
+fn channel_notify(chnl: &ActorChannel) -> Arc<Notify> {
+    chnl.tokio_notify().unwrap_or_else(|| {
+        panic!(
+            "channel_notify: {} already has a wakeup, is it run by another executor?",
+            chnl.sender.name
+        )
+    })
+}
+
+// An actor running as a task
+#[derive(Debug)]
+struct ActorTask {
+    name: String,
+    actor_id: AnId,
+    chnl: ActorChannel,
+    supervision: Supervision,
+    task: JoinHandle<()>,
+}
+
+// Why an actor's task ended
+#[derive(Debug)]
+enum TaskEnd {
+    Failed(String),
+    Done,
+}
+
+// Sent by an actor's task to the executor when it ends
+#[derive(Debug)]
+struct TaskEnded {
+    instance_id: AnId,
+    end: TaskEnd,
+}
+
+#[derive(Debug)]
+pub struct TokioActorExecutor {
+    pub name: String,
+    pub instance_id: AnId,
+    pub supervisor_policy: SupervisorPolicy,
+    chnl: ActorChannel,
+    con_mgr_instance_id: AnId,
+    con_mgr_tx: ActorSender,
+    runtime: Handle,
+    protocol_enforcement: ProtocolEnforcement,
+    actors: HashMap<AnId, ActorTask>,
+    metrics: Arc<ExecutorCounters>,
+    recorder: Option<Arc<Recorder>>, // Records the messages our actors receive and send
+    ended_tx: mpsc::UnboundedSender<TaskEnded>,
+    supervisor_instance_id: Option<AnId>, // Sent the summary of every shutdown
+    shutdown: Option<Shutdown>,
+    done: bool,
+}
+
+impl TokioActorExecutor {
+    /// Start an executor on runtime, it is added to the sender_map so
+    /// actors are added with ReqAddActor or ReqSpawnActor as usual.
+    ///
+    /// Returns the executor's task and its instance_id, panics if two of
+    /// the ids registered with id_inventory collide.
+    pub fn start(
+        name: &str,
+        con_mgr_instance_id: &AnId,
+        runtime: &Handle,
+    ) -> (JoinHandle<()>, AnId) {
+        Self::start_with_options(
+            name,
+            con_mgr_instance_id,
+            runtime,
+            ActorExecutorOptions::default(),
+        )
+    }
+
+    /// Start an executor whose actors are sent only the messages their
+    /// ProtocolSet receives if protocol_enforcement is Strict, see start.
+    pub fn start_with_protocol_enforcement(
+        name: &str,
+        con_mgr_instance_id: &AnId,
+        runtime: &Handle,
+        protocol_enforcement: ProtocolEnforcement,
+    ) -> (JoinHandle<()>, AnId) {
+        let options = ActorExecutorOptions {
+            protocol_enforcement,
+            ..Default::default()
+        };
+        Self::start_with_options(name, con_mgr_instance_id, runtime, options)
+    }
+
+    /// Start an executor with options, see start.
+    pub fn start_with_options(
+        name: &str,
+        con_mgr_instance_id: &AnId,
+        runtime: &Handle,
+        options: ActorExecutorOptions,
+    ) -> (JoinHandle<()>, AnId) {
+        // Two messages, protocols or actors sharing an id would be confused
+        // by downcasting and dispatch, so refuse to start if there are any
+        if let Err(collisions) = id_inventory::validate_ids_once() {
+            let collisions: Vec<String> = collisions.iter().map(|c| c.to_string()).collect();
+            panic!("AE:{name}::start: {}", collisions.join(" "));
+        }
+
+        // Messages our actors send continue the trace of the message being
+        // processed however they send them
+        send_hook_set(continue_trace);
+
+        let instance_id = AnId::new();
+        let chnl = ActorChannel::new(name, &instance_id);
+        let notify = channel_notify(&chnl);
+        sender_map_insert(&instance_id, &chnl.sender);
+        let (ended_tx, ended_rx) = mpsc::unbounded_channel();
+
+        let ae = Self {
+            name: name.to_owned(),
+            instance_id,
+            supervisor_policy: SupervisorPolicy::default(),
+            chnl,
+            con_mgr_instance_id: *con_mgr_instance_id,
+            con_mgr_tx: sender_map_get(con_mgr_instance_id).unwrap(),
+            runtime: runtime.clone(),
+            protocol_enforcement: options.protocol_enforcement,
+            actors: HashMap::new(),
+            metrics: executor_metrics_register(name, &instance_id),
+            recorder: options.recorder,
+            ended_tx,
+            supervisor_instance_id: options.supervisor_instance_id,
+            shutdown: None,
+            done: false,
+        };
+        let join_handle = runtime.spawn(ae.run(notify, ended_rx));
+
+        (join_handle, instance_id)
+    }
+
+    async fn run(mut self, notify: Arc<Notify>, mut ended_rx: mpsc::UnboundedReceiver<TaskEnded>) {
+        log::debug!("TAE:{}:+", self.name);
+        while !self.done {
+            let mut handled = 0;
+            while let Ok(msg_any) = self.chnl.receiver.rx.try_recv() {
+                self.handle_msg(msg_any);
+                if self.done {
+                    break;
+                }
+                handled += 1;
+                if handled % MSGS_PER_YIELD == 0 {
+                    tokio::task::yield_now().await;
+                }
+            }
+            if self.done {
+                break;
+            }
+
+            let deadline = self
+                .shutdown
+                .as_ref()
+                .map(|s| tokio::time::Instant::from_std(s.deadline));
+            tokio::select! {
+                _ = notify.notified() => (),
+                Some(ended) = ended_rx.recv() => self.task_ended(ended),
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)),
+                    if deadline.is_some() => {
+                    log::warn!("TAE:{}: shutdown timed out", self.name);
+                    self.finish_shutdown();
+                }
+            }
+            self.metrics.select_wakeups.fetch_add(1, Ordering::Relaxed);
+        }
+
+        for actor_task in self.actors.values() {
+            actor_task.task.abort();
+        }
+        executor_metrics_unregister(&self.instance_id);
+        if let Some(Err(why)) = self.recorder.as_ref().map(|r| r.flush()) {
+            log::warn!("TAE:{}: error flushing recorder: {why}", self.name);
+        }
+        log::debug!("TAE:{}:-", self.name);
+    }
+
+    // An actor's task ended, ignored if the actor was already removed
+    fn task_ended(&mut self, ended: TaskEnded) {
+        match ended.end {
+            TaskEnd::Failed(reason) => self.actor_failed(&ended.instance_id, &reason),
+            TaskEnd::Done => self.stop_actor(&ended.instance_id),
+        }
+    }
+
+    fn handle_msg(&mut self, msg_any: BoxMsgAny) {
+        log::trace!("TAE:{}: msg_any={msg_any:?}", self.name);
+
+        if msg_any.downcast_ref::<ReqAddActor>().is_some() {
+            let msg = msg_any.downcast::<ReqAddActor>().unwrap();
+            log::debug!("TAE:{}: msg={msg:?}", self.name);
+            let requester_instance_id = *msg.src_id();
+            let actor_id = *msg.actor.get_actor_id();
+            let actor_instance_id = *msg.actor.get_instance_id();
+            let status = if self.shutdown.is_some() {
+                log::warn!(
+                    "TAE:{}: shutting down, dropping {}",
+                    self.name,
+                    msg.actor.get_name()
+                );
+                sender_map_remove(&actor_instance_id);
+                RspAddActorStatus::ShuttingDown
+            } else {
+                self.insert_actor(msg.actor, Supervision::new(&requester_instance_id, ""));
+                RspAddActorStatus::Success
+            };
+            send_msg(
+                &requester_instance_id,
+                Box::new(RspAddActor::new(
+                    &requester_instance_id,
+                    &self.instance_id,
+                    &actor_id,
+                    &actor_instance_id,
+                    status,
+                )),
+            );
+            self.send_cmd_init(&actor_instance_id);
+        } else if msg_any.downcast_ref::<ReqMoveActor>().is_some() {
+            let msg = msg_any.downcast::<ReqMoveActor>().unwrap();
+            log::debug!("TAE:{}: msg={msg:?}", self.name);
+            self.move_actor(*msg);
+        } else if let Some(msg_any) = self.handle_executor_msg(msg_any) {
+            log::warn!(
+                "TAE:{}: Unknown msg_id={:?}",
+                self.name,
+                MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any)
+            );
+        }
+    }
+
+    // We can only be the destination of a move, the source AE initialized
+    // the actor so there is no CmdInit and the requester becomes its
+    // supervisor. Its sender is replaced so cached senders are dropped.
+    fn move_actor(&mut self, msg: ReqMoveActor) {
+        let requester_instance_id = msg.requester_instance_id;
+        let actor_instance_id = msg.actor_instance_id;
+        let status = match msg.actor {
+            Some(actor) => {
+                sender_map_replace(&actor_instance_id, &actor.get_chnl().sender);
+                self.insert_actor(actor, Supervision::new(&requester_instance_id, ""));
+                RspMoveActorStatus::Success
+            }
+            None => {
+                log::warn!(
+                    "TAE:{}: can't move {actor_instance_id}, actors aren't moved off a TokioActorExecutor",
+                    self.name
+                );
+                RspMoveActorStatus::ActorNotFound
+            }
+        };
+        send_rsp_move_actor(
+            &requester_instance_id,
+            &self.instance_id,
+            &actor_instance_id,
+            status,
+        );
+    }
+}
+
+impl Executor for TokioActorExecutor {
+    const LOG_TAG: &'static str = "TAE";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn instance_id(&self) -> &AnId {
+        &self.instance_id
+    }
+
+    fn con_mgr_instance_id(&self) -> &AnId {
+        &self.con_mgr_instance_id
+    }
+
+    fn con_mgr_tx(&self) -> &ActorSender {
+        &self.con_mgr_tx
+    }
+
+    fn supervisor_instance_id(&self) -> Option<&AnId> {
+        self.supervisor_instance_id.as_ref()
+    }
+
+    fn supervisor_policy(&self) -> &SupervisorPolicy {
+        &self.supervisor_policy
+    }
+
+    fn set_supervisor_policy(&mut self, policy: SupervisorPolicy) {
+        self.supervisor_policy = policy;
+    }
+
+    fn shutdown_mut(&mut self) -> &mut Option<Shutdown> {
+        &mut self.shutdown
+    }
+
+    fn set_done(&mut self) {
+        self.done = true;
+    }
+
+    fn actor_instance_ids(&self) -> Vec<AnId> {
+        self.actors.keys().copied().collect()
+    }
+
+    fn send_to_actor(
+        &self,
+        instance_id: &AnId,
+        msg: BoxMsgAny,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(actor_task) = self.actors.get(instance_id) else {
+            return Err(format!("not running instance_id={instance_id}").into());
+        };
+        actor_task.chnl.sender.send(msg)
+    }
+
+    fn actor_name_and_id(&self, instance_id: &AnId) -> Option<(&str, &AnId)> {
+        let actor_task = self.actors.get(instance_id)?;
+        Some((&actor_task.name, &actor_task.actor_id))
+    }
+
+    fn supervision(&self, instance_id: &AnId) -> Option<&Supervision> {
+        Some(&self.actors.get(instance_id)?.supervision)
+    }
+
+    fn supervision_mut(&mut self, instance_id: &AnId) -> Option<&mut Supervision> {
+        Some(&mut self.actors.get_mut(instance_id)?.supervision)
+    }
+
+    // Start a task running actor
+    fn insert_actor(&mut self, actor: Box<dyn Actor>, supervision: Supervision) {
+        let instance_id = *actor.get_instance_id();
+        let chnl = actor.get_chnl().clone();
+        let notify = channel_notify(&chnl);
+        let name = actor.get_name().to_owned();
+        let actor_id = *actor.get_actor_id();
+        let counters = self
+            .metrics
+            .actor_add(&name, &instance_id, &chnl.receiver.rx);
+        let env = TaskEnv {
+            ae_name: self.name.clone(),
+            ae_instance_id: self.instance_id,
+            ae_sndr: self.chnl.sender.clone(),
+            con_mgr_sndr: self.con_mgr_tx.clone(),
+            protocol_enforcement: self.protocol_enforcement,
+            counters,
+            recorder: self.recorder.clone(),
+            ended_tx: self.ended_tx.clone(),
+        };
+        let task = self.runtime.spawn(run_actor(actor, notify, env));
+        self.actors.insert(
+            instance_id,
+            ActorTask {
+                name,
+                actor_id,
+                chnl,
+                supervision,
+                task,
+            },
+        );
+    }
+
+    // The task is aborted, the actor is dropped when it next yields
+    fn take_actor(&mut self, instance_id: &AnId) -> Option<RemovedActor> {
+        let actor_task = self.actors.remove(instance_id)?;
+        actor_task.task.abort();
+        self.metrics.actor_remove(instance_id);
+        Some(RemovedActor {
+            name: actor_task.name,
+            actor_id: actor_task.actor_id,
+            supervision: actor_task.supervision,
+            receiver: actor_task.chnl.receiver,
+        })
+    }
+
+    // The actors are owned by their tasks so their state isn't reported
+    fn admin_actor_infos(&self) -> Vec<AdminActorInfo> {
+        self.actors
+            .iter()
+            .map(|(instance_id, actor_task)| AdminActorInfo {
+                name: actor_task.name.clone(),
+                actor_id: actor_task.actor_id,
+                instance_id: *instance_id,
+                state: String::new(),
+            })
+            .collect()
+    }
+}
+
+// What an actor's task uses of the executor that started it
+struct TaskEnv {
+    ae_name: String,
+    ae_instance_id: AnId,
+    ae_sndr: ActorSender,
+    con_mgr_sndr: ActorSender,
+    protocol_enforcement: ProtocolEnforcement,
+    counters: Arc<ActorCounters>,
+    recorder: Option<Arc<Recorder>>,
+    ended_tx: mpsc::UnboundedSender<TaskEnded>,
+}
+
+// Process the messages sent to actor until it is done or fails, then
+// tell the executor
+async fn run_actor(mut actor: Box<dyn Actor>, notify: Arc<Notify>, env: TaskEnv) {
+    log::debug!("TAE:{}: {}:+", env.ae_name, actor.get_name());
+    let chnl = actor.get_chnl().clone();
+    let sender_cache = SenderCache::default();
+    let receives = receive_msg_ids(actor.as_ref());
+    let end = 'run: loop {
+        let mut handled = 0;
+        while let Ok(msg_any) = chnl.receiver.rx.try_recv() {
+            if let Some(end) = dispatch(&mut actor, msg_any, &env, &sender_cache, &receives) {
+                break 'run end;
+            }
+            handled += 1;
+            if handled % MSGS_PER_YIELD == 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+        notify.notified().await;
+    };
+    log::debug!("TAE:{}: {}:- {end:?}", env.ae_name, actor.get_name());
+    let ended = TaskEnded {
+        instance_id: *actor.get_instance_id(),
+        end,
+    };
+    if env.ended_tx.send(ended).is_err() {
+        log::debug!("TAE:{}: executor has stopped", env.ae_name);
+    }
+}
+
+// Have actor process msg_any, Some if it failed or is done
+fn dispatch(
+    actor: &mut Box<dyn Actor>,
+    msg_any: BoxMsgAny,
+    env: &TaskEnv,
+    sender_cache: &SenderCache,
+    receives: &Option<HashSet<AnId>>,
+) -> Option<TaskEnd> {
+    let ae_name = &env.ae_name;
+    tap_observe(&TapPoint::Executor(env.ae_instance_id), &msg_any);
+    if let Some(recorder) = &env.recorder {
+        if let Err(why) = recorder.record_msg(Direction::Inbound, &msg_any) {
+            log::warn!("TAE:{ae_name}: error recording: {why}");
+        }
+    }
+    let msg_id = *MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
+    let undeclared = receives
+        .as_ref()
+        .is_some_and(|receives| !receives.contains(&msg_id));
+    if undeclared {
+        if let Some(ps) = actor.get_protocol_set() {
+            let reason = format!(
+                "{} didn't declare msg_id={msg_id} in {}",
+                actor.get_name(),
+                ps.name
+            );
+            match env.protocol_enforcement {
+                ProtocolEnforcement::Permissive => {
+                    log::warn!("TAE:{ae_name}: {reason}");
+                }
+                ProtocolEnforcement::Strict => {
+                    log::error!("TAE:{ae_name}: {reason}, rejected");
+                    dead_letter_post(msg_any, &reason);
+                    return None;
+                }
+            }
+        }
+    }
+
+    // Continue the messages trace, if it has one, while it's processed
+    let trace = MsgHeader::get_trace_from_boxed_msg_any(&msg_any);
+    let span = dispatch_span(actor.as_ref(), &msg_id, trace);
+    let _enter = span.enter();
+    let trace_span = enter_span(
+        trace,
+        "dispatch",
+        actor.get_name(),
+        actor.get_instance_id(),
+        &msg_id,
+    );
+    let context = Context {
+        ae_sndr: &env.ae_sndr,
+        con_mgr_sndr: &env.con_mgr_sndr,
+        dst_id: *MsgHeader::get_src_id_from_boxed_msg_any(&msg_any),
+        sender_cache,
+        unknown_msgs: Cell::new(0),
+    };
+
+    // Record what the actor sends however it sends it
+    let _send_observer = env
+        .recorder
+        .as_ref()
+        .map(|r| observe_sends(Recorder::outbound_observer(r)));
+
+    // Isolate panics to the actor so the other actors keep running
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        actor.process_msg_any(&context, msg_any)
+    }));
+    env.counters
+        .processed(start.elapsed(), context.unknown_msgs.get());
+    drop(trace_span);
+    match result {
+        Err(payload) => Some(TaskEnd::Failed(panic_reason(&*payload))),
+        Ok(_) if actor.done() => Some(TaskEnd::Done),
+        Ok(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        tests::{send_echo_req, set_supervisor_policy_blocking, Crasher, CRASHER_ACTOR_ID},
+        ActorExecutor,
+    };
+    use actor_channel::ActorReceiver;
+    use actor_factory::actor_factory_insert;
+    use cmd_done_issuer_protocol::CmdDone;
+    use cmd_init_issuer_protocol::{CmdReady, CmdReadyStatus};
+    use con_mgr::ConMgr;
+    use echo_requestee_protocol::{EchoReq, EchoRsp};
+    use metrics_protocol::{MetricsReq, MetricsRsp};
+    use req_shutdown::ReqShutdown;
+    use rsp_move_actor::RspMoveActor;
+    use rsp_shutdown::RspShutdown;
+    use server::Server;
+    use std::time::Duration;
+    use supervisor_protocol::{
+        ActorFailed, ActorRestarted, ActorStopped, SupervisorDirective, SupervisorStrategy,
+    };
+
+    // Start a TokioActorExecutor on runtime with ConMgr added to it
+    //
+    // Returns the supervisor_instance_id and channel, the executor's task,
+    // its instance_id and the instance_id of ConMgr
+    fn start_supervisor_con_mgr_executor(
+        runtime: &Handle,
+    ) -> (AnId, ActorChannel, JoinHandle<()>, AnId, AnId) {
+        let supervisor_instance_id = AnId::new();
+        let supervisor_chnl = ActorChannel::new("supervisor", &supervisor_instance_id);
+        sender_map_insert(&supervisor_instance_id, &supervisor_chnl.sender);
+
+        let con_mgr = Box::new(ConMgr::new("con_mgr"));
+        let con_mgr_instance_id = *con_mgr.get_instance_id();
+        let (join_handle, ae_instance_id) =
+            TokioActorExecutor::start("tae", &con_mgr_instance_id, runtime);
+        add_actor_blocking(
+            con_mgr,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        (
+            supervisor_instance_id,
+            supervisor_chnl,
+            join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        )
+    }
+
+    fn add_actor_blocking(
+        actor: Box<dyn Actor>,
+        ae_instance_id: &AnId,
+        supervisor_instance_id: &AnId,
+        supervisor_receiver: &ActorReceiver,
+    ) -> AnId {
+        let (_, instance_id) = super::super::add_actor_to_actor_executor_blocking(
+            actor,
+            ae_instance_id,
+            supervisor_instance_id,
+            supervisor_receiver,
+        );
+        instance_id
+    }
+
+    #[test]
+    fn test_tokio_executor_echo_and_shutdown() {
+        println!("\ntest_tokio_executor_echo_and_shutdown:+");
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let (supervisor_instance_id, supervisor_chnl, join_handle, ae_instance_id, con_mgr_iid) =
+            start_supervisor_con_mgr_executor(runtime.handle());
+
+        // Many more actors than threads
+        const SERVERS: usize = 100;
+        let server_iids: Vec<AnId> = (0..SERVERS)
+            .map(|i| {
+                add_actor_blocking(
+                    Box::new(Server::new(&format!("server{i}"))),
+                    &ae_instance_id,
+                    &supervisor_instance_id,
+                    &supervisor_chnl.receiver,
+                )
+            })
+            .collect();
+
+        for (counter, server_iid) in server_iids.iter().enumerate() {
+            sender_map_get(server_iid)
+                .unwrap()
+                .send(Box::new(EchoReq::new(
+                    server_iid,
+                    &supervisor_instance_id,
+                    counter as u64,
+                )))
+                .unwrap();
+        }
+        let mut counters: Vec<u64> = (0..SERVERS)
+            .map(|_| {
+                let msg_any = supervisor_chnl.receiver.recv().unwrap();
+                msg_any.downcast_ref::<EchoRsp>().unwrap().counter
+            })
+            .collect();
+        counters.sort();
+        assert_eq!(counters, (0..SERVERS as u64).collect::<Vec<_>>());
+
+        sender_map_get(&ae_instance_id)
+            .unwrap()
+            .send(Box::new(ReqShutdown::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                Duration::from_secs(5),
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspShutdown>().unwrap();
+        println!(
+            "test_tokio_executor_echo_and_shutdown: summary={:?}",
+            msg.summary
+        );
+
+        // ConMgr is shutdown last
+        assert_eq!(msg.summary.acked.len(), SERVERS + 1);
+        assert_eq!(msg.summary.acked.last(), Some(&con_mgr_iid));
+        assert!(msg.summary.timed_out.is_empty());
+        assert_eq!(msg.summary.unregistered, SERVERS);
+        runtime.block_on(join_handle).unwrap();
+
+        println!("test_tokio_executor_echo_and_shutdown:-");
+    }
+
+    #[test]
+    fn test_tokio_executor_move_actor_from_thread() {
+        println!("\ntest_tokio_executor_move_actor_from_thread:+");
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let (supervisor_instance_id, supervisor_chnl, join_handle, tae_instance_id, con_mgr_iid) =
+            start_supervisor_con_mgr_executor(runtime.handle());
+        let (ae_join_handle, ae_instance_id) = ActorExecutor::start("ae", &con_mgr_iid);
+        let ae_sender = sender_map_get(&ae_instance_id).unwrap();
+
+        // Server s1 starts on the thread ActorExecutor
+        let s1_instance_id = add_actor_blocking(
+            Box::new(Server::new("server1")),
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let s1_sndr = sender_map_get(&s1_instance_id).unwrap();
+        let send_echo_req = |counter: u64| {
+            s1_sndr
+                .send(Box::new(EchoReq::new(
+                    &s1_instance_id,
+                    &supervisor_instance_id,
+                    counter,
+                )))
+                .unwrap();
+        };
+
+        // Interleave EchoReq's with moving s1 to the TokioActorExecutor
+        (1..=5).for_each(send_echo_req);
+        ae_sender
+            .send(Box::new(ReqMoveActor::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+                &s1_instance_id,
+                &tae_instance_id,
+            )))
+            .unwrap();
+        (6..=10).for_each(send_echo_req);
+
+        // All EchoRsp's must arrive in order plus one RspMoveActor
+        let mut expected_counter = 1;
+        let mut rsp_move_actor_count = 0;
+        while expected_counter <= 10 || rsp_move_actor_count == 0 {
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            if let Some(msg) = msg_any.downcast_ref::<EchoRsp>() {
+                assert_eq!(msg.counter, expected_counter);
+                expected_counter += 1;
+            } else if let Some(msg) = msg_any.downcast_ref::<RspMoveActor>() {
+                assert_eq!(msg.status, RspMoveActorStatus::Success);
+                assert_eq!(msg.actor_instance_id, s1_instance_id);
+                assert_eq!(msg.ae_instance_id, tae_instance_id);
+                rsp_move_actor_count += 1;
+            } else {
+                panic!("test_tokio_executor_move_actor_from_thread: unexpected msg");
+            }
+        }
+        assert_eq!(rsp_move_actor_count, 1);
+
+        // s1 runs as a task so it still responds after ae is done
+        ae_sender
+            .send(Box::new(CmdDone::new(
+                &ae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        ae_join_handle.join().unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<RspShutdown>().is_some());
+        send_echo_req(11);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 11);
+
+        // Actors aren't moved off a TokioActorExecutor
+        sender_map_get(&tae_instance_id)
+            .unwrap()
+            .send(Box::new(ReqMoveActor::new(
+                &tae_instance_id,
+                &supervisor_instance_id,
+                &s1_instance_id,
+                &ae_instance_id,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspMoveActor>().unwrap();
+        assert_eq!(msg.status, RspMoveActorStatus::ActorNotFound);
+
+        // s1 is shutdown with the other actors
+        sender_map_get(&tae_instance_id)
+            .unwrap()
+            .send(Box::new(ReqShutdown::new(
+                &tae_instance_id,
+                &supervisor_instance_id,
+                Duration::from_secs(5),
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<RspShutdown>().unwrap();
+        assert!(msg.summary.acked.contains(&s1_instance_id));
+        assert!(msg.summary.timed_out.is_empty());
+        runtime.block_on(join_handle).unwrap();
+
+        println!("test_tokio_executor_move_actor_from_thread:-");
+    }
+
+    #[test]
+    fn test_tokio_executor_supervision_and_metrics() {
+        println!("\ntest_tokio_executor_supervision_and_metrics:+");
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let (supervisor_instance_id, supervisor_chnl, join_handle, tae_instance_id, _) =
+            start_supervisor_con_mgr_executor(runtime.handle());
+        let policy = SupervisorPolicy::new(
+            SupervisorStrategy::OneForOne,
+            SupervisorDirective::Restart,
+            1,
+            Duration::from_secs(60),
+        );
+        actor_factory_insert(&CRASHER_ACTOR_ID, Crasher::factory);
+        set_supervisor_policy_blocking(
+            &tae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl,
+            policy,
+        );
+        let c1_instance_id = add_actor_blocking(
+            Box::new(Crasher::new("crasher1")),
+            &tae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let s1_instance_id = add_actor_blocking(
+            Box::new(Server::new("server1")),
+            &tae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        // c1 is restarted once, the new instance is initialized and works
+        send_echo_req(&c1_instance_id, &supervisor_instance_id, 0);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorFailed>().unwrap();
+        assert_eq!(msg.instance_id, c1_instance_id);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorRestarted>().unwrap();
+        assert_eq!(msg.old_instance_id, c1_instance_id);
+        let c2_instance_id = msg.new_instance_id;
+        assert!(sender_map_get(&c1_instance_id).is_none());
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<CmdReady>().unwrap();
+        assert_eq!(msg.src_id(), &c2_instance_id);
+        assert_eq!(msg.status, CmdReadyStatus::Ready);
+        send_echo_req(&c2_instance_id, &supervisor_instance_id, 1);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 1);
+
+        // Restarting again would exceed max_restarts so it's stopped
+        send_echo_req(&c2_instance_id, &supervisor_instance_id, 0);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<ActorFailed>().is_some());
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<ActorStopped>().unwrap();
+        assert_eq!(msg.instance_id, c2_instance_id);
+        assert!(sender_map_get(&c2_instance_id).is_none());
+
+        // The executor's metrics are those of ConMgr and s1
+        send_echo_req(&s1_instance_id, &supervisor_instance_id, 2);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<EchoRsp>().unwrap().counter, 2);
+        sender_map_get(&tae_instance_id)
+            .unwrap()
+            .send(Box::new(MetricsReq::new(
+                &tae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = msg_any.downcast_ref::<MetricsRsp>().unwrap();
+        let tae_metrics = msg
+            .snapshot
+            .executors
+            .iter()
+            .find(|e| e.instance_id == tae_instance_id)
+            .unwrap();
+        assert_eq!(tae_metrics.active_actors, 2);
+        let s1_metrics = tae_metrics
+            .actors
+            .iter()
+            .find(|a| a.instance_id == s1_instance_id)
+            .unwrap();
+        assert!(s1_metrics.msgs_processed >= 2);
+
+        sender_map_get(&tae_instance_id)
+            .unwrap()
+            .send(Box::new(CmdDone::new(
+                &tae_instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        runtime.block_on(join_handle).unwrap();
+
+        println!("test_tokio_executor_supervision_and_metrics:-");
+    }
+}
@@ -0,0 +1,52 @@
+//! This module provides a thread safe hashmap that maps an actor_id
+//! to a factory which constructs instances of that actor.
+use std::sync::RwLock;
+
+use actor::Actor;
+use an_id::AnId;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+/// Construct an actor given its name and serialized init parameters,
+/// an empty `params` means use the defaults.
+pub type ActorFactoryFn =
+    fn(name: &str, params: &str) -> Result<Box<dyn Actor>, Box<dyn std::error::Error>>;
+
+static ACTOR_FACTORY_HASHMAP: Lazy<RwLock<HashMap<AnId, ActorFactoryFn>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+// Add the factory for actor_id.
+//
+// This is thread safe and but only one factory is added per actor_id
+// additional invocations will be ignored.
+pub fn actor_factory_insert(actor_id: &AnId, factory: ActorFactoryFn) {
+    let mut wlocked_hashmap = ACTOR_FACTORY_HASHMAP.write().unwrap(); // TODO: remove unwrap
+    if !wlocked_hashmap.contains_key(actor_id) {
+        log::debug!("actor_factory_insert: actor_id: {}", actor_id);
+        wlocked_hashmap.insert(*actor_id, factory);
+    }
+}
+
+// Get the factory for actor_id.
+pub fn actor_factory_get(actor_id: &AnId) -> Option<ActorFactoryFn> {
+    let rlocked_hashmap = ACTOR_FACTORY_HASHMAP.read().unwrap(); // TODO: remove unwrap
+    rlocked_hashmap.get(actor_id).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use server::{Server, SERVER_ACTOR_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_actor_factory() {
+        assert!(actor_factory_get(&AnId::new()).is_none());
+
+        actor_factory_insert(&SERVER_ACTOR_ID, Server::factory);
+        let factory = actor_factory_get(&SERVER_ACTOR_ID).unwrap();
+        let actor = factory("server1", "").unwrap();
+        assert_eq!(actor.get_name(), "server1");
+        assert_eq!(actor.get_actor_id(), &SERVER_ACTOR_ID);
+    }
+}
@@ -0,0 +1,475 @@
+//! The lifecycle every actor goes through, handled by the framework.
+//!
+//! An actor holds a Lifecycle, implements ActorLifecycle and calls
+//! `add_lifecycle_handlers` with its base state. The handlers then take
+//! the actor through the phases:
+//!
+//!   Initializing -> Registering -> Ready -> Stopping
+//!
+//! On CmdInit the actor registers with ConMgr, when ConMgr responds
+//! with Success the actor is Ready, `on_ready` is called and CmdReady is
+//! sent to the issuer of CmdInit, the ActorExecutor, which passes it on
+//! to the supervisor. If registration fails the phase is Failed and
+//! CmdReady is sent with a RegistrationFailed status. On CmdShutdown the
+//! phase is Stopping, `on_stopping` is called and CmdShutdownAck is sent.
+use actor::{Actor, ActorContext, ProcessMsgFn};
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use cmd_init_issuee_protocol::{CmdInit, CmdReady, CmdReadyStatus, CMD_INIT_ID};
+use cmd_shutdown_issuee_protocol::{CmdShutdown, CmdShutdownAck, CMD_SHUTDOWN_ID};
+use con_mgr_register_actor_protocol::{
+    ConMgrRegisterActorReq, ConMgrRegisterActorRsp, ConMgrRegisterActorStatus,
+    CON_MGR_REGISTER_ACTOR_RSP_ID,
+};
+use sender_map_by_instance_id::sender_map_get;
+use state_machine::{mismatched_msg, StateMachine};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecyclePhase {
+    Initializing,
+    Registering,
+    Ready,
+    Stopping,
+    Failed,
+}
+
+#[derive(Debug)]
+pub struct Lifecycle {
+    phase: LifecyclePhase,
+    issuer_id: Option<AnId>,
+    register_with_con_mgr: bool,
+}
+
+impl Lifecycle {
+    /// ConMgr itself passes false as it doesn't register with itself
+    pub fn new(register_with_con_mgr: bool) -> Self {
+        Self {
+            phase: LifecyclePhase::Initializing,
+            issuer_id: None,
+            register_with_con_mgr,
+        }
+    }
+
+    pub fn phase(&self) -> LifecyclePhase {
+        self.phase
+    }
+
+    /// The instance_id of the issuer of CmdInit
+    pub fn issuer_id(&self) -> Option<&AnId> {
+        self.issuer_id.as_ref()
+    }
+}
+
+pub trait ActorLifecycle: Actor + Sized {
+    fn lifecycle(&self) -> &Lifecycle;
+    fn lifecycle_mut(&mut self) -> &mut Lifecycle;
+
+    /// Called once the actor is registered and CmdReady is sent
+    fn on_ready(&mut self, _context: &dyn ActorContext) {}
+
+    /// Called on CmdShutdown before CmdShutdownAck is sent
+    fn on_stopping(&mut self, _context: &dyn ActorContext) {}
+}
+
+/// Add the lifecycle handlers to state, usually the base state, so
+/// they're used by every state that has it as an ancestor.
+pub fn add_lifecycle_handlers<A: ActorLifecycle>(
+    state_machine: &mut StateMachine<A>,
+    state: ProcessMsgFn<A>,
+) {
+    state_machine.add_handler(state, CMD_INIT_ID, handle_cmd_init::<A>);
+    state_machine.add_handler(
+        state,
+        CON_MGR_REGISTER_ACTOR_RSP_ID,
+        handle_con_mgr_register_actor_rsp::<A>,
+    );
+    state_machine.add_handler(state, CMD_SHUTDOWN_ID, handle_cmd_shutdown::<A>);
+}
+
+fn handle_cmd_init<A: ActorLifecycle>(
+    actor: &mut A,
+    context: &dyn ActorContext,
+    msg_any: BoxMsgAny,
+) {
+    let Some(msg) = msg_any.downcast_ref::<CmdInit>() else {
+        mismatched_msg(context, &msg_any, "CmdInit");
+        return;
+    };
+    log::debug!("{}:Lifecycle: {msg:?}", actor.get_name());
+    actor.lifecycle_mut().issuer_id = Some(*msg.src_id());
+
+    if !actor.lifecycle().register_with_con_mgr {
+        ready(actor, context);
+        return;
+    }
+
+    let Some(protocol_set) = actor.get_protocol_set() else {
+        failed(actor, "actor has no ProtocolSet to register");
+        return;
+    };
+
+    // Register ourselves with ConMgr
+    let msg = Box::new(ConMgrRegisterActorReq::new(
+        context.get_con_mgr_instance_id(),
+        actor.get_instance_id(),
+        actor.get_name(),
+        actor.get_actor_id(),
+        actor.get_instance_id(),
+        protocol_set,
+    ));
+    log::trace!(
+        "{}:Lifecycle: sending ConMgrRegisterActorReq={msg:?}",
+        actor.get_name()
+    );
+    match context.send_con_mgr(msg) {
+        Ok(()) => actor.lifecycle_mut().phase = LifecyclePhase::Registering,
+        Err(e) => failed(
+            actor,
+            &format!("sending ConMgrRegisterActorReq failed: {e}"),
+        ),
+    }
+}
+
+fn handle_con_mgr_register_actor_rsp<A: ActorLifecycle>(
+    actor: &mut A,
+    context: &dyn ActorContext,
+    msg_any: BoxMsgAny,
+) {
+    let Some(msg) = msg_any.downcast_ref::<ConMgrRegisterActorRsp>() else {
+        mismatched_msg(context, &msg_any, "ConMgrRegisterActorRsp");
+        return;
+    };
+    log::debug!("{}:Lifecycle: {msg:?}", actor.get_name());
+    if actor.lifecycle().phase != LifecyclePhase::Registering {
+        log::warn!(
+            "{}:Lifecycle: unexpected {msg:?} in phase {:?}",
+            actor.get_name(),
+            actor.lifecycle().phase
+        );
+        return;
+    }
+
+    match msg.status {
+        ConMgrRegisterActorStatus::Success => ready(actor, context),
+        ref status => failed(actor, &format!("registration failed: {status:?}")),
+    }
+}
+
+fn handle_cmd_shutdown<A: ActorLifecycle>(
+    actor: &mut A,
+    context: &dyn ActorContext,
+    msg_any: BoxMsgAny,
+) {
+    let Some(msg) = msg_any.downcast_ref::<CmdShutdown>() else {
+        mismatched_msg(context, &msg_any, "CmdShutdown");
+        return;
+    };
+    log::debug!("{}:Lifecycle: {msg:?}", actor.get_name());
+    actor.lifecycle_mut().phase = LifecyclePhase::Stopping;
+    actor.on_stopping(context);
+    if let Err(e) = context.send_dst(Box::new(CmdShutdownAck::new(
+        context.get_dst_instance_id(),
+        actor.get_instance_id(),
+    ))) {
+        log::error!(
+            "{}:Lifecycle: sending CmdShutdownAck failed: {e}",
+            actor.get_name()
+        );
+    }
+}
+
+fn ready<A: ActorLifecycle>(actor: &mut A, context: &dyn ActorContext) {
+    actor.lifecycle_mut().phase = LifecyclePhase::Ready;
+    send_cmd_ready(actor, CmdReadyStatus::Ready);
+    actor.on_ready(context);
+}
+
+fn failed<A: ActorLifecycle>(actor: &mut A, reason: &str) {
+    log::error!("{}:Lifecycle: {reason}", actor.get_name());
+    actor.lifecycle_mut().phase = LifecyclePhase::Failed;
+    send_cmd_ready(actor, CmdReadyStatus::RegistrationFailed(reason.to_owned()));
+}
+
+fn send_cmd_ready<A: ActorLifecycle>(actor: &A, status: CmdReadyStatus) {
+    let Some(issuer_id) = actor.lifecycle().issuer_id else {
+        log::warn!("{}:Lifecycle: no issuer for CmdReady", actor.get_name());
+        return;
+    };
+    let Some(sndr) = sender_map_get(&issuer_id) else {
+        log::warn!(
+            "{}:Lifecycle: issuer {issuer_id} not in sender_map",
+            actor.get_name()
+        );
+        return;
+    };
+    let msg = Box::new(CmdReady::new(&issuer_id, actor.get_instance_id(), status));
+    if let Err(e) = sndr.send(msg) {
+        log::error!(
+            "{}:Lifecycle: sending CmdReady failed: {e}",
+            actor.get_name()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actor_channel::{ActorChannel, ActorSender};
+    use cmd_init_issuee_protocol::CMD_READY_ID;
+    use con_mgr_register_actor_protocol::CON_MGR_REGISTER_ACTOR_REQ_ID;
+    use msg_header::MsgHeader;
+    use protocol_set::ProtocolSet;
+    use sender_map_by_instance_id::sender_map_insert;
+    use state_machine::{state_machine, HasStateMachine};
+    use std::{
+        collections::HashMap,
+        fmt::{self, Debug},
+    };
+
+    struct Context {
+        sndr: ActorSender,
+    }
+
+    impl ActorContext for Context {
+        fn actor_executor_sndr(&self) -> &ActorSender {
+            &self.sndr
+        }
+
+        fn send_con_mgr(&self, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+            self.sndr.send(msg)
+        }
+
+        fn get_con_mgr_instance_id(&self) -> &AnId {
+            self.sndr.get_dst_instance_id()
+        }
+
+        fn send_self(&self, _msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn send_dst(&self, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+            self.sndr.send(msg)
+        }
+
+        fn get_dst_instance_id(&self) -> &AnId {
+            self.sndr.get_dst_instance_id()
+        }
+
+        fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>> {
+            Ok(self.sndr.clone())
+        }
+    }
+
+    struct Lc {
+        instance_id: AnId,
+        chnl: ActorChannel,
+        protocol_set: ProtocolSet,
+        state_machine: StateMachine<Self>,
+        lifecycle: Lifecycle,
+        ready_count: u32,
+        stopping_count: u32,
+    }
+
+    // TODO: For Send implementors must guarantee maybe moved between threads. ??
+    unsafe impl Send for Lc {}
+
+    // TODO: This Sync guarantee is valid because multiple threads will never access an Actor. ??
+    unsafe impl Sync for Lc {}
+
+    impl Debug for Lc {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Lc {{ lifecycle: {:?} }}", self.lifecycle)
+        }
+    }
+
+    impl Actor for Lc {
+        fn get_name(&self) -> &str {
+            "lc"
+        }
+
+        fn get_actor_id(&self) -> &AnId {
+            &self.instance_id
+        }
+
+        fn get_instance_id(&self) -> &AnId {
+            &self.instance_id
+        }
+
+        fn get_chnl(&self) -> &ActorChannel {
+            &self.chnl
+        }
+
+        fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
+            self.dispatch(context, msg);
+        }
+
+        fn done(&self) -> bool {
+            false
+        }
+
+        fn get_protocol_set(&self) -> Option<&ProtocolSet> {
+            Some(&self.protocol_set)
+        }
+    }
+
+    impl HasStateMachine for Lc {
+        fn state_machine(&self) -> &StateMachine<Self> {
+            &self.state_machine
+        }
+
+        fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+            &mut self.state_machine
+        }
+    }
+
+    impl ActorLifecycle for Lc {
+        fn lifecycle(&self) -> &Lifecycle {
+            &self.lifecycle
+        }
+
+        fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+            &mut self.lifecycle
+        }
+
+        fn on_ready(&mut self, _context: &dyn ActorContext) {
+            self.ready_count += 1;
+        }
+
+        fn on_stopping(&mut self, _context: &dyn ActorContext) {
+            self.stopping_count += 1;
+        }
+    }
+
+    impl Lc {
+        fn new(register_with_con_mgr: bool) -> Self {
+            let instance_id = AnId::new();
+            let mut state_machine = state_machine!(Self, [base]);
+            add_lifecycle_handlers(&mut state_machine, Self::base);
+            Self {
+                instance_id,
+                chnl: ActorChannel::new("lc", &instance_id),
+                protocol_set: ProtocolSet::new("lc_ps", AnId::new(), HashMap::new()),
+                state_machine,
+                lifecycle: Lifecycle::new(register_with_con_mgr),
+                ready_count: 0,
+                stopping_count: 0,
+            }
+        }
+
+        fn base(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
+            let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
+            context.record_unknown_msg(msg_id);
+        }
+    }
+
+    fn supervisor() -> (AnId, ActorChannel, Context) {
+        let supervisor_instance_id = AnId::new();
+        let supervisor_chnl = ActorChannel::new("supervisor", &supervisor_instance_id);
+        sender_map_insert(&supervisor_instance_id, &supervisor_chnl.sender);
+        let context = Context {
+            sndr: supervisor_chnl.sender.clone(),
+        };
+        (supervisor_instance_id, supervisor_chnl, context)
+    }
+
+    fn recv_cmd_ready(chnl: &ActorChannel) -> CmdReady {
+        let msg_any = chnl.receiver.recv().unwrap();
+        assert_eq!(
+            MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any),
+            &CMD_READY_ID
+        );
+        msg_any.downcast_ref::<CmdReady>().unwrap().clone()
+    }
+
+    #[test]
+    fn test_lifecycle_register_ready_stopping() {
+        println!("\ntest_lifecycle_register_ready_stopping:+");
+        let (supervisor_instance_id, supervisor_chnl, context) = supervisor();
+        let mut lc = Lc::new(true);
+        assert_eq!(lc.lifecycle.phase(), LifecyclePhase::Initializing);
+
+        lc.process_msg_any(
+            &context,
+            Box::new(CmdInit::new(&lc.instance_id, &supervisor_instance_id)),
+        );
+        assert_eq!(lc.lifecycle.phase(), LifecyclePhase::Registering);
+        assert_eq!(lc.lifecycle.issuer_id(), Some(&supervisor_instance_id));
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(
+            MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any),
+            &CON_MGR_REGISTER_ACTOR_REQ_ID
+        );
+
+        lc.process_msg_any(
+            &context,
+            Box::new(ConMgrRegisterActorRsp::new(
+                &lc.instance_id,
+                &supervisor_instance_id,
+                ConMgrRegisterActorStatus::Success,
+            )),
+        );
+        assert_eq!(lc.lifecycle.phase(), LifecyclePhase::Ready);
+        assert_eq!(lc.ready_count, 1);
+        let cmd_ready = recv_cmd_ready(&supervisor_chnl);
+        assert_eq!(cmd_ready.src_id(), &lc.instance_id);
+        assert_eq!(cmd_ready.status, CmdReadyStatus::Ready);
+
+        lc.process_msg_any(
+            &context,
+            Box::new(CmdShutdown::new(&lc.instance_id, &supervisor_instance_id)),
+        );
+        assert_eq!(lc.lifecycle.phase(), LifecyclePhase::Stopping);
+        assert_eq!(lc.stopping_count, 1);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(msg_any.downcast_ref::<CmdShutdownAck>().is_some());
+        println!("test_lifecycle_register_ready_stopping:-");
+    }
+
+    #[test]
+    fn test_lifecycle_without_registration() {
+        println!("\ntest_lifecycle_without_registration:+");
+        let (supervisor_instance_id, supervisor_chnl, context) = supervisor();
+        let mut lc = Lc::new(false);
+
+        lc.process_msg_any(
+            &context,
+            Box::new(CmdInit::new(&lc.instance_id, &supervisor_instance_id)),
+        );
+        assert_eq!(lc.lifecycle.phase(), LifecyclePhase::Ready);
+        assert_eq!(lc.ready_count, 1);
+        assert_eq!(
+            recv_cmd_ready(&supervisor_chnl).status,
+            CmdReadyStatus::Ready
+        );
+        println!("test_lifecycle_without_registration:-");
+    }
+
+    #[test]
+    fn test_lifecycle_registration_failed() {
+        println!("\ntest_lifecycle_registration_failed:+");
+        let (supervisor_instance_id, supervisor_chnl, context) = supervisor();
+        let mut lc = Lc::new(true);
+
+        lc.process_msg_any(
+            &context,
+            Box::new(CmdInit::new(&lc.instance_id, &supervisor_instance_id)),
+        );
+        let _register_req = supervisor_chnl.receiver.recv().unwrap();
+
+        lc.process_msg_any(
+            &context,
+            Box::new(ConMgrRegisterActorRsp::new(
+                &lc.instance_id,
+                &supervisor_instance_id,
+                ConMgrRegisterActorStatus::ActorAlreadyRegistered,
+            )),
+        );
+        assert_eq!(lc.lifecycle.phase(), LifecyclePhase::Failed);
+        assert_eq!(lc.ready_count, 0);
+        assert!(matches!(
+            recv_cmd_ready(&supervisor_chnl).status,
+            CmdReadyStatus::RegistrationFailed(_)
+        ));
+        println!("test_lifecycle_registration_failed:-");
+    }
+}
@@ -0,0 +1,261 @@
+//! Client side of admin_protocol used by the admin command line tool
+//! to inspect and control a running node through one of its
+//! dispatchers that has admin enabled.
+use std::{error::Error, net::TcpStream, time::Duration};
+
+use admin_protocol::{
+    admin_rsp_from_serde_json_buf, AdminHello, AdminHelloAck, AdminListActorsReq,
+    AdminListActorsRsp, AdminNodeInfo, AdminRegistration, AdminRegistrationsReq,
+    AdminRegistrationsRsp, AdminRemoveActorReq, AdminRemoveActorRsp, AdminRemoveActorStatus,
+};
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use cmd_done::CmdDone;
+use echo_requestee_protocol::{EchoReq, EchoRsp};
+use echo_start_complete_protocol::{EchoComplete, EchoStart};
+use metrics_protocol::{MetricsReq, MetricsRsp};
+use runtime_metrics::LinkMetrics;
+use utils::{read_msg_buf_from_tcp_stream, write_msg_buf_to_tcp_stream};
+use uuid::Uuid;
+
+/// How long to wait for a response
+pub const ADMIN_RSP_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct AdminClient {
+    stream: TcpStream,
+    pub instance_id: AnId,
+    pub reply_instance_id: AnId, // The src_id of our requests
+    pub node: AdminNodeInfo,
+}
+
+impl AdminClient {
+    /// Connect to the dispatcher at addr and exchange AdminHello's
+    pub fn connect(addr: &str) -> Result<Self, Box<dyn Error>> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(ADMIN_RSP_TIMEOUT))?;
+
+        let instance_id = AnId::new();
+        let hello = Box::new(AdminHello::new(&AnId::nil(), &instance_id));
+        let buf = AdminHello::to_serde_json_buf(hello).ok_or("unable to serialize AdminHello")?;
+        write_msg_buf_to_tcp_stream(&mut stream, &buf)?;
+
+        let buf = read_msg_buf_from_tcp_stream(&mut stream)
+            .map_err(|why| format!("no AdminHelloAck from {addr}, is admin enabled? {why}"))?;
+        let msg_any =
+            admin_rsp_from_serde_json_buf(&buf).ok_or("unable to decode AdminHelloAck")?;
+        let ack = AdminHelloAck::from_box_msg_any(&msg_any).ok_or("expected AdminHelloAck")?;
+
+        Ok(Self {
+            stream,
+            instance_id,
+            reply_instance_id: ack.reply_instance_id,
+            node: ack.node.clone(),
+        })
+    }
+
+    /// The actors of each of the executors
+    pub fn list_actors(&mut self) -> Result<Vec<AdminListActorsRsp>, Box<dyn Error>> {
+        let mut executors = Vec::new();
+        for executor in self.node.executors.clone() {
+            let req = Box::new(AdminListActorsReq::new(
+                &executor.instance_id,
+                &self.reply_instance_id,
+            ));
+            self.send(AdminListActorsReq::to_serde_json_buf(req))?;
+            let msg_any = self.recv()?;
+            let rsp = AdminListActorsRsp::from_box_msg_any(&msg_any)
+                .ok_or("expected AdminListActorsRsp")?;
+            executors.push(rsp.clone());
+        }
+        Ok(executors)
+    }
+
+    /// The actors registered with the ConMgr
+    pub fn registrations(&mut self) -> Result<Vec<AdminRegistration>, Box<dyn Error>> {
+        let req = Box::new(AdminRegistrationsReq::new(
+            &self.node.con_mgr_instance_id,
+            &self.reply_instance_id,
+        ));
+        self.send(AdminRegistrationsReq::to_serde_json_buf(req))?;
+        let msg_any = self.recv()?;
+        let rsp = AdminRegistrationsRsp::from_box_msg_any(&msg_any)
+            .ok_or("expected AdminRegistrationsRsp")?;
+        Ok(rsp.registrations.clone())
+    }
+
+    /// The counters of each of the links of the node
+    pub fn link_status(&mut self) -> Result<Vec<LinkMetrics>, Box<dyn Error>> {
+        let executor = self.node.executors.first().ok_or("node has no executors")?;
+        let req = Box::new(MetricsReq::new(
+            &executor.instance_id,
+            &self.reply_instance_id,
+        ));
+        self.send(MetricsReq::to_serde_json_buf(req))?;
+        let msg_any = self.recv()?;
+        let rsp = MetricsRsp::from_box_msg_any(&msg_any).ok_or("expected MetricsRsp")?;
+        Ok(rsp.snapshot.links.clone())
+    }
+
+    /// Send CmdDone to instance_id, there is no response
+    pub fn cmd_done(&mut self, instance_id: &AnId) -> Result<(), Box<dyn Error>> {
+        let msg = Box::new(CmdDone::new(instance_id, &self.reply_instance_id));
+        self.send(CmdDone::to_serde_json_buf(msg))
+    }
+
+    /// Send an EchoReq to instance_id and wait for its EchoRsp
+    pub fn echo(&mut self, instance_id: &AnId, counter: u64) -> Result<EchoRsp, Box<dyn Error>> {
+        let req = Box::new(EchoReq::new(instance_id, &self.reply_instance_id, counter));
+        self.send(EchoReq::to_serde_json_buf(req))?;
+        let msg_any = self.recv()?;
+        let rsp = EchoRsp::from_box_msg_any(&msg_any).ok_or("expected EchoRsp")?;
+        if rsp.counter != counter {
+            return Err(format!("expected counter {counter} got {}", rsp.counter).into());
+        }
+        Ok(rsp.clone())
+    }
+
+    /// Send an EchoStart to the Client at instance_id, which echoes
+    /// ping_count times with partner_instance_id, and wait for its
+    /// EchoComplete
+    pub fn echo_start(
+        &mut self,
+        instance_id: &AnId,
+        partner_instance_id: &AnId,
+        ping_count: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        let req = Box::new(EchoStart::new(
+            instance_id,
+            &self.reply_instance_id,
+            partner_instance_id,
+            ping_count,
+        ));
+        self.send(EchoStart::to_serde_json_buf(req))?;
+        let msg_any = self.recv()?;
+        EchoComplete::from_box_msg_any(&msg_any).ok_or("expected EchoComplete")?;
+        Ok(())
+    }
+
+    /// Remove the actor at instance_id from the executor running it
+    pub fn remove_actor(
+        &mut self,
+        instance_id: &AnId,
+    ) -> Result<AdminRemoveActorStatus, Box<dyn Error>> {
+        let Some(executor) = self
+            .list_actors()?
+            .into_iter()
+            .find(|rsp| rsp.actors.iter().any(|a| a.instance_id == *instance_id))
+            .map(|rsp| rsp.executor)
+        else {
+            return Ok(AdminRemoveActorStatus::NotFound);
+        };
+
+        let req = Box::new(AdminRemoveActorReq::new(
+            &executor.instance_id,
+            &self.reply_instance_id,
+            instance_id,
+        ));
+        self.send(AdminRemoveActorReq::to_serde_json_buf(req))?;
+        let msg_any = self.recv()?;
+        let rsp = AdminRemoveActorRsp::from_box_msg_any(&msg_any)
+            .ok_or("expected AdminRemoveActorRsp")?;
+        Ok(rsp.status)
+    }
+
+    fn send(&mut self, buf: Option<Vec<u8>>) -> Result<(), Box<dyn Error>> {
+        let buf = buf.ok_or("unable to serialize request")?;
+        write_msg_buf_to_tcp_stream(&mut self.stream, &buf)
+    }
+
+    fn recv(&mut self) -> Result<BoxMsgAny, Box<dyn Error>> {
+        let buf = read_msg_buf_from_tcp_stream(&mut self.stream)?;
+        Ok(admin_rsp_from_serde_json_buf(&buf).ok_or("unable to decode response")?)
+    }
+}
+
+/// Parse an instance_id as displayed by the tool
+pub fn parse_instance_id(s: &str) -> Result<AnId, Box<dyn Error>> {
+    Ok(AnId::from(Uuid::parse_str(s)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actor_factory::actor_factory_insert;
+    use node::{Node, NodeConfig};
+    use server::{Server, SERVER_ACTOR_ID};
+
+    #[test]
+    fn test_admin_client() {
+        println!("\ntest_admin_client:+");
+        actor_factory_insert(&SERVER_ACTOR_ID, Server::factory);
+
+        let config = NodeConfig::from_toml_str(
+            r#"
+            name = "node1"
+
+            [[executors]]
+            name = "ae1"
+
+            [[executors]]
+            name = "ae2"
+
+            [[dispatchers]]
+            name = "admin"
+            executor = "ae1"
+            listen_addr = "127.0.0.1:0"
+            admin = true
+
+            [[actors]]
+            name = "server1"
+            actor = "server"
+            executor = "ae2"
+            "#,
+        )
+        .unwrap();
+        let node = Node::start(&config).unwrap();
+        let server1_instance_id = *node.instance_id("server1").unwrap();
+
+        let admin_addr = node.local_addr("admin").unwrap().to_string();
+        let mut client = AdminClient::connect(&admin_addr).unwrap();
+        assert_eq!(client.node.name, "node1");
+        assert_eq!(client.node.con_mgr_instance_id, node.con_mgr_instance_id);
+        assert_eq!(client.node.executors.len(), 2);
+
+        let executors = client.list_actors().unwrap();
+        println!("test_admin_client: executors={executors:#?}");
+        assert_eq!(executors[0].executor.name, "ae1");
+        let ae2 = &executors[1];
+        assert_eq!(ae2.executor.name, "ae2");
+        assert_eq!(ae2.actors.len(), 1);
+        assert_eq!(ae2.actors[0].instance_id, server1_instance_id);
+        assert!(ae2.actors[0].state.contains("current_state: state0"));
+
+        let registrations = client.registrations().unwrap();
+        assert!(registrations
+            .iter()
+            .any(|r| r.instance_id == server1_instance_id && r.link_instance_id.is_none()));
+
+        let rsp = client.echo(&server1_instance_id, 1).unwrap();
+        assert_eq!(rsp.counter, 1);
+
+        let links = client.link_status().unwrap();
+        assert!(links.iter().any(|link| link.name == "admin_deserializer"));
+
+        assert_eq!(
+            client.remove_actor(&server1_instance_id).unwrap(),
+            AdminRemoveActorStatus::Success
+        );
+        assert_eq!(
+            client.remove_actor(&server1_instance_id).unwrap(),
+            AdminRemoveActorStatus::NotFound
+        );
+        assert!(!client
+            .registrations()
+            .unwrap()
+            .iter()
+            .any(|r| r.instance_id == server1_instance_id));
+
+        node.shutdown();
+        println!("test_admin_client:-");
+    }
+}
@@ -0,0 +1,85 @@
+//! Admin tool for a running node, it connects to a dispatcher of the
+//! node that has admin enabled.
+use std::{error::Error, process::ExitCode};
+
+use admin_cli::{parse_instance_id, AdminClient};
+
+const USAGE: &str = "Usage: admin_cli <addr> <command>
+
+Commands:
+  actors                 List the executors and their actors
+  registrations          List the actors registered with ConMgr
+  links                  Show the status of the links
+  echo <instance_id>     Send EchoReq to an actor and wait for the EchoRsp
+  done <instance_id>     Send CmdDone to an executor
+  remove <instance_id>   Remove an actor";
+
+fn run(addr: &str, command: &str, arg: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut client = AdminClient::connect(addr)?;
+    match (command, arg) {
+        ("actors", None) => {
+            for rsp in client.list_actors()? {
+                println!("{} {}", rsp.executor.name, rsp.executor.instance_id);
+                for actor in rsp.actors {
+                    println!("  {} {}", actor.name, actor.instance_id);
+                    println!("    {}", actor.state);
+                }
+            }
+        }
+        ("registrations", None) => {
+            for r in client.registrations()? {
+                let link = r
+                    .link_instance_id
+                    .map_or("local".to_owned(), |link| format!("link {link}"));
+                println!(
+                    "{} {} {} {link}",
+                    r.name, r.instance_id, r.protocol_set_name
+                );
+            }
+        }
+        ("links", None) => {
+            for link in client.link_status()? {
+                println!("{} {}", link.name, link.instance_id);
+                println!(
+                    "  frames in/out {}/{} bytes in/out {}/{} decode_errors {} reconnects {} refused {}",
+                    link.frames_in,
+                    link.frames_out,
+                    link.bytes_in,
+                    link.bytes_out,
+                    link.decode_errors,
+                    link.reconnects,
+                    link.refused
+                );
+            }
+        }
+        ("echo", Some(instance_id)) => {
+            let rsp = client.echo(&parse_instance_id(instance_id)?, 1)?;
+            println!("{rsp:?}");
+        }
+        ("done", Some(instance_id)) => {
+            client.cmd_done(&parse_instance_id(instance_id)?)?;
+        }
+        ("remove", Some(instance_id)) => {
+            let status = client.remove_actor(&parse_instance_id(instance_id)?)?;
+            println!("{status:?}");
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 || args.len() > 4 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    match run(&args[1], &args[2], args.get(3).map(String::as_str)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(why) => {
+            eprintln!("{why}");
+            ExitCode::FAILURE
+        }
+    }
+}
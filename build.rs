@@ -0,0 +1,72 @@
+//! Generates `use <crate> as _;` for every library in the workspace so
+//! tests/test_id_inventory.rs links, and checks the ids of, all of them.
+//! A member that isn't a dependency of this package is a compile error
+//! in that test rather than a crate silently left out.
+use std::{env, fs, path::Path};
+
+use toml::Table;
+
+fn read_manifest(path: &Path) -> Table {
+    println!("cargo:rerun-if-changed={}", path.display());
+    let manifest =
+        fs::read_to_string(path).unwrap_or_else(|why| panic!("build.rs: reading {path:?}: {why}"));
+    manifest
+        .parse::<Table>()
+        .unwrap_or_else(|why| panic!("build.rs: parsing {path:?}: {why}"))
+}
+
+// The `members` of the `[workspace]` table
+fn workspace_members(manifest: &Table) -> Vec<String> {
+    let Some(members) = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(|members| members.as_array())
+    else {
+        panic!("build.rs: no workspace members in Cargo.toml");
+    };
+    members
+        .iter()
+        .map(|member| {
+            member
+                .as_str()
+                .unwrap_or_else(|| panic!("build.rs: workspace member {member} isn't a string"))
+                .to_owned()
+        })
+        .collect()
+}
+
+fn package_name(manifest: &Table) -> Option<&str> {
+    manifest.get("package")?.get("name")?.as_str()
+}
+
+fn is_proc_macro(manifest: &Table) -> bool {
+    manifest
+        .get("lib")
+        .and_then(|lib| lib.get("proc-macro"))
+        .and_then(|proc_macro| proc_macro.as_bool())
+        .unwrap_or(false)
+}
+
+fn main() {
+    let manifest = read_manifest(Path::new("Cargo.toml"));
+
+    let mut uses = String::new();
+    for member in workspace_members(&manifest) {
+        let member_dir = Path::new(&member);
+        let member_manifest_path = member_dir.join("Cargo.toml");
+        let member_manifest = read_manifest(&member_manifest_path);
+
+        // Proc macros and executables don't register ids at runtime
+        if is_proc_macro(&member_manifest) || !member_dir.join("src/lib.rs").exists() {
+            continue;
+        }
+        let Some(name) = package_name(&member_manifest) else {
+            panic!("build.rs: no package name in {member_manifest_path:?}");
+        };
+        uses.push_str(&format!("use {} as _;\n", name.replace('-', "_")));
+    }
+
+    let out_dir = env::var("OUT_DIR").expect("build.rs: OUT_DIR");
+    fs::write(Path::new(&out_dir).join("workspace_libs.rs"), uses)
+        .expect("build.rs: writing workspace_libs.rs");
+}
@@ -1,39 +1,30 @@
-use actor::{Actor, ActorContext, ProcessMsgFn};
+use actor::{Actor, ActorContext};
 use actor_channel::{ActorChannel, ActorSender};
+use actor_lifecycle::{add_lifecycle_handlers, ActorLifecycle, Lifecycle};
 use an_id::{anid, paste, AnId};
 use box_msg_any::BoxMsgAny;
-use cmd_init_issuee_protocol::{cmd_init_issuee_protocol, CmdInit, CMD_INIT_ID};
-use con_mgr_register_actor_protocol::{
-    ConMgrRegisterActorReq, ConMgrRegisterActorRsp, ConMgrRegisterActorStatus,
-    CON_MGR_REGISTER_ACTOR_RSP_ID,
-};
+use cmd_init_issuee_protocol::cmd_init_issuee_protocol;
+use cmd_shutdown_issuee_protocol::cmd_shutdown_issuee_protocol;
+use con_mgr_register_actor_protocol::con_mgr_registee_actor_protocol;
 use echo_requestee_protocol::echo_requestee_protocol;
-use echo_requester_protocol::{
-    echo_requester_protocol, EchoReq, EchoRsp, ECHO_REQ_ID, ECHO_RSP_ID,
-};
+use echo_requester_protocol::{echo_requester_protocol, EchoReq, EchoRsp, ECHO_RSP_ID};
 use echo_start_complete_protocol::{
     echo_start_complete_protocol, EchoComplete, EchoStart, ECHO_START_ID,
 };
+use id_inventory::register_id;
 use msg1::Msg1;
-use msg2::Msg2;
-use msg_header::MsgHeader;
+use msg2::{Msg2, MSG2_ID};
 use protocol::Protocol;
 use protocol_set::ProtocolSet;
 use sender_map_by_instance_id::{sender_map_get, sender_map_insert};
+use state_machine::{
+    add_base_state, base_state, msg_handlers, state_machine, HasStateMachine, StateMachine,
+};
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
 };
 
-// State information
-#[derive(Debug)]
-pub struct StateInfo {
-    pub name: String,
-}
-
-// HashMap that maps address of a ProcessMsgFn to StateInfo
-type StateInfoMap<SM> = HashMap<*const ProcessMsgFn<SM>, StateInfo>;
-
 /// Client which supports being controlled and a partner
 /// to pinging.
 ///
@@ -50,8 +41,8 @@ pub struct Client {
     pub instance_id: AnId,
     pub protocol_set: ProtocolSet,
     pub chnl: ActorChannel,
-    pub current_state: ProcessMsgFn<Self>,
-    pub state_info_hash: StateInfoMap<Self>,
+    pub state_machine: StateMachine<Self>,
+    pub lifecycle: Lifecycle,
     pub partner_instance_id: Option<AnId>,
     pub partner_sndr: Option<ActorSender>,
     pub controller_instance_id: Option<AnId>,
@@ -83,37 +74,53 @@ impl Actor for Client {
     }
 
     fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
-        (self.current_state)(self, context, msg);
+        self.dispatch(context, msg);
     }
 
     fn done(&self) -> bool {
         false
     }
+
+    fn get_protocol_set(&self) -> Option<&ProtocolSet> {
+        Some(&self.protocol_set)
+    }
+}
+
+impl HasStateMachine for Client {
+    fn state_machine(&self) -> &StateMachine<Self> {
+        &self.state_machine
+    }
+
+    fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+        &mut self.state_machine
+    }
+}
+
+impl ActorLifecycle for Client {
+    fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+        &mut self.lifecycle
+    }
 }
 
 impl Debug for Client {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let fn_ptr = self.current_state as *const ProcessMsgFn<Self>;
-        let fn_ptr_string = format!("{fn_ptr:p}");
-        let state_name = if let Some(n) = self.state_info_hash.get(&fn_ptr) {
-            // State does have a name
-            n.name.as_str()
-        } else {
-            // State does NOT have a name, use address
-            fn_ptr_string.as_str()
-        };
-
         write!(
             f,
-            "{} {{ id: {} instance_id: {} state_info_hash: {:?}; current_state: {state_name}; ping_count: {}; protocol_set: {:?}}}",
-            self.name, self.actor_id, self.instance_id, self.state_info_hash, self.ping_count, self.protocol_set
+            "{} {{ id: {} instance_id: {} state_machine: {:?}; ping_count: {}; protocol_set: {:?}}}",
+            self.name, self.actor_id, self.instance_id, self.state_machine, self.ping_count, self.protocol_set
         )
     }
 }
 
 // From: https://www.uuidgenerator.net/version4
-const CLIENT_ACTOR_ID: AnId = anid!("02960323-48ef-4e9e-b3b7-d8a3ad6b49ed");
+pub const CLIENT_ACTOR_ID: AnId = anid!("02960323-48ef-4e9e-b3b7-d8a3ad6b49ed");
 const CLIENT_PROTOCOL_SET_ID: AnId = anid!("1a7b43ed-4676-42cd-9969-72283f258ef1");
+register_id!(Actor, CLIENT_ACTOR_ID, "client");
+register_id!(ProtocolSet, CLIENT_PROTOCOL_SET_ID, "client_ps");
 
 impl Client {
     pub fn new(name: &str) -> Self {
@@ -127,20 +134,34 @@ impl Client {
         client_pm.insert(errp.id, errp.clone());
         client_pm.insert(erep.id, erep.clone());
         client_pm.insert(escp.id, escp.clone());
+        let cs_iep = cmd_shutdown_issuee_protocol();
+        client_pm.insert(cs_iep.id, cs_iep.clone());
+        let cm_rap = con_mgr_registee_actor_protocol();
+        client_pm.insert(cm_rap.id, cm_rap.clone());
 
         let client_ps = ProtocolSet::new("client_ps", CLIENT_PROTOCOL_SET_ID, client_pm);
 
         let client_instance_id = AnId::new();
         let chnl = ActorChannel::new(name, &client_instance_id);
 
-        let mut this = Self {
+        let mut state_machine = state_machine!(Self, [state0]);
+        let base = add_base_state(&mut state_machine);
+        state_machine.set_parent(Self::state0, base);
+        add_lifecycle_handlers(&mut state_machine, base);
+        msg_handlers!(state_machine, Self, state0, [
+            ECHO_RSP_ID: EchoRsp => handle_echo_rsp,
+            ECHO_START_ID: EchoStart => handle_echo_start,
+            MSG2_ID: Msg2 => handle_msg2,
+        ]);
+
+        let this = Self {
             name: name.to_owned(),
             actor_id: CLIENT_ACTOR_ID,
             instance_id: client_instance_id,
             protocol_set: client_ps,
-            current_state: Self::state0,
+            state_machine,
+            lifecycle: Lifecycle::new(true),
             chnl,
-            state_info_hash: StateInfoMap::<Self>::new(),
             partner_instance_id: None,
             partner_sndr: None,
             controller_instance_id: None,
@@ -151,25 +172,19 @@ impl Client {
         // Add ourself to the sender_map
         sender_map_insert(&this.instance_id, &this.chnl.sender.clone());
 
-        this.add_state(Self::state0, "state0");
         this
     }
 
-    pub fn add_state(&mut self, state: ProcessMsgFn<Self>, name: &str) {
-        let s = StateInfo {
-            name: name.to_owned(),
-        };
-        let k = state as *const ProcessMsgFn<Self>;
-        self.state_info_hash.insert(k, s);
-    }
-
-    #[allow(unused)]
-    fn transition(&mut self, dest: ProcessMsgFn<Self>) {
-        self.current_state = dest;
+    // ActorFactoryFn for Client, there are no init parameters so params is ignored
+    pub fn factory(
+        name: &str,
+        _params: &str,
+    ) -> Result<Box<dyn Actor>, Box<dyn std::error::Error>> {
+        Ok(Box::new(Self::new(name)))
     }
 
     fn send_echo_req_or_complete(&mut self, counter: u64) {
-        println!(
+        log::trace!(
             "{}:send_echo_req_or_complete:+ counter={counter} ping_count={} * 2 = {}",
             self.name,
             self.ping_count,
@@ -182,13 +197,13 @@ impl Client {
                     &self.instance_id,
                     counter,
                 ));
-                println!(
+                log::trace!(
                     "{}:send_echo_req_or_complete:- to partner_tx msg={req_msg:?}",
                     self.name
                 );
                 sndr.send(req_msg).unwrap();
             } else {
-                println!("{}:send_echo_req_or_complete:- no partner_tx", self.name);
+                log::warn!("{}:send_echo_req_or_complete:- no partner_tx", self.name);
             }
         } else if let Some(tx) = &self.controller_sndr {
             tx.send(Box::new(EchoComplete::new(
@@ -196,74 +211,44 @@ impl Client {
                 &self.instance_id,
             )))
             .unwrap();
-            println!(
+            log::trace!(
                 "{}:send_echo_req_or_complete:- send Complete to controller_tx",
                 self.name
             );
         } else {
-            println!("{}:send_echo_req_or_complete:- no controller_tx", self.name);
+            log::warn!("{}:send_echo_req_or_complete:- no controller_tx", self.name);
         }
     }
 
     pub fn state0(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
-        if let Some(msg) = msg_any.downcast_ref::<EchoRsp>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &ECHO_RSP_ID);
-            self.send_echo_req_or_complete(msg.counter + 1);
-        } else if let Some(msg) = msg_any.downcast_ref::<EchoReq>() {
-            println!("{}:State0: msg={msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &ECHO_REQ_ID);
-            let rsp_msg = Box::new(EchoRsp::new(
-                context.get_dst_instance_id(),
-                &self.instance_id,
-                msg.req_timestamp_ns,
-                msg.counter,
-            ));
-            println!("{}:State0: sending rsp_msg={rsp_msg:?}", self.name);
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<EchoStart>() {
-            println!("{}:State0: msg={msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &ECHO_START_ID);
-            self.partner_instance_id = Some(msg.partner_instance_id);
-            self.partner_sndr = sender_map_get(&self.partner_instance_id.unwrap());
-            self.controller_instance_id = Some(*msg.src_id());
-            self.controller_sndr = sender_map_get(&self.controller_instance_id.unwrap());
-            self.ping_count = msg.ping_count;
-            println!(
-                "{}:State0: Successfully connected to partner start echoing",
-                self.name
-            );
-            self.send_echo_req_or_complete(1);
-        } else if let Some(msg) = msg_any.downcast_ref::<Msg2>() {
-            // Got a Msg2 so self send a Msg1
-            println!("{}:State0: {msg:?}", self.name);
-            let msg1 = Box::new(Msg1::new(msg.src_id(), &self.instance_id, 123));
-            context.send_dst(msg1).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<CmdInit>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CMD_INIT_ID);
-
-            // Register ourselves with ConMgr
-            let msg = Box::new(ConMgrRegisterActorReq::new(
-                msg.src_id(),
-                &self.instance_id,
-                &self.name,
-                &self.actor_id,
-                &self.instance_id,
-                &self.protocol_set,
-            ));
-            context.send_con_mgr(msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<ConMgrRegisterActorRsp>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CON_MGR_REGISTER_ACTOR_RSP_ID);
-            assert_eq!(msg.status, ConMgrRegisterActorStatus::Success);
-        } else {
-            let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
-            println!(
-                "{}:State0: Unknown msg_any={msg_any:?} {msg_id:?}",
-                self.name
-            );
-        }
+        // Messages without a handler are unknown
+        base_state(self, context, msg_any);
+    }
+
+    fn handle_echo_rsp(&mut self, _context: &dyn ActorContext, msg: &EchoRsp) {
+        log::trace!("{}:State0: {msg:?}", self.name);
+        self.send_echo_req_or_complete(msg.counter + 1);
+    }
+
+    fn handle_echo_start(&mut self, _context: &dyn ActorContext, msg: &EchoStart) {
+        log::trace!("{}:State0: msg={msg:?}", self.name);
+        self.partner_instance_id = Some(msg.partner_instance_id);
+        self.partner_sndr = sender_map_get(&self.partner_instance_id.unwrap());
+        self.controller_instance_id = Some(*msg.src_id());
+        self.controller_sndr = sender_map_get(&self.controller_instance_id.unwrap());
+        self.ping_count = msg.ping_count;
+        log::trace!(
+            "{}:State0: Successfully connected to partner start echoing",
+            self.name
+        );
+        self.send_echo_req_or_complete(1);
+    }
+
+    fn handle_msg2(&mut self, context: &dyn ActorContext, msg: &Msg2) {
+        // Got a Msg2 so self send a Msg1
+        log::trace!("{}:State0: {msg:?}", self.name);
+        let msg1 = Box::new(Msg1::new(msg.src_id(), &self.instance_id, 123));
+        context.send_dst(msg1).unwrap();
     }
 }
 
@@ -271,6 +256,7 @@ impl Client {
 mod test {
     use super::*;
     use chrono::Utc;
+    use cmd_init_issuee_protocol::{CmdInit, CMD_READY_ID};
     use con_mgr_register_actor_protocol::{
         ConMgrRegisterActorRsp, ConMgrRegisterActorStatus, CON_MGR_REGISTER_ACTOR_REQ_ID,
     };
@@ -309,8 +295,8 @@ mod test {
             self.dst_sndr.get_dst_instance_id()
         }
 
-        fn clone_dst_sndr(&self) -> ActorSender {
-            self.dst_sndr.clone()
+        fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>> {
+            Ok(self.dst_sndr.clone())
         }
     }
 
@@ -352,6 +338,13 @@ mod test {
         ));
         client.process_msg_any(&client_context, msg);
 
+        // Client is ready and sends CmdReady to the issuer of CmdInit
+        let ready_msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(
+            MsgHeader::get_msg_id_from_boxed_msg_any(&ready_msg_any),
+            &CMD_READY_ID
+        );
+
         // Send Msg2 expect Msg1 back
         let msg = Box::new(Msg2::new(client.get_instance_id(), &supervisor_instance_id));
         client.process_msg_any(&client_context, msg);
@@ -402,6 +395,13 @@ mod test {
         ));
         client.process_msg_any(&supervisor_with_clnt_context, msg);
 
+        // Client is ready and sends CmdReady to the issuer of CmdInit
+        let ready_msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert_eq!(
+            MsgHeader::get_msg_id_from_boxed_msg_any(&ready_msg_any),
+            &CMD_READY_ID
+        );
+
         // Server channel with the supervisor as the server
         let srvr_instance_id = AnId::new();
         let srvr_chnl = ActorChannel::new("server", &srvr_instance_id);
@@ -1,37 +1,50 @@
 //! Connection Manager
 use std::error::Error;
 
-use actor::{Actor, ActorContext, ProcessMsgFn};
+use actor::{Actor, ActorContext};
 use actor_channel::{ActorChannel, ActorSender};
-use cmd_init_issuee_protocol::{cmd_init_issuee_protocol, CmdInit, CMD_INIT_ID};
-use con_mgr_query_protocol::{ConMgrQueryReq, ConMgrQueryRsp, CON_MGR_QUERY_REQ_ID};
+use actor_lifecycle::{add_lifecycle_handlers, ActorLifecycle, Lifecycle};
+use admin_protocol::{
+    admin_con_mgr_protocol, AdminRegistration, AdminRegistrationsReq, AdminRegistrationsRsp,
+    ADMIN_REGISTRATIONS_REQ_ID,
+};
+use cmd_init_issuee_protocol::cmd_init_issuee_protocol;
+use cmd_shutdown_issuee_protocol::cmd_shutdown_issuee_protocol;
+use con_mgr_federation_protocol::{
+    con_mgr_federation_issuee_protocol, con_mgr_federation_issuer_protocol, con_mgr_peer_protocol,
+    ConMgrAddPeerReq, ConMgrAddPeerRsp, ConMgrAddPeerStatus, ConMgrFederationDelta,
+    ConMgrFederationEntry, ConMgrFederationSnapshot, ConMgrFederationSnapshotReq,
+    ConMgrRemovePeerReq, ConMgrRemovePeerRsp, ConMgrRemovePeerStatus, CON_MGR_ADD_PEER_REQ_ID,
+    CON_MGR_FEDERATION_DELTA_ID, CON_MGR_FEDERATION_SNAPSHOT_ID,
+    CON_MGR_FEDERATION_SNAPSHOT_REQ_ID, CON_MGR_REMOVE_PEER_REQ_ID,
+};
+use con_mgr_query_protocol::{
+    con_mgr_query_protocol, ConMgrConnectReq, ConMgrConnectRsp, ConMgrConnectStatus,
+    ConMgrQueryReq, ConMgrQueryRsp, CON_MGR_CONNECT_REQ_ID, CON_MGR_QUERY_REQ_ID,
+};
 use con_mgr_register_actor_protocol::{
     con_mgr_register_actor_protocol, ConMgrRegisterActorReq, ConMgrRegisterActorRsp,
-    ConMgrRegisterActorStatus, CON_MGR_REGISTER_ACTOR_REQ_ID,
+    ConMgrRegisterActorStatus, ConMgrUnregisterActorReq, ConMgrUnregisterActorRsp,
+    ConMgrUnregisterActorStatus, CON_MGR_REGISTER_ACTOR_REQ_ID, CON_MGR_UNREGISTER_ACTOR_REQ_ID,
 };
 
 use an_id::{anid, paste, AnId};
-use echo_requestee_protocol::{echo_requestee_protocol, EchoReq, EchoRsp, ECHO_REQ_ID};
+use echo_requestee_protocol::echo_requestee_protocol;
+use id_inventory::register_id;
 use protocol::Protocol;
 use protocol_set::ProtocolSet;
 use sender_map_by_instance_id::sender_map_get;
 use sender_map_by_instance_id::sender_map_insert;
+use sender_map_by_instance_id::sender_map_remove;
+use state_machine::{
+    add_base_state, base_state, msg_handlers, state_machine, HasStateMachine, StateMachine,
+};
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
 };
 
 use box_msg_any::BoxMsgAny;
-use msg_header::MsgHeader;
-
-// State information
-#[derive(Debug)]
-pub struct StateInfo {
-    pub name: String,
-}
-
-// HashMap that maps address of a ProcessMsgFn to StateInfo
-type StateInfoMap<SM> = HashMap<*const ProcessMsgFn<SM>, StateInfo>;
 
 // State machine for channel to network
 pub struct ConMgr {
@@ -39,16 +52,37 @@ pub struct ConMgr {
     pub actor_id: AnId,
     pub instance_id: AnId,
     pub protocol_set: ProtocolSet,
-    pub current_state: ProcessMsgFn<Self>,
-    pub state_info_hash: StateInfoMap<Self>,
+    pub state_machine: StateMachine<Self>,
+    pub lifecycle: Lifecycle,
     pub chnl: ActorChannel,
 
     vec_of_actor_tx: Vec<ActorSender>,
+    vec_of_actor_protocol_set: Vec<ProtocolSet>,
     actors_map_by_instance_id: HashMap<AnId, usize>,
     actors_map_by_name: HashMap<String, Vec<usize>>,
     actors_map_by_id: HashMap<AnId, Vec<usize>>,
     actors_map_by_protocol_set_id: HashMap<AnId, Vec<usize>>,
     actors_map_by_protocol_id: HashMap<AnId, Vec<usize>>,
+    vec_of_actor_entry: Vec<ConMgrFederationEntry>,
+
+    peers: HashMap<AnId, ConMgrPeer>, // Key is the instance_id of the peer ConMgr
+    remote_actors: Vec<RemoteActor>,
+}
+
+/// A ConMgr in another process and the link its messages are sent on
+#[derive(Debug, Clone)]
+pub struct ConMgrPeer {
+    pub link_instance_id: AnId,
+    pub sender: ActorSender,
+}
+
+/// An actor registered with a peer ConMgr
+#[derive(Debug, Clone)]
+pub struct RemoteActor {
+    pub entry: ConMgrFederationEntry,
+    pub con_mgr_instance_id: AnId,
+    pub link_instance_id: AnId,
+    pub owns_sender: bool, // We added its sender to the sender_map so we remove it
 }
 
 // TODO: For Send implementors must guarantee maybe moved between threads. ??
@@ -75,33 +109,52 @@ impl Actor for ConMgr {
     }
 
     fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
-        (self.current_state)(self, context, msg);
+        self.dispatch(context, msg);
     }
 
     fn done(&self) -> bool {
         false
     }
+
+    fn get_protocol_set(&self) -> Option<&ProtocolSet> {
+        Some(&self.protocol_set)
+    }
+}
+
+impl HasStateMachine for ConMgr {
+    fn state_machine(&self) -> &StateMachine<Self> {
+        &self.state_machine
+    }
+
+    fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+        &mut self.state_machine
+    }
+}
+
+impl ActorLifecycle for ConMgr {
+    fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+        &mut self.lifecycle
+    }
 }
 
 impl Debug for ConMgr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let fn_ptr = self.current_state as *const ProcessMsgFn<Self>;
-        let fn_ptr_string = format!("{fn_ptr:p}");
-        let state_name = if let Some(si) = self.state_info_hash.get(&fn_ptr) {
-            // State does have a name
-            si.name.as_str()
-        } else {
-            // State does NOT have a name, use address
-            fn_ptr_string.as_str()
-        };
-
         write!(
             f,
-            "{} {{ id: {} instance_id: {} state_info_hash: {:?}; current_state: {state_name}",
-            self.name, self.actor_id, self.instance_id, self.state_info_hash
+            "{} {{ id: {} instance_id: {} state_machine: {:?}",
+            self.name, self.actor_id, self.instance_id, self.state_machine
         )?;
 
         write!(f, " vec_of_actor_tx: {:?},", self.vec_of_actor_tx,)?;
+        write!(
+            f,
+            " vec_of_actor_protocol_set: {:?},",
+            self.vec_of_actor_protocol_set
+        )?;
         write!(
             f,
             " actors_map_by_instance_id: {:?},",
@@ -116,9 +169,11 @@ impl Debug for ConMgr {
         )?;
         write!(
             f,
-            " actors_map_by_protocol_id: {:?} ",
+            " actors_map_by_protocol_id: {:?},",
             self.actors_map_by_protocol_id
         )?;
+        write!(f, " peers: {:?},", self.peers)?;
+        write!(f, " remote_actors: {:?} ", self.remote_actors)?;
         write!(f, "}}",)
     }
 }
@@ -126,14 +181,18 @@ impl Debug for ConMgr {
 // From: https://www.uuidgenerator.net/version4
 const CON_MGR_ACTOR_ID: AnId = anid!("3f82508e-7970-44e9-8fb9-b7936c9c4833");
 const CON_MGR_PROTOCOL_SET_ID: AnId = anid!("ea140384-faa7-4599-9f7d-dd4c2380a5fb");
+register_id!(Actor, CON_MGR_ACTOR_ID, "con_mgr");
+register_id!(ProtocolSet, CON_MGR_PROTOCOL_SET_ID, "con_mgr_ps");
 
 impl ConMgr {
     pub fn new(name: &str) -> Self {
         // Create the ConMgr ProtocolSet.
-        println!("ConMgr::new({})", name);
+        log::debug!("ConMgr::new({})", name);
         let mut cm_pm = HashMap::<AnId, Protocol>::new();
         let ci_protocol = cmd_init_issuee_protocol();
         cm_pm.insert(ci_protocol.id, ci_protocol.clone());
+        let cs_iep = cmd_shutdown_issuee_protocol();
+        cm_pm.insert(cs_iep.id, cs_iep.clone());
         let requestee_protocol = echo_requestee_protocol();
         cm_pm.insert(requestee_protocol.id, requestee_protocol.clone());
         let con_mgr_reg_actor_protoocl = con_mgr_register_actor_protocol();
@@ -141,21 +200,49 @@ impl ConMgr {
             con_mgr_reg_actor_protoocl.id,
             con_mgr_reg_actor_protoocl.clone(),
         );
+        let query_protocol = con_mgr_query_protocol();
+        cm_pm.insert(query_protocol.id, query_protocol.clone());
+        let federation_irp = con_mgr_federation_issuer_protocol();
+        cm_pm.insert(federation_irp.id, federation_irp.clone());
+        let federation_iep = con_mgr_federation_issuee_protocol();
+        cm_pm.insert(federation_iep.id, federation_iep.clone());
+        let peer_protocol = con_mgr_peer_protocol();
+        cm_pm.insert(peer_protocol.id, peer_protocol.clone());
+        let admin_protocol = admin_con_mgr_protocol();
+        cm_pm.insert(admin_protocol.id, admin_protocol.clone());
         let ps = ProtocolSet::new("con_mgr_ps", CON_MGR_PROTOCOL_SET_ID, cm_pm);
 
         let con_mgr_instance_id = AnId::new();
         let chnl = ActorChannel::new(name, &con_mgr_instance_id);
 
-        println!("ConMgr::new({}):", name);
-
-        let mut this = Self {
+        log::trace!("ConMgr::new({}):", name);
+
+        let mut state_machine = state_machine!(Self, [state0]);
+        let base = add_base_state(&mut state_machine);
+        state_machine.set_parent(Self::state0, base);
+        add_lifecycle_handlers(&mut state_machine, base);
+        msg_handlers!(state_machine, Self, state0, [
+            CON_MGR_REGISTER_ACTOR_REQ_ID: ConMgrRegisterActorReq => handle_con_mgr_register_actor_req,
+            CON_MGR_UNREGISTER_ACTOR_REQ_ID: ConMgrUnregisterActorReq => handle_con_mgr_unregister_actor_req,
+            CON_MGR_QUERY_REQ_ID: ConMgrQueryReq => handle_con_mgr_query_req,
+            CON_MGR_CONNECT_REQ_ID: ConMgrConnectReq => handle_con_mgr_connect_req,
+            CON_MGR_ADD_PEER_REQ_ID: ConMgrAddPeerReq => handle_con_mgr_add_peer_req,
+            CON_MGR_REMOVE_PEER_REQ_ID: ConMgrRemovePeerReq => handle_con_mgr_remove_peer_req,
+            CON_MGR_FEDERATION_SNAPSHOT_ID: ConMgrFederationSnapshot => handle_con_mgr_federation_snapshot,
+            CON_MGR_FEDERATION_SNAPSHOT_REQ_ID: ConMgrFederationSnapshotReq => handle_con_mgr_federation_snapshot_req,
+            CON_MGR_FEDERATION_DELTA_ID: ConMgrFederationDelta => handle_con_mgr_federation_delta,
+            ADMIN_REGISTRATIONS_REQ_ID: AdminRegistrationsReq => handle_admin_registrations_req,
+        ]);
+
+        let this = Self {
             name: name.to_owned(),
             actor_id: CON_MGR_ACTOR_ID,
             instance_id: con_mgr_instance_id,
             protocol_set: ps,
-            current_state: Self::state0,
-            state_info_hash: StateInfoMap::<Self>::new(),
+            state_machine,
+            lifecycle: Lifecycle::new(false),
             vec_of_actor_tx: Vec::new(),
+            vec_of_actor_protocol_set: Vec::new(),
             chnl,
             //actor_executor_instance_id: *actor_executor_instance_id,
             actors_map_by_instance_id: HashMap::new(),
@@ -163,40 +250,30 @@ impl ConMgr {
             actors_map_by_id: HashMap::new(),
             actors_map_by_protocol_id: HashMap::new(),
             actors_map_by_protocol_set_id: HashMap::new(),
+            vec_of_actor_entry: Vec::new(),
+            peers: HashMap::new(),
+            remote_actors: Vec::new(),
         };
 
         // Add ourself to the sender_map
         sender_map_insert(&this.instance_id, &this.chnl.sender);
 
-        this.add_state(Self::state0, "state0");
         this
     }
 
-    pub fn add_state(&mut self, state: ProcessMsgFn<Self>, name: &str) {
-        let s = StateInfo {
-            name: name.to_owned(),
-        };
-        let k = state as *const ProcessMsgFn<Self>;
-        self.state_info_hash.insert(k, s);
-    }
-
-    #[allow(unused)]
-    fn transition(&mut self, dest: ProcessMsgFn<Self>) {
-        self.current_state = dest;
-    }
-
     /// Add an Actor.
     pub fn add_actor(&mut self, msg: &ConMgrRegisterActorReq) -> Result<(), Box<dyn Error>> {
-        println!("{}::add_actor:+ msg={msg:?}", self.name);
+        log::debug!("{}::add_actor:+ msg={msg:?}", self.name);
 
         let idx = self.vec_of_actor_tx.len();
 
-        println!(
+        log::trace!(
             "{}::add_actor: add_map_by_instance_id={} idx={idx}",
-            self.name, msg.instance_id
+            self.name,
+            msg.instance_id
         );
         if let Some(idx) = self.actors_map_by_instance_id.insert(msg.instance_id, idx) {
-            println!("{}::add_actor: already added at idx={idx}", self.name);
+            log::warn!("{}::add_actor: already added at idx={idx}", self.name);
             return Err(format!(
                 "{}-{}::add_actor {} instance_id:{} : Actor already added at idx: {idx}",
                 self.name, self.actor_id, msg.name, msg.instance_id
@@ -206,26 +283,334 @@ impl ConMgr {
 
         let actor_tx = sender_map_get(&msg.instance_id).unwrap();
         self.vec_of_actor_tx.push(actor_tx);
+        self.vec_of_actor_protocol_set
+            .push(msg.protocol_set.clone());
 
         self.add_map_by_name(idx, &msg.name);
         self.add_map_by_id(idx, &msg.id);
         self.add_map_by_protocol_set(idx, &msg.protocol_set);
 
-        println!("{}::add_actor:- msg={msg:?}", self.name);
+        let entry =
+            ConMgrFederationEntry::new(&msg.name, &msg.id, &msg.instance_id, &msg.protocol_set);
+        self.send_delta_to_peers(std::slice::from_ref(&entry), &[]);
+        self.vec_of_actor_entry.push(entry);
+
+        log::debug!("{}::add_actor:- msg={msg:?}", self.name);
+        Ok(())
+    }
+
+    /// Remove an Actor.
+    ///
+    /// The actors entry in vec_of_actor_tx is left in place so the
+    /// indexes in the other maps remain valid.
+    pub fn remove_actor(&mut self, instance_id: &AnId) -> Result<(), Box<dyn Error>> {
+        log::debug!("{}::remove_actor:+ instance_id={instance_id}", self.name);
+
+        let Some(idx) = self.actors_map_by_instance_id.remove(instance_id) else {
+            return Err(format!(
+                "{}-{}::remove_actor instance_id:{instance_id} : Actor not registered",
+                self.name, self.actor_id
+            )
+            .into());
+        };
+
+        fn remove_idx<K>(map: &mut HashMap<K, Vec<usize>>, idx: usize) {
+            map.retain(|_, v| {
+                v.retain(|i| *i != idx);
+                !v.is_empty()
+            });
+        }
+        remove_idx(&mut self.actors_map_by_name, idx);
+        remove_idx(&mut self.actors_map_by_id, idx);
+        remove_idx(&mut self.actors_map_by_protocol_set_id, idx);
+        remove_idx(&mut self.actors_map_by_protocol_id, idx);
+
+        self.send_delta_to_peers(&[], &[*instance_id]);
+
+        log::debug!("{}::remove_actor:- instance_id={instance_id}", self.name);
         Ok(())
     }
 
+    /// Check that the actor at instance_id may connect to the actor at
+    /// peer_instance_id using protocol_id. The peers ProtocolSet must have
+    /// the complementary protocol, its id is returned.
+    ///
+    /// The peer may be a remote actor, its sender sends its messages over
+    /// the link to its process.
+    pub fn connect(
+        &self,
+        instance_id: &AnId,
+        peer_instance_id: &AnId,
+        protocol_id: &AnId,
+    ) -> Result<AnId, ConMgrConnectStatus> {
+        let Some(idx) = self.actors_map_by_instance_id.get(instance_id) else {
+            return Err(ConMgrConnectStatus::ActorNotRegistered);
+        };
+        let (peer_protocol_set, remote_sender) =
+            if let Some(peer_idx) = self.actors_map_by_instance_id.get(peer_instance_id) {
+                (&self.vec_of_actor_protocol_set[*peer_idx], None)
+            } else if let Some(remote_actor) = self.remote_actor(peer_instance_id) {
+                let Some(sender) = self.remote_sender(remote_actor) else {
+                    return Err(ConMgrConnectStatus::PeerUnreachable);
+                };
+                (&remote_actor.entry.protocol_set, Some(sender))
+            } else {
+                return Err(ConMgrConnectStatus::PeerNotRegistered);
+            };
+        let Some(protocol) = self.vec_of_actor_protocol_set[*idx]
+            .protocols_map
+            .get(protocol_id)
+        else {
+            return Err(ConMgrConnectStatus::ProtocolNotInProtocolSet);
+        };
+        let Some(peer_protocol) = peer_protocol_set.complement_of(protocol) else {
+            return Err(ConMgrConnectStatus::PeerHasNoComplementaryProtocol);
+        };
+
+        if let Some(sender) = remote_sender {
+            // Ignored if the peer already has a sender
+            sender_map_insert(peer_instance_id, &sender);
+        }
+        Ok(peer_protocol.id)
+    }
+
+    /// The instance_ids of the actors matching all of the criteria that
+    /// are Some. The local actors, in the order they registered, are
+    /// followed by the remote actors.
+    pub fn query(
+        &self,
+        name: Option<&str>,
+        id: Option<&AnId>,
+        protocol_id: Option<&AnId>,
+        protocol_set_id: Option<&AnId>,
+    ) -> Vec<AnId> {
+        let mut local_idxs: Vec<usize> = self.actors_map_by_instance_id.values().copied().collect();
+        local_idxs.sort();
+        let local = local_idxs
+            .into_iter()
+            .map(|idx| &self.vec_of_actor_entry[idx])
+            .filter(|entry| entry.matches(name, id, protocol_id, protocol_set_id));
+        let remote = self
+            .remote_actors
+            .iter()
+            .map(|remote_actor| &remote_actor.entry)
+            .filter(|entry| entry.matches(name, id, protocol_id, protocol_set_id));
+
+        local.chain(remote).map(|entry| entry.instance_id).collect()
+    }
+
+    /// The registered local actors, in the order they registered,
+    /// followed by the remote actors.
+    pub fn registrations(&self) -> Vec<AdminRegistration> {
+        let mut local_idxs: Vec<usize> = self.actors_map_by_instance_id.values().copied().collect();
+        local_idxs.sort();
+        let local = local_idxs
+            .into_iter()
+            .map(|idx| (&self.vec_of_actor_entry[idx], None));
+        let remote = self
+            .remote_actors
+            .iter()
+            .map(|remote_actor| (&remote_actor.entry, Some(remote_actor.link_instance_id)));
+
+        local
+            .chain(remote)
+            .map(|(entry, link_instance_id)| AdminRegistration {
+                name: entry.name.clone(),
+                id: entry.id,
+                instance_id: entry.instance_id,
+                protocol_set_name: entry.protocol_set.name.clone(),
+                link_instance_id,
+            })
+            .collect()
+    }
+
+    /// Peer with the ConMgr at peer_con_mgr_instance_id sending it a
+    /// snapshot of the actors registered with us. The peer is asked for
+    /// its snapshot as it ignored any it sent before we peered.
+    pub fn add_peer(
+        &mut self,
+        peer_con_mgr_instance_id: &AnId,
+        link_instance_id: &AnId,
+        sender: &ActorSender,
+    ) -> ConMgrAddPeerStatus {
+        log::debug!(
+            "{}::add_peer: peer_con_mgr_instance_id={peer_con_mgr_instance_id} link_instance_id={link_instance_id}",
+            self.name
+        );
+        if self.peers.contains_key(peer_con_mgr_instance_id) {
+            return ConMgrAddPeerStatus::PeerAlreadyAdded;
+        }
+        let peer = ConMgrPeer {
+            link_instance_id: *link_instance_id,
+            sender: sender.clone(),
+        };
+        self.send_snapshot(peer_con_mgr_instance_id, &peer);
+        let msg = Box::new(ConMgrFederationSnapshotReq::new(
+            peer_con_mgr_instance_id,
+            &self.instance_id,
+        ));
+        if let Err(why) = peer.sender.send(msg) {
+            log::warn!("{}::add_peer: error sending snapshot req {why}", self.name);
+        }
+
+        self.peers.insert(*peer_con_mgr_instance_id, peer);
+        ConMgrAddPeerStatus::Success
+    }
+
+    /// Stop peering with the ConMgr at peer_con_mgr_instance_id, its
+    /// actors are forgotten.
+    pub fn remove_peer(&mut self, peer_con_mgr_instance_id: &AnId) -> ConMgrRemovePeerStatus {
+        log::debug!(
+            "{}::remove_peer: peer_con_mgr_instance_id={peer_con_mgr_instance_id}",
+            self.name
+        );
+        if self.peers.remove(peer_con_mgr_instance_id).is_none() {
+            return ConMgrRemovePeerStatus::PeerNotFound;
+        }
+        self.remove_remote_actors(peer_con_mgr_instance_id, None);
+        ConMgrRemovePeerStatus::Success
+    }
+
+    fn send_snapshot(&self, peer_con_mgr_instance_id: &AnId, peer: &ConMgrPeer) {
+        let mut local_idxs: Vec<usize> = self.actors_map_by_instance_id.values().copied().collect();
+        local_idxs.sort();
+        let entries: Vec<ConMgrFederationEntry> = local_idxs
+            .into_iter()
+            .map(|idx| self.vec_of_actor_entry[idx].clone())
+            .collect();
+        let msg = Box::new(ConMgrFederationSnapshot::new(
+            peer_con_mgr_instance_id,
+            &self.instance_id,
+            &entries,
+        ));
+        if let Err(why) = peer.sender.send(msg) {
+            log::warn!(
+                "{}::send_snapshot: error sending to {peer_con_mgr_instance_id} {why}",
+                self.name
+            );
+        }
+    }
+
+    /// The link a remote actor is reachable through
+    pub fn remote_link(&self, instance_id: &AnId) -> Option<&AnId> {
+        self.remote_actor(instance_id)
+            .map(|remote_actor| &remote_actor.link_instance_id)
+    }
+
+    fn remote_actor(&self, instance_id: &AnId) -> Option<&RemoteActor> {
+        self.remote_actors
+            .iter()
+            .find(|remote_actor| remote_actor.entry.instance_id == *instance_id)
+    }
+
+    /// A sender for remote_actor which sends over the link to its peer,
+    /// None if its ConMgr isn't a peer
+    fn remote_sender(&self, remote_actor: &RemoteActor) -> Option<ActorSender> {
+        let peer = self.peers.get(&remote_actor.con_mgr_instance_id)?;
+        Some(
+            peer.sender
+                .with_dst(&remote_actor.entry.name, &remote_actor.entry.instance_id),
+        )
+    }
+
+    /// Remember the actors of the peer and give each a sender, so local
+    /// actors can answer them without connecting first
+    fn add_remote_actors(
+        &mut self,
+        con_mgr_instance_id: &AnId,
+        link_instance_id: &AnId,
+        entries: &[ConMgrFederationEntry],
+    ) {
+        for entry in entries {
+            if self
+                .actors_map_by_instance_id
+                .contains_key(&entry.instance_id)
+            {
+                log::warn!(
+                    "{}::add_remote_actors: {} is registered locally, ignoring",
+                    self.name,
+                    entry.instance_id
+                );
+                continue;
+            }
+            self.remove_remote_actor(&entry.instance_id);
+            let mut remote_actor = RemoteActor {
+                entry: entry.clone(),
+                con_mgr_instance_id: *con_mgr_instance_id,
+                link_instance_id: *link_instance_id,
+                owns_sender: false,
+            };
+            if let Some(sender) = self.remote_sender(&remote_actor) {
+                // Not added if the actor already has a sender
+                remote_actor.owns_sender = sender_map_insert(&entry.instance_id, &sender);
+            }
+            self.remote_actors.push(remote_actor);
+        }
+    }
+
+    /// Forget the actors of the peer at con_mgr_instance_id whose
+    /// instance_ids are listed, or all of them if None, and their senders.
+    fn remove_remote_actors(&mut self, con_mgr_instance_id: &AnId, instance_ids: Option<&[AnId]>) {
+        let (removed, kept): (Vec<RemoteActor>, Vec<RemoteActor>) =
+            std::mem::take(&mut self.remote_actors)
+                .into_iter()
+                .partition(|remote_actor| {
+                    remote_actor.con_mgr_instance_id == *con_mgr_instance_id
+                        && instance_ids.is_none_or(|instance_ids| {
+                            instance_ids.contains(&remote_actor.entry.instance_id)
+                        })
+                });
+        self.remote_actors = kept;
+        for remote_actor in removed {
+            if remote_actor.owns_sender {
+                sender_map_remove(&remote_actor.entry.instance_id);
+            }
+        }
+    }
+
+    /// Forget the remote actor with instance_id, if there is one, and its
+    /// sender if we added it
+    fn remove_remote_actor(&mut self, instance_id: &AnId) {
+        let Some(idx) = self
+            .remote_actors
+            .iter()
+            .position(|remote_actor| remote_actor.entry.instance_id == *instance_id)
+        else {
+            return;
+        };
+        if self.remote_actors.remove(idx).owns_sender {
+            sender_map_remove(instance_id);
+        }
+    }
+
+    fn send_delta_to_peers(&self, added: &[ConMgrFederationEntry], removed: &[AnId]) {
+        for (peer_con_mgr_instance_id, peer) in self.peers.iter() {
+            let msg = Box::new(ConMgrFederationDelta::new(
+                peer_con_mgr_instance_id,
+                &self.instance_id,
+                added,
+                removed,
+            ));
+            if let Err(why) = peer.sender.send(msg) {
+                log::warn!(
+                    "{}::send_delta_to_peers: error sending to {peer_con_mgr_instance_id} {why}",
+                    self.name
+                );
+            }
+        }
+    }
+
     fn add_map_by_name(&mut self, idx: usize, name: &str) {
         if let Some(v) = self.actors_map_by_name.get_mut(name) {
             // Add another actor with that name
-            println!(
+            log::trace!(
                 "{}::add_map_by_name: another instance of name={name} push idx={idx}",
                 self.name
             );
             v.push(idx);
         } else {
             // First time seeing this name, add to vector with one item
-            println!(
+            log::trace!(
                 "{}::add_map_by_name: first instance of name={name} add vec with idx={idx}",
                 self.name
             );
@@ -236,14 +621,14 @@ impl ConMgr {
     fn add_map_by_id(&mut self, idx: usize, id: &AnId) {
         if let Some(v) = self.actors_map_by_id.get_mut(id) {
             // Add another idx
-            println!(
+            log::trace!(
                 "{}::add_map_by_id: another instance of id={id} push idx={idx}",
                 self.name
             );
             v.push(idx);
         } else {
             // First time seeing this actor_id, add vector with one item
-            println!(
+            log::trace!(
                 "{}::add_map_by_id: first instance of id={id} add vec with idx={idx}",
                 self.name
             );
@@ -254,14 +639,15 @@ impl ConMgr {
     fn add_map_by_protocol_set(&mut self, idx: usize, ps: &ProtocolSet) {
         if let Some(v) = self.actors_map_by_protocol_set_id.get_mut(&ps.id) {
             // Add another idx
-            println!(
+            log::trace!(
                 "{}::add_map_by_protocol_id: another instance of protocol_set_id={} push idx={idx}",
-                self.name, ps.id
+                self.name,
+                ps.id
             );
             v.push(idx);
         } else {
             // First time seeing this protocol_set, add vector with one item
-            println!("{}::add_map_by_protocol_id: first instance of protocol_set_id={} add vec with idx={idx}", self.name, ps.id);
+            log::trace!("{}::add_map_by_protocol_id: first instance of protocol_set_id={} add vec with idx={idx}", self.name, ps.id);
             self.actors_map_by_protocol_set_id.insert(ps.id, vec![idx]);
 
             self.add_map_by_protocol_id(idx, ps);
@@ -273,70 +659,209 @@ impl ConMgr {
 
         for k in protocol_map.keys() {
             if let Some(v) = self.actors_map_by_protocol_id.get_mut(k) {
-                #[cfg(test)]
-                println!("{}::add_map_by_protocol_id: another instance of protocol_id={k} push idx={idx}", self.name);
+                log::trace!("{}::add_map_by_protocol_id: another instance of protocol_id={k} push idx={idx}", self.name);
                 v.push(idx);
             } else {
                 // First time seeing this protocol_id, add vector with one item
-                #[cfg(test)]
-                println!("{}::add_map_by_protocol_id: first instance of protocol_id={k}, add vec with idx={idx}", self.name);
+                log::trace!("{}::add_map_by_protocol_id: first instance of protocol_id={k}, add vec with idx={idx}", self.name);
                 self.actors_map_by_protocol_id.insert(*k, vec![idx]);
             }
         }
     }
 
     pub fn state0(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
-        if let Some(msg) = msg_any.downcast_ref::<ConMgrRegisterActorReq>() {
-            println!("{}:State0: msg={msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CON_MGR_REGISTER_ACTOR_REQ_ID);
-            let status = if self.add_actor(msg).is_ok() {
-                ConMgrRegisterActorStatus::Success
-            } else {
-                ConMgrRegisterActorStatus::ActorAlreadyRegistered
-            };
+        // Messages without a handler are unknown
+        base_state(self, context, msg_any);
+    }
 
-            println!("Sending ConMgrRegisterActorRsp");
-            context
-                .send_dst(Box::new(ConMgrRegisterActorRsp::new(
-                    context.get_dst_instance_id(),
-                    &self.instance_id,
-                    status,
-                )))
-                .unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<ConMgrQueryReq>() {
-            println!(
-                "{}:State0: msg={msg:?} TODO response is ALWAYS empty, fix!",
-                self.name
-            );
-            assert_eq!(msg.msg_id(), &CON_MGR_QUERY_REQ_ID);
-            context
-                .send_dst(Box::new(ConMgrQueryRsp::new(
-                    context.get_dst_instance_id(),
-                    &self.instance_id,
-                    &[],
-                )))
-                .unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<EchoReq>() {
-            //println!("{}:State0: msg={msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &ECHO_REQ_ID);
-            let rsp_msg = Box::new(EchoRsp::new(
+    fn handle_con_mgr_register_actor_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &ConMgrRegisterActorReq,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let status = if self.add_actor(msg).is_ok() {
+            ConMgrRegisterActorStatus::Success
+        } else {
+            ConMgrRegisterActorStatus::ActorAlreadyRegistered
+        };
+
+        log::trace!("Sending ConMgrRegisterActorRsp");
+        context
+            .send_dst(Box::new(ConMgrRegisterActorRsp::new(
                 context.get_dst_instance_id(),
                 &self.instance_id,
-                msg.req_timestamp_ns,
-                msg.counter,
-            ));
-            //println!("{}:State0: sending rsp_msg={rsp_msg:?}", self.name);
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<CmdInit>() {
-            println!("{}:State0: {msg:?} nothing to do", self.name);
-            assert_eq!(msg.msg_id(), &CMD_INIT_ID);
+                status,
+            )))
+            .unwrap();
+    }
+
+    fn handle_con_mgr_unregister_actor_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &ConMgrUnregisterActorReq,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let status = if self.remove_actor(&msg.instance_id).is_ok() {
+            ConMgrUnregisterActorStatus::Success
         } else {
-            let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
-            println!(
-                "{}:State0: Unknown msg_any={msg_any:?} {msg_id:?}",
+            ConMgrUnregisterActorStatus::ActorNotRegistered
+        };
+        context
+            .send_dst(Box::new(ConMgrUnregisterActorRsp::new(
+                context.get_dst_instance_id(),
+                &self.instance_id,
+                &msg.instance_id,
+                status,
+            )))
+            .unwrap();
+    }
+
+    fn handle_con_mgr_query_req(&mut self, context: &dyn ActorContext, msg: &ConMgrQueryReq) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let instance_ids = self.query(
+            msg.name.as_deref(),
+            msg.id.as_ref(),
+            msg.protocol_id.as_ref(),
+            msg.protocol_set_id.as_ref(),
+        );
+        context
+            .send_dst(Box::new(ConMgrQueryRsp::new(
+                context.get_dst_instance_id(),
+                &self.instance_id,
+                &instance_ids,
+            )))
+            .unwrap();
+    }
+
+    fn handle_con_mgr_connect_req(&mut self, context: &dyn ActorContext, msg: &ConMgrConnectReq) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let (peer_protocol_id, status) =
+            match self.connect(&msg.header.src_id, &msg.instance_id, &msg.protocol_id) {
+                Ok(peer_protocol_id) => (Some(peer_protocol_id), ConMgrConnectStatus::Success),
+                Err(status) => {
+                    log::warn!("{}:State0: {msg:?} refused: {status:?}", self.name);
+                    (None, status)
+                }
+            };
+        context
+            .send_dst(Box::new(ConMgrConnectRsp::new(
+                context.get_dst_instance_id(),
+                &self.instance_id,
+                &msg.instance_id,
+                &msg.protocol_id,
+                peer_protocol_id,
+                status,
+            )))
+            .unwrap();
+    }
+
+    fn handle_con_mgr_add_peer_req(&mut self, context: &dyn ActorContext, msg: &ConMgrAddPeerReq) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let status = self.add_peer(
+            &msg.peer_con_mgr_instance_id,
+            &msg.link_instance_id,
+            &msg.sender,
+        );
+        context
+            .send_dst(Box::new(ConMgrAddPeerRsp::new(
+                context.get_dst_instance_id(),
+                &self.instance_id,
+                &msg.peer_con_mgr_instance_id,
+                status,
+            )))
+            .unwrap();
+    }
+
+    fn handle_con_mgr_remove_peer_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &ConMgrRemovePeerReq,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let status = self.remove_peer(&msg.peer_con_mgr_instance_id);
+        context
+            .send_dst(Box::new(ConMgrRemovePeerRsp::new(
+                context.get_dst_instance_id(),
+                &self.instance_id,
+                &msg.peer_con_mgr_instance_id,
+                status,
+            )))
+            .unwrap();
+    }
+
+    /// The link of the peer that sent msg, None if it isn't a peer and
+    /// msg is to be ignored
+    fn peer_link(&self, msg: &dyn Debug, src_id: &AnId) -> Option<AnId> {
+        let link_instance_id = self.peers.get(src_id).map(|peer| peer.link_instance_id);
+        if link_instance_id.is_none() {
+            log::warn!(
+                "{}:State0: ignoring {msg:?}, {src_id} isn't a peer",
                 self.name
             );
         }
+        link_instance_id
+    }
+
+    fn handle_con_mgr_federation_snapshot(
+        &mut self,
+        _context: &dyn ActorContext,
+        msg: &ConMgrFederationSnapshot,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let con_mgr_instance_id = *msg.src_id();
+        let Some(link_instance_id) = self.peer_link(msg, &con_mgr_instance_id) else {
+            return;
+        };
+        let stale: Vec<AnId> = self
+            .remote_actors
+            .iter()
+            .filter(|remote_actor| remote_actor.con_mgr_instance_id == con_mgr_instance_id)
+            .map(|remote_actor| remote_actor.entry.instance_id)
+            .filter(|instance_id| !msg.entries.iter().any(|e| e.instance_id == *instance_id))
+            .collect();
+        self.remove_remote_actors(&con_mgr_instance_id, Some(&stale));
+        self.add_remote_actors(&con_mgr_instance_id, &link_instance_id, &msg.entries);
+    }
+
+    fn handle_con_mgr_federation_snapshot_req(
+        &mut self,
+        _context: &dyn ActorContext,
+        msg: &ConMgrFederationSnapshotReq,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        if self.peer_link(msg, msg.src_id()).is_some() {
+            self.send_snapshot(msg.src_id(), &self.peers[msg.src_id()]);
+        }
+    }
+
+    fn handle_con_mgr_federation_delta(
+        &mut self,
+        _context: &dyn ActorContext,
+        msg: &ConMgrFederationDelta,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let con_mgr_instance_id = *msg.src_id();
+        let Some(link_instance_id) = self.peer_link(msg, &con_mgr_instance_id) else {
+            return;
+        };
+        self.remove_remote_actors(&con_mgr_instance_id, Some(&msg.removed));
+        self.add_remote_actors(&con_mgr_instance_id, &link_instance_id, &msg.added);
+    }
+
+    fn handle_admin_registrations_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &AdminRegistrationsReq,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        context
+            .send_dst(Box::new(AdminRegistrationsRsp::new(
+                context.get_dst_instance_id(),
+                &self.instance_id,
+                &self.registrations(),
+            )))
+            .unwrap();
     }
 }
 
@@ -345,13 +870,14 @@ mod test {
     use super::*;
     use chrono::Utc;
     use client::Client;
-    use cmd_init_issuer_protocol::CmdInit;
+    use cmd_init_issuer_protocol::{CmdInit, CmdReady, CmdReadyStatus};
     use con_mgr_register_actor_protocol::{
         CON_MGR_REGISTER_ACTOR_REQ_ID, CON_MGR_REGISTER_ACTOR_RSP_ID,
     };
-    use echo_requestee_protocol::echo_requestee_protocol;
+    use echo_requestee_protocol::{echo_requestee_protocol, EchoReq, EchoRsp};
     use echo_requester_protocol::echo_requester_protocol;
     use echo_start_complete_protocol::echo_start_complete_protocol;
+    use msg_header::MsgHeader;
     use server::Server;
     struct Context {
         ae_sndr: ActorSender,
@@ -384,8 +910,8 @@ mod test {
             &self.dst_sndr.dst_instance_id
         }
 
-        fn clone_dst_sndr(&self) -> ActorSender {
-            self.dst_sndr.clone()
+        fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>> {
+            Ok(self.dst_sndr.clone())
         }
     }
 
@@ -414,6 +940,11 @@ mod test {
 
         issue_cmd_init(&mut context, &mut con_mgr, &supervisor_instance_id);
 
+        // ConMgr doesn't register with itself so it's ready immediately
+        let ready_msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let ready_msg = ready_msg_any.downcast_ref::<CmdReady>().unwrap();
+        assert_eq!(ready_msg.status, CmdReadyStatus::Ready);
+
         // Warm up reading time stamp
         let first_now_ns = Utc::now().timestamp_nanos();
         let second_now_ns = Utc::now().timestamp_nanos();
@@ -612,7 +1143,7 @@ mod test {
                 .unwrap(),
             &vec![0]
         );
-        assert_eq!(con_mgr.actors_map_by_protocol_id.len(), 4);
+        assert_eq!(con_mgr.actors_map_by_protocol_id.len(), 6);
         assert_eq!(
             con_mgr
                 .actors_map_by_protocol_id
@@ -701,7 +1232,7 @@ mod test {
                 .unwrap(),
             &vec![1]
         );
-        assert_eq!(con_mgr.actors_map_by_protocol_id.len(), 4);
+        assert_eq!(con_mgr.actors_map_by_protocol_id.len(), 6);
         assert_eq!(
             con_mgr
                 .actors_map_by_protocol_id
@@ -717,6 +1248,326 @@ mod test {
             &vec![0, 1]
         );
 
+        // The client can connect to the server as an echo requester
+        assert_eq!(
+            con_mgr.connect(
+                &client.instance_id,
+                &server.instance_id,
+                &echo_requester_protocol().id
+            ),
+            Ok(echo_requestee_protocol().id)
+        );
+
+        // But not as a cmd init issuee, the server is also an issuee
+        assert_eq!(
+            con_mgr.connect(
+                &client.instance_id,
+                &server.instance_id,
+                &cmd_init_issuee_protocol().id
+            ),
+            Err(ConMgrConnectStatus::PeerHasNoComplementaryProtocol)
+        );
+
+        // Nor with a protocol it doesn't implement
+        assert_eq!(
+            con_mgr.connect(
+                &server.instance_id,
+                &client.instance_id,
+                &echo_requester_protocol().id
+            ),
+            Err(ConMgrConnectStatus::ProtocolNotInProtocolSet)
+        );
+        assert_eq!(
+            con_mgr.connect(
+                &client.instance_id,
+                &AnId::new(),
+                &echo_requester_protocol().id
+            ),
+            Err(ConMgrConnectStatus::PeerNotRegistered)
+        );
+
+        // Via a ConMgrConnectReq from the client
+        let msg = Box::new(ConMgrConnectReq::new(
+            &con_mgr.instance_id,
+            &client.instance_id,
+            &server.instance_id,
+            &echo_requester_protocol().id,
+        ));
+        context.dst_sndr = sender_map_get(&client.instance_id).unwrap();
+        con_mgr.process_msg_any(&context, msg);
+        let msg_any = client.chnl.receiver.recv().unwrap();
+        let rsp = msg_any.downcast_ref::<ConMgrConnectRsp>().unwrap();
+        assert_eq!(rsp.status, ConMgrConnectStatus::Success);
+        assert_eq!(rsp.instance_id, server.instance_id);
+        assert_eq!(rsp.peer_protocol_id, Some(echo_requestee_protocol().id));
+
         println!("test_reg_client_server:-");
     }
+
+    #[test]
+    fn test_federation() {
+        println!("\ntest_federation:+");
+
+        let supervisor_instance_id = AnId::new();
+        let supervisor_chnl = ActorChannel::new("supervisor", &supervisor_instance_id);
+        sender_map_insert(&supervisor_instance_id, &supervisor_chnl.sender);
+
+        let mut con_mgr = ConMgr::new("con_mgr");
+        let mut context = Context {
+            ae_sndr: supervisor_chnl.sender.clone(),
+            con_mgr_sndr: con_mgr.chnl.sender.clone(),
+            dst_sndr: supervisor_chnl.sender.clone(),
+        };
+        issue_cmd_init(&mut context, &mut con_mgr, &supervisor_instance_id);
+        let ready_msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(ready_msg_any.downcast_ref::<CmdReady>().is_some());
+
+        // A local echo requester
+        let mut requester_pm = HashMap::<AnId, Protocol>::new();
+        let erp = echo_requester_protocol();
+        requester_pm.insert(erp.id, erp.clone());
+        let requester_ps = ProtocolSet::new("requester_ps", AnId::new(), requester_pm);
+        let local_instance_id = AnId::new();
+        let local_chnl = ActorChannel::new("local", &local_instance_id);
+        sender_map_insert(&local_instance_id, &local_chnl.sender);
+        con_mgr
+            .add_actor(&ConMgrRegisterActorReq::new(
+                &con_mgr.instance_id,
+                &local_instance_id,
+                "local",
+                &AnId::new(),
+                &local_instance_id,
+                &requester_ps,
+            ))
+            .unwrap();
+
+        // An echo requestee registered with a ConMgr in another process
+        let mut requestee_pm = HashMap::<AnId, Protocol>::new();
+        let erep = echo_requestee_protocol();
+        requestee_pm.insert(erep.id, erep.clone());
+        let requestee_ps = ProtocolSet::new("requestee_ps", AnId::new(), requestee_pm);
+        let peer_con_mgr_instance_id = AnId::new();
+        let remote_instance_id = AnId::new();
+        let remote_entry =
+            ConMgrFederationEntry::new("remote", &AnId::new(), &remote_instance_id, &requestee_ps);
+
+        // The snapshot of a ConMgr we haven't peered with is ignored
+        let snapshot_msg = Box::new(ConMgrFederationSnapshot::new(
+            &con_mgr.instance_id,
+            &peer_con_mgr_instance_id,
+            std::slice::from_ref(&remote_entry),
+        ));
+        con_mgr.process_msg_any(&context, snapshot_msg.clone());
+        assert_eq!(
+            con_mgr.query(None, None, None, None),
+            vec![local_instance_id]
+        );
+        assert_eq!(con_mgr.remote_link(&remote_instance_id), None);
+        assert_eq!(
+            con_mgr.connect(&local_instance_id, &remote_instance_id, &erp.id),
+            Err(ConMgrConnectStatus::PeerNotRegistered)
+        );
+
+        // Peer over a link, the link channel stands in for the forwarder
+        let link_instance_id = AnId::new();
+        let link_chnl = ActorChannel::new("link", &link_instance_id);
+        let add_peer_req = Box::new(ConMgrAddPeerReq::new(
+            &con_mgr.instance_id,
+            &supervisor_instance_id,
+            &peer_con_mgr_instance_id,
+            &link_instance_id,
+            &link_chnl.sender,
+        ));
+        con_mgr.process_msg_any(&context, add_peer_req.clone());
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let rsp = msg_any.downcast_ref::<ConMgrAddPeerRsp>().unwrap();
+        assert_eq!(rsp.status, ConMgrAddPeerStatus::Success);
+
+        // The peer is sent a snapshot of our actors and asked for its own
+        let msg_any = link_chnl.receiver.recv().unwrap();
+        let snapshot = msg_any.downcast_ref::<ConMgrFederationSnapshot>().unwrap();
+        assert_eq!(snapshot.dst_id(), &peer_con_mgr_instance_id);
+        assert_eq!(snapshot.entries.len(), 1);
+        assert_eq!(snapshot.entries[0].instance_id, local_instance_id);
+        let msg_any = link_chnl.receiver.recv().unwrap();
+        let snapshot_req = msg_any
+            .downcast_ref::<ConMgrFederationSnapshotReq>()
+            .unwrap();
+        assert_eq!(snapshot_req.dst_id(), &peer_con_mgr_instance_id);
+
+        // The peer answers and the remote actor is now reachable
+        con_mgr.process_msg_any(&context, snapshot_msg);
+        assert_eq!(
+            con_mgr.query(None, None, None, None),
+            vec![local_instance_id, remote_instance_id]
+        );
+        assert_eq!(
+            con_mgr.query(None, None, Some(&erep.id), None),
+            vec![remote_instance_id]
+        );
+        assert_eq!(
+            con_mgr.remote_link(&remote_instance_id),
+            Some(&link_instance_id)
+        );
+
+        // An entry with the instance_id of a local actor is ignored
+        let local_entry =
+            ConMgrFederationEntry::new("local", &AnId::new(), &local_instance_id, &requestee_ps);
+        con_mgr.process_msg_any(
+            &context,
+            Box::new(ConMgrFederationDelta::new(
+                &con_mgr.instance_id,
+                &peer_con_mgr_instance_id,
+                std::slice::from_ref(&local_entry),
+                &[],
+            )),
+        );
+        assert_eq!(con_mgr.remote_link(&local_instance_id), None);
+        assert_eq!(
+            con_mgr.query(None, None, None, None),
+            vec![local_instance_id, remote_instance_id]
+        );
+
+        // A remote actor whose sender we didn't add keeps it when removed
+        let other_instance_id = AnId::new();
+        let other_chnl = ActorChannel::new("other", &other_instance_id);
+        sender_map_insert(&other_instance_id, &other_chnl.sender);
+        let other_entry =
+            ConMgrFederationEntry::new("other", &AnId::new(), &other_instance_id, &requestee_ps);
+        con_mgr.process_msg_any(
+            &context,
+            Box::new(ConMgrFederationDelta::new(
+                &con_mgr.instance_id,
+                &peer_con_mgr_instance_id,
+                std::slice::from_ref(&other_entry),
+                &[],
+            )),
+        );
+        assert_eq!(
+            con_mgr.remote_link(&other_instance_id),
+            Some(&link_instance_id)
+        );
+        con_mgr.process_msg_any(
+            &context,
+            Box::new(ConMgrFederationDelta::new(
+                &con_mgr.instance_id,
+                &peer_con_mgr_instance_id,
+                &[],
+                &[other_instance_id],
+            )),
+        );
+        assert_eq!(con_mgr.remote_link(&other_instance_id), None);
+        assert!(sender_map_get(&other_instance_id).is_some());
+        sender_map_remove(&other_instance_id);
+
+        // Connecting checks the protocols, the remote actor's sender
+        // sends over the link
+        assert_eq!(
+            con_mgr.connect(&local_instance_id, &remote_instance_id, &erp.id),
+            Ok(erep.id)
+        );
+        let remote_sndr = sender_map_get(&remote_instance_id).unwrap();
+        remote_sndr
+            .send(Box::new(EchoReq::new(
+                &remote_instance_id,
+                &local_instance_id,
+                1,
+            )))
+            .unwrap();
+        let msg_any = link_chnl.receiver.recv().unwrap();
+        let echo_req = msg_any.downcast_ref::<EchoReq>().unwrap();
+        assert_eq!(echo_req.dst_id(), &remote_instance_id);
+
+        // Peering twice is rejected
+        con_mgr.process_msg_any(&context, add_peer_req);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let rsp = msg_any.downcast_ref::<ConMgrAddPeerRsp>().unwrap();
+        assert_eq!(rsp.status, ConMgrAddPeerStatus::PeerAlreadyAdded);
+
+        // The peer asking for a snapshot gets one
+        con_mgr.process_msg_any(
+            &context,
+            Box::new(ConMgrFederationSnapshotReq::new(
+                &con_mgr.instance_id,
+                &peer_con_mgr_instance_id,
+            )),
+        );
+        let msg_any = link_chnl.receiver.recv().unwrap();
+        let snapshot = msg_any.downcast_ref::<ConMgrFederationSnapshot>().unwrap();
+        assert_eq!(snapshot.entries[0].instance_id, local_instance_id);
+
+        // A delta from another ConMgr is ignored
+        con_mgr.process_msg_any(
+            &context,
+            Box::new(ConMgrFederationDelta::new(
+                &con_mgr.instance_id,
+                &AnId::new(),
+                &[],
+                &[remote_instance_id],
+            )),
+        );
+        assert_eq!(
+            con_mgr.remote_link(&remote_instance_id),
+            Some(&link_instance_id)
+        );
+
+        // A delta from the peer removes the remote actor and its sender
+        con_mgr.process_msg_any(
+            &context,
+            Box::new(ConMgrFederationDelta::new(
+                &con_mgr.instance_id,
+                &peer_con_mgr_instance_id,
+                &[],
+                &[remote_instance_id],
+            )),
+        );
+        assert_eq!(
+            con_mgr.query(None, None, None, None),
+            vec![local_instance_id]
+        );
+        assert!(sender_map_get(&remote_instance_id).is_none());
+
+        // And removing our actor sends the peer a delta
+        con_mgr.remove_actor(&local_instance_id).unwrap();
+        let msg_any = link_chnl.receiver.recv().unwrap();
+        let delta = msg_any.downcast_ref::<ConMgrFederationDelta>().unwrap();
+        assert!(delta.added.is_empty());
+        assert_eq!(delta.removed, vec![local_instance_id]);
+        assert!(con_mgr.query(None, None, None, None).is_empty());
+
+        // Removing the peer forgets its actors and their senders
+        con_mgr.process_msg_any(
+            &context,
+            Box::new(ConMgrFederationDelta::new(
+                &con_mgr.instance_id,
+                &peer_con_mgr_instance_id,
+                std::slice::from_ref(&remote_entry),
+                &[],
+            )),
+        );
+        assert_eq!(
+            con_mgr.query(None, None, None, None),
+            vec![remote_instance_id]
+        );
+        assert!(sender_map_get(&remote_instance_id).is_some());
+        let remove_peer_req = Box::new(ConMgrRemovePeerReq::new(
+            &con_mgr.instance_id,
+            &supervisor_instance_id,
+            &peer_con_mgr_instance_id,
+        ));
+        con_mgr.process_msg_any(&context, remove_peer_req.clone());
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let rsp = msg_any.downcast_ref::<ConMgrRemovePeerRsp>().unwrap();
+        assert_eq!(rsp.status, ConMgrRemovePeerStatus::Success);
+        assert!(con_mgr.query(None, None, None, None).is_empty());
+        assert!(sender_map_get(&remote_instance_id).is_none());
+
+        // Removing it twice fails
+        con_mgr.process_msg_any(&context, remove_peer_req);
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let rsp = msg_any.downcast_ref::<ConMgrRemovePeerRsp>().unwrap();
+        assert_eq!(rsp.status, ConMgrRemovePeerStatus::PeerNotFound);
+
+        println!("test_federation:-");
+    }
 }
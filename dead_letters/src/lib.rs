@@ -0,0 +1,120 @@
+//! Dead letters are messages that were rejected rather than delivered,
+//! for instance an ActorExecutor rejects messages its actors didn't
+//! declare in their ProtocolSet.
+//!
+//! The most recent DEAD_LETTERS_CAPACITY dead letters are kept, with
+//! the message itself, so they can be inspected, and all of them are
+//! counted.
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use msg_header::MsgHeader;
+use once_cell::sync::Lazy;
+
+/// Number of dead letters kept, older ones are dropped
+pub const DEAD_LETTERS_CAPACITY: usize = 1024;
+
+#[derive(Debug)]
+pub struct DeadLetter {
+    pub msg_id: AnId,
+    pub dst_id: AnId,
+    pub src_id: AnId,
+    pub reason: String,
+    pub timestamp_ns: u128, // Since UNIX_EPOCH
+    pub msg: BoxMsgAny,
+}
+
+static DEAD_LETTERS_COUNT: AtomicU64 = AtomicU64::new(0);
+static DEAD_LETTERS: Lazy<Mutex<VecDeque<DeadLetter>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Post msg to the dead letters because of reason
+pub fn dead_letter_post(msg: BoxMsgAny, reason: &str) {
+    let dead_letter = DeadLetter {
+        msg_id: *MsgHeader::get_msg_id_from_boxed_msg_any(&msg),
+        dst_id: *MsgHeader::get_dst_id_from_boxed_msg_any(&msg),
+        src_id: *MsgHeader::get_src_id_from_boxed_msg_any(&msg),
+        reason: reason.to_owned(),
+        timestamp_ns: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+        msg,
+    };
+    log::debug!(
+        "dead_letter_post: msg_id={} dst_id={} src_id={} {reason}",
+        dead_letter.msg_id,
+        dead_letter.dst_id,
+        dead_letter.src_id
+    );
+
+    DEAD_LETTERS_COUNT.fetch_add(1, Ordering::Relaxed);
+    let mut locked_dead_letters = DEAD_LETTERS.lock().unwrap(); // TODO: remove unwrap
+    if locked_dead_letters.len() == DEAD_LETTERS_CAPACITY {
+        locked_dead_letters.pop_front();
+    }
+    locked_dead_letters.push_back(dead_letter);
+}
+
+/// Number of dead letters posted since the process started
+pub fn dead_letters_count() -> u64 {
+    DEAD_LETTERS_COUNT.load(Ordering::Relaxed)
+}
+
+/// Remove and return the dead letters that have been kept, oldest first
+pub fn dead_letters_take() -> Vec<DeadLetter> {
+    let mut locked_dead_letters = DEAD_LETTERS.lock().unwrap(); // TODO: remove unwrap
+    locked_dead_letters.drain(..).collect()
+}
+
+/// Remove and return the kept dead letters whose destination is dst_id
+pub fn dead_letters_take_for(dst_id: &AnId) -> Vec<DeadLetter> {
+    let mut locked_dead_letters = DEAD_LETTERS.lock().unwrap(); // TODO: remove unwrap
+    let (taken, kept): (VecDeque<DeadLetter>, _) = locked_dead_letters
+        .drain(..)
+        .partition(|dl| dl.dst_id == *dst_id);
+    *locked_dead_letters = kept;
+    taken.into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use echo_req::{EchoReq, ECHO_REQ_ID};
+
+    #[test]
+    fn test_dead_letters() {
+        println!("test_dead_letters:+");
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let before = dead_letters_count();
+
+        dead_letter_post(Box::new(EchoReq::new(&dst_id, &src_id, 1)), "testing");
+        dead_letter_post(Box::new(EchoReq::new(&dst_id, &src_id, 2)), "testing");
+        dead_letter_post(Box::new(EchoReq::new(&src_id, &dst_id, 3)), "other");
+        assert!(dead_letters_count() >= before + 3);
+
+        let dead_letters = dead_letters_take_for(&dst_id);
+        assert_eq!(dead_letters.len(), 2);
+        for (dl, counter) in dead_letters.iter().zip([1, 2]) {
+            assert_eq!(dl.msg_id, ECHO_REQ_ID);
+            assert_eq!(dl.dst_id, dst_id);
+            assert_eq!(dl.src_id, src_id);
+            assert_eq!(dl.reason, "testing");
+            let msg = dl.msg.downcast_ref::<EchoReq>().unwrap();
+            assert_eq!(msg.counter, counter);
+        }
+        assert!(dead_letters_take_for(&dst_id).is_empty());
+
+        let dead_letters = dead_letters_take();
+        assert!(dead_letters.iter().any(|dl| dl.reason == "other"));
+        println!("test_dead_letters:-");
+    }
+}
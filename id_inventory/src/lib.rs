@@ -0,0 +1,214 @@
+//! A process wide inventory of the hand-copied ids.
+//!
+//! Message, protocol, protocol set and actor ids are UUIDs pasted into
+//! the source, so a copy-paste mistake silently makes two different
+//! things look the same to downcasting and dispatch. Each of them is
+//! submitted to the inventory with register_id! and validate_ids
+//! reports any two entries sharing an id, or two entries of the same
+//! kind sharing a name.
+//!
+//! Only crates linked into the executable contribute entries.
+use std::{collections::HashMap, fmt::Display, sync::OnceLock};
+
+use an_id::AnId;
+
+#[doc(hidden)]
+pub use inventory;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IdKind {
+    Message,
+    Protocol,
+    ProtocolSet,
+    Actor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdEntry {
+    pub kind: IdKind,
+    pub id: AnId,
+    pub name: &'static str,
+    pub module: &'static str, // module_path!() of the registration
+}
+
+impl IdEntry {
+    pub const fn new(kind: IdKind, id: AnId, name: &'static str, module: &'static str) -> Self {
+        Self {
+            kind,
+            id,
+            name,
+            module,
+        }
+    }
+}
+
+inventory::collect!(IdEntry);
+
+/// Submit an id to the inventory, invoked at module scope.
+///
+/// `register_id!(Protocol, ECHO_REQUESTER_PROTOCOL_ID, ECHO_REQUESTER_PROTOCOL_NAME);`
+#[macro_export]
+macro_rules! register_id {
+    ($kind:ident, $id:expr, $name:expr) => {
+        $crate::inventory::submit! {
+            $crate::IdEntry::new($crate::IdKind::$kind, $id, $name, module_path!())
+        }
+    };
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdCollision {
+    /// Two or more entries with the same id
+    Id { id: AnId, entries: Vec<IdEntry> },
+
+    /// Two or more entries of the same kind with the same name
+    Name {
+        kind: IdKind,
+        name: &'static str,
+        entries: Vec<IdEntry>,
+    },
+}
+
+impl Display for IdCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (what, entries) = match self {
+            IdCollision::Id { id, entries } => (format!("id {id}"), entries),
+            IdCollision::Name {
+                kind,
+                name,
+                entries,
+            } => (format!("{kind:?} name \"{name}\""), entries),
+        };
+        write!(f, "duplicate {what} used by:")?;
+        for e in entries {
+            write!(f, " {:?} {} in {};", e.kind, e.name, e.module)?;
+        }
+        Ok(())
+    }
+}
+
+/// All registered entries sorted by kind, name and module
+pub fn id_entries() -> Vec<IdEntry> {
+    let mut entries: Vec<IdEntry> = inventory::iter::<IdEntry>.into_iter().copied().collect();
+    entries.sort_by(|a, b| (a.kind, a.name, a.module).cmp(&(b.kind, b.name, b.module)));
+    entries
+}
+
+/// Check the registered entries for collisions
+pub fn validate_ids() -> Result<(), Vec<IdCollision>> {
+    find_collisions(&id_entries())
+}
+
+fn find_collisions(entries: &[IdEntry]) -> Result<(), Vec<IdCollision>> {
+    let mut by_id: HashMap<AnId, Vec<IdEntry>> = HashMap::new();
+    let mut by_name: HashMap<(IdKind, &'static str), Vec<IdEntry>> = HashMap::new();
+    for e in entries {
+        by_id.entry(e.id).or_default().push(*e);
+        by_name.entry((e.kind, e.name)).or_default().push(*e);
+    }
+
+    let mut collisions: Vec<IdCollision> = by_id
+        .into_iter()
+        .filter(|(_, entries)| entries.len() > 1)
+        .map(|(id, entries)| IdCollision::Id { id, entries })
+        .collect();
+    collisions.extend(
+        by_name
+            .into_iter()
+            .filter(|(_, entries)| entries.len() > 1)
+            .map(|((kind, name), entries)| IdCollision::Name {
+                kind,
+                name,
+                entries,
+            }),
+    );
+
+    if collisions.is_empty() {
+        Ok(())
+    } else {
+        collisions.sort_by_key(|c| c.to_string());
+        Err(collisions)
+    }
+}
+
+static VALIDATED_IDS: OnceLock<Result<(), Vec<IdCollision>>> = OnceLock::new();
+
+/// Validate the ids the first time it's called, logging any collisions,
+/// and return the result of that validation on every call. Used at
+/// startup so it's cheap to check in each executor.
+pub fn validate_ids_once() -> Result<(), &'static [IdCollision]> {
+    VALIDATED_IDS
+        .get_or_init(|| {
+            let result = validate_ids();
+            if let Err(collisions) = &result {
+                for c in collisions {
+                    log::error!("validate_ids_once: {c}");
+                }
+            }
+            result
+        })
+        .as_ref()
+        .map(|_| ())
+        .map_err(|collisions| collisions.as_slice())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use an_id::{anid, paste};
+
+    // From: https://www.uuidgenerator.net/version4
+    const ID_A: AnId = anid!("bce8db67-7a0a-4b0c-a5b9-57b85e46e2f7");
+    const ID_B: AnId = anid!("7d1e4a56-44dc-44d5-9ce5-b5b4a0e5fcd1");
+    const ID_C: AnId = anid!("4a4e0bc6-1bc4-4d37-bd2c-dfc0c0fe2f6e");
+
+    register_id!(Message, ID_A, "IdInventoryTestMsgA");
+    register_id!(Protocol, ID_B, "id_inventory_test_protocol");
+
+    #[test]
+    fn test_registered() {
+        println!("test_registered:+");
+        let entries = id_entries();
+        let a = entries.iter().find(|e| e.id == ID_A).unwrap();
+        assert_eq!(a.kind, IdKind::Message);
+        assert_eq!(a.name, "IdInventoryTestMsgA");
+        assert_eq!(a.module, "id_inventory::test");
+        assert!(entries
+            .iter()
+            .any(|e| e.kind == IdKind::Protocol && e.id == ID_B));
+        assert_eq!(validate_ids(), Ok(()));
+        assert_eq!(validate_ids_once(), Ok(()));
+        println!("test_registered:-");
+    }
+
+    #[test]
+    fn test_collisions() {
+        println!("test_collisions:+");
+        let entries = [
+            IdEntry::new(IdKind::Message, ID_A, "A", "m1"),
+            IdEntry::new(IdKind::Protocol, ID_B, "b", "m1"),
+            IdEntry::new(IdKind::Actor, ID_C, "b", "m1"),
+        ];
+        assert_eq!(find_collisions(&entries), Ok(()));
+
+        let entries = [
+            IdEntry::new(IdKind::Message, ID_A, "A", "m1"),
+            IdEntry::new(IdKind::Message, ID_A, "A2", "m2"),
+            IdEntry::new(IdKind::Protocol, ID_B, "b", "m1"),
+            IdEntry::new(IdKind::Protocol, ID_C, "b", "m2"),
+        ];
+        let collisions = find_collisions(&entries).unwrap_err();
+        println!("test_collisions: {collisions:#?}");
+        assert_eq!(collisions.len(), 2);
+        assert!(collisions.contains(&IdCollision::Id {
+            id: ID_A,
+            entries: vec![entries[0], entries[1]],
+        }));
+        assert!(collisions.contains(&IdCollision::Name {
+            kind: IdKind::Protocol,
+            name: "b",
+            entries: vec![entries[2], entries[3]],
+        }));
+        println!("test_collisions:-");
+    }
+}
@@ -160,6 +160,38 @@ fn working() {
     }
 }
 
+// What the experiments above were after, DynamicSelect owns the
+// receivers so they can be added and removed without unsafe.
+fn dynamic_select() {
+    use actor_channel::DynamicSelect;
+
+    let mut senders: Vec<Sender<i32>> = Vec::new();
+    let mut sel = DynamicSelect::new();
+
+    // Incrementally add a receiver and test it
+    let mut handles = Vec::new();
+    for i in 0..=1 {
+        let (tx, rx) = unbounded::<i32>();
+        senders.push(tx);
+        handles.push(sel.add(rx));
+
+        let send_value = 10 + (i as i32);
+        println!("send: {send_value}");
+        senders[i].send(send_value).unwrap();
+        let (handle, value) = sel.select();
+        println!("handle: {handle:?} recv: {value:?}");
+        assert_eq!(handle, handles[i]);
+        assert_eq!(value, Ok(send_value));
+    }
+
+    // Remove the first receiver, the second is still selected
+    let rx0 = sel.remove(handles[0]).unwrap();
+    senders[0].send(20).unwrap();
+    senders[1].send(21).unwrap();
+    assert_eq!(sel.select(), (handles[1], Ok(21)));
+    assert_eq!(rx0.try_recv(), Ok(20));
+}
+
 fn main() {
     #[cfg(feature = "simple")]
     simple();
@@ -171,4 +203,5 @@ fn main() {
     select_immutable();
 
     working();
+    dynamic_select();
 }
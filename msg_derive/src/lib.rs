@@ -0,0 +1,404 @@
+//! `#[derive(Message)]` implements a message, it replaces msg_local_macro!
+//! and msg_serde_macro! so messages are ordinary structs which may have
+//! doc comments, attributes, generics and `#[serde(...)]` field options:
+//!
+//! ```ignore
+//! // From: https://www.uuidgenerator.net/version4
+//! #[derive(Debug, Clone, Serialize, Deserialize, Message)]
+//! #[msg(id = "a88ba7e7-0930-4df6-bb24-240338bf8eb5", serde)]
+//! #[repr(C)]
+//! pub struct Msg1 {
+//!     pub header: MsgHeader,
+//!     pub v: u64,
+//! }
+//! ```
+//!
+//! Generates the `MSG1_ID_STR` and `MSG1_ID` constants, registers the id
+//! with id_inventory and generates the msg_id, dst_id, src_id and
+//! from_box_msg_any methods. With `serde` it also generates
+//! from_serde_json_buf, to_serde_json_buf and to_serde_json_buf_ref and,
+//! unless the message is generic, registers them as its MsgCodec. The
+//! crate using it must depend on an_id, box_msg_any, id_inventory,
+//! msg_header and uuid, plus log and serde_json for `serde`.
+//!
+//! The header must be the first field and the struct `#[repr(C)]` so the
+//! header is at offset 0. Malformed ids and ids used by two messages in
+//! the same crate are compile errors. Each message also exports a symbol
+//! named after its id so two messages in different crates with the same
+//! id fail to link.
+use std::{collections::BTreeMap, sync::Mutex};
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, punctuated::Punctuated, Data, DeriveInput, Error, Fields, LitStr, Token,
+    Type,
+};
+use uuid::Uuid;
+
+// Ids seen while compiling the current crate and the message using them
+static MSG_IDS: Mutex<BTreeMap<Uuid, String>> = Mutex::new(BTreeMap::new());
+
+#[proc_macro_derive(Message, attributes(msg))]
+pub fn derive_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+struct MsgAttr {
+    id: LitStr,
+    serde: bool,
+}
+
+fn parse_msg_attr(input: &DeriveInput) -> syn::Result<MsgAttr> {
+    let mut id: Option<LitStr> = None;
+    let mut serde = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("msg")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                if id.is_some() {
+                    return Err(meta.error("duplicate `id`"));
+                }
+                id = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("serde") {
+                serde = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `id = \"uuid\"` or `serde`"))
+            }
+        })?;
+    }
+    let Some(id) = id else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Message requires #[msg(id = \"uuid\")]",
+        ));
+    };
+    Ok(MsgAttr { id, serde })
+}
+
+// The id must be a hyphenated lowercase uuid because that is how it is
+// serialized and from_serde_json_buf compares it with the buffer.
+fn parse_id(id: &LitStr) -> syn::Result<Uuid> {
+    let s = id.value();
+    let uuid = Uuid::parse_str(&s)
+        .map_err(|why| Error::new(id.span(), format!("malformed uuid \"{s}\": {why}")))?;
+    let hyphenated = uuid.hyphenated().to_string();
+    if s != hyphenated {
+        return Err(Error::new(
+            id.span(),
+            format!("uuid must be hyphenated lowercase, i.e. \"{hyphenated}\""),
+        ));
+    }
+    if uuid.is_nil() {
+        return Err(Error::new(id.span(), "uuid must not be nil"));
+    }
+    Ok(uuid)
+}
+
+fn check_unique(uuid: Uuid, id: &LitStr, name: &Ident) -> syn::Result<()> {
+    let mut msg_ids = MSG_IDS.lock().unwrap_or_else(|e| e.into_inner());
+    match msg_ids.get(&uuid) {
+        // The same message may be expanded more than once
+        Some(other) if *name != *other => Err(Error::new(
+            id.span(),
+            format!("duplicate uuid \"{uuid}\", it is also the id of {other}"),
+        )),
+        _ => {
+            msg_ids.insert(uuid, name.to_string());
+            Ok(())
+        }
+    }
+}
+
+fn check_layout(input: &DeriveInput) -> syn::Result<()> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input.ident, "Message must be a struct"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Message must have named fields",
+        ));
+    };
+    let first_is_header = fields.named.first().is_some_and(|f| {
+        f.ident.as_ref().is_some_and(|i| i == "header")
+            && matches!(&f.ty, Type::Path(p) if p.path.segments.last().is_some_and(|s| s.ident == "MsgHeader"))
+    });
+    if !first_is_header {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "the first field of a Message must be `header: MsgHeader`",
+        ));
+    }
+
+    let mut repr_c = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        let reprs = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+        repr_c |= reprs.iter().any(|r| r == "C");
+    }
+    if !repr_c {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Message must be #[repr(C)] so the header is at offset 0",
+        ));
+    }
+    Ok(())
+}
+
+// Same as paste's `:snake:upper`, so MsgA is MSG_A and Msg1 is MSG1
+fn snake_upper(name: &str) -> String {
+    let mut s = String::new();
+    let mut prev = '_';
+    for ch in name.chars() {
+        if ch.is_uppercase() && prev != '_' {
+            s.push('_');
+        }
+        s.push(ch);
+        prev = ch;
+    }
+    s.to_uppercase()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let attr = parse_msg_attr(input)?;
+    let uuid = parse_id(&attr.id)?;
+    check_layout(input)?;
+    let name = &input.ident;
+    check_unique(uuid, &attr.id, name)?;
+
+    let vis = &input.vis;
+    let prefix = snake_upper(&name.to_string());
+    let id_str_const = format_ident!("{prefix}_ID_STR");
+    let id_const = format_ident!("{prefix}_ID");
+    let id_symbol = format_ident!("{prefix}_ID_SYMBOL");
+    let id_symbol_name = format!("__msg_id_{}", uuid.simple());
+    let id_str = &attr.id;
+    let id_u128 = uuid.as_u128();
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let serde_fns = if attr.serde {
+        quote! {
+            pub fn from_serde_json_buf(buf: &[u8]) -> std::option::Option<box_msg_any::BoxMsgAny> {
+                let id = msg_header::get_msg_id_str_from_buf(buf);
+                if id == #id_str {
+                    if let Ok(s) = std::str::from_utf8(buf) {
+                        match serde_json::from_str::<Self>(s) {
+                            Ok(msg) => Some(Box::new(msg)),
+                            Err(why) => {
+                                log::error!("{}::from_serde_json_str: {why}", #name_str);
+                                None
+                            }
+                        }
+                    } else {
+                        log::error!("{}::from_serde_json_buf: buf parameter was NOT UTF8", #name_str);
+                        None
+                    }
+                } else {
+                    log::error!("{} id: {}, does not match buffer id: {id}", #name_str, #id_str);
+                    None
+                }
+            }
+
+            pub fn to_serde_json_buf(
+                boxed_msg_any: box_msg_any::BoxMsgAny,
+            ) -> std::option::Option<Vec<u8>> {
+                Self::to_serde_json_buf_ref(&boxed_msg_any)
+            }
+
+            pub fn to_serde_json_buf_ref(
+                boxed_msg_any: &box_msg_any::BoxMsgAny,
+            ) -> std::option::Option<Vec<u8>> {
+                let m = boxed_msg_any.downcast_ref::<Self>()?;
+                match serde_json::to_vec(m) {
+                    Ok(v) => Some(v),
+                    Err(why) => {
+                        log::error!("{}.to_serde_json_buf: Error {why}", #name_str);
+                        None
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Generic messages have no single codec to register
+    let register_codec = if attr.serde && input.generics.params.is_empty() {
+        quote! {
+            msg_header::register_msg_codec!(#id_const, #name_str, #name);
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        #[allow(unused)]
+        #vis const #id_str_const: &str = #id_str;
+
+        #[allow(unused)]
+        #vis const #id_const: an_id::AnId = an_id::AnId(uuid::Uuid::from_u128(#id_u128));
+
+        id_inventory::register_id!(Message, #id_const, #name_str);
+
+        // Linking two messages with the same id defines this symbol twice
+        #[doc(hidden)]
+        #[used]
+        #[export_name = #id_symbol_name]
+        static #id_symbol: u8 = 0;
+
+        #register_codec
+
+        #[allow(unused)]
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub fn msg_id(&self) -> &an_id::AnId {
+                &self.header.msg_id
+            }
+
+            pub fn dst_id(&self) -> &an_id::AnId {
+                &self.header.dst_id
+            }
+
+            pub fn src_id(&self) -> &an_id::AnId {
+                &self.header.src_id
+            }
+
+            pub fn from_box_msg_any(msg: &box_msg_any::BoxMsgAny) -> Option<&Self> {
+                msg.downcast_ref::<Self>()
+            }
+
+            #serde_fns
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_quote;
+
+    fn expand_err(input: DeriveInput) -> String {
+        expand(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_snake_upper() {
+        assert_eq!(snake_upper("Msg1"), "MSG1");
+        assert_eq!(snake_upper("MsgA"), "MSG_A");
+        assert_eq!(snake_upper("EchoReq"), "ECHO_REQ");
+        assert_eq!(
+            snake_upper("ConMgrRegisterActorRsp"),
+            "CON_MGR_REGISTER_ACTOR_RSP"
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let tokens = expand(&parse_quote! {
+            /// A documented generic message
+            #[msg(id = "5d6f3c1a-9b2e-4f87-a3d0-6c1e8b7f2a94", serde)]
+            #[repr(C)]
+            pub struct TestExpandMsg<T> {
+                pub header: msg_header::MsgHeader,
+                #[serde(default)]
+                pub t: T,
+            }
+        })
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("TEST_EXPAND_MSG_ID_STR"));
+        assert!(tokens.contains("TEST_EXPAND_MSG_ID"));
+        assert!(tokens.contains("impl < T > TestExpandMsg < T >"));
+        assert!(tokens.contains("fn from_serde_json_buf"));
+        assert!(tokens.contains("fn to_serde_json_buf_ref"));
+        assert!(!tokens.contains("register_msg_codec"));
+
+        // Without serde there are no codec functions
+        let tokens = expand(&parse_quote! {
+            #[msg(id = "0e4a8f27-c6b1-4d53-9e72-b8f1a3c5d609")]
+            #[repr(C)]
+            pub struct TestExpandLocal {
+                pub header: MsgHeader,
+            }
+        })
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("fn from_box_msg_any"));
+        assert!(!tokens.contains("fn from_serde_json_buf"));
+        assert!(!tokens.contains("register_msg_codec"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = expand_err(parse_quote! {
+            #[repr(C)]
+            pub struct NoId { pub header: MsgHeader }
+        });
+        assert!(err.contains("requires #[msg(id"), "{err}");
+
+        let err = expand_err(parse_quote! {
+            #[msg(id = "not-a-uuid")]
+            #[repr(C)]
+            pub struct Malformed { pub header: MsgHeader }
+        });
+        assert!(err.contains("malformed uuid"), "{err}");
+
+        let err = expand_err(parse_quote! {
+            #[msg(id = "7F3B9C2E-1A4D-4E6F-8B0C-D2E5F7A9C1B3")]
+            #[repr(C)]
+            pub struct UpperCase { pub header: MsgHeader }
+        });
+        assert!(err.contains("hyphenated lowercase"), "{err}");
+
+        let err = expand_err(parse_quote! {
+            #[msg(id = "b1c2d3e4-f5a6-4b7c-8d9e-0f1a2b3c4d5e")]
+            pub struct NotReprC { pub header: MsgHeader }
+        });
+        assert!(err.contains("repr(C)"), "{err}");
+
+        let err = expand_err(parse_quote! {
+            #[msg(id = "c2d3e4f5-a6b7-4c8d-9e0f-1a2b3c4d5e6f")]
+            #[repr(C)]
+            pub struct HeaderNotFirst { pub v: u64, pub header: MsgHeader }
+        });
+        assert!(err.contains("first field"), "{err}");
+
+        // The second message using an id is an error, expanding the first again isn't
+        let first: DeriveInput = parse_quote! {
+            #[msg(id = "d3e4f5a6-b7c8-4d9e-8f1a-2b3c4d5e6f70")]
+            #[repr(C)]
+            pub struct First { pub header: MsgHeader }
+        };
+        expand(&first).unwrap();
+        expand(&first).unwrap();
+        let err = expand_err(parse_quote! {
+            #[msg(id = "d3e4f5a6-b7c8-4d9e-8f1a-2b3c4d5e6f70")]
+            #[repr(C)]
+            pub struct Second { pub header: MsgHeader }
+        });
+        assert!(err.contains("duplicate uuid"), "{err}");
+        assert!(err.contains("First"), "{err}");
+    }
+
+    #[test]
+    fn test_id_symbol() {
+        // Messages in other crates with the same id export the same symbol
+        let tokens = expand(&parse_quote! {
+            #[msg(id = "0b8a3c2e-5d4f-4a6b-9c7d-8e9f0a1b2c3d")]
+            #[repr(C)]
+            pub struct WithSymbol { pub header: MsgHeader }
+        })
+        .unwrap()
+        .to_string();
+        assert!(
+            tokens.contains("\"__msg_id_0b8a3c2e5d4f4a6b9c7d8e9f0a1b2c3d\""),
+            "{tokens}"
+        );
+        assert!(tokens.contains("WITH_SYMBOL_ID_SYMBOL"), "{tokens}");
+    }
+}
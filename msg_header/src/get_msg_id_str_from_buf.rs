@@ -3,6 +3,7 @@ use box_msg_any::BoxMsgAny;
 
 pub type FromSerdeJsonBuf = fn(&[u8]) -> std::option::Option<BoxMsgAny>;
 pub type ToSerdeJsonBuf = fn(BoxMsgAny) -> std::option::Option<Vec<u8>>;
+pub type ToSerdeJsonBufRef = fn(&BoxMsgAny) -> std::option::Option<Vec<u8>>;
 
 pub fn get_msg_id_str_from_buf(serde_json_msg_header: &[u8]) -> &str {
     const SERDE_JSON_MSG_HEADER_PREFIX: &str = r#"{"header":{"msg_id":""#;
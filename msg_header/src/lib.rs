@@ -31,11 +31,37 @@ use sender_map_by_instance_id::sender_map_get;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display};
 mod get_msg_id_str_from_buf;
-pub use get_msg_id_str_from_buf::{get_msg_id_str_from_buf, FromSerdeJsonBuf, ToSerdeJsonBuf};
+pub use get_msg_id_str_from_buf::{
+    get_msg_id_str_from_buf, FromSerdeJsonBuf, ToSerdeJsonBuf, ToSerdeJsonBufRef,
+};
+mod msg_codec;
+pub use msg_codec::{msg_codec_get, msg_codecs, MsgCodec};
+
+#[doc(hidden)]
+pub use inventory;
 
 
 pub const MSG_ID_STR_LEN: usize = "00000000-0000-0000-0000-000000000000".len();
 
+// Trace context, identifies the trace a message is part of and the
+// span that sent it so message flows can be correlated across actors
+// and processes.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: AnId, // Same for all of the spans of a trace
+    pub span_id: AnId,  // The span that sent the message
+}
+
+impl TraceContext {
+    // Start a new trace
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: AnId::new(),
+            span_id: AnId::new(),
+        }
+    }
+}
+
 // Message Header
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[repr(C)]
@@ -46,6 +72,10 @@ pub struct MsgHeader {
     // multiplexing messages to multiple actors. ATM, this is
     // not needed with local actors connected with plain channels.
     pub src_id: AnId, // Source ID
+
+    // Optional, only serialized if the message is being traced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceContext>,
 }
 
 impl MsgHeader {
@@ -55,6 +85,7 @@ impl MsgHeader {
             msg_id,
             dst_id,
             src_id,
+            trace: None,
         }
     }
 
@@ -115,6 +146,30 @@ impl MsgHeader {
         &DEBUG_ANID
     }
 
+    #[rustversion::nightly]
+    pub fn get_trace_from_boxed_msg_any(msg_any: &BoxMsgAny) -> Option<&TraceContext> {
+        // See https://doc.rust-lang.org/std/any/trait.Any.html#method.downcast_ref_unchecked
+        let mh: &MsgHeader = unsafe { msg_any.downcast_ref_unchecked() };
+
+        mh.trace.as_ref()
+    }
+
+    #[rustversion::stable]
+    pub fn get_trace_from_boxed_msg_any(_msg_any: &BoxMsgAny) -> Option<&TraceContext> {
+        None
+    }
+
+    #[rustversion::nightly]
+    pub fn set_trace_in_boxed_msg_any(msg_any: &mut BoxMsgAny, trace: Option<TraceContext>) {
+        // See https://doc.rust-lang.org/std/any/trait.Any.html#method.downcast_mut_unchecked
+        let mh: &mut MsgHeader = unsafe { msg_any.downcast_mut_unchecked() };
+
+        mh.trace = trace;
+    }
+
+    #[rustversion::stable]
+    pub fn set_trace_in_boxed_msg_any(_msg_any: &mut BoxMsgAny, _trace: Option<TraceContext>) {}
+
     pub fn get_src_tx_from_boxed_msg_any(msg_any: &BoxMsgAny) -> Option<ActorSender> {
         // TODO: Consider validating that this is AnId. One way
         // would be to have a "global" hashmap of valid values another
@@ -142,6 +197,7 @@ impl Debug for MsgHeader {
                 .field("msg_id", &self.msg_id)
                 .field("dst_id", &self.dst_id)
                 .field("src_id", &self.src_id)
+                .field("trace", &self.trace)
                 .finish()
         } else {
             write!(f, "{}", self.simple_display())
@@ -169,7 +225,7 @@ mod test {
         println!("test_default: size_of_val(&header)={size}    {{header}}={header}");
         println!("test_default: size_of_val(&header)={size}  {{header:?}}={header:?}");
         println!("test_default: size_of_val(&header)={size} {{header:#?}}={header:#?}");
-        assert_eq!(size, 48);
+        assert_eq!(size, 48 + 33);
     }
 
     #[test]
@@ -188,6 +244,27 @@ mod test {
         assert_eq!(header.src_id, src_id);
     }
 
+    #[test]
+    fn test_trace_serde() {
+        println!("\n");
+        let mut header = MsgHeader::new(AnId::new(), AnId::new(), AnId::new());
+
+        // Not traced, trace isn't serialized
+        let s = serde_json::to_string(&header).unwrap();
+        println!("test_trace_serde: s={s}");
+        assert!(!s.contains("trace"));
+        let header2: MsgHeader = serde_json::from_str(&s).unwrap();
+        assert_eq!(header2, header);
+
+        // Traced
+        header.trace = Some(TraceContext::new_root());
+        let s = serde_json::to_string(&header).unwrap();
+        println!("test_trace_serde: s={s}");
+        assert!(s.starts_with(r#"{"msg_id":""#));
+        let header2: MsgHeader = serde_json::from_str(&s).unwrap();
+        assert_eq!(header2, header);
+    }
+
     #[test]
     fn test_msg_id_utf8_len() {
         println!("\n");
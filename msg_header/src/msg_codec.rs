@@ -0,0 +1,76 @@
+//! A process wide inventory of the messages that can be serialized.
+//!
+//! msg_serde_macro! and `#[derive(Message)]` with `serde` submit a
+//! MsgCodec for each message, so a link can forward and dispatch, and a
+//! tap can record, any serializable message linked into the executable
+//! without each type being registered by hand.
+use std::collections::HashMap;
+
+use an_id::AnId;
+use once_cell::sync::Lazy;
+
+use crate::{FromSerdeJsonBuf, ToSerdeJsonBuf, ToSerdeJsonBufRef};
+
+#[derive(Clone, Copy, Debug)]
+pub struct MsgCodec {
+    pub msg_id: AnId,
+    pub name: &'static str,
+    pub to_serde_json_buf: ToSerdeJsonBuf,
+    pub to_serde_json_buf_ref: ToSerdeJsonBufRef,
+    pub from_serde_json_buf: FromSerdeJsonBuf,
+}
+
+impl MsgCodec {
+    pub const fn new(
+        msg_id: AnId,
+        name: &'static str,
+        to_serde_json_buf: ToSerdeJsonBuf,
+        to_serde_json_buf_ref: ToSerdeJsonBufRef,
+        from_serde_json_buf: FromSerdeJsonBuf,
+    ) -> Self {
+        Self {
+            msg_id,
+            name,
+            to_serde_json_buf,
+            to_serde_json_buf_ref,
+            from_serde_json_buf,
+        }
+    }
+}
+
+inventory::collect!(MsgCodec);
+
+/// Submit a MsgCodec to the inventory, invoked at module scope by the
+/// message macros.
+#[macro_export]
+macro_rules! register_msg_codec {
+    ($id:expr, $name:expr, $ty:ty) => {
+        $crate::inventory::submit! {
+            $crate::MsgCodec::new(
+                $id,
+                $name,
+                <$ty>::to_serde_json_buf,
+                <$ty>::to_serde_json_buf_ref,
+                <$ty>::from_serde_json_buf,
+            )
+        }
+    };
+}
+
+static MSG_CODECS: Lazy<HashMap<AnId, &'static MsgCodec>> = Lazy::new(|| {
+    inventory::iter::<MsgCodec>
+        .into_iter()
+        .map(|codec| (codec.msg_id, codec))
+        .collect()
+});
+
+/// The codec of msg_id, None if it isn't a serializable message linked
+/// into the executable
+pub fn msg_codec_get(msg_id: &AnId) -> Option<&'static MsgCodec> {
+    MSG_CODECS.get(msg_id).copied()
+}
+
+/// All of the codecs in the inventory
+pub fn msg_codecs() -> impl Iterator<Item = &'static MsgCodec> {
+    MSG_CODECS.values().copied()
+}
@@ -1,4 +1,9 @@
 //! `The msg_macro!` provides a simple way to create msgs.
+//!
+//! Superseded by `#[derive(Message)]` in msg_derive, which new messages
+//! should use.
+#[doc(hidden)]
+pub use id_inventory;
 pub use paste::paste;
 
 // TODO: Ugly, too much repeated code, I tired to add
@@ -15,6 +20,8 @@ macro_rules! msg_local_macro_not_cloneable {
 
             #[allow(unused)]
             pub const [ <$name:snake:upper _ID> ] : an_id::AnId = an_id::anid!($id_str);
+
+            $crate::id_inventory::register_id!(Message, [ <$name:snake:upper _ID> ], stringify!($name));
         }
 
         #[derive(Debug)]
@@ -60,6 +67,8 @@ macro_rules! msg_local_macro {
 
             #[allow(unused)]
             pub const [ <$name:snake:upper _ID> ] : an_id::AnId = an_id::anid!($id_str);
+
+            $crate::id_inventory::register_id!(Message, [ <$name:snake:upper _ID> ], stringify!($name));
         }
 
         #[derive(Debug, Clone)]
@@ -102,6 +111,8 @@ macro_rules! msg_local_macro {
 
             #[allow(unused)]
             pub const [ <$name:snake:upper _ID> ] : msg_header::MsgId = msg_header::MsgId(an_id::anid!($id_str));
+
+            $crate::id_inventory::register_id!(Message, [ <$name:snake:upper _ID> ].0, stringify!($name));
         }
 
         #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1,43 +1,64 @@
-use actor::{Actor, ActorContext, ProcessMsgFn};
+use actor::{Actor, ActorContext};
 use actor_channel::ActorChannel;
+use actor_lifecycle::{add_lifecycle_handlers, ActorLifecycle, Lifecycle};
+use admin_protocol::{
+    admin_req_from_serde_json_bufs, admin_rsp_to_serde_json_buf, AdminHello, AdminHelloAck,
+    AdminNodeInfo, ADMIN_HELLO_ID_STR,
+};
 use an_id::{anid, paste, AnId};
-use cmd_init_issuee_protocol::{cmd_init_issuee_protocol, CmdInit, CMD_INIT_ID};
-use con_mgr_register_actor_protocol::{
-    ConMgrRegisterActorReq, ConMgrRegisterActorRsp, ConMgrRegisterActorStatus,
-    CON_MGR_REGISTER_ACTOR_RSP_ID,
+use cmd_init_issuee_protocol::cmd_init_issuee_protocol;
+use cmd_shutdown_issuee_protocol::cmd_shutdown_issuee_protocol;
+use con_mgr_federation_protocol::{
+    con_mgr_federation_issuee_protocol, con_mgr_federation_issuer_protocol,
+};
+use con_mgr_register_actor_protocol::con_mgr_registee_actor_protocol;
+use crossbeam_channel::{bounded, select, Receiver, Sender};
+use dead_letters::dead_letter_post;
+use dispatcher_listening_protocol::{
+    dispatcher_listening_requestee_protocol, DispatcherListening, DispatcherListeningReq,
+    DispatcherListeningRsp, DispatcherListeningStatus, DISPATCHER_LISTENING_REQ_ID,
 };
-use crossbeam_channel::bounded;
-use echo_requestee_protocol::{echo_requestee_protocol, EchoReq, EchoRsp, ECHO_REQ_ID};
+use echo_requestee_protocol::echo_requestee_protocol;
+use id_inventory::register_id;
 use insert_key_msg_id_value_from_serde_json_buf_requestee_protocol::{
     insert_key_msg_id_value_from_serde_json_buf_requestee_protocol,
     InsertKeyMsgIdValueFromSerdeJsonBufReq, InsertKeyMsgIdValueFromSerdeJsonBufRsp,
     InsertKeyMsgIdValueFromSerdeJsonBufRspStatus,
+    INSERT_KEY_MSG_ID_VALUE_FROM_SERDE_JSON_BUF_REQ_ID,
 };
+use link_protocol::{LinkCompatibility, LinkHello, LinkHelloAck, LinkRefused, LINK_HELLO_ID_STR};
+use msg_replay::{Direction, Recorder};
+use msg_tap::tap_observe;
 use protocol::Protocol;
 use protocol_set::ProtocolSet;
-use sender_map_by_instance_id::sender_map_insert;
+use runtime_metrics::{link_metrics_register, link_metrics_unregister, LinkCounters};
+use sender_map_by_instance_id::{sender_map_get, sender_map_insert, sender_map_remove};
+use state_machine::{
+    add_base_state, base_state, msg_handlers, state_machine, HasStateMachine, StateMachine,
+};
 use std::{
     any::Any,
     collections::{hash_map::Entry, HashMap},
+    error::Error,
     fmt::{self, Debug},
-    io::Read,
-    net::TcpListener,
-    sync::{atomic::AtomicU64, Arc, RwLock},
-    thread,
+    io::{self, Read},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
 };
-use utils::buf_u8_le_to_u16;
-
-use box_msg_any::BoxMsgAny;
-use msg_header::{get_msg_id_str_from_buf, FromSerdeJsonBuf, MsgHeader};
+use tap_protocol::TapPoint;
+use utils::{buf_u8_le_to_u16, write_msg_buf_to_tcp_stream};
+use uuid::Uuid;
 
-// State information
-#[derive(Debug)]
-pub struct StateInfo {
-    pub name: String,
-}
+#[cfg(feature = "tokio")]
+mod tokio_deserializer;
 
-// HashMap that maps address of a ProcessMsgFn to StateInfo
-type StateInfoMap<SM> = HashMap<*const ProcessMsgFn<SM>, StateInfo>;
+use box_msg_any::BoxMsgAny;
+use msg_header::{get_msg_id_str_from_buf, msg_codec_get, FromSerdeJsonBuf, MsgHeader};
 
 // State machine for channel to network
 pub struct MsgRouterDispatcher {
@@ -45,12 +66,25 @@ pub struct MsgRouterDispatcher {
     pub actor_id: AnId,
     pub instance_id: AnId,
     pub protocol_set: ProtocolSet,
-    pub current_state: ProcessMsgFn<Self>,
-    pub state_info_hash: StateInfoMap<Self>,
+    pub state_machine: StateMachine<Self>,
+    pub lifecycle: Lifecycle,
     pub chnl: ActorChannel,
     pub addr: String, // IP Address of a msg-router-receiver
     pub insert_key_msg_id_value_from_serde_json_buf_map:
         Arc<RwLock<HashMap<String, FromSerdeJsonBuf>>>, // Map of MsgId of each message
+    pub link_instance_id: AnId, // Identifies the link in metrics and taps
+    pub link_protocols: Vec<Protocol>, // Protocols answered in the LinkHelloAck
+    con_mgr_instance_id: AnId, // Answered in the LinkHelloAck, nil until ready
+    pub admin_node: Option<AdminNodeInfo>, // Some if admin connections are accepted
+    pub listening_status: DispatcherListeningStatus, // Of the deserializer's listener
+    listening_notify: Option<AnId>, // Sent a DispatcherListening once the bind completes
+    deserializer_thread: Option<JoinHandle<()>>,
+    deserializer_stop: Arc<AtomicBool>,
+    recorder: Option<Arc<Recorder>>,
+    #[cfg(feature = "tokio")]
+    runtime: Option<tokio::runtime::Handle>,
+    #[cfg(feature = "tokio")]
+    deserializer_task: Option<tokio_deserializer::TaskStop>,
 }
 
 // TODO: For Send implementors must guarantee maybe moved between threads. ??
@@ -77,49 +111,357 @@ impl Actor for MsgRouterDispatcher {
     }
 
     fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
-        (self.current_state)(self, context, msg);
+        self.dispatch(context, msg);
     }
 
     fn done(&self) -> bool {
         false
     }
+
+    fn get_protocol_set(&self) -> Option<&ProtocolSet> {
+        Some(&self.protocol_set)
+    }
 }
 
-impl Debug for MsgRouterDispatcher {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let fn_ptr = self.current_state as *const ProcessMsgFn<Self>;
-        let fn_ptr_string = format!("{fn_ptr:p}");
-        let state_name = if let Some(n) = self.state_info_hash.get(&fn_ptr) {
-            // State does have a name
-            n.name.as_str()
-        } else {
-            // State does NOT have a name, use address
-            fn_ptr_string.as_str()
+impl HasStateMachine for MsgRouterDispatcher {
+    fn state_machine(&self) -> &StateMachine<Self> {
+        &self.state_machine
+    }
+
+    fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+        &mut self.state_machine
+    }
+}
+
+impl ActorLifecycle for MsgRouterDispatcher {
+    fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+        &mut self.lifecycle
+    }
+
+    fn on_ready(&mut self, context: &dyn ActorContext) {
+        log::debug!("{}:Lifecycle: starting deserializer", self.name);
+        self.con_mgr_instance_id = *context.get_con_mgr_instance_id();
+        self.listening_status = match self.deserializer() {
+            Ok(local_addr) => DispatcherListeningStatus::Listening(local_addr),
+            Err(why) => {
+                log::error!(
+                    "{}:Lifecycle: unable to bind {}: {why}",
+                    self.name,
+                    self.addr
+                );
+                DispatcherListeningStatus::BindFailed(why.to_string())
+            }
         };
+        if let Some(dst_id) = &self.listening_notify {
+            let msg = Box::new(DispatcherListening::new(
+                dst_id,
+                &self.instance_id,
+                &self.addr,
+                &self.listening_status,
+            ));
+            let sent = match sender_map_get(dst_id) {
+                Some(sndr) => sndr.send(msg).map_err(|why| why.to_string()),
+                None => Err("not in the sender_map".to_owned()),
+            };
+            if let Err(why) = sent {
+                log::warn!(
+                    "{}:Lifecycle: unable to send DispatcherListening to {dst_id}: {why}",
+                    self.name
+                );
+            }
+        }
+    }
 
+    fn on_stopping(&mut self, _context: &dyn ActorContext) {
+        self.stop_deserializer();
+    }
+}
+
+impl Debug for MsgRouterDispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{} {{ id: {} instance_id: {} state_info_hash: {:?}; current_state: {state_name}; protocol_set: {:?}}}",
-            self.name, self.actor_id, self.instance_id, self.state_info_hash, self.protocol_set
+            "{} {{ id: {} instance_id: {} state_machine: {:?}; protocol_set: {:?}}}",
+            self.name, self.actor_id, self.instance_id, self.state_machine, self.protocol_set
         )
     }
 }
 
+/// Why the frames of a connection are refused before its LinkHello
+const NO_LINK_HELLO: &str = "no LinkHello received";
+
+/// Answer the LinkHello in msg_buf. Returns the answer to write to the
+/// connection, a LinkHelloAck listing link_protocols and
+/// con_mgr_instance_id, and which messages
+/// from the forwarder can't be accepted. If the LinkHello can't be
+/// decoded the answer is a LinkRefused, the compatibility is None and
+/// the connection is to be closed.
+fn link_hello_ack(
+    name: &str,
+    src_id: &AnId,
+    con_mgr_instance_id: &AnId,
+    link_protocols: &[Protocol],
+    msg_buf: &[u8],
+) -> (Option<Vec<u8>>, Option<LinkCompatibility>) {
+    let Some(hello) = LinkHello::from_serde_json_buf(msg_buf)
+        .and_then(|msg_any| msg_any.downcast::<LinkHello>().ok())
+    else {
+        let reason = "unable to decode LinkHello";
+        log::error!("{name}::link_hello_ack: {reason}, refusing the link");
+        let refused = Box::new(LinkRefused::new(&AnId::nil(), src_id, reason));
+        return (LinkRefused::to_serde_json_buf(refused), None);
+    };
+
+    let ack = Box::new(LinkHelloAck::new(
+        hello.src_id(),
+        src_id,
+        link_protocols,
+        con_mgr_instance_id,
+    ));
+    let ack_buf = LinkHelloAck::to_serde_json_buf(ack);
+    if ack_buf.is_none() {
+        log::warn!("{name}::link_hello_ack: unable to serialize LinkHelloAck");
+    }
+
+    let compatibility = LinkCompatibility::new(link_protocols, &hello.protocols);
+    log::debug!("{name}::link_hello_ack: {compatibility:?}");
+    (ack_buf, Some(compatibility))
+}
+
+/// The reply channel of an admin connection, messages sent to its
+/// instance_id are serialized and written to the connection.
+///
+/// The instance_id is removed from the sender_map when it's dropped.
+struct AdminReplyChannel {
+    name: String,
+    instance_id: AnId,
+    chnl: ActorChannel,
+    ack_buf: Vec<u8>, // The AdminHelloAck to write to the connection
+}
+
+impl AdminReplyChannel {
+    /// Accept the AdminHello in msg_buf creating the reply channel
+    /// announced in the AdminHelloAck
+    fn accept(name: &str, src_id: &AnId, node: &AdminNodeInfo, msg_buf: &[u8]) -> Option<Self> {
+        let Some(hello) = AdminHello::from_serde_json_buf(msg_buf)
+            .and_then(|msg_any| msg_any.downcast::<AdminHello>().ok())
+        else {
+            log::warn!("{name}::AdminReplyChannel::accept: unable to decode AdminHello");
+            return None;
+        };
+
+        let reply_instance_id = AnId::new();
+        let ack = Box::new(AdminHelloAck::new(
+            hello.src_id(),
+            src_id,
+            &reply_instance_id,
+            node,
+        ));
+        let Some(ack_buf) = AdminHelloAck::to_serde_json_buf(ack) else {
+            log::warn!("{name}::AdminReplyChannel::accept: unable to serialize AdminHelloAck");
+            return None;
+        };
+
+        let reply_name = format!("{name}_admin_reply");
+        let chnl = ActorChannel::new(&reply_name, &reply_instance_id);
+        sender_map_insert(&reply_instance_id, &chnl.sender);
+        Some(Self {
+            name: reply_name,
+            instance_id: reply_instance_id,
+            chnl,
+            ack_buf,
+        })
+    }
+}
+
+impl Drop for AdminReplyChannel {
+    fn drop(&mut self) {
+        sender_map_remove(&self.instance_id);
+    }
+}
+
+/// Routes the frames received on the connections to a dispatcher to
+/// their destinations
+#[derive(Clone)]
+struct FrameRouter {
+    insert_key_msg_id_value_from_serde_json_buf_map: Arc<RwLock<HashMap<String, FromSerdeJsonBuf>>>,
+    link_instance_id: AnId,
+    con_mgr_instance_id: AnId, // Sent in the LinkHelloAck
+    link_counters: Arc<LinkCounters>,
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl FrameRouter {
+    /// Count a frame with msg_len bytes received
+    fn received(&self, msg_len: usize) {
+        LinkCounters::add(&self.link_counters.frames_in, 1);
+        LinkCounters::add(&self.link_counters.bytes_in, (msg_len + 2) as u64);
+    }
+
+    /// Deserialize msg_buf, whose msg_id is id_str, and send it to its
+    /// destination unless compatibility refuses it
+    fn route(&self, name: &str, compatibility: &LinkCompatibility, id_str: &str, msg_buf: &[u8]) {
+        let msg_id = Uuid::parse_str(id_str).ok().map(AnId::from);
+        if let Some(why) = msg_id.and_then(|id| compatibility.refused(&id)) {
+            log::error!("{name}: refusing msg_id={id_str}, {why}");
+            LinkCounters::add(&self.link_counters.refused, 1);
+            return;
+        }
+
+        if let Some(Err(why)) = self
+            .recorder
+            .as_ref()
+            .map(|r| r.record_buf(Direction::Inbound, msg_buf))
+        {
+            log::warn!("{name}: error recording: {why}");
+        }
+        let Ok(map) = self.insert_key_msg_id_value_from_serde_json_buf_map.read() else {
+            log::warn!("{name}: insert_key_msg_id_value_from_serde_json_buf_map, NO lock");
+            return;
+        };
+        log::trace!(
+            "{name}: insert_key_msg_id_value_from_serde_json_buf_map, GOT lock. map.len={}",
+            map.len()
+        );
+        // Messages that weren't added are decoded by their MsgCodec
+        let from_serde_json_buf = map.get(id_str).copied().or_else(|| {
+            msg_id
+                .and_then(|id| msg_codec_get(&id))
+                .map(|codec| codec.from_serde_json_buf)
+        });
+        drop(map);
+        let Some(fn_from_serde_json_buf) = from_serde_json_buf else {
+            log::warn!("{name}: map.get({id_str}) NOT found");
+            LinkCounters::add(&self.link_counters.decode_errors, 1);
+            return;
+        };
+        let Some(box_msg_any) = fn_from_serde_json_buf(msg_buf) else {
+            log::warn!("{name}: unable to decode msg_id={id_str}, dropping");
+            LinkCounters::add(&self.link_counters.decode_errors, 1);
+            return;
+        };
+
+        tap_observe(&TapPoint::Link(self.link_instance_id), &box_msg_any);
+
+        let Some(sndr) = MsgHeader::get_dst_sndr_from_boxed_msg_any(&box_msg_any) else {
+            let dst_id = MsgHeader::get_dst_id_from_boxed_msg_any(&box_msg_any);
+            log::warn!("{name}: no sender for dst_id={dst_id} msg_id={id_str}, dropping");
+            LinkCounters::add(&self.link_counters.decode_errors, 1);
+            dead_letter_post(box_msg_any, "no sender for dst_id");
+            return;
+        };
+        if let Err(why) = sndr.send(box_msg_any) {
+            log::warn!("{name}: tx.send failed: {why}");
+        }
+    }
+}
+
+/// The path for responses to the requests of an admin connection.
+///
+/// Messages sent to the reply channel are written to the connection by
+/// the writer thread.
+struct AdminReply {
+    writer_thread: JoinHandle<()>,
+    stop_tx: Sender<()>, // Dropped to stop the writer thread
+}
+
+impl AdminReply {
+    /// Answer the AdminHello in msg_buf and start the writer thread
+    fn start(
+        name: &str,
+        stream: &TcpStream,
+        src_id: &AnId,
+        node: &AdminNodeInfo,
+        msg_buf: &[u8],
+    ) -> Option<Self> {
+        let Ok(mut stream) = stream.try_clone() else {
+            log::warn!("{name}::AdminReply::start: unable to clone tcp_stream");
+            return None;
+        };
+        let reply = AdminReplyChannel::accept(name, src_id, node, msg_buf)?;
+        if let Err(why) = write_msg_buf_to_tcp_stream(&mut stream, &reply.ack_buf) {
+            log::warn!("{name}::AdminReply::start: {why}");
+            return None;
+        }
+
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let writer_thread = thread::spawn(move || Self::writer(reply, stream, stop_rx));
+        Some(Self {
+            writer_thread,
+            stop_tx,
+        })
+    }
+
+    fn writer(reply: AdminReplyChannel, mut stream: TcpStream, stop_rx: Receiver<()>) {
+        let name = &reply.name;
+        log::debug!("{name}::writer:+");
+        loop {
+            select! {
+                recv(reply.chnl.receiver.rx) -> msg_any => {
+                    let Ok(msg_any) = msg_any else {
+                        break;
+                    };
+                    let Some(buf) = admin_rsp_to_serde_json_buf(msg_any) else {
+                        log::warn!("{name}::writer: not an admin response, dropping");
+                        continue;
+                    };
+                    if let Err(why) = write_msg_buf_to_tcp_stream(&mut stream, &buf) {
+                        log::warn!("{name}::writer: {why}");
+                        break;
+                    }
+                }
+                recv(stop_rx) -> _ => break,
+            }
+        }
+        log::debug!("{name}::writer:-");
+    }
+
+    /// Stop the writer thread, which drops the reply channel
+    fn stop(self) {
+        drop(self.stop_tx);
+        let _ = self.writer_thread.join();
+    }
+}
+
+/// How long the deserializer thread waits between accepts when there is
+/// no connection pending, it bounds how long stop_deserializer takes
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 // From: https://www.uuidgenerator.net/version4
 const MSG_ROUTER_DISPATCHER_ACTOR_ID: AnId = anid!("c9079a70-d7d6-465c-96bb-3bd0a6b24294");
 const MSG_ROUTER_DISPATCHER_PROTOCOL_SET_ID: AnId = anid!("d285f0a1-2b71-414e-935d-2559d4a02c3c");
+register_id!(
+    Actor,
+    MSG_ROUTER_DISPATCHER_ACTOR_ID,
+    "msg_router_dispatcher"
+);
+register_id!(
+    ProtocolSet,
+    MSG_ROUTER_DISPATCHER_PROTOCOL_SET_ID,
+    "msg_router_dispatcher_ps"
+);
 
 impl MsgRouterDispatcher {
     pub fn new(name: &str, addr: &str) -> Self {
         // Create the msg_router ProtocolSet, `ps`.
-        println!("MsgRouterDispatcher::new({})", name);
+        log::debug!("MsgRouterDispatcher::new({})", name);
         let mut pm = HashMap::<AnId, Protocol>::new();
         let ci_iep = cmd_init_issuee_protocol();
         pm.insert(ci_iep.id, ci_iep.clone());
+        let cs_iep = cmd_shutdown_issuee_protocol();
+        pm.insert(cs_iep.id, cs_iep.clone());
         let e_rep = echo_requestee_protocol();
         pm.insert(e_rep.id, e_rep.clone());
         let ik_rep = insert_key_msg_id_value_from_serde_json_buf_requestee_protocol();
         pm.insert(ik_rep.id, ik_rep.clone());
+        let cm_rap = con_mgr_registee_actor_protocol();
+        pm.insert(cm_rap.id, cm_rap.clone());
+        let dl_rep = dispatcher_listening_requestee_protocol();
+        pm.insert(dl_rep.id, dl_rep.clone());
         let msg_router_dispatcher_ps = ProtocolSet::new(
             "msg_router_dispatcher_ps",
             MSG_ROUTER_DISPATCHER_PROTOCOL_SET_ID,
@@ -129,13 +471,23 @@ impl MsgRouterDispatcher {
         let msg_router_instance_id = AnId::new();
         let chnl = ActorChannel::new(name, &msg_router_instance_id);
 
-        let mut this = Self {
+        let mut state_machine = state_machine!(Self, [state0]);
+        let base = add_base_state(&mut state_machine);
+        state_machine.set_parent(Self::state0, base);
+        add_lifecycle_handlers(&mut state_machine, base);
+        msg_handlers!(state_machine, Self, state0, [
+            INSERT_KEY_MSG_ID_VALUE_FROM_SERDE_JSON_BUF_REQ_ID: InsertKeyMsgIdValueFromSerdeJsonBufReq
+                => handle_insert_key_msg_id_value_from_serde_json_buf_req,
+            DISPATCHER_LISTENING_REQ_ID: DispatcherListeningReq => handle_dispatcher_listening_req,
+        ]);
+
+        let this = Self {
             name: name.to_owned(),
             actor_id: MSG_ROUTER_DISPATCHER_ACTOR_ID,
             instance_id: msg_router_instance_id,
             protocol_set: msg_router_dispatcher_ps,
-            current_state: Self::state0,
-            state_info_hash: StateInfoMap::<Self>::new(),
+            state_machine,
+            lifecycle: Lifecycle::new(true),
             chnl,
             addr: addr.to_owned(),
             insert_key_msg_id_value_from_serde_json_buf_map: Arc::new(RwLock::new(HashMap::<
@@ -143,28 +495,27 @@ impl MsgRouterDispatcher {
                 FromSerdeJsonBuf,
             >::new(
             ))),
+            link_instance_id: AnId::new(),
+            link_protocols: Vec::new(),
+            con_mgr_instance_id: AnId::nil(),
+            admin_node: None,
+            listening_status: DispatcherListeningStatus::NotStarted,
+            listening_notify: None,
+            deserializer_thread: None,
+            deserializer_stop: Arc::new(AtomicBool::new(false)),
+            recorder: None,
+            #[cfg(feature = "tokio")]
+            runtime: None,
+            #[cfg(feature = "tokio")]
+            deserializer_task: None,
         };
 
         // Add ourself to the sender_map
         sender_map_insert(&this.instance_id, &this.chnl.sender);
 
-        this.add_state(Self::state0, "state0");
         this
     }
 
-    pub fn add_state(&mut self, state: ProcessMsgFn<Self>, name: &str) {
-        let s = StateInfo {
-            name: name.to_owned(),
-        };
-        let k = state as *const ProcessMsgFn<Self>;
-        self.state_info_hash.insert(k, s);
-    }
-
-    #[allow(unused)]
-    fn transition(&mut self, dest: ProcessMsgFn<Self>) {
-        self.current_state = dest;
-    }
-
     pub fn add_msg_id_from_serde_json_buf(
         &mut self,
         msg_id: AnId,
@@ -177,7 +528,7 @@ impl MsgRouterDispatcher {
             .unwrap(); // TODO: remove unwrap
 
         if let Entry::Vacant(e) = wlocked_hashmap.entry(msg_id.to_string()) {
-            println!("add_msg_id_from_serde_json_buf: msg_id: {msg_id}");
+            log::trace!("add_msg_id_from_serde_json_buf: msg_id: {msg_id}");
             e.insert(from_serde_json_buf);
 
             true
@@ -186,195 +537,325 @@ impl MsgRouterDispatcher {
         }
     }
 
-    /// Reads messages from a TcpStream, deserializes them and sends them to an associated channel
-    pub fn deserializer(&self) {
-        println!("{}::deserializer:+", &self.name);
-        let (status_tx, status_rx) = bounded::<String>(1);
+    /// Send instance_id a DispatcherListening once the deserializer has
+    /// bound addr, or failed to, so it needn't ask with a
+    /// DispatcherListeningReq. Must be set before the deserializer is
+    /// started.
+    pub fn notify_listening(&mut self, instance_id: &AnId) {
+        self.listening_notify = Some(*instance_id);
+    }
+
+    /// Add a protocol whose messages are dispatched, must be added
+    /// before the deserializer is started
+    pub fn add_link_protocol(&mut self, protocol: &Protocol) {
+        self.link_protocols.push(protocol.clone());
+    }
+
+    /// Dispatch the messages of the ConMgrs federated over links to this
+    /// dispatcher, see con_mgr_federation_protocol. Must be enabled
+    /// before the deserializer is started.
+    pub fn enable_federation(&mut self) {
+        self.add_link_protocol(con_mgr_federation_issuee_protocol());
+        self.add_link_protocol(con_mgr_federation_issuer_protocol());
+    }
+
+    /// Accept admin connections, see admin_protocol, node is returned
+    /// in the AdminHelloAck. Must be enabled before the deserializer is
+    /// started.
+    pub fn enable_admin(&mut self, node: &AdminNodeInfo) {
+        for (msg_id, from_serde_json_buf) in admin_req_from_serde_json_bufs() {
+            self.add_msg_id_from_serde_json_buf(msg_id, from_serde_json_buf);
+        }
+        self.admin_node = Some(node.clone());
+    }
+
+    /// Record the frames received by the deserializer, must be set
+    /// before it is started
+    pub fn set_recorder(&mut self, recorder: Arc<Recorder>) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Run the deserializer as tasks on runtime, one for accepting and
+    /// one for each connection, instead of threads. Must be set before
+    /// the deserializer is started.
+    #[cfg(feature = "tokio")]
+    pub fn set_runtime(&mut self, runtime: &tokio::runtime::Handle) {
+        self.runtime = Some(runtime.clone());
+    }
+
+    /// Bind addr and start a thread reading messages from the
+    /// connections to it, deserializing them and sending them to their
+    /// destinations. Returns the address bound.
+    pub fn deserializer(&mut self) -> Result<SocketAddr, Box<dyn Error>> {
+        log::trace!("{}::deserializer:+", self.name);
+        let listener = TcpListener::bind(&self.addr)?;
+        let local_addr = listener.local_addr()?;
 
         // Make copies of the data we need in the thread
         let self_name = self.name.clone();
-        let deser_thread_addr = self.addr.clone();
-        let deser_thread_insert_key_msg_id_value_from_serde_json_buf_map =
-            Arc::clone(&self.insert_key_msg_id_value_from_serde_json_buf_map);
-        thread::spawn(move || {
-            println!("{}::deserializer_thread:+", &self_name);
-
-            // Ignore errors for the moment
-            let listener = TcpListener::bind(deser_thread_addr).unwrap();
+        let router = FrameRouter {
+            insert_key_msg_id_value_from_serde_json_buf_map: Arc::clone(
+                &self.insert_key_msg_id_value_from_serde_json_buf_map,
+            ),
+            link_instance_id: self.link_instance_id,
+            con_mgr_instance_id: self.con_mgr_instance_id,
+            link_counters: link_metrics_register(
+                &(self.name.clone() + "_deserializer"),
+                &self.link_instance_id,
+            ),
+            recorder: self.recorder.clone(),
+        };
+        let link_protocols = self.link_protocols.clone();
+        let admin_node = self.admin_node.clone();
+
+        #[cfg(feature = "tokio")]
+        if let Some(runtime) = self.runtime.clone() {
+            self.tokio_deserializer(&runtime, listener, router, link_protocols, admin_node)?;
+            log::debug!(
+                "{}::deserializer:- task running local_addr={local_addr}",
+                self.name
+            );
+            return Ok(local_addr);
+        }
 
-            // Indicate we're ready
-            status_tx.send("ready".to_owned()).unwrap_or_else(|_| {
-                panic!(
-                    "{}::deserializer_thread: Unable to indicate we're ready",
-                    &self_name
-                )
-            });
-            println!("{}::deserializer_thread: ready", &self_name);
+        // Nonblocking so the thread sees deserializer_stop between accepts
+        listener.set_nonblocking(true)?;
+        let deser_thread_stop = Arc::clone(&self.deserializer_stop);
+        let deserializer_thread = thread::spawn(move || {
+            log::debug!(
+                "{}::deserializer_thread:+ local_addr={local_addr}",
+                self_name
+            );
 
             let stream_id = AtomicU64::new(0);
-            for stream in listener.incoming() {
-                match stream {
-                    Ok(mut tcp_stream) => {
-                        // TODO: Make async, but for now spin up a separate thread for each connection
+            let mut inner_threads = Vec::<(TcpStream, JoinHandle<()>)>::new();
+            while !deser_thread_stop.load(Ordering::SeqCst) {
+                // Reap the connections that have closed, dropping their streams
+                inner_threads.retain(|(_, inner_thread)| !inner_thread.is_finished());
+
+                match listener.accept() {
+                    Err(why) if why.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                    Ok((mut tcp_stream, _)) => {
+                        // The connection must block, on some platforms it
+                        // inherits nonblocking from the listener
+                        if let Err(why) = tcp_stream.set_nonblocking(false) {
+                            log::warn!(
+                                "{}::deserializer_thread: dropping connection: {why}",
+                                self_name
+                            );
+                            continue;
+                        }
+                        // A thread for each connection, the tokio feature
+                        // provides a deserializer with a task for each.
                         let inner_thread_id =
                             stream_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
                         let deser_inner_thread_name = format!(
                             "{}::deserializer_inner_thread:{}",
                             self_name, inner_thread_id
                         );
-                        let deser_inner_thread_insert_key_msg_id_value_from_serde_json_buf_map =
-                            deser_thread_insert_key_msg_id_value_from_serde_json_buf_map.clone();
-                        thread::spawn(move || {
-                            //println!( "{}: stream:+", &deser_inner_thread_name);
-
+                        let router = router.clone();
+                        let link_protocols = link_protocols.clone();
+                        let admin_node = admin_node.clone();
+                        let Ok(stream_clone) = tcp_stream.try_clone() else {
+                            log::warn!(
+                                "{}: Unable to clone tcp_stream, dropping connection",
+                                deser_inner_thread_name
+                            );
+                            continue;
+                        };
+                        let inner_thread = thread::spawn(move || {
+                            // Everything is refused until the forwarder sends a LinkHello
+                            let mut compatibility = LinkCompatibility::refuse_all(NO_LINK_HELLO);
+                            let mut admin_reply: Option<AdminReply> = None;
                             loop {
                                 // TODO: Probably need a signature and version indicator too.
                                 let mut msg_len_buf = [0u8; 2];
                                 if tcp_stream.read_exact(&mut msg_len_buf).is_err() {
-                                    println!(
+                                    log::trace!(
                                         "{}: stream closed reading msg_len, stopping",
-                                        &deser_inner_thread_name
+                                        deser_inner_thread_name
                                     );
                                     break;
                                 }
 
                                 let msg_len = buf_u8_le_to_u16(&msg_len_buf) as usize;
-                                //println!( "{}{inner_thread_id} msg_len={msg_len}", &deser_inner_thread_name);
 
                                 // We need to initialize the Vec so read_exact knows how much to read.
                                 // TODO: Consider using [read_buf_exact](https://doc.rust-lang.org/std/io/trait.Read.html#method.read_buf_exact).
                                 let mut msg_buf = vec![0; msg_len];
                                 if tcp_stream.read_exact(msg_buf.as_mut_slice()).is_err() {
-                                    println!(
+                                    log::trace!(
                                         "{}: stream close reading msg_buf, stopping",
-                                        &deser_inner_thread_name
+                                        deser_inner_thread_name
                                     );
                                     break;
                                 }
+                                router.received(msg_len);
 
                                 let id_str = get_msg_id_str_from_buf(&msg_buf);
-                                //println!("{}: mag.get({id_str}) lookup", &deser_inner_thread_name);
-                                if let Ok(map) = deser_inner_thread_insert_key_msg_id_value_from_serde_json_buf_map.read() {
-                                    println!("{}: deser_inner_thread_insert_key_msg_id_value_from_serde_json_buf_map, GOT lock. map.len={}", &deser_inner_thread_name, map.len());
-                                    if let Some(fn_from_serde_json_buf) = map.get(id_str) {
-                                        let box_msg_any =
-                                            (*fn_from_serde_json_buf)(&msg_buf).unwrap();
-                                        //println!(
-                                        //    "{}: box_msg_any {:p} {} {box_msg_any:?}",
-                                        //    &deser_inner_thread_name,
-                                        //    box_msg_any,
-                                        //    std::mem::size_of::<BoxMsgAny>()
-                                        //);
-
-                                        let sndr = MsgHeader::get_dst_sndr_from_boxed_msg_any(
-                                            &box_msg_any,
-                                        )
-                                        .unwrap();
-                                        match sndr.send(box_msg_any) {
-                                            Ok(_) => (),
-                                            Err(why) => {
-                                                println!(
-                                                    "{}: tx.send failed: {why}",
-                                                    &deser_inner_thread_name
-                                                );
-                                            }
+                                if id_str == LINK_HELLO_ID_STR {
+                                    let (answer_buf, accepted) = link_hello_ack(
+                                        &deser_inner_thread_name,
+                                        &router.link_instance_id,
+                                        &router.con_mgr_instance_id,
+                                        &link_protocols,
+                                        &msg_buf,
+                                    );
+                                    if let Some(answer_buf) = answer_buf {
+                                        if let Err(why) = write_msg_buf_to_tcp_stream(
+                                            &mut tcp_stream,
+                                            &answer_buf,
+                                        ) {
+                                            log::warn!("{deser_inner_thread_name}: {why}");
+                                        }
+                                    }
+                                    match accepted {
+                                        Some(accepted) => compatibility = accepted,
+                                        None => {
+                                            // Refused, the connection is closed
+                                            LinkCounters::add(&router.link_counters.refused, 1);
+                                            break;
+                                        }
+                                    }
+                                    continue;
+                                }
+                                if id_str == ADMIN_HELLO_ID_STR {
+                                    if let (Some(node), None) = (&admin_node, &admin_reply) {
+                                        admin_reply = AdminReply::start(
+                                            &deser_inner_thread_name,
+                                            &tcp_stream,
+                                            &router.link_instance_id,
+                                            node,
+                                            &msg_buf,
+                                        );
+                                        // Admin connections send requests without a LinkHello
+                                        if admin_reply.is_some() {
+                                            compatibility = LinkCompatibility::default();
                                         }
                                     } else {
-                                        println!(
-                                            "{}: map.get({id_str}) NOT found",
-                                            &deser_inner_thread_name
+                                        log::warn!(
+                                            "{}: ignoring AdminHello",
+                                            deser_inner_thread_name
                                         );
                                     }
-                                } else {
-                                    println!(
-                                        "{}: deser_inner_thread_insert_key_msg_id_value_from_serde_json_buf_map, NO lock",
-                                        &deser_inner_thread_name
-                                    );
+                                    continue;
                                 }
+                                router.route(
+                                    &deser_inner_thread_name,
+                                    &compatibility,
+                                    id_str,
+                                    &msg_buf,
+                                );
+                            }
+                            if let Some(admin_reply) = admin_reply {
+                                admin_reply.stop();
                             }
-                            //println!( "{}:-", &deser_inner_thread_name);
                         });
+                        inner_threads.push((stream_clone, inner_thread));
                     }
                     Err(why) => {
-                        println!(
+                        log::warn!(
                             "{}::deserializer_thread: Error accepting connection: {why}",
-                            &self_name
+                            self_name
                         );
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
                     }
                 }
             }
 
-            println!("{}::deserializer_thread:-", &self_name);
+            // Close the connections so the inner threads stop reading, then wait for them
+            for (tcp_stream, inner_thread) in inner_threads {
+                let _ = tcp_stream.shutdown(Shutdown::Both);
+                let _ = inner_thread.join();
+            }
+
+            link_metrics_unregister(&router.link_instance_id);
+            if let Some(Err(why)) = router.recorder.as_ref().map(|r| r.flush()) {
+                log::warn!(
+                    "{}::deserializer_thread: error flushing recorder: {why}",
+                    self_name
+                );
+            }
+            log::debug!("{}::deserializer_thread:-", self_name);
         });
 
-        // Wait for outer thread to be running
-        println!(
-            "{}::deserializer: Wait for thread to be running",
-            &self.name
+        self.deserializer_thread = Some(deserializer_thread);
+        log::debug!(
+            "{}::deserializer:- thread running local_addr={local_addr}",
+            self.name
         );
-        status_rx
-            .recv()
-            .expect("{}::dserializer error, loop must have died");
-        println!("{}::deserializer: thread running", &self.name);
+        Ok(local_addr)
+    }
+
+    /// Stop the deserializer thread or tasks, closing the listener and any
+    /// connections, and wait for it to exit
+    pub fn stop_deserializer(&mut self) {
+        log::debug!("{}::stop_deserializer:+", self.name);
+        #[cfg(feature = "tokio")]
+        if let Some(task_stop) = self.deserializer_task.take() {
+            task_stop.stop(&self.name);
+        }
+        if let Some(deserializer_thread) = self.deserializer_thread.take() {
+            // Seen by the thread within ACCEPT_POLL_INTERVAL
+            self.deserializer_stop.store(true, Ordering::SeqCst);
+            if deserializer_thread.join().is_err() {
+                log::warn!(
+                    "{}::stop_deserializer: deserializer thread panicked",
+                    self.name
+                );
+            }
+        }
+        log::debug!("{}::stop_deserializer:-", self.name);
     }
 
     pub fn state0(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
-        if let Some(msg) = msg_any.downcast_ref::<InsertKeyMsgIdValueFromSerdeJsonBufReq>() {
-            let msg_id = &msg.msg_id;
-            let from_serde_json_buf: fn(&[u8]) -> Option<Box<dyn Any + Send>> =
-                msg.from_serde_json_buf;
-            let status = if self.add_msg_id_from_serde_json_buf(*msg_id, from_serde_json_buf) {
-                InsertKeyMsgIdValueFromSerdeJsonBufRspStatus::Success
-            } else {
-                InsertKeyMsgIdValueFromSerdeJsonBufRspStatus::AlreadyInserted
-            };
-            let rsp_msg = Box::new(InsertKeyMsgIdValueFromSerdeJsonBufRsp::new(
-                context.get_dst_instance_id(),
-                &self.instance_id,
-                msg_id,
-                status,
-            ));
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<EchoReq>() {
-            assert_eq!(msg.msg_id(), &ECHO_REQ_ID);
-            //println!("{}:State0: msg={msg:?}", self.name);
-            let rsp_msg = Box::new(EchoRsp::new(
-                context.get_dst_instance_id(),
-                &self.instance_id,
-                msg.req_timestamp_ns,
-                msg.counter,
-            ));
-            //println!("{}:State0: sending rsp_msg={rsp_msg:?}", self.name);
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<CmdInit>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CMD_INIT_ID);
-
-            // Register ourselves with ConMgr
-            let msg = Box::new(ConMgrRegisterActorReq::new(
-                msg.src_id(),
-                &self.instance_id,
-                &self.name,
-                &self.actor_id,
-                &self.instance_id,
-                &self.protocol_set,
-            ));
-            print!(
-                "{}:State0: sending ConMgrRegisterActorReq={msg:?}",
+        // Messages without a handler are unknown
+        base_state(self, context, msg_any);
+    }
+
+    fn handle_insert_key_msg_id_value_from_serde_json_buf_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &InsertKeyMsgIdValueFromSerdeJsonBufReq,
+    ) {
+        let msg_id = &msg.msg_id;
+        let from_serde_json_buf: fn(&[u8]) -> Option<Box<dyn Any + Send>> = msg.from_serde_json_buf;
+        let status = if self.add_msg_id_from_serde_json_buf(*msg_id, from_serde_json_buf) {
+            InsertKeyMsgIdValueFromSerdeJsonBufRspStatus::Success
+        } else {
+            InsertKeyMsgIdValueFromSerdeJsonBufRspStatus::AlreadyInserted
+        };
+        let rsp_msg = Box::new(InsertKeyMsgIdValueFromSerdeJsonBufRsp::new(
+            context.get_dst_instance_id(),
+            &self.instance_id,
+            msg_id,
+            status,
+        ));
+        if let Err(why) = context.send_dst(rsp_msg) {
+            log::warn!(
+                "{}:State0: unable to send InsertKeyMsgIdValueFromSerdeJsonBufRsp: {why}",
                 self.name
             );
-            context.send_con_mgr(msg).unwrap();
-
-            println!("{}:State0: starting deserializer", self.name);
-            self.deserializer();
-        } else if let Some(msg) = msg_any.downcast_ref::<ConMgrRegisterActorRsp>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CON_MGR_REGISTER_ACTOR_RSP_ID);
-            assert_eq!(msg.status, ConMgrRegisterActorStatus::Success);
-        } else {
-            let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
-            println!(
-                "{}:State0: Unknown msg_any={msg_any:?} {msg_id:?}",
+        }
+    }
+
+    fn handle_dispatcher_listening_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &DispatcherListeningReq,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+        let rsp_msg = Box::new(DispatcherListeningRsp::new(
+            context.get_dst_instance_id(),
+            &self.instance_id,
+            &self.addr,
+            &self.listening_status,
+        ));
+        if let Err(why) = context.send_dst(rsp_msg) {
+            log::warn!(
+                "{}:State0: unable to send DispatcherListeningRsp: {why}",
                 self.name
             );
         }
@@ -383,7 +864,7 @@ impl MsgRouterDispatcher {
 
 #[cfg(test)]
 mod test {
-    use std::net::TcpStream;
+    use std::{net::TcpStream, time::Duration};
 
     use actor_channel::ActorSender;
     use actor_executor::{
@@ -391,9 +872,14 @@ mod test {
     };
     use chrono::Utc;
     use cmd_done_issuer_protocol::CmdDone;
-    use echo_requestee_protocol::ECHO_RSP_ID;
-    use sender_map_by_instance_id::sender_map_get;
-    use utils::write_msg_buf_to_tcp_stream;
+    use cmd_init_issuee_protocol::CmdReady;
+    use echo_requestee_protocol::{EchoReq, EchoRsp, ECHO_REQ_ID, ECHO_RSP_ID};
+    use echo_requester_protocol::echo_requester_protocol;
+    use protocol::Version;
+    use req_add_actor::ReqAddActor;
+    use rsp_add_actor::RspAddActor;
+    use runtime_metrics::metrics_snapshot;
+    use utils::{read_msg_buf_from_tcp_stream, write_msg_buf_to_tcp_stream};
 
     use super::*;
 
@@ -428,17 +914,32 @@ mod test {
             self.dst_sndr.get_dst_instance_id()
         }
 
-        fn clone_dst_sndr(&self) -> ActorSender {
-            self.dst_sndr.clone()
+        fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>> {
+            Ok(self.dst_sndr.clone())
         }
     }
 
+    /// Ask the dispatcher at instance_id about its listener
+    fn dispatcher_listening(
+        instance_id: &AnId,
+        supervisor_instance_id: &AnId,
+        supervisor_chnl: &ActorChannel,
+    ) -> DispatcherListeningRsp {
+        let msg = Box::new(DispatcherListeningReq::new(
+            instance_id,
+            supervisor_instance_id,
+        ));
+        sender_map_get(instance_id).unwrap().send(msg).unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let msg = DispatcherListeningRsp::from_box_msg_any(&msg_any).unwrap();
+        println!("dispatcher_listening: msg={msg:?}");
+        msg.clone()
+    }
+
     #[test]
     fn test_1() {
         println!("\ntest_1:+");
 
-        let mrd1_addr = "localhost:12345";
-
         // Initialize Supervisor starting a single ActorExecutor and the connection manager
         let (
             supervisor_instance_id,
@@ -449,8 +950,10 @@ mod test {
         ) = initialize_supervisor_con_mgr_actor_executor_blocking();
         let _ae_sender = sender_map_get(&ae_instance_id).unwrap();
 
-        // Add MsgRouterDispatcher to ActorExecutor
-        let mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", mrd1_addr));
+        // Add MsgRouterDispatcher to ActorExecutor, it binds any free port
+        let mut mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+        mrd1.add_link_protocol(echo_requestee_protocol());
+        let mrd1_link_instance_id = mrd1.link_instance_id;
 
         let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
             mrd1,
@@ -458,6 +961,10 @@ mod test {
             &supervisor_instance_id,
             &supervisor_chnl.receiver,
         );
+        let mrd1_addr =
+            dispatcher_listening(&mrd1_instance_id, &supervisor_instance_id, &supervisor_chnl)
+                .local_addr()
+                .unwrap();
 
         // Context for msg_router is supervisor
         let _msg_router_context = Context {
@@ -486,6 +993,35 @@ mod test {
         // Connect to MsgRouterDispatcher
         let mut writer = TcpStream::connect(mrd1_addr).unwrap();
 
+        // Messages are refused until a LinkHello is sent
+        let echo_msg = Box::new(EchoReq::new(
+            &con_mgr_instance_id,
+            &supervisor_instance_id,
+            0,
+        ));
+        let buf = EchoReq::to_serde_json_buf(echo_msg).unwrap();
+        write_msg_buf_to_tcp_stream(&mut writer, &buf).unwrap();
+        let hello = Box::new(LinkHello::new(
+            &AnId::nil(),
+            &supervisor_instance_id,
+            &[echo_requester_protocol().clone()],
+        ));
+        let buf = LinkHello::to_serde_json_buf(hello).unwrap();
+        write_msg_buf_to_tcp_stream(&mut writer, &buf).unwrap();
+        let buf = read_msg_buf_from_tcp_stream(&mut writer).unwrap();
+        assert!(LinkHelloAck::from_serde_json_buf(&buf).is_some());
+
+        // A message for an unknown dst_id is dropped to the dead letters
+        // and the following messages are still routed
+        let unknown_instance_id = AnId::new();
+        let echo_msg = Box::new(EchoReq::new(
+            &unknown_instance_id,
+            &supervisor_instance_id,
+            0,
+        ));
+        let buf = EchoReq::to_serde_json_buf(echo_msg).unwrap();
+        write_msg_buf_to_tcp_stream(&mut writer, &buf).unwrap();
+
         let before_timestamp_ns = Utc::now().timestamp_nanos();
 
         // Create EchoReq message and serialize it
@@ -533,6 +1069,18 @@ mod test {
         assert!(msg.req_timestamp_ns < msg.rsp_timestamp_ns);
         assert!(msg.rsp_timestamp_ns < after_timestamp_ns);
 
+        let dead_letters = dead_letters::dead_letters_take_for(&unknown_instance_id);
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].msg_id, ECHO_REQ_ID);
+        let snapshot = metrics_snapshot();
+        let link = snapshot
+            .links
+            .iter()
+            .find(|l| l.instance_id == mrd1_link_instance_id)
+            .unwrap();
+        assert_eq!(link.refused, 1);
+        assert_eq!(link.decode_errors, 1);
+
         println!("test1: send CmdDone to ae");
         let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
         sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
@@ -545,4 +1093,268 @@ mod test {
 
         println!("test_1:-");
     }
+
+    #[test]
+    fn test_link_hello_incompatible_version() {
+        println!("\ntest_link_hello_incompatible_version:+");
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        let mut mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+        mrd1.add_link_protocol(echo_requestee_protocol());
+        let mrd1_link_instance_id = mrd1.link_instance_id;
+        let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
+            mrd1,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let mrd1_addr =
+            dispatcher_listening(&mrd1_instance_id, &supervisor_instance_id, &supervisor_chnl)
+                .local_addr()
+                .unwrap();
+
+        let msg = Box::new(InsertKeyMsgIdValueFromSerdeJsonBufReq::new(
+            &mrd1_instance_id,
+            &supervisor_instance_id,
+            &ECHO_REQ_ID,
+            EchoReq::from_serde_json_buf,
+        ));
+        sender_map_get(&mrd1_instance_id)
+            .unwrap()
+            .send(msg)
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(InsertKeyMsgIdValueFromSerdeJsonBufRsp::from_box_msg_any(&msg_any).is_some());
+
+        // Offer version 2 of the echo protocol and check the ack
+        let mut stream = TcpStream::connect(mrd1_addr).unwrap();
+        let mut echo_requester_v2 = echo_requester_protocol().clone();
+        echo_requester_v2.version = Version::new(2, 0, 0);
+        let hello = Box::new(LinkHello::new(
+            &AnId::nil(),
+            &supervisor_instance_id,
+            &[echo_requester_v2],
+        ));
+        let buf = LinkHello::to_serde_json_buf(hello).unwrap();
+        write_msg_buf_to_tcp_stream(&mut stream, &buf).unwrap();
+        let buf = read_msg_buf_from_tcp_stream(&mut stream).unwrap();
+        let msg_any = LinkHelloAck::from_serde_json_buf(&buf).unwrap();
+        let ack = LinkHelloAck::from_box_msg_any(&msg_any).unwrap();
+        println!("test_link_hello_incompatible_version: ack={ack:?}");
+        assert_eq!(ack.dst_id(), &supervisor_instance_id);
+        assert_eq!(ack.protocols, vec![echo_requestee_protocol().clone()]);
+        assert_eq!(ack.con_mgr_instance_id, con_mgr_instance_id);
+
+        // The EchoReq is refused instead of being dispatched
+        let echo_msg = Box::new(EchoReq::new(
+            &con_mgr_instance_id,
+            &supervisor_instance_id,
+            1,
+        ));
+        let buf = EchoReq::to_serde_json_buf(echo_msg).unwrap();
+        write_msg_buf_to_tcp_stream(&mut stream, &buf).unwrap();
+        assert!(supervisor_chnl
+            .receiver
+            .rx
+            .recv_timeout(Duration::from_millis(500))
+            .is_err());
+        let snapshot = metrics_snapshot();
+        let link = snapshot
+            .links
+            .iter()
+            .find(|l| l.instance_id == mrd1_link_instance_id)
+            .unwrap();
+        println!("test_link_hello_incompatible_version: link={link:?}");
+        assert_eq!(link.refused, 1);
+        assert_eq!(link.decode_errors, 0);
+
+        let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        ae_join_handle
+            .join()
+            .expect("Failed joining ae_join_handle");
+
+        println!("test_link_hello_incompatible_version:-");
+    }
+
+    #[test]
+    fn test_link_hello_undecodable() {
+        println!("\ntest_link_hello_undecodable:+");
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            _con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        let mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+        let mrd1_link_instance_id = mrd1.link_instance_id;
+        let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
+            mrd1,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let mrd1_addr =
+            dispatcher_listening(&mrd1_instance_id, &supervisor_instance_id, &supervisor_chnl)
+                .local_addr()
+                .unwrap();
+
+        // A LinkHello whose body doesn't decode is answered with a LinkRefused
+        let mut stream = TcpStream::connect(mrd1_addr).unwrap();
+        let hello = Box::new(LinkHello::new(
+            &AnId::nil(),
+            &supervisor_instance_id,
+            &[echo_requester_protocol().clone()],
+        ));
+        let buf = LinkHello::to_serde_json_buf(hello).unwrap();
+        let buf = String::from_utf8(buf)
+            .unwrap()
+            .replace("\"protocols\"", "\"garbage\"")
+            .into_bytes();
+        write_msg_buf_to_tcp_stream(&mut stream, &buf).unwrap();
+        let buf = read_msg_buf_from_tcp_stream(&mut stream).unwrap();
+        let msg_any = LinkRefused::from_serde_json_buf(&buf).unwrap();
+        let refused = LinkRefused::from_box_msg_any(&msg_any).unwrap();
+        println!("test_link_hello_undecodable: refused={refused:?}");
+        assert_eq!(refused.dst_id(), &AnId::nil());
+        assert_eq!(refused.reason, "unable to decode LinkHello");
+
+        // And the connection is closed
+        assert!(read_msg_buf_from_tcp_stream(&mut stream).is_err());
+        let snapshot = metrics_snapshot();
+        let link = snapshot
+            .links
+            .iter()
+            .find(|l| l.instance_id == mrd1_link_instance_id)
+            .unwrap();
+        println!("test_link_hello_undecodable: link={link:?}");
+        assert_eq!(link.refused, 1);
+
+        let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        ae_join_handle
+            .join()
+            .expect("Failed joining ae_join_handle");
+
+        println!("test_link_hello_undecodable:-");
+    }
+
+    #[test]
+    fn test_admin_reply_channel_dropped() {
+        println!("\ntest_admin_reply_channel_dropped:+");
+        let node = AdminNodeInfo {
+            name: "node1".to_string(),
+            con_mgr_instance_id: AnId::new(),
+            executors: vec![],
+        };
+        let hello = Box::new(AdminHello::new(&AnId::new(), &AnId::new()));
+        let msg_buf = AdminHello::to_serde_json_buf(hello).unwrap();
+
+        let reply = AdminReplyChannel::accept("mrd1", &AnId::new(), &node, &msg_buf).unwrap();
+        let reply_instance_id = reply.instance_id;
+        assert!(sender_map_get(&reply_instance_id).is_some());
+
+        // The reply channel is removed from the sender_map with the connection
+        drop(reply);
+        assert!(sender_map_get(&reply_instance_id).is_none());
+        println!("test_admin_reply_channel_dropped:-");
+    }
+
+    #[test]
+    fn test_bind_failed() {
+        println!("\ntest_bind_failed:+");
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            _con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        // The address is in use so the bind fails
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", &addr));
+        let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
+            mrd1,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let msg =
+            dispatcher_listening(&mrd1_instance_id, &supervisor_instance_id, &supervisor_chnl);
+        assert_eq!(msg.addr, addr);
+        assert!(matches!(
+            msg.status,
+            DispatcherListeningStatus::BindFailed(_)
+        ));
+
+        let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        ae_join_handle
+            .join()
+            .expect("Failed joining ae_join_handle");
+        println!("test_bind_failed:-");
+    }
+
+    #[test]
+    fn test_dispatcher_listening_notify() {
+        println!("\ntest_dispatcher_listening_notify:+");
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            _con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        let mut mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+        mrd1.notify_listening(&supervisor_instance_id);
+        let mrd1_instance_id = mrd1.instance_id;
+        let msg = Box::new(ReqAddActor::new(
+            &ae_instance_id,
+            &supervisor_instance_id,
+            mrd1,
+        ));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(RspAddActor::from_box_msg_any(&msg_any).is_some());
+
+        // The DispatcherListening is sent directly so it may arrive
+        // before or after the CmdReady the executor passes on
+        let mut listening = None;
+        for _ in 0..2 {
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            if let Some(msg) = DispatcherListening::from_box_msg_any(&msg_any) {
+                listening = Some(msg.clone());
+            } else {
+                assert!(CmdReady::from_box_msg_any(&msg_any).is_some());
+            }
+        }
+        let listening = listening.unwrap();
+        println!("test_dispatcher_listening_notify: {listening:?}");
+        assert_eq!(listening.src_id(), &mrd1_instance_id);
+        assert_eq!(listening.addr, "127.0.0.1:0");
+        let DispatcherListeningStatus::Listening(local_addr) = listening.status else {
+            panic!("not listening {:?}", listening.status);
+        };
+        assert_ne!(local_addr.port(), 0);
+
+        let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        ae_join_handle
+            .join()
+            .expect("Failed joining ae_join_handle");
+        println!("test_dispatcher_listening_notify:-");
+    }
 }
@@ -0,0 +1,361 @@
+//! The deserializer as tokio tasks, enabled by the `tokio` feature and
+//! used when MsgRouterDispatcher::set_runtime was called.
+//!
+//! An accept task spawns a task for each connection, so a dispatcher
+//! with many connections doesn't need a thread for each. Frames are
+//! handled like the threaded deserializer, the LinkHello and AdminHello
+//! are answered and everything else is routed by the FrameRouter.
+use std::{error::Error, net::TcpListener as StdTcpListener, sync::Arc, time::Duration};
+
+use admin_protocol::{admin_rsp_to_serde_json_buf, AdminNodeInfo, ADMIN_HELLO_ID_STR};
+use an_id::AnId;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use link_protocol::{LinkCompatibility, LINK_HELLO_ID_STR};
+use msg_header::get_msg_id_str_from_buf;
+use protocol::Protocol;
+use runtime_metrics::{link_metrics_unregister, LinkCounters};
+use tokio::{
+    net::{tcp::OwnedWriteHalf, TcpListener, TcpStream},
+    runtime::Handle,
+    sync::{Mutex, Notify},
+    task::{JoinHandle, JoinSet},
+};
+use utils::{read_msg_buf_from_async_stream, write_msg_buf_to_async_stream};
+
+use super::{link_hello_ack, AdminReplyChannel, FrameRouter, MsgRouterDispatcher, NO_LINK_HELLO};
+
+/// How long stop waits for the accept task to close its connections
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Stops the accept task of a deserializer
+#[derive(Debug)]
+pub(crate) struct TaskStop {
+    stop: Arc<Notify>,
+    stopped_rx: Receiver<()>,
+}
+
+impl TaskStop {
+    /// Stop the accept task and wait for it to close the listener and
+    /// the connections. This blocks, but only until the task finishes
+    /// or STOP_TIMEOUT, so it may be called from an actor on the runtime.
+    pub(crate) fn stop(self, name: &str) {
+        self.stop.notify_one();
+        if self.stopped_rx.recv_timeout(STOP_TIMEOUT).is_err() {
+            log::warn!("{name}::TaskStop::stop: accept task didn't stop in {STOP_TIMEOUT:?}");
+        }
+    }
+}
+
+// Aborts the task when dropped so an admin writer doesn't outlive its
+// connection, even when the connection task is aborted.
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl MsgRouterDispatcher {
+    /// Start the accept task for listener on runtime
+    pub(crate) fn tokio_deserializer(
+        &mut self,
+        runtime: &Handle,
+        listener: StdTcpListener,
+        router: FrameRouter,
+        link_protocols: Vec<Protocol>,
+        admin_node: Option<AdminNodeInfo>,
+    ) -> Result<(), Box<dyn Error>> {
+        listener.set_nonblocking(true)?;
+        let listener = {
+            // from_std registers the listener with the runtime's reactor
+            let _guard = runtime.enter();
+            TcpListener::from_std(listener)?
+        };
+
+        let stop = Arc::new(Notify::new());
+        let (stopped_tx, stopped_rx) = bounded::<()>(1);
+        runtime.spawn(accept(
+            self.name.clone(),
+            listener,
+            router,
+            link_protocols,
+            admin_node,
+            Arc::clone(&stop),
+            stopped_tx,
+        ));
+        self.deserializer_task = Some(TaskStop { stop, stopped_rx });
+        Ok(())
+    }
+}
+
+/// Accept connections until stop is notified, then close them
+async fn accept(
+    name: String,
+    listener: TcpListener,
+    router: FrameRouter,
+    link_protocols: Vec<Protocol>,
+    admin_node: Option<AdminNodeInfo>,
+    stop: Arc<Notify>,
+    stopped_tx: Sender<()>,
+) {
+    log::debug!("{name}::accept:+");
+    let mut connections = JoinSet::new();
+    let mut connection_id = 0u64;
+    loop {
+        tokio::select! {
+            _ = stop.notified() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    connections.spawn(connection(
+                        format!("{name}::connection:{connection_id}"),
+                        stream,
+                        router.clone(),
+                        link_protocols.clone(),
+                        admin_node.clone(),
+                    ));
+                    connection_id += 1;
+                }
+                Err(why) => log::warn!("{name}::accept: Error accepting connection: {why}"),
+            },
+            // Reap the connections that have closed
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+        }
+    }
+
+    drop(listener);
+    connections.shutdown().await;
+    link_metrics_unregister(&router.link_instance_id);
+    if let Some(Err(why)) = router.recorder.as_ref().map(|r| r.flush()) {
+        log::warn!("{name}::accept: error flushing recorder: {why}");
+    }
+    let _ = stopped_tx.send(());
+    log::debug!("{name}::accept:-");
+}
+
+/// Read the frames of a connection until it's closed
+async fn connection(
+    name: String,
+    stream: TcpStream,
+    router: FrameRouter,
+    link_protocols: Vec<Protocol>,
+    admin_node: Option<AdminNodeInfo>,
+) {
+    log::trace!("{name}:+");
+    let (mut reader, writer) = stream.into_split();
+    let writer = Arc::new(Mutex::new(writer));
+
+    // Everything is refused until the forwarder sends a LinkHello
+    let mut compatibility = LinkCompatibility::refuse_all(NO_LINK_HELLO);
+    let mut admin_writer: Option<AbortOnDrop> = None;
+    loop {
+        let msg_buf = match read_msg_buf_from_async_stream(&mut reader).await {
+            Ok(msg_buf) => msg_buf,
+            Err(why) => {
+                log::trace!("{name}: stream closed, stopping: {why}");
+                break;
+            }
+        };
+        router.received(msg_buf.len());
+
+        let id_str = get_msg_id_str_from_buf(&msg_buf);
+        if id_str == LINK_HELLO_ID_STR {
+            let (answer_buf, accepted) = link_hello_ack(
+                &name,
+                &router.link_instance_id,
+                &router.con_mgr_instance_id,
+                &link_protocols,
+                &msg_buf,
+            );
+            if let Some(answer_buf) = answer_buf {
+                let mut writer = writer.lock().await;
+                if let Err(why) = write_msg_buf_to_async_stream(&mut *writer, &answer_buf).await {
+                    log::warn!("{name}: {why}");
+                }
+            }
+            match accepted {
+                Some(accepted) => compatibility = accepted,
+                None => {
+                    // Refused, the connection is closed
+                    LinkCounters::add(&router.link_counters.refused, 1);
+                    break;
+                }
+            }
+            continue;
+        }
+        if id_str == ADMIN_HELLO_ID_STR {
+            if let (Some(node), None) = (&admin_node, &admin_writer) {
+                admin_writer =
+                    admin_reply_start(&name, &writer, &router.link_instance_id, node, &msg_buf)
+                        .await;
+                // Admin connections send requests without a LinkHello
+                if admin_writer.is_some() {
+                    compatibility = LinkCompatibility::default();
+                }
+            } else {
+                log::warn!("{name}: ignoring AdminHello");
+            }
+            continue;
+        }
+        router.route(&name, &compatibility, id_str, &msg_buf);
+    }
+    log::trace!("{name}:-");
+}
+
+/// Answer the AdminHello in msg_buf and start the task writing the
+/// responses to the connection
+async fn admin_reply_start(
+    name: &str,
+    writer: &Arc<Mutex<OwnedWriteHalf>>,
+    src_id: &AnId,
+    node: &AdminNodeInfo,
+    msg_buf: &[u8],
+) -> Option<AbortOnDrop> {
+    let reply = AdminReplyChannel::accept(name, src_id, node, msg_buf)?;
+    let notify = reply.chnl.tokio_notify()?;
+    if let Err(why) = write_msg_buf_to_async_stream(&mut *writer.lock().await, &reply.ack_buf).await
+    {
+        log::warn!("{name}::admin_reply_start: {why}");
+        return None;
+    }
+
+    let task = tokio::spawn(admin_writer(reply, Arc::clone(writer), notify));
+    Some(AbortOnDrop(task))
+}
+
+/// Write the admin responses sent to the reply channel until the
+/// connection fails or the task is aborted, either drops the reply channel
+async fn admin_writer(
+    reply: AdminReplyChannel,
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+    notify: Arc<Notify>,
+) {
+    let name = &reply.name;
+    log::debug!("{name}::admin_writer:+");
+    'writing: loop {
+        while let Ok(msg_any) = reply.chnl.receiver.rx.try_recv() {
+            let Some(buf) = admin_rsp_to_serde_json_buf(msg_any) else {
+                log::warn!("{name}::admin_writer: not an admin response, dropping");
+                continue;
+            };
+            let mut writer = writer.lock().await;
+            if let Err(why) = write_msg_buf_to_async_stream(&mut *writer, &buf).await {
+                log::warn!("{name}::admin_writer: {why}");
+                break 'writing;
+            }
+        }
+        notify.notified().await;
+    }
+    log::debug!("{name}::admin_writer:-");
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpStream as StdTcpStream;
+
+    use actor_executor::{
+        add_actor_to_actor_executor_blocking, initialize_supervisor_con_mgr_actor_executor_blocking,
+    };
+    use cmd_done_issuer_protocol::CmdDone;
+    use cmd_shutdown_issuer_protocol::{CmdShutdown, CmdShutdownAck};
+    use dispatcher_listening_protocol::{DispatcherListeningReq, DispatcherListeningRsp};
+    use echo_requestee_protocol::{echo_requestee_protocol, EchoReq, EchoRsp, ECHO_REQ_ID};
+    use echo_requester_protocol::echo_requester_protocol;
+    use link_protocol::{LinkHello, LinkHelloAck};
+    use sender_map_by_instance_id::sender_map_get;
+    use utils::{read_msg_buf_from_tcp_stream, write_msg_buf_to_tcp_stream};
+
+    use super::*;
+
+    #[test]
+    fn test_tokio_deserializer_echo() {
+        println!("\ntest_tokio_deserializer_echo:+");
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        let mut mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+        mrd1.set_runtime(runtime.handle());
+        mrd1.add_link_protocol(echo_requestee_protocol());
+        mrd1.add_msg_id_from_serde_json_buf(ECHO_REQ_ID, EchoReq::from_serde_json_buf);
+        let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
+            mrd1,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        let msg = Box::new(DispatcherListeningReq::new(
+            &mrd1_instance_id,
+            &supervisor_instance_id,
+        ));
+        sender_map_get(&mrd1_instance_id)
+            .unwrap()
+            .send(msg)
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let mrd1_addr = DispatcherListeningRsp::from_box_msg_any(&msg_any)
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        // Several connections, each a task, exchange a LinkHello and echo
+        let mut streams = Vec::new();
+        for _ in 0..4 {
+            let mut stream = StdTcpStream::connect(mrd1_addr).unwrap();
+            let hello = Box::new(LinkHello::new(
+                &AnId::nil(),
+                &supervisor_instance_id,
+                &[echo_requester_protocol().clone()],
+            ));
+            let buf = LinkHello::to_serde_json_buf(hello).unwrap();
+            write_msg_buf_to_tcp_stream(&mut stream, &buf).unwrap();
+            let buf = read_msg_buf_from_tcp_stream(&mut stream).unwrap();
+            let msg_any = LinkHelloAck::from_serde_json_buf(&buf).unwrap();
+            let ack = LinkHelloAck::from_box_msg_any(&msg_any).unwrap();
+            assert_eq!(ack.protocols, vec![echo_requestee_protocol().clone()]);
+            streams.push(stream);
+        }
+        for (counter, stream) in streams.iter_mut().enumerate() {
+            let echo_msg = Box::new(EchoReq::new(
+                &con_mgr_instance_id,
+                &supervisor_instance_id,
+                counter as u64,
+            ));
+            let buf = EchoReq::to_serde_json_buf(echo_msg).unwrap();
+            write_msg_buf_to_tcp_stream(stream, &buf).unwrap();
+            let msg_any = supervisor_chnl.receiver.recv().unwrap();
+            let msg = EchoRsp::from_box_msg_any(&msg_any).unwrap();
+            assert_eq!(msg.src_id(), &con_mgr_instance_id);
+            assert_eq!(msg.counter, counter as u64);
+        }
+
+        // Shutting the dispatcher down stops the tasks, closing the connections
+        let msg = Box::new(CmdShutdown::new(&mrd1_instance_id, &supervisor_instance_id));
+        sender_map_get(&mrd1_instance_id)
+            .unwrap()
+            .send(msg)
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(CmdShutdownAck::from_box_msg_any(&msg_any).is_some());
+        for stream in streams.iter_mut() {
+            assert!(read_msg_buf_from_tcp_stream(stream).is_err());
+        }
+
+        let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        ae_join_handle
+            .join()
+            .expect("Failed joining ae_join_handle");
+        println!("test_tokio_deserializer_echo:-");
+    }
+}
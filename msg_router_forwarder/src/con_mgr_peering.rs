@@ -0,0 +1,89 @@
+//! Peering the ConMgr of this process with the ConMgr across a link,
+//! enabled by MsgRouterForwarder::enable_federation.
+//!
+//! When the link comes up the forwarder learns the instance_id of the
+//! peers ConMgr from the LinkHelloAck and sends a ConMgrAddPeerReq to
+//! its ConMgr, the forwarder's sender being how the peer is reached.
+//! When the link is lost, or comes up to a different ConMgr, the peer is
+//! removed with a ConMgrRemovePeerReq.
+use std::{io, net::TcpStream};
+
+use actor_channel::ActorSender;
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use con_mgr_federation_protocol::{ConMgrAddPeerReq, ConMgrRemovePeerReq};
+use sender_map_by_instance_id::sender_map_get;
+
+pub(crate) struct ConMgrPeering {
+    pub name: String,
+    pub con_mgr_instance_id: AnId,
+    pub src_id: AnId, // Of the MsgRouterForwarder, the responses are sent to it
+    pub link_instance_id: AnId,
+    pub sender: ActorSender, // Of the forwarder
+    pub peer_con_mgr_instance_id: Option<AnId>,
+}
+
+impl ConMgrPeering {
+    /// The link is up and the ConMgr across it is peer_con_mgr_instance_id,
+    /// None if the dispatcher didn't say
+    pub fn linked(&mut self, peer_con_mgr_instance_id: Option<AnId>) {
+        if self.peer_con_mgr_instance_id == peer_con_mgr_instance_id {
+            return;
+        }
+        self.lost();
+        let Some(peer_con_mgr_instance_id) = peer_con_mgr_instance_id else {
+            log::warn!("{}::linked: the peers ConMgr is unknown", self.name);
+            return;
+        };
+        log::info!(
+            "{}::linked: peering with {peer_con_mgr_instance_id}",
+            self.name
+        );
+        self.send_con_mgr(Box::new(ConMgrAddPeerReq::new(
+            &self.con_mgr_instance_id,
+            &self.src_id,
+            &peer_con_mgr_instance_id,
+            &self.link_instance_id,
+            &self.sender,
+        )));
+        self.peer_con_mgr_instance_id = Some(peer_con_mgr_instance_id);
+    }
+
+    /// The link is down, stop peering
+    pub fn lost(&mut self) {
+        if let Some(peer_con_mgr_instance_id) = self.peer_con_mgr_instance_id.take() {
+            log::info!(
+                "{}::lost: removing peer {peer_con_mgr_instance_id}",
+                self.name
+            );
+            self.send_con_mgr(Box::new(ConMgrRemovePeerReq::new(
+                &self.con_mgr_instance_id,
+                &self.src_id,
+                &peer_con_mgr_instance_id,
+            )));
+        }
+    }
+
+    fn send_con_mgr(&self, msg: BoxMsgAny) {
+        let result = sender_map_get(&self.con_mgr_instance_id)
+            .ok_or_else(|| "no sender for the ConMgr".into())
+            .and_then(|sender| sender.send(msg));
+        if let Err(why) = result {
+            log::warn!("{}::send_con_mgr: {why}", self.name);
+        }
+    }
+}
+
+/// True if the dispatcher closed the connection, checked without blocking
+/// as the dispatcher doesn't write after the LinkHelloAck.
+pub(crate) fn link_closed(stream: &TcpStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return true;
+    }
+    let closed = match stream.peek(&mut [0u8; 1]) {
+        Ok(0) => true,
+        Ok(_) => false,
+        Err(why) => why.kind() != io::ErrorKind::WouldBlock,
+    };
+    closed || stream.set_nonblocking(false).is_err()
+}
@@ -1,44 +1,55 @@
-use actor::{Actor, ActorContext, ProcessMsgFn};
+use actor::{Actor, ActorContext};
 use actor_channel::ActorChannel;
+use actor_lifecycle::{add_lifecycle_handlers, ActorLifecycle, Lifecycle};
 use an_id::{anid, paste, AnId};
-use cmd_init_issuee_protocol::{cmd_init_issuee_protocol, CmdInit, CMD_INIT_ID};
-use con_mgr_register_actor_protocol::{
-    ConMgrRegisterActorReq, ConMgrRegisterActorRsp, ConMgrRegisterActorStatus,
-    CON_MGR_REGISTER_ACTOR_RSP_ID,
+use cmd_init_issuee_protocol::cmd_init_issuee_protocol;
+use cmd_shutdown_issuee_protocol::{cmd_shutdown_issuee_protocol, CmdShutdown, CMD_SHUTDOWN_ID};
+use con_mgr_federation_protocol::{
+    con_mgr_federation_issuee_protocol, con_mgr_federation_issuer_protocol,
+    con_mgr_peer_requester_protocol, ConMgrAddPeerRsp, ConMgrAddPeerStatus, ConMgrRemovePeerRsp,
+    CON_MGR_ADD_PEER_RSP_ID, CON_MGR_REMOVE_PEER_RSP_ID,
 };
-use crossbeam_channel::bounded;
-use echo_requestee_protocol::{echo_requestee_protocol, EchoReq, EchoRsp, ECHO_REQ_ID};
+use con_mgr_peering::{link_closed, ConMgrPeering};
+use con_mgr_register_actor_protocol::con_mgr_registee_actor_protocol;
+use crossbeam_channel::{bounded, RecvTimeoutError};
+use echo_requestee_protocol::echo_requestee_protocol;
+use id_inventory::register_id;
 use insert_key_msg_id_value_to_serde_json_buf_requestee_protocol::{
     insert_key_msg_id_value_to_serde_json_buf_requestee_protocol,
     InsertKeyMsgIdValueToSerdeJsonBufReq, InsertKeyMsgIdValueToSerdeJsonBufRsp,
-    InsertKeyMsgIdValueToSerdeJsonBufRspStatus,
+    InsertKeyMsgIdValueToSerdeJsonBufRspStatus, INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQ_ID,
 };
+use link_protocol::{con_mgr_instance_id_from_answer, LinkCompatibility, LinkHello};
 use msg_router_forwarder_actor_sender_requestee_protocol::{
-    MsgRouterForwarderActorSenderReq, MsgRouterForwarderActorSenderRsp,
+    msg_router_forwarder_actor_sender_requestee_protocol, MsgRouterForwarderActorSenderReq,
+    MsgRouterForwarderActorSenderRsp, MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQ_ID,
 };
+use msg_tap::tap_observe;
 use protocol::Protocol;
 use protocol_set::ProtocolSet;
+use runtime_metrics::{link_metrics_register, link_metrics_unregister, LinkCounters};
 use sender_map_by_instance_id::sender_map_insert;
+use state_machine::{
+    add_base_state, base_state, msg_handlers, state_machine, HasStateMachine, StateMachine,
+};
 use std::{
     collections::{hash_map::Entry, HashMap},
+    error::Error,
     fmt::{self, Debug},
     net::TcpStream,
     sync::{Arc, RwLock},
-    thread,
+    thread::{self, JoinHandle},
+    time::Duration,
 };
-use utils::write_msg_buf_to_tcp_stream;
+use tap_protocol::TapPoint;
+use utils::{read_msg_buf_from_tcp_stream, write_msg_buf_to_tcp_stream};
 
 use box_msg_any::BoxMsgAny;
-use msg_header::{MsgHeader, ToSerdeJsonBuf};
+use msg_header::{msg_codec_get, MsgHeader, ToSerdeJsonBuf};
 
-// State information
-#[derive(Debug)]
-pub struct StateInfo {
-    pub name: String,
-}
-
-// HashMap that maps address of a ProcessMsgFn to StateInfo
-type StateInfoMap<SM> = HashMap<*const ProcessMsgFn<SM>, StateInfo>;
+mod con_mgr_peering;
+#[cfg(feature = "tokio")]
+mod tokio_forwarder;
 
 // State machine for channel to network
 pub struct MsgRouterForwarder {
@@ -46,14 +57,22 @@ pub struct MsgRouterForwarder {
     pub actor_id: AnId,
     pub instance_id: AnId,
     pub protocol_set: ProtocolSet,
-    pub current_state: ProcessMsgFn<Self>,
-    pub state_info_hash: StateInfoMap<Self>,
+    pub state_machine: StateMachine<Self>,
+    pub lifecycle: Lifecycle,
     pub chnl: ActorChannel,
     pub forwarder_name: String,
     pub forwarder_instance_id: AnId,
     pub forwarder_chnl: ActorChannel,
     pub addr: String, // IP Address and port of a msg-router-dispatcher this connects to
     pub map_key_msg_id_value_to_serde_json_buf: Arc<RwLock<HashMap<AnId, ToSerdeJsonBuf>>>, // Map of MsgId to ToSerdeJsonBuf for each message
+    pub link_protocols: Vec<Protocol>, // Protocols offered in the LinkHello, if empty there is no handshake
+    federate: bool,                    // Peer our ConMgr with the one across the link
+    con_mgr_instance_id: AnId,         // Nil until ready
+    forwarder_thread: Option<JoinHandle<()>>,
+    #[cfg(feature = "tokio")]
+    runtime: Option<tokio::runtime::Handle>,
+    #[cfg(feature = "tokio")]
+    forwarder_task: Option<crossbeam_channel::Receiver<()>>, // Signalled when the task stops
 }
 
 // TODO: For Send implementors must guarantee maybe moved between threads. ??
@@ -80,49 +99,237 @@ impl Actor for MsgRouterForwarder {
     }
 
     fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
-        (self.current_state)(self, context, msg);
+        self.dispatch(context, msg);
     }
 
     fn done(&self) -> bool {
         false
     }
+
+    fn get_protocol_set(&self) -> Option<&ProtocolSet> {
+        Some(&self.protocol_set)
+    }
+}
+
+impl HasStateMachine for MsgRouterForwarder {
+    fn state_machine(&self) -> &StateMachine<Self> {
+        &self.state_machine
+    }
+
+    fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+        &mut self.state_machine
+    }
+}
+
+impl ActorLifecycle for MsgRouterForwarder {
+    fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+        &mut self.lifecycle
+    }
+
+    fn on_ready(&mut self, context: &dyn ActorContext) {
+        log::debug!("{}:Lifecycle: starting forwarder", self.name);
+        self.con_mgr_instance_id = *context.get_con_mgr_instance_id();
+        self.forwarder();
+    }
+
+    fn on_stopping(&mut self, _context: &dyn ActorContext) {
+        self.stop_forwarder();
+    }
 }
 
 impl Debug for MsgRouterForwarder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let fn_ptr = self.current_state as *const ProcessMsgFn<Self>;
-        let fn_ptr_string = format!("{fn_ptr:p}");
-        let state_name = if let Some(n) = self.state_info_hash.get(&fn_ptr) {
-            // State does have a name
-            n.name.as_str()
-        } else {
-            // State does NOT have a name, use address
-            fn_ptr_string.as_str()
-        };
-
         write!(
             f,
-            "{} {{ id: {} instance_id: {} state_info_hash: {:?}; current_state: {state_name}; protocol_set: {:?}}}",
-            self.name, self.actor_id, self.instance_id, self.state_info_hash, self.protocol_set
+            "{} {{ id: {} instance_id: {} state_machine: {:?}; protocol_set: {:?}}}",
+            self.name, self.actor_id, self.instance_id, self.state_machine, self.protocol_set
         )
     }
 }
 
+// How long to wait for the dispatcher to answer a LinkHello
+const LINK_HELLO_ACK_TIMEOUT: Duration = Duration::from_secs(2);
+
+// How often an idle federated link is checked, and reconnected if lost
+const LINK_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+// The backoff between connects while the dispatcher isn't reachable,
+// doubled after each failed connect
+const CONNECT_BACKOFF_MIN: Duration = Duration::from_millis(50);
+const CONNECT_BACKOFF_MAX: Duration = Duration::from_secs(2);
+
+// How long stop_forwarder waits for the forwarder task
+#[cfg(feature = "tokio")]
+const FORWARDER_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Send a LinkHello offering link_protocols and determine from the
+/// answer which messages the dispatcher can't accept, see
+/// LinkCompatibility::from_answer, and the ConMgr in its process.
+/// Nothing is refused if there are no link_protocols or the dispatcher
+/// didn't answer, though a dispatcher refuses the messages of a link
+/// without a LinkHello.
+fn link_hello(
+    name: &str,
+    stream: &mut TcpStream,
+    src_id: &AnId,
+    link_protocols: &[Protocol],
+) -> (LinkCompatibility, Option<AnId>) {
+    if link_protocols.is_empty() {
+        return (LinkCompatibility::default(), None);
+    }
+
+    match link_hello_exchange(stream, src_id, link_protocols) {
+        Ok(buf) => {
+            let compatibility = LinkCompatibility::from_answer(link_protocols, &buf);
+            if compatibility.is_refused() {
+                log::error!("{name}::link_hello: {compatibility:?}");
+            } else {
+                log::debug!("{name}::link_hello: {compatibility:?}");
+            }
+            (compatibility, con_mgr_instance_id_from_answer(&buf))
+        }
+        Err(why) => {
+            log::warn!("{name}::link_hello: no LinkHelloAck, {why}");
+            (LinkCompatibility::default(), None)
+        }
+    }
+}
+
+// Returns the dispatcher's answer to the LinkHello
+fn link_hello_exchange(
+    stream: &mut TcpStream,
+    src_id: &AnId,
+    link_protocols: &[Protocol],
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let hello = Box::new(LinkHello::new(&AnId::nil(), src_id, link_protocols));
+    let buf = LinkHello::to_serde_json_buf(hello).ok_or("unable to serialize LinkHello")?;
+    write_msg_buf_to_tcp_stream(stream, &buf)?;
+
+    stream.set_read_timeout(Some(LINK_HELLO_ACK_TIMEOUT))?;
+    let result = read_msg_buf_from_tcp_stream(stream);
+    stream.set_read_timeout(None)?;
+    result
+}
+
+// The connection to the dispatcher, stream is None until a connect succeeds
+struct Link {
+    name: String,
+    addr: String,
+    link_instance_id: AnId,
+    link_protocols: Vec<Protocol>,
+    link_counters: Arc<LinkCounters>,
+    stream: Option<TcpStream>,
+    compatibility: LinkCompatibility,
+    peering: Option<ConMgrPeering>,
+    backoff: Duration, // Until the next connect while there is no connection
+}
+
+impl Link {
+    fn connect(&mut self) -> Result<&mut TcpStream, Box<dyn Error>> {
+        let mut stream = match TcpStream::connect(&self.addr) {
+            Ok(stream) => stream,
+            Err(why) => {
+                self.backoff = (self.backoff * 2).min(CONNECT_BACKOFF_MAX);
+                if let Some(peering) = &mut self.peering {
+                    peering.lost();
+                }
+                return Err(why.into());
+            }
+        };
+        self.backoff = CONNECT_BACKOFF_MIN;
+        let peer_con_mgr_instance_id;
+        (self.compatibility, peer_con_mgr_instance_id) = link_hello(
+            &self.name,
+            &mut stream,
+            &self.link_instance_id,
+            &self.link_protocols,
+        );
+        if let Some(peering) = &mut self.peering {
+            peering.linked(peer_con_mgr_instance_id);
+        }
+        Ok(self.stream.insert(stream))
+    }
+
+    /// Write buf, connecting if there is no connection. If the write
+    /// fails reconnect once and retry.
+    fn write(&mut self, buf: &[u8]) -> Result<(), Box<dyn Error>> {
+        if let Some(stream) = &mut self.stream {
+            match write_msg_buf_to_tcp_stream(stream, buf) {
+                Ok(()) => return Ok(()),
+                Err(why) => {
+                    log::warn!("{}::write: {why}, reconnecting", self.name);
+                    LinkCounters::add(&self.link_counters.reconnects, 1);
+                    self.stream = None;
+                }
+            }
+        }
+        let stream = self.connect()?;
+        write_msg_buf_to_tcp_stream(stream, buf)
+    }
+
+    /// How long to wait for a message before calling idle
+    fn idle_timeout(&self) -> Duration {
+        if self.stream.is_some() {
+            LINK_CHECK_INTERVAL
+        } else {
+            self.backoff
+        }
+    }
+
+    /// Connect if there is no connection and reconnect a federated link
+    /// whose dispatcher closed the connection
+    fn idle(&mut self) {
+        if let Some(stream) = &self.stream {
+            if self.peering.is_none() || !link_closed(stream) {
+                return;
+            }
+            log::debug!("{}::idle: link closed, reconnecting", self.name);
+            LinkCounters::add(&self.link_counters.reconnects, 1);
+            self.stream = None;
+        }
+        if let Err(why) = self.connect() {
+            log::debug!(
+                "{}::idle: unable to connect to {}, {why}",
+                self.name,
+                self.addr
+            );
+        }
+    }
+}
+
 // From: https://www.uuidgenerator.net/version4
 const MSG_ROUTER_RECEIVER_ACTOR_ID: AnId = anid!("31408435-3d0a-400d-83fd-a649c1321f7b");
 const MSG_ROUTER_RECEIVER_PROTOCOL_SET_ID: AnId = anid!("cbfbb7cc-d5bd-41be-9a0b-30bafb518be2");
+register_id!(Actor, MSG_ROUTER_RECEIVER_ACTOR_ID, "msg_router_forwarder");
+register_id!(
+    ProtocolSet,
+    MSG_ROUTER_RECEIVER_PROTOCOL_SET_ID,
+    "msg_router_forwarder_ps"
+);
 
 impl MsgRouterForwarder {
     pub fn new(name: &str, addr: &str) -> Self {
         // Create the msg_router ProtocolSet, `ps`.
-        println!("MsgRouterforwarder::new({})", name);
+        log::debug!("MsgRouterforwarder::new({})", name);
         let mut pm = HashMap::<AnId, Protocol>::new();
         let ci_iep = cmd_init_issuee_protocol();
         pm.insert(ci_iep.id, ci_iep.clone());
+        let cs_iep = cmd_shutdown_issuee_protocol();
+        pm.insert(cs_iep.id, cs_iep.clone());
         let e_rep = echo_requestee_protocol();
         pm.insert(e_rep.id, e_rep.clone());
         let ik_rep = insert_key_msg_id_value_to_serde_json_buf_requestee_protocol();
         pm.insert(ik_rep.id, ik_rep.clone());
+        let mrfas_rep = msg_router_forwarder_actor_sender_requestee_protocol();
+        pm.insert(mrfas_rep.id, mrfas_rep.clone());
+        let cm_prp = con_mgr_peer_requester_protocol();
+        pm.insert(cm_prp.id, cm_prp.clone());
+        let cm_rap = con_mgr_registee_actor_protocol();
+        pm.insert(cm_rap.id, cm_rap.clone());
         let msg_router_forwarder_ps = ProtocolSet::new(
             "msg_router_forwarder_ps",
             MSG_ROUTER_RECEIVER_PROTOCOL_SET_ID,
@@ -135,13 +342,26 @@ impl MsgRouterForwarder {
         let forwarder_name = name.to_owned() + "_forwarder";
         let forwarder_chnl = ActorChannel::new(&forwarder_name, &forwarder_instance_id);
 
-        let mut this = Self {
+        let mut state_machine = state_machine!(Self, [state0]);
+        let base = add_base_state(&mut state_machine);
+        state_machine.set_parent(Self::state0, base);
+        add_lifecycle_handlers(&mut state_machine, base);
+        msg_handlers!(state_machine, Self, state0, [
+            INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQ_ID: InsertKeyMsgIdValueToSerdeJsonBufReq
+                => handle_insert_key_msg_id_value_to_serde_json_buf_req,
+            MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQ_ID: MsgRouterForwarderActorSenderReq
+                => handle_msg_router_forwarder_actor_sender_req,
+            CON_MGR_ADD_PEER_RSP_ID: ConMgrAddPeerRsp => handle_con_mgr_add_peer_rsp,
+            CON_MGR_REMOVE_PEER_RSP_ID: ConMgrRemovePeerRsp => handle_con_mgr_remove_peer_rsp,
+        ]);
+
+        let this = Self {
             name: name.to_owned(),
             actor_id: MSG_ROUTER_RECEIVER_ACTOR_ID,
             instance_id: msg_router_instance_id,
             protocol_set: msg_router_forwarder_ps,
-            current_state: Self::state0,
-            state_info_hash: StateInfoMap::<Self>::new(),
+            state_machine,
+            lifecycle: Lifecycle::new(true),
             chnl,
             forwarder_name,
             forwarder_instance_id,
@@ -151,28 +371,22 @@ impl MsgRouterForwarder {
                 AnId,
                 ToSerdeJsonBuf,
             >::new())),
+            link_protocols: Vec::new(),
+            federate: false,
+            con_mgr_instance_id: AnId::nil(),
+            forwarder_thread: None,
+            #[cfg(feature = "tokio")]
+            runtime: None,
+            #[cfg(feature = "tokio")]
+            forwarder_task: None,
         };
 
         // Add ourself to the sender_map
         sender_map_insert(&this.instance_id, &this.chnl.sender);
 
-        this.add_state(Self::state0, "state0");
         this
     }
 
-    pub fn add_state(&mut self, state: ProcessMsgFn<Self>, name: &str) {
-        let s = StateInfo {
-            name: name.to_owned(),
-        };
-        let k = state as *const ProcessMsgFn<Self>;
-        self.state_info_hash.insert(k, s);
-    }
-
-    #[allow(unused)]
-    fn transition(&mut self, dest: ProcessMsgFn<Self>) {
-        self.current_state = dest;
-    }
-
     pub fn add_msg_id_to_serde_json_buf(
         &mut self,
         msg_id: AnId,
@@ -185,7 +399,7 @@ impl MsgRouterForwarder {
             .unwrap(); // TODO: remove unwrap
 
         if let Entry::Vacant(e) = wlocked_hashmap.entry(msg_id) {
-            println!("add_msg_id_to_serde_json_buf: msg_id: {msg_id}");
+            log::trace!("add_msg_id_to_serde_json_buf: msg_id: {msg_id}");
             e.insert(to_serde_json_buf);
 
             true
@@ -194,134 +408,231 @@ impl MsgRouterForwarder {
         }
     }
 
+    /// Add a protocol whose messages are forwarded, must be added
+    /// before the forwarder is started
+    pub fn add_link_protocol(&mut self, protocol: &Protocol) {
+        self.link_protocols.push(protocol.clone());
+    }
+
+    /// Peer the ConMgr of this process with the ConMgr across the link
+    /// while the link is up, see con_mgr_federation_protocol. Must be
+    /// enabled before the forwarder is started.
+    pub fn enable_federation(&mut self) {
+        self.add_link_protocol(con_mgr_federation_issuer_protocol());
+        self.add_link_protocol(con_mgr_federation_issuee_protocol());
+        self.federate = true;
+    }
+
+    // The peering of the forwarder if federation is enabled
+    fn con_mgr_peering(&self) -> Option<ConMgrPeering> {
+        self.federate.then(|| ConMgrPeering {
+            name: self.forwarder_name.clone(),
+            con_mgr_instance_id: self.con_mgr_instance_id,
+            src_id: self.instance_id,
+            link_instance_id: self.forwarder_instance_id,
+            sender: self.forwarder_chnl.sender.clone(),
+            peer_con_mgr_instance_id: None,
+        })
+    }
+
+    /// Run the forwarder as a task on runtime instead of a thread, must
+    /// be set before the forwarder is started
+    #[cfg(feature = "tokio")]
+    pub fn set_runtime(&mut self, runtime: &tokio::runtime::Handle) {
+        self.runtime = Some(runtime.clone());
+    }
+
     /// Receive messages on a channel, serializes them and then writes them to TcpStream
-    pub fn forwarder(&self) {
-        println!("{}::forwarder:+", &self.name);
+    pub fn forwarder(&mut self) {
+        log::trace!("{}::forwarder:+", self.name);
+        #[cfg(feature = "tokio")]
+        if let Some(runtime) = self.runtime.clone() {
+            self.tokio_forwarder(&runtime);
+            return;
+        }
+
         let (status_tx, status_rx) = bounded(1);
-        let self_name = self.name.clone();
-        let self_addr = self.addr.clone();
+        let mut link = Link {
+            name: self.name.clone() + "::forwarder_thread",
+            addr: self.addr.clone(),
+            link_instance_id: self.forwarder_instance_id,
+            link_protocols: self.link_protocols.clone(),
+            link_counters: link_metrics_register(&self.forwarder_name, &self.forwarder_instance_id),
+            stream: None,
+            compatibility: LinkCompatibility::default(),
+            peering: self.con_mgr_peering(),
+            backoff: CONNECT_BACKOFF_MIN,
+        };
         let self_forwarder_chnl_receiver = self.forwarder_chnl.receiver.clone();
         let arc_clone_map_key_msg_id_value_to_serde_json_buf =
             Arc::clone(&self.map_key_msg_id_value_to_serde_json_buf);
-        thread::spawn(move || {
-            println!("{}::forwarder_thread:+", &self_name);
+        let forwarder_thread = thread::spawn(move || {
+            let self_name = link.name.clone();
+            log::debug!("{self_name}:+");
 
             // Indicate ready to receive messages
-            status_tx.send(()).unwrap_or_else(|_| {
-                panic!(
-                    "{}::forwarder_thread: erroring sending status ready",
-                    &self_name
-                )
-            });
-
-            // Ignore errors for the moment
-            let mut writer = TcpStream::connect(self_addr).unwrap();
-
-            println!("{}::forwarder_thread: Waiting  BoxMsgAny", &self_name);
-            while let Ok(msg) = self_forwarder_chnl_receiver.recv() {
-                println!("{}::forwarder_thread: Received msg", &self_name);
-
-                let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg);
-                if let Ok(map) = arc_clone_map_key_msg_id_value_to_serde_json_buf.read() {
-                    println!("{}: arc_clone_map_key_msg_id_value_to_serde_json_buf, GOT lock. map.len={}", &self_name, map.len());
-                    if let Some(fn_to_serde_json_buf) = map.get(msg_id) {
-                        let buf = (*fn_to_serde_json_buf)(msg).unwrap();
-                        println!("{}: serialized msg buf.len()={}", &self_name, buf.len());
-                        //println!("{}:                      buf={buf:x?}", &self_name);
-
-                        match write_msg_buf_to_tcp_stream(&mut writer, &buf) {
-                            Ok(_) => {
-                                println!("{}: successfully wrote msg to tcp_stream", &self_name)
-                            }
-                            Err(why) => panic!("{}::forwarder_thread: {why}", &self_name),
-                        }
-                    } else {
-                        println!("{}: map.get({msg_id}) NOT found", &self_name);
+            status_tx
+                .send(())
+                .unwrap_or_else(|_| panic!("{self_name}: erroring sending status ready"));
+
+            if let Err(why) = link.connect() {
+                log::warn!("{self_name}: unable to connect to {}, {why}", link.addr);
+            }
+
+            log::trace!("{self_name}: Waiting  BoxMsgAny");
+            loop {
+                let msg = match self_forwarder_chnl_receiver
+                    .rx
+                    .recv_timeout(link.idle_timeout())
+                {
+                    Ok(msg) => msg,
+                    Err(RecvTimeoutError::Timeout) => {
+                        link.idle();
+                        continue;
                     }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                log::trace!("{self_name}: Received msg");
+
+                let msg_id = *MsgHeader::get_msg_id_from_boxed_msg_any(&msg);
+                if msg_id == CMD_SHUTDOWN_ID {
+                    // Sent by stop_forwarder, the stream is dropped closing the connection
+                    log::debug!("{self_name}: stopping");
+                    break;
                 }
+                if let Some(why) = link.compatibility.refused(&msg_id) {
+                    log::error!("{self_name}: refusing msg_id={msg_id}, {why}");
+                    LinkCounters::add(&link.link_counters.refused, 1);
+                    continue;
+                }
+                tap_observe(&TapPoint::Link(link.link_instance_id), &msg);
+
+                // The lock isn't held across the write, messages that weren't
+                // added are serialized by their MsgCodec
+                let to_serde_json_buf =
+                    match arc_clone_map_key_msg_id_value_to_serde_json_buf.read() {
+                        Ok(map) => map.get(&msg_id).copied(),
+                        Err(_) => continue,
+                    }
+                    .or_else(|| msg_codec_get(&msg_id).map(|codec| codec.to_serde_json_buf));
+                let buf = to_serde_json_buf.map(|to_serde_json_buf| to_serde_json_buf(msg));
+                let Some(Some(buf)) = buf else {
+                    log::warn!("{self_name}: unable to serialize msg_id={msg_id}, dropping");
+                    continue;
+                };
+                log::trace!("{self_name}: serialized msg buf.len()={}", buf.len());
+                match link.write(&buf) {
+                    Ok(()) => {
+                        log::trace!("{self_name}: successfully wrote msg to tcp_stream");
+                        LinkCounters::add(&link.link_counters.frames_out, 1);
+                        LinkCounters::add(
+                            &link.link_counters.bytes_out,
+                            (buf.len() + 2) as u64, // Includes the length prefix
+                        );
+                    }
+                    Err(why) => log::error!("{self_name}: dropping msg_id={msg_id} {why}"),
+                }
+            }
+            if let Some(peering) = &mut link.peering {
+                peering.lost();
             }
-            println!("{}::forwarder_thread:-", &self_name);
+            link_metrics_unregister(&link.link_instance_id);
+            log::debug!("{self_name}:-");
         });
 
         // Wait for thread to be running
-        println!(
-            "{}::forwarder: Waiting for thread to be running",
-            &self.name
-        );
+        log::trace!("{}::forwarder: Waiting for thread to be running", self.name);
         status_rx
             .recv()
             .expect("{}::forwarder error, loop must have died");
-        println!("{}::forwarder:- thread running", &self.name);
+        self.forwarder_thread = Some(forwarder_thread);
+        log::debug!("{}::forwarder:- thread running", self.name);
     }
 
-    pub fn state0(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
-        if let Some(msg) = msg_any.downcast_ref::<InsertKeyMsgIdValueToSerdeJsonBufReq>() {
-            let msg_id = &msg.msg_id;
-            let to_serde_json_buf: fn(BoxMsgAny) -> Option<Vec<u8>> = msg.to_serde_json_buf;
-            let status = if self.add_msg_id_to_serde_json_buf(*msg_id, to_serde_json_buf) {
-                InsertKeyMsgIdValueToSerdeJsonBufRspStatus::Success
-            } else {
-                InsertKeyMsgIdValueToSerdeJsonBufRspStatus::AlreadyInserted
-            };
-            let rsp_msg = Box::new(InsertKeyMsgIdValueToSerdeJsonBufRsp::new(
-                context.get_dst_instance_id(),
-                &self.instance_id,
-                msg_id,
-                status,
-            ));
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<MsgRouterForwarderActorSenderReq>() {
-            let _instance_id = &msg.instance_id;
-            let rsp_msg = Box::new(MsgRouterForwarderActorSenderRsp::new(
-                context.get_dst_instance_id(),
+    /// Stop the forwarder thread or task, closing its TcpStream, and wait
+    /// for it to exit
+    pub fn stop_forwarder(&mut self) {
+        log::debug!("{}::stop_forwarder:+", self.name);
+        #[cfg(feature = "tokio")]
+        if let Some(stopped_rx) = self.forwarder_task.take() {
+            let msg = Box::new(CmdShutdown::new(
+                &self.forwarder_instance_id,
                 &self.instance_id,
-                &self.forwarder_chnl.sender.clone(),
             ));
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<EchoReq>() {
-            assert_eq!(msg.msg_id(), &ECHO_REQ_ID);
-            //println!("{}:State0: msg={msg:?}", self.name);
-            let rsp_msg = Box::new(EchoRsp::new(
-                context.get_dst_instance_id(),
-                &self.instance_id,
-                msg.req_timestamp_ns,
-                msg.counter,
-            ));
-            //println!("{}:State0: sending rsp_msg={rsp_msg:?}", self.name);
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<CmdInit>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CMD_INIT_ID);
-
-            // Register ourselves with ConMgr
-            let msg = Box::new(ConMgrRegisterActorReq::new(
-                msg.src_id(),
-                &self.instance_id,
-                &self.name,
-                &self.actor_id,
+            // The task may be blocked connecting, don't wait forever
+            if self.forwarder_chnl.sender.send(msg).is_ok()
+                && stopped_rx.recv_timeout(FORWARDER_STOP_TIMEOUT).is_err()
+            {
+                log::warn!("{}::stop_forwarder: task didn't stop", self.name);
+            }
+        }
+        if let Some(forwarder_thread) = self.forwarder_thread.take() {
+            let msg = Box::new(CmdShutdown::new(
+                &self.forwarder_instance_id,
                 &self.instance_id,
-                &self.protocol_set,
             ));
-            print!(
-                "{}:State0: sending ConMgrRegisterActorReq={msg:?}",
-                self.name
-            );
-            context.send_con_mgr(msg).unwrap();
-
-            println!("{}:State0: starting serializer", self.name);
-            self.forwarder();
-        } else if let Some(msg) = msg_any.downcast_ref::<ConMgrRegisterActorRsp>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CON_MGR_REGISTER_ACTOR_RSP_ID);
-            assert_eq!(msg.status, ConMgrRegisterActorStatus::Success);
+            if self.forwarder_chnl.sender.send(msg).is_ok() {
+                let _ = forwarder_thread.join();
+            }
+        }
+        log::debug!("{}::stop_forwarder:-", self.name);
+    }
+
+    pub fn state0(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
+        // Messages without a handler are unknown
+        base_state(self, context, msg_any);
+    }
+
+    fn handle_insert_key_msg_id_value_to_serde_json_buf_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &InsertKeyMsgIdValueToSerdeJsonBufReq,
+    ) {
+        let msg_id = &msg.msg_id;
+        let to_serde_json_buf: fn(BoxMsgAny) -> Option<Vec<u8>> = msg.to_serde_json_buf;
+        let status = if self.add_msg_id_to_serde_json_buf(*msg_id, to_serde_json_buf) {
+            InsertKeyMsgIdValueToSerdeJsonBufRspStatus::Success
         } else {
-            let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
-            println!(
-                "{}:State0: Unknown msg_any={msg_any:?} {msg_id:?}",
-                self.name
-            );
+            InsertKeyMsgIdValueToSerdeJsonBufRspStatus::AlreadyInserted
+        };
+        let rsp_msg = Box::new(InsertKeyMsgIdValueToSerdeJsonBufRsp::new(
+            context.get_dst_instance_id(),
+            &self.instance_id,
+            msg_id,
+            status,
+        ));
+        context.send_dst(rsp_msg).unwrap();
+    }
+
+    fn handle_msg_router_forwarder_actor_sender_req(
+        &mut self,
+        context: &dyn ActorContext,
+        msg: &MsgRouterForwarderActorSenderReq,
+    ) {
+        let _instance_id = &msg.instance_id;
+        let rsp_msg = Box::new(MsgRouterForwarderActorSenderRsp::new(
+            context.get_dst_instance_id(),
+            &self.instance_id,
+            &self.forwarder_chnl.sender.clone(),
+        ));
+        context.send_dst(rsp_msg).unwrap();
+    }
+
+    fn handle_con_mgr_add_peer_rsp(&mut self, _context: &dyn ActorContext, msg: &ConMgrAddPeerRsp) {
+        if msg.status == ConMgrAddPeerStatus::Success {
+            log::debug!("{}:State0: msg={msg:?}", self.name);
+        } else {
+            log::warn!("{}:State0: msg={msg:?}", self.name);
         }
     }
+
+    fn handle_con_mgr_remove_peer_rsp(
+        &mut self,
+        _context: &dyn ActorContext,
+        msg: &ConMgrRemovePeerRsp,
+    ) {
+        log::debug!("{}:State0: msg={msg:?}", self.name);
+    }
 }
 
 #[cfg(test)]
@@ -333,6 +644,7 @@ mod test {
         add_actor_to_actor_executor_blocking, initialize_supervisor_con_mgr_actor_executor_blocking,
     };
     use cmd_done_issuer_protocol::CmdDone;
+    use echo_requestee_protocol::{EchoReq, ECHO_REQ_ID};
     use sender_map_by_instance_id::sender_map_get;
     use utils::buf_u8_le_to_u16;
 
@@ -342,9 +654,9 @@ mod test {
     fn test_1() {
         println!("\ntest_1:+");
 
-        // Create a mock MsgRouterDispatcher
-        let mock_mrd_addr = "localhost:12345";
-        let mock_mrd_listener = TcpListener::bind(mock_mrd_addr).unwrap();
+        // Create a mock MsgRouterDispatcher, port 0 binds any free port
+        let mock_mrd_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mock_mrd_addr = &mock_mrd_listener.local_addr().unwrap().to_string();
 
         // Initialize Supervisor starting a single ActorExecutor and the connection manager
         let (
@@ -441,4 +753,58 @@ mod test {
 
         println!("test_1:-");
     }
+
+    #[test]
+    fn test_forwarder_connects_late() {
+        println!("\ntest_forwarder_connects_late:+");
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        // Nothing is listening yet so the first connects fail
+        let mock_mrd_addr = unused_local_addr();
+        let mrf1 = Box::new(MsgRouterForwarder::new("mrf1", &mock_mrd_addr));
+        let forwarder = mrf1.forwarder_chnl.sender.clone();
+        let (_mrf1_actor_id, _mrf1_instance_id) = add_actor_to_actor_executor_blocking(
+            mrf1,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+        thread::sleep(Duration::from_millis(200));
+
+        // The forwarder keeps retrying and connects once the dispatcher is up
+        let mock_mrd_listener = TcpListener::bind(&mock_mrd_addr).unwrap();
+        let (mut stream, _) = mock_mrd_listener.accept().unwrap();
+        let echo_msg = Box::new(EchoReq::new(
+            &con_mgr_instance_id,
+            &supervisor_instance_id,
+            1,
+        ));
+        forwarder.send(echo_msg).unwrap();
+        let msg_buf = read_msg_buf_from_tcp_stream(&mut stream).unwrap();
+        let msg = EchoReq::from_serde_json_buf(&msg_buf)
+            .unwrap()
+            .downcast::<EchoReq>()
+            .unwrap();
+        assert_eq!(msg.counter, 1);
+
+        let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        ae_join_handle
+            .join()
+            .expect("Failed joining ae_join_handle");
+        println!("test_forwarder_connects_late:-");
+    }
+
+    // A loopback address nothing is listening on, for tests which start
+    // listening after the forwarder has started connecting
+    pub(crate) fn unused_local_addr() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
 }
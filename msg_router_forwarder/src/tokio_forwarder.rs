@@ -0,0 +1,336 @@
+//! The forwarder as a tokio task, enabled by the `tokio` feature and
+//! used when MsgRouterForwarder::set_runtime was called.
+//!
+//! The task is woken when a message is sent to the forwarder_chnl and
+//! otherwise behaves like the forwarder thread. Unlike the thread, which
+//! retries a failed connect with a backoff, the connect is retried when
+//! the next message is forwarded or a federated link is next checked.
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, RwLock},
+};
+
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use cmd_shutdown_issuee_protocol::CMD_SHUTDOWN_ID;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use link_protocol::{con_mgr_instance_id_from_answer, LinkCompatibility, LinkHello};
+use msg_header::{msg_codec_get, MsgHeader, ToSerdeJsonBuf};
+use msg_tap::tap_observe;
+use protocol::Protocol;
+use runtime_metrics::{link_metrics_register, link_metrics_unregister, LinkCounters};
+use tap_protocol::TapPoint;
+use tokio::{net::TcpStream, runtime::Handle, sync::Notify, time::timeout};
+use utils::{read_msg_buf_from_async_stream, write_msg_buf_to_async_stream};
+
+use super::{ConMgrPeering, MsgRouterForwarder, LINK_CHECK_INTERVAL, LINK_HELLO_ACK_TIMEOUT};
+
+/// Like link_hello, send a LinkHello offering link_protocols and
+/// determine from the answer which messages can't be accepted and the
+/// ConMgr in the dispatcher's process
+async fn link_hello(
+    name: &str,
+    stream: &mut TcpStream,
+    src_id: &AnId,
+    link_protocols: &[Protocol],
+) -> (LinkCompatibility, Option<AnId>) {
+    if link_protocols.is_empty() {
+        return (LinkCompatibility::default(), None);
+    }
+
+    match link_hello_exchange(stream, src_id, link_protocols).await {
+        Ok(buf) => {
+            let compatibility = LinkCompatibility::from_answer(link_protocols, &buf);
+            if compatibility.is_refused() {
+                log::error!("{name}::link_hello: {compatibility:?}");
+            } else {
+                log::debug!("{name}::link_hello: {compatibility:?}");
+            }
+            (compatibility, con_mgr_instance_id_from_answer(&buf))
+        }
+        Err(why) => {
+            log::warn!("{name}::link_hello: no LinkHelloAck, {why}");
+            (LinkCompatibility::default(), None)
+        }
+    }
+}
+
+async fn link_hello_exchange(
+    stream: &mut TcpStream,
+    src_id: &AnId,
+    link_protocols: &[Protocol],
+) -> io::Result<Vec<u8>> {
+    let hello = Box::new(LinkHello::new(&AnId::nil(), src_id, link_protocols));
+    let buf = LinkHello::to_serde_json_buf(hello)
+        .ok_or_else(|| io::Error::other("unable to serialize LinkHello"))?;
+    write_msg_buf_to_async_stream(stream, &buf).await?;
+
+    timeout(
+        LINK_HELLO_ACK_TIMEOUT,
+        read_msg_buf_from_async_stream(stream),
+    )
+    .await
+    .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))?
+}
+
+// The connection to the dispatcher, stream is None until a connect succeeds
+struct Link {
+    name: String,
+    addr: String,
+    link_instance_id: AnId,
+    link_protocols: Vec<Protocol>,
+    link_counters: Arc<LinkCounters>,
+    stream: Option<TcpStream>,
+    compatibility: LinkCompatibility,
+    peering: Option<ConMgrPeering>,
+}
+
+impl Link {
+    async fn connect(&mut self) -> io::Result<&mut TcpStream> {
+        let mut stream = match TcpStream::connect(&self.addr).await {
+            Ok(stream) => stream,
+            Err(why) => {
+                if let Some(peering) = &mut self.peering {
+                    peering.lost();
+                }
+                return Err(why);
+            }
+        };
+        let peer_con_mgr_instance_id;
+        (self.compatibility, peer_con_mgr_instance_id) = link_hello(
+            &self.name,
+            &mut stream,
+            &self.link_instance_id,
+            &self.link_protocols,
+        )
+        .await;
+        if let Some(peering) = &mut self.peering {
+            peering.linked(peer_con_mgr_instance_id);
+        }
+        Ok(self.stream.insert(stream))
+    }
+
+    /// Reconnect a federated link whose dispatcher closed the connection
+    /// or that isn't connected
+    async fn check(&mut self) {
+        if self.peering.is_none() {
+            return;
+        }
+        if let Some(stream) = &self.stream {
+            match stream.try_read(&mut [0u8; 1]) {
+                Ok(0) => {}
+                Ok(_) => return,
+                Err(why) if why.kind() == io::ErrorKind::WouldBlock => return,
+                Err(_) => {}
+            }
+            LinkCounters::add(&self.link_counters.reconnects, 1);
+            self.stream = None;
+        }
+        log::debug!("{}::check: link closed, reconnecting", self.name);
+        let _ = self.connect().await;
+    }
+
+    /// Write buf, connecting if there is no connection. If the write
+    /// fails reconnect once and retry.
+    async fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        if let Some(stream) = &mut self.stream {
+            match write_msg_buf_to_async_stream(stream, buf).await {
+                Ok(()) => return Ok(()),
+                Err(why) => {
+                    log::warn!("{}::write: {why}, reconnecting", self.name);
+                    LinkCounters::add(&self.link_counters.reconnects, 1);
+                    self.stream = None;
+                }
+            }
+        }
+        let stream = self.connect().await?;
+        write_msg_buf_to_async_stream(stream, buf).await
+    }
+}
+
+impl MsgRouterForwarder {
+    /// Start the forwarder task on runtime
+    pub(crate) fn tokio_forwarder(&mut self, runtime: &Handle) {
+        let Some(notify) = self.forwarder_chnl.tokio_notify() else {
+            log::error!(
+                "{}::tokio_forwarder: forwarder_chnl already has a wakeup, not starting",
+                self.name
+            );
+            return;
+        };
+
+        let link = Link {
+            name: self.name.clone() + "::forwarder_task",
+            addr: self.addr.clone(),
+            link_instance_id: self.forwarder_instance_id,
+            link_protocols: self.link_protocols.clone(),
+            link_counters: link_metrics_register(&self.forwarder_name, &self.forwarder_instance_id),
+            stream: None,
+            compatibility: LinkCompatibility::default(),
+            peering: self.con_mgr_peering(),
+        };
+        let (stopped_tx, stopped_rx) = bounded::<()>(1);
+        runtime.spawn(forward(
+            link,
+            self.forwarder_chnl.receiver.rx.clone(),
+            notify,
+            Arc::clone(&self.map_key_msg_id_value_to_serde_json_buf),
+            stopped_tx,
+        ));
+        self.forwarder_task = Some(stopped_rx);
+        log::debug!("{}::tokio_forwarder: task running", self.name);
+    }
+}
+
+/// Forward the messages received on rx until CmdShutdown is received
+async fn forward(
+    mut link: Link,
+    rx: Receiver<BoxMsgAny>,
+    notify: Arc<Notify>,
+    map: Arc<RwLock<HashMap<AnId, ToSerdeJsonBuf>>>,
+    stopped_tx: Sender<()>,
+) {
+    let name = link.name.clone();
+    log::debug!("{name}:+");
+    if let Err(why) = link.connect().await {
+        log::warn!("{name}: unable to connect to {}, {why}", link.addr);
+    }
+
+    'forwarding: loop {
+        while let Ok(msg) = rx.try_recv() {
+            let msg_id = *MsgHeader::get_msg_id_from_boxed_msg_any(&msg);
+            if msg_id == CMD_SHUTDOWN_ID {
+                // Sent by stop_forwarder, the stream is dropped closing the connection
+                log::debug!("{name}: stopping");
+                break 'forwarding;
+            }
+            if let Some(why) = link.compatibility.refused(&msg_id) {
+                log::error!("{name}: refusing msg_id={msg_id}, {why}");
+                LinkCounters::add(&link.link_counters.refused, 1);
+                continue;
+            }
+            tap_observe(&TapPoint::Link(link.link_instance_id), &msg);
+
+            // The lock isn't held across the write, messages that weren't
+            // added are serialized by their MsgCodec
+            let to_serde_json_buf = match map.read() {
+                Ok(map) => map.get(&msg_id).copied(),
+                Err(_) => continue,
+            }
+            .or_else(|| msg_codec_get(&msg_id).map(|codec| codec.to_serde_json_buf));
+            let buf = to_serde_json_buf.map(|to_serde_json_buf| to_serde_json_buf(msg));
+            let Some(Some(buf)) = buf else {
+                log::warn!("{name}: unable to serialize msg_id={msg_id}, dropping");
+                continue;
+            };
+            match link.write(&buf).await {
+                Ok(()) => {
+                    LinkCounters::add(&link.link_counters.frames_out, 1);
+                    LinkCounters::add(
+                        &link.link_counters.bytes_out,
+                        (buf.len() + 2) as u64, // Includes the length prefix
+                    );
+                }
+                Err(why) => log::error!("{name}: dropping msg_id={msg_id} {why}"),
+            }
+        }
+        if timeout(LINK_CHECK_INTERVAL, notify.notified())
+            .await
+            .is_err()
+        {
+            link.check().await;
+        }
+    }
+
+    if let Some(peering) = &mut link.peering {
+        peering.lost();
+    }
+    link_metrics_unregister(&link.link_instance_id);
+    let _ = stopped_tx.send(());
+    log::debug!("{name}:-");
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener as StdTcpListener;
+
+    use actor_executor::{
+        add_actor_to_actor_executor_blocking, initialize_supervisor_con_mgr_actor_executor_blocking,
+    };
+    use cmd_done_issuer_protocol::CmdDone;
+    use cmd_shutdown_issuee_protocol::{CmdShutdown, CmdShutdownAck};
+    use echo_requestee_protocol::{EchoReq, ECHO_REQ_ID};
+    use sender_map_by_instance_id::sender_map_get;
+    use utils::read_msg_buf_from_tcp_stream;
+
+    use super::*;
+
+    #[test]
+    fn test_tokio_forwarder_connects_late() {
+        println!("\ntest_tokio_forwarder_connects_late:+");
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let (
+            supervisor_instance_id,
+            supervisor_chnl,
+            ae_join_handle,
+            ae_instance_id,
+            con_mgr_instance_id,
+        ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+        // Nothing is listening yet so the first connect fails
+        let mock_mrd_addr = crate::test::unused_local_addr();
+        let mut mrf1 = Box::new(MsgRouterForwarder::new("mrf1", &mock_mrd_addr));
+        mrf1.set_runtime(runtime.handle());
+        mrf1.add_msg_id_to_serde_json_buf(ECHO_REQ_ID, EchoReq::to_serde_json_buf);
+        let forwarder = mrf1.forwarder_chnl.sender.clone();
+        let (_mrf1_actor_id, mrf1_instance_id) = add_actor_to_actor_executor_blocking(
+            mrf1,
+            &ae_instance_id,
+            &supervisor_instance_id,
+            &supervisor_chnl.receiver,
+        );
+
+        // The forwarder connects when it has a message to forward
+        let mock_mrd_listener = StdTcpListener::bind(&mock_mrd_addr).unwrap();
+        for counter in 1..=2 {
+            let echo_msg = Box::new(EchoReq::new(
+                &con_mgr_instance_id,
+                &supervisor_instance_id,
+                counter,
+            ));
+            forwarder.send(echo_msg).unwrap();
+        }
+        let (mut stream, _) = mock_mrd_listener.accept().unwrap();
+        for counter in 1..=2 {
+            let msg_buf = read_msg_buf_from_tcp_stream(&mut stream).unwrap();
+            let msg = EchoReq::from_serde_json_buf(&msg_buf)
+                .unwrap()
+                .downcast::<EchoReq>()
+                .unwrap();
+            assert_eq!(msg.counter, counter);
+        }
+
+        // Shutting the forwarder down stops the task, closing the connection
+        let msg = Box::new(CmdShutdown::new(&mrf1_instance_id, &supervisor_instance_id));
+        sender_map_get(&mrf1_instance_id)
+            .unwrap()
+            .send(msg)
+            .unwrap();
+        let msg_any = supervisor_chnl.receiver.recv().unwrap();
+        assert!(CmdShutdownAck::from_box_msg_any(&msg_any).is_some());
+        assert!(read_msg_buf_from_tcp_stream(&mut stream).is_err());
+
+        let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+        sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+        ae_join_handle
+            .join()
+            .expect("Failed joining ae_join_handle");
+        println!("test_tokio_forwarder_connects_late:-");
+    }
+}
@@ -1,4 +1,9 @@
 //! `The msg_macro!` provides a simple way to create msgs.
+//!
+//! Superseded by `#[derive(Message)]` in msg_derive, which new messages
+//! should use.
+#[doc(hidden)]
+pub use id_inventory;
 pub use paste::paste;
 
 // TODO: Ugly, too much repeated code, I tired to add
@@ -15,6 +20,9 @@ macro_rules! msg_serde_macro {
 
             #[allow(unused)]
             pub const [ <$name:snake:upper _ID> ] : an_id::AnId = an_id::anid!($id_str);
+
+            $crate::id_inventory::register_id!(Message, [ <$name:snake:upper _ID> ], stringify!($name));
+            msg_header::register_msg_codec!([ <$name:snake:upper _ID> ], stringify!($name), $name);
         }
 
         #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -78,6 +86,12 @@ macro_rules! msg_serde_macro {
 
             pub fn to_serde_json_buf(
                 boxed_msg_any: box_msg_any::BoxMsgAny,
+            ) -> std::option::Option<Vec<u8>> {
+                Self::to_serde_json_buf_ref(&boxed_msg_any)
+            }
+
+            pub fn to_serde_json_buf_ref(
+                boxed_msg_any: &box_msg_any::BoxMsgAny,
             ) -> std::option::Option<Vec<u8>> {
                 if let Some(m) = boxed_msg_any.downcast_ref::<Self>() {
                     match serde_json::to_vec(m) {
@@ -101,6 +115,9 @@ macro_rules! msg_serde_macro {
 
             #[allow(unused)]
             pub const [ <$name:snake:upper _ID> ] : an_id::AnId = an_id::anid!($id_str);
+
+            $crate::id_inventory::register_id!(Message, [ <$name:snake:upper _ID> ], stringify!($name));
+            msg_header::register_msg_codec!([ <$name:snake:upper _ID> ], stringify!($name), $name);
         }
 
         #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
@@ -161,6 +178,12 @@ macro_rules! msg_serde_macro {
 
             pub fn to_serde_json_buf(
                 boxed_msg_any: box_msg_any::BoxMsgAny,
+            ) -> std::option::Option<Vec<u8>> {
+                Self::to_serde_json_buf_ref(&boxed_msg_any)
+            }
+
+            pub fn to_serde_json_buf_ref(
+                boxed_msg_any: &box_msg_any::BoxMsgAny,
             ) -> std::option::Option<Vec<u8>> {
                 if let Some(m) = boxed_msg_any.downcast_ref::<Self>() {
                     match serde_json::to_vec(m) {
@@ -219,6 +242,17 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_msg_codec() {
+        let codec = msg_header::msg_codec_get(&MSG_B_ID).unwrap();
+        assert_eq!(codec.name, "MsgB");
+        let msg_b: BoxMsgAny = Box::new(MsgB::new(&AnId::new(), &AnId::new(), 1, "hi"));
+        let buf = (codec.to_serde_json_buf_ref)(&msg_b).unwrap();
+        let msg_any = (codec.from_serde_json_buf)(&buf).unwrap();
+        assert_eq!(MsgB::from_box_msg_any(&msg_any).unwrap().a_string, "hi");
+        assert!(msg_header::msg_codecs().any(|codec| codec.msg_id == MSG_A_ID));
+    }
+
     #[test]
     fn test_with_fields() {
         let dst_id = AnId::new();
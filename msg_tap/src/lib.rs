@@ -0,0 +1,279 @@
+//! Taps mirror the messages passing through a process to observers.
+//!
+//! ActorExecutors call tap_observe for every message they dispatch and
+//! links call it for every message they forward or dispatch. Each tap
+//! whose TapFilter matches sends its observer a TapMsg summarizing the
+//! message and, if it's recording, appends the summary and the messages
+//! body to its file as a line of JSON. Type names and bodies come from the
+//! MsgCodec inventory so they're only available for serializable messages.
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, LineWriter, Write},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex, RwLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use msg_header::{msg_codec_get, MsgHeader};
+use once_cell::sync::Lazy;
+use sender_map_by_instance_id::sender_map_get;
+use serde::{Deserialize, Serialize};
+use tap_protocol::{TapFilter, TapMsg, TapPoint, TAP_MSG_ID};
+
+/// A tapped message as written to a taps record file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TapRecord {
+    pub tap_id: AnId,
+    pub point: TapPoint,
+    pub msg_header: MsgHeader,
+    pub type_name: String,
+    pub size: usize,
+    pub timestamp_ns: u128,              // Since UNIX_EPOCH
+    pub body: Option<serde_json::Value>, // None if the type isn't serializable
+}
+
+#[derive(Debug)]
+struct Tap {
+    tap_id: AnId,
+    observer_id: AnId,
+    filter: TapFilter,
+    sample_every: u64,
+    matched: AtomicU64,
+    record: Option<Mutex<LineWriter<File>>>, // Flushed as each record is written
+}
+
+// Number of attached taps so tap_observe is cheap when there are none
+static TAP_COUNT: AtomicUsize = AtomicUsize::new(0);
+static TAPS: Lazy<RwLock<Vec<Tap>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+fn now_ns() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+/// Attach a tap, observer_id is sent a TapMsg for every `sample_every`th
+/// message that matches filter. If record_path is Some the tapped
+/// messages are also appended to that file.
+///
+/// Returns the tap_id used to detach it
+pub fn tap_attach(
+    observer_id: &AnId,
+    filter: TapFilter,
+    sample_every: u64,
+    record_path: Option<&str>,
+) -> io::Result<AnId> {
+    let record = match record_path {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            Some(Mutex::new(LineWriter::new(file)))
+        }
+        None => None,
+    };
+    let tap = Tap {
+        tap_id: AnId::new(),
+        observer_id: *observer_id,
+        filter,
+        sample_every: sample_every.max(1),
+        matched: AtomicU64::new(0),
+        record,
+    };
+    let tap_id = tap.tap_id;
+    log::debug!("tap_attach: tap_id={tap_id} {filter:?} observer_id={observer_id}");
+
+    let mut wlocked_vec = TAPS.write().unwrap(); // TODO: remove unwrap
+    wlocked_vec.push(tap);
+    TAP_COUNT.store(wlocked_vec.len(), Ordering::Release);
+    Ok(tap_id)
+}
+
+/// Detach a tap flushing its record file
+///
+/// Returns Ok(false) if there is no tap with tap_id
+pub fn tap_detach(tap_id: &AnId) -> io::Result<bool> {
+    let mut wlocked_vec = TAPS
+        .write()
+        .map_err(|why| io::Error::other(why.to_string()))?;
+    let Some(idx) = wlocked_vec.iter().position(|tap| tap.tap_id == *tap_id) else {
+        return Ok(false);
+    };
+    let tap = wlocked_vec.remove(idx);
+    TAP_COUNT.store(wlocked_vec.len(), Ordering::Release);
+    drop(wlocked_vec);
+
+    log::debug!("tap_detach: tap_id={tap_id}");
+    if let Some(record) = tap.record {
+        record
+            .into_inner()
+            .map_err(|why| io::Error::other(why.to_string()))?
+            .flush()?;
+    }
+    Ok(true)
+}
+
+/// Mirror msg_any to the taps that match it at point
+pub fn tap_observe(point: &TapPoint, msg_any: &BoxMsgAny) {
+    if TAP_COUNT.load(Ordering::Acquire) == 0 {
+        return;
+    }
+
+    let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(msg_any);
+    if *msg_id == TAP_MSG_ID {
+        // Never tap what taps send, an observer tapping itself would loop forever
+        return;
+    }
+    let mut msg_header = MsgHeader::new(
+        *msg_id,
+        *MsgHeader::get_dst_id_from_boxed_msg_any(msg_any),
+        *MsgHeader::get_src_id_from_boxed_msg_any(msg_any),
+    );
+    msg_header.trace = MsgHeader::get_trace_from_boxed_msg_any(msg_any).copied();
+
+    let Ok(taps) = TAPS.read() else {
+        return;
+    };
+    for tap in taps
+        .iter()
+        .filter(|tap| tap.filter.matches(point, &msg_header))
+    {
+        let matched = tap.matched.fetch_add(1, Ordering::Relaxed);
+        if matched % tap.sample_every != 0 {
+            continue;
+        }
+
+        let codec = msg_codec_get(msg_id);
+        let type_name = codec.map(|c| c.name.to_owned()).unwrap_or_default();
+        let size = std::mem::size_of_val(&**msg_any);
+        let timestamp_ns = now_ns();
+
+        if let Some(record) = &tap.record {
+            let body = codec
+                .and_then(|c| (c.to_serde_json_buf_ref)(msg_any))
+                .and_then(|buf| serde_json::from_slice(&buf).ok());
+            let tap_record = TapRecord {
+                tap_id: tap.tap_id,
+                point: *point,
+                msg_header: msg_header.clone(),
+                type_name: type_name.clone(),
+                size,
+                timestamp_ns,
+                body,
+            };
+            if let Ok(mut writer) = record.lock() {
+                // Write each record as one line so LineWriter flushes it whole
+                let result = serde_json::to_vec(&tap_record)
+                    .map_err(io::Error::from)
+                    .and_then(|mut line| {
+                        line.push(b'\n');
+                        writer.write_all(&line)
+                    });
+                if let Err(why) = result {
+                    log::warn!("tap_observe: tap_id={} error recording: {why}", tap.tap_id);
+                }
+            }
+        }
+
+        let msg = Box::new(TapMsg {
+            header: MsgHeader::new(TAP_MSG_ID, tap.observer_id, tap.tap_id),
+            tap_id: tap.tap_id,
+            point: *point,
+            msg_header: msg_header.clone(),
+            type_name,
+            size,
+            timestamp_ns,
+        });
+        match sender_map_get(&tap.observer_id) {
+            Some(sndr) => {
+                if let Err(why) = sndr.send(msg) {
+                    log::warn!("tap_observe: tap_id={} error sending: {why}", tap.tap_id);
+                }
+            }
+            None => log::warn!(
+                "tap_observe: tap_id={} no sender for observer_id={}",
+                tap.tap_id,
+                tap.observer_id
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader};
+
+    use actor_channel::ActorChannel;
+    use echo_req::{EchoReq, ECHO_REQ_ID};
+    use sender_map_by_instance_id::sender_map_insert;
+
+    use super::*;
+
+    #[test]
+    fn test_tap() {
+        println!("\ntest_tap:+");
+        let observer_id = AnId::new();
+        let observer_chnl = ActorChannel::new("observer", &observer_id);
+        sender_map_insert(&observer_id, &observer_chnl.sender);
+
+        let link_id = AnId::new();
+        let point = TapPoint::Link(link_id);
+        let path = std::env::temp_dir().join(format!("test_tap-{}.jsonl", std::process::id()));
+        let tap_id = tap_attach(
+            &observer_id,
+            TapFilter::Instance(observer_id),
+            1,
+            path.to_str(),
+        )
+        .unwrap();
+        let link_tap_id = tap_attach(&observer_id, TapFilter::Link(link_id), 2, None).unwrap();
+
+        // Only every other message is tapped by link_tap_id
+        let dst_id = AnId::new();
+        for counter in 0..4 {
+            let msg: BoxMsgAny = Box::new(EchoReq::new(&dst_id, &observer_id, counter));
+            tap_observe(&point, &msg);
+        }
+        let tapped: Vec<BoxMsgAny> = observer_chnl.receiver.rx.try_iter().collect();
+        assert_eq!(tapped.len(), 2);
+        for msg_any in tapped.iter() {
+            let msg = msg_any.downcast_ref::<TapMsg>().unwrap();
+            println!("test_tap: {msg:?}");
+            assert_eq!(msg.tap_id, link_tap_id);
+            assert_eq!(msg.type_name, "EchoReq");
+            assert_eq!(msg.msg_header.msg_id, ECHO_REQ_ID);
+            assert_eq!(msg.size, std::mem::size_of::<EchoReq>());
+        }
+
+        // Instance filters match only at executors
+        let ae_point = TapPoint::Executor(AnId::new());
+        let msg: BoxMsgAny = Box::new(EchoReq::new(&dst_id, &observer_id, 10));
+        tap_observe(&ae_point, &msg);
+        let msg_any = observer_chnl.receiver.rx.try_recv().unwrap();
+        assert_eq!(msg_any.downcast_ref::<TapMsg>().unwrap().tap_id, tap_id);
+
+        // The record is flushed before the tap is detached
+        let file = File::open(&path).unwrap();
+        assert_eq!(BufReader::new(file).lines().count(), 1);
+
+        assert!(tap_detach(&tap_id).unwrap());
+        assert!(tap_detach(&link_tap_id).unwrap());
+        assert!(!tap_detach(&tap_id).unwrap());
+        tap_observe(&ae_point, &msg);
+        assert!(observer_chnl.receiver.rx.try_recv().is_err());
+
+        // The record has the body
+        let file = File::open(&path).unwrap();
+        let lines: Vec<String> = BufReader::new(file).lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        let tap_record: TapRecord = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(tap_record.tap_id, tap_id);
+        assert_eq!(tap_record.body.unwrap()["counter"], 10);
+        std::fs::remove_file(&path).unwrap();
+
+        println!("test_tap:-");
+    }
+}
@@ -0,0 +1,202 @@
+//! This module continues the TraceContext carried in a MsgHeader.
+//!
+//! While a traced message is being processed there is a current span,
+//! messages sent during that time continue the trace with the current
+//! span as their parent. When the span ends it is written to the span
+//! exporter, if there is one, as a line of JSON so message flows can be
+//! reconstructed offline even when they cross processes.
+use std::{
+    cell::Cell,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use msg_header::{MsgHeader, TraceContext};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// A span as written by the exporter
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SpanRecord {
+    pub trace_id: AnId,
+    pub span_id: AnId,
+    pub parent_span_id: AnId,
+    pub name: String,
+    pub actor: String,
+    pub instance_id: AnId,
+    pub msg_id: AnId,
+    pub pid: u32,
+    pub start_ns: u128, // Since UNIX_EPOCH
+    pub end_ns: u128,
+}
+
+thread_local! {
+    static CURRENT: Cell<Option<TraceContext>> = const { Cell::new(None) };
+}
+
+static SPAN_EXPORTER: Lazy<Mutex<Option<BufWriter<File>>>> = Lazy::new(|| Mutex::new(None));
+
+fn now_ns() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+/// Returns the TraceContext of the current span
+pub fn current_trace() -> Option<TraceContext> {
+    CURRENT.with(|c| c.get())
+}
+
+/// If there is a current span and msg_any isn't already traced it
+/// continues the trace with the current span as its parent.
+pub fn continue_trace(msg_any: &mut BoxMsgAny) {
+    if let Some(trace) = current_trace() {
+        if MsgHeader::get_trace_from_boxed_msg_any(msg_any).is_none() {
+            MsgHeader::set_trace_in_boxed_msg_any(msg_any, Some(trace));
+        }
+    }
+}
+
+/// A span that is current until it is dropped, then it is exported
+#[derive(Debug)]
+pub struct SpanGuard {
+    record: SpanRecord,
+    previous: Option<TraceContext>,
+}
+
+impl SpanGuard {
+    pub fn trace(&self) -> TraceContext {
+        TraceContext {
+            trace_id: self.record.trace_id,
+            span_id: self.record.span_id,
+        }
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|c| c.set(self.previous));
+        self.record.end_ns = now_ns();
+        export_span(&self.record);
+    }
+}
+
+/// Enter a child span of parent, None if parent is None as the
+/// message being processed isn't traced.
+pub fn enter_span(
+    parent: Option<&TraceContext>,
+    name: &str,
+    actor: &str,
+    instance_id: &AnId,
+    msg_id: &AnId,
+) -> Option<SpanGuard> {
+    let parent = parent?;
+    let record = SpanRecord {
+        trace_id: parent.trace_id,
+        span_id: AnId::new(),
+        parent_span_id: parent.span_id,
+        name: name.to_owned(),
+        actor: actor.to_owned(),
+        instance_id: *instance_id,
+        msg_id: *msg_id,
+        pid: std::process::id(),
+        start_ns: now_ns(),
+        end_ns: 0,
+    };
+    let current = TraceContext {
+        trace_id: record.trace_id,
+        span_id: record.span_id,
+    };
+    let previous = CURRENT.with(|c| c.replace(Some(current)));
+
+    Some(SpanGuard { record, previous })
+}
+
+/// Spans are appended to the file at path as JSON lines, replaces
+/// the previous exporter if there was one.
+pub fn span_exporter_init<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut exporter = SPAN_EXPORTER.lock().unwrap(); // TODO: remove unwrap
+    if let Some(mut previous) = exporter.replace(BufWriter::new(file)) {
+        previous.flush()?;
+    }
+    Ok(())
+}
+
+/// Flush and close the exporter, spans are no longer exported
+pub fn span_exporter_shutdown() -> std::io::Result<()> {
+    let mut exporter = SPAN_EXPORTER.lock().unwrap(); // TODO: remove unwrap
+    if let Some(mut writer) = exporter.take() {
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn export_span(record: &SpanRecord) {
+    let mut exporter = SPAN_EXPORTER.lock().unwrap(); // TODO: remove unwrap
+    if let Some(writer) = exporter.as_mut() {
+        let result = serde_json::to_writer(&mut *writer, record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"));
+        if let Err(why) = result {
+            log::warn!("export_span: error writing span: {why}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader};
+
+    use echo_req::EchoReq;
+
+    use super::*;
+
+    #[test]
+    fn test_continue_trace_and_export() {
+        let path = std::env::temp_dir().join(format!("msg_trace-{}.jsonl", AnId::new()));
+        span_exporter_init(&path).unwrap();
+
+        // No current span, messages aren't traced
+        let mut msg: BoxMsgAny = Box::new(EchoReq::new(&AnId::new(), &AnId::new(), 1));
+        continue_trace(&mut msg);
+        assert!(MsgHeader::get_trace_from_boxed_msg_any(&msg).is_none());
+        assert!(enter_span(None, "dispatch", "a1", &AnId::new(), &AnId::new()).is_none());
+
+        // Messages sent while processing a traced message continue its trace
+        let root = TraceContext::new_root();
+        let instance_id = AnId::new();
+        let msg_id = AnId::new();
+        let guard = enter_span(Some(&root), "dispatch", "a1", &instance_id, &msg_id).unwrap();
+        assert_eq!(current_trace(), Some(guard.trace()));
+        continue_trace(&mut msg);
+        let trace = *MsgHeader::get_trace_from_boxed_msg_any(&msg).unwrap();
+        assert_eq!(trace.trace_id, root.trace_id);
+        assert_ne!(trace.span_id, root.span_id);
+        drop(guard);
+        assert_eq!(current_trace(), None);
+        span_exporter_shutdown().unwrap();
+
+        let reader = BufReader::new(File::open(&path).unwrap());
+        let spans: Vec<SpanRecord> = reader
+            .lines()
+            .map(|l| serde_json::from_str(&l.unwrap()).unwrap())
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+        println!("test_continue_trace_and_export: spans={spans:?}");
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].trace_id, root.trace_id);
+        assert_eq!(spans[0].span_id, trace.span_id);
+        assert_eq!(spans[0].parent_span_id, root.span_id);
+        assert_eq!(spans[0].actor, "a1");
+        assert_eq!(spans[0].instance_id, instance_id);
+        assert_eq!(spans[0].msg_id, msg_id);
+        assert!(spans[0].end_ns >= spans[0].start_ns);
+    }
+}
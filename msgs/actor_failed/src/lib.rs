@@ -0,0 +1,34 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+
+// Sent by an ActorExecutor to the supervisor of an actor that panicked
+// while processing a message, before the SupervisorPolicy is applied.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "74690564-28aa-4ac0-867e-aa2d5ea7db86")]
+#[repr(C)]
+pub struct ActorFailed {
+    pub header: MsgHeader,
+    pub actor_id: AnId,
+    pub instance_id: AnId,
+    pub reason: String,
+}
+
+impl ActorFailed {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        actor_id: &AnId,
+        instance_id: &AnId,
+        reason: &str,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(ACTOR_FAILED_ID, *dst_id, *src_id),
+            actor_id: *actor_id,
+            instance_id: *instance_id,
+            reason: reason.to_owned(),
+        }
+    }
+}
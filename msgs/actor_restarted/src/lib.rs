@@ -0,0 +1,34 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+
+// Sent by an ActorExecutor to the supervisor of a failed actor that was
+// replaced by a new instance.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "319030d7-510c-41c3-8147-521624ae071d")]
+#[repr(C)]
+pub struct ActorRestarted {
+    pub header: MsgHeader,
+    pub actor_id: AnId,
+    pub old_instance_id: AnId,
+    pub new_instance_id: AnId,
+}
+
+impl ActorRestarted {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        actor_id: &AnId,
+        old_instance_id: &AnId,
+        new_instance_id: &AnId,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(ACTOR_RESTARTED_ID, *dst_id, *src_id),
+            actor_id: *actor_id,
+            old_instance_id: *old_instance_id,
+            new_instance_id: *new_instance_id,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+
+// Sent by an ActorExecutor to the supervisor of an actor that was
+// removed after a failure.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "7a93e2ea-f6f4-432e-b336-0e3c365c2d2f")]
+#[repr(C)]
+pub struct ActorStopped {
+    pub header: MsgHeader,
+    pub actor_id: AnId,
+    pub instance_id: AnId,
+}
+
+impl ActorStopped {
+    pub fn new(dst_id: &AnId, src_id: &AnId, actor_id: &AnId, instance_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(ACTOR_STOPPED_ID, *dst_id, *src_id),
+            actor_id: *actor_id,
+            instance_id: *instance_id,
+        }
+    }
+}
@@ -1,9 +1,15 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_serde_macro::{msg_serde_macro, paste};
+use serde::{Deserialize, Serialize};
 
 // From: https://www.uuidgenerator.net/version4
-msg_serde_macro!(CmdDone "92a8798e-e2c9-493e-b863-edae4d302f14");
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "92a8798e-e2c9-493e-b863-edae4d302f14", serde)]
+#[repr(C)]
+pub struct CmdDone {
+    pub header: MsgHeader,
+}
 
 impl CmdDone {
     pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
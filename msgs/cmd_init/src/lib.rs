@@ -1,9 +1,15 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_serde_macro::{msg_serde_macro, paste};
+use serde::{Deserialize, Serialize};
 
 // From: https://www.uuidgenerator.net/version4
-msg_serde_macro!(CmdInit "16119f69-17e9-4b3b-9b5c-eeac60af6056");
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "16119f69-17e9-4b3b-9b5c-eeac60af6056", serde)]
+#[repr(C)]
+pub struct CmdInit {
+    pub header: MsgHeader,
+}
 
 impl CmdInit {
     pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
@@ -0,0 +1,51 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum CmdReadyStatus {
+    Ready,
+    RegistrationFailed(String),
+}
+
+/// Sent by an actor to the issuer of its CmdInit once it's ready, or
+/// couldn't become ready, the ActorExecutor passes it on to the actors
+/// supervisor. The src_id is the instance_id of the actor.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "cef4dbe8-c5e2-4e6d-bd3a-f7dc89fb543f", serde)]
+#[repr(C)]
+pub struct CmdReady {
+    pub header: MsgHeader,
+    pub status: CmdReadyStatus,
+}
+
+impl CmdReady {
+    pub fn new(dst_id: &AnId, src_id: &AnId, status: CmdReadyStatus) -> Self {
+        Self {
+            header: MsgHeader::new(CMD_READY_ID, *dst_id, *src_id),
+            status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cmd_ready_new() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let msg = CmdReady::new(&dst_id, &src_id, CmdReadyStatus::Ready);
+        println!("test_cmd_ready_new msg={msg:?}");
+        assert_eq!(msg.msg_id(), &CMD_READY_ID);
+        assert_eq!(msg.dst_id(), &dst_id);
+        assert_eq!(msg.src_id(), &src_id);
+        assert_eq!(msg.status, CmdReadyStatus::Ready);
+        assert_eq!(msg.msg_id().to_string(), CMD_READY_ID_STR);
+    }
+}
@@ -0,0 +1,40 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+// Sent by an ActorExecutor to its actors when shutting down, the actor
+// releases its resources and responds with CmdShutdownAck.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "e615efe8-1107-4505-97e2-ca79907934e3", serde)]
+#[repr(C)]
+pub struct CmdShutdown {
+    pub header: MsgHeader,
+}
+
+impl CmdShutdown {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(CMD_SHUTDOWN_ID, *dst_id, *src_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cmd_shutdown_new() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let msg = CmdShutdown::new(&dst_id, &src_id);
+        println!("test_cmd_shutdown_new msg={msg:?}");
+        assert_eq!(msg.msg_id(), &CMD_SHUTDOWN_ID);
+        assert_eq!(msg.dst_id(), &dst_id);
+        assert_eq!(msg.src_id(), &src_id);
+        assert_eq!(msg.msg_id().to_string(), CMD_SHUTDOWN_ID_STR);
+    }
+}
@@ -0,0 +1,37 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "7eff4917-1a6e-4ff0-b707-34fb2e81b709", serde)]
+#[repr(C)]
+pub struct CmdShutdownAck {
+    pub header: MsgHeader,
+}
+
+impl CmdShutdownAck {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(CMD_SHUTDOWN_ACK_ID, *dst_id, *src_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cmd_shutdown_ack_new() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let msg = CmdShutdownAck::new(&dst_id, &src_id);
+        println!("test_cmd_shutdown_ack_new msg={msg:?}");
+        assert_eq!(msg.msg_id(), &CMD_SHUTDOWN_ACK_ID);
+        assert_eq!(msg.dst_id(), &dst_id);
+        assert_eq!(msg.src_id(), &src_id);
+        assert_eq!(msg.msg_id().to_string(), CMD_SHUTDOWN_ACK_ID_STR);
+    }
+}
@@ -1,9 +1,15 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_serde_macro::{msg_serde_macro, paste};
+use serde::{Deserialize, Serialize};
 
 // https://www.uuidgenerator.net/version4
-msg_serde_macro!(EchoComplete "d8c84131-901c-4900-b506-e4bac6665a58");
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "d8c84131-901c-4900-b506-e4bac6665a58", serde)]
+#[repr(C)]
+pub struct EchoComplete {
+    pub header: MsgHeader,
+}
 
 impl EchoComplete {
     pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
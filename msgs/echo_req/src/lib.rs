@@ -1,13 +1,18 @@
 use an_id::AnId;
 use chrono::Utc;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_serde_macro::{msg_serde_macro, paste};
+use serde::{Deserialize, Serialize};
 
 // From: https://www.uuidgenerator.net/version4
-msg_serde_macro!(EchoReq "ada0f9a9-b556-46ba-b3d5-d19c87ec216c" {
-    req_timestamp_ns: i64,
-    counter: u64
-});
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "ada0f9a9-b556-46ba-b3d5-d19c87ec216c", serde)]
+#[repr(C)]
+pub struct EchoReq {
+    pub header: MsgHeader,
+    pub req_timestamp_ns: i64,
+    pub counter: u64,
+}
 
 impl EchoReq {
     pub fn new(dst_id: &AnId, src_id: &AnId, counter: u64) -> Self {
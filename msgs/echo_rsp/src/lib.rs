@@ -1,14 +1,19 @@
 use an_id::AnId;
 use chrono::Utc;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_serde_macro::{msg_serde_macro, paste};
+use serde::{Deserialize, Serialize};
 
 // From: https://www.uuidgenerator.net/version4
-msg_serde_macro!(EchoRsp "8206e26f-a69d-4875-8a85-0cfb636ca7c2" {
-    req_timestamp_ns: i64,
-    counter: u64,
-    rsp_timestamp_ns: i64
-});
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "8206e26f-a69d-4875-8a85-0cfb636ca7c2", serde)]
+#[repr(C)]
+pub struct EchoRsp {
+    pub header: MsgHeader,
+    pub req_timestamp_ns: i64,
+    pub counter: u64,
+    pub rsp_timestamp_ns: i64,
+}
 
 impl EchoRsp {
     pub fn new(dst_id: &AnId, src_id: &AnId, req_timestamp: i64, counter: u64) -> Self {
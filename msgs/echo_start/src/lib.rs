@@ -1,12 +1,17 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro, paste};
+use serde::{Deserialize, Serialize};
 
 // https://www.uuidgenerator.net/version4
-msg_local_macro!(EchoStart "f13f7f26-40eb-4c94-a408-c455677f6730" {
-    partner_instance_id: AnId,
-    ping_count: u64
-});
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "f13f7f26-40eb-4c94-a408-c455677f6730", serde)]
+#[repr(C)]
+pub struct EchoStart {
+    pub header: MsgHeader,
+    pub partner_instance_id: AnId,
+    pub ping_count: u64,
+}
 
 impl EchoStart {
     pub fn new(dst_id: &AnId, src_id: &AnId, partner_instance_id: &AnId, ping_count: u64) -> Self {
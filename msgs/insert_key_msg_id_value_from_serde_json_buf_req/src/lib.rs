@@ -1,12 +1,16 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::{FromSerdeJsonBuf, MsgHeader};
-use msg_local_macro::{msg_local_macro, paste};
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(InsertKeyMsgIdValueFromSerdeJsonBufReq "fddef416-6314-4540-abd7-d8f6352fbb87" {
-    msg_id: AnId,
-    from_serde_json_buf: FromSerdeJsonBuf
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "fddef416-6314-4540-abd7-d8f6352fbb87")]
+#[repr(C)]
+pub struct InsertKeyMsgIdValueFromSerdeJsonBufReq {
+    pub header: MsgHeader,
+    pub msg_id: AnId,
+    pub from_serde_json_buf: FromSerdeJsonBuf,
+}
 
 impl InsertKeyMsgIdValueFromSerdeJsonBufReq {
     pub fn new(
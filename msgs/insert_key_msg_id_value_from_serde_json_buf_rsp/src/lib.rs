@@ -1,6 +1,6 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro, paste};
 use serde::{Deserialize, Serialize};
 
 #[repr(C)]
@@ -11,10 +11,14 @@ pub enum InsertKeyMsgIdValueFromSerdeJsonBufRspStatus {
 }
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(InsertKeyMsgIdValueFromSerdeJsonBufRsp "6b0076ec-d404-43fd-a974-96320a6a093c" {
-    msg_id: AnId,
-    status: InsertKeyMsgIdValueFromSerdeJsonBufRspStatus
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "6b0076ec-d404-43fd-a974-96320a6a093c")]
+#[repr(C)]
+pub struct InsertKeyMsgIdValueFromSerdeJsonBufRsp {
+    pub header: MsgHeader,
+    pub msg_id: AnId,
+    pub status: InsertKeyMsgIdValueFromSerdeJsonBufRspStatus,
+}
 
 impl InsertKeyMsgIdValueFromSerdeJsonBufRsp {
     pub fn new(
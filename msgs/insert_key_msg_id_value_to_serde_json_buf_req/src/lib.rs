@@ -1,12 +1,16 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::{MsgHeader, ToSerdeJsonBuf};
-use msg_local_macro::{msg_local_macro, paste};
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(InsertKeyMsgIdValueToSerdeJsonBufReq "3610bfb2-9636-4129-bc9f-67ff0c88c3c8" {
-    msg_id: AnId,
-    to_serde_json_buf: ToSerdeJsonBuf
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "3610bfb2-9636-4129-bc9f-67ff0c88c3c8")]
+#[repr(C)]
+pub struct InsertKeyMsgIdValueToSerdeJsonBufReq {
+    pub header: MsgHeader,
+    pub msg_id: AnId,
+    pub to_serde_json_buf: ToSerdeJsonBuf,
+}
 
 impl InsertKeyMsgIdValueToSerdeJsonBufReq {
     pub fn new(
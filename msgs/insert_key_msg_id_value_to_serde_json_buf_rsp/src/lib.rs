@@ -1,6 +1,6 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro, paste};
 use serde::{Deserialize, Serialize};
 
 #[repr(C)]
@@ -11,10 +11,14 @@ pub enum InsertKeyMsgIdValueToSerdeJsonBufRspStatus {
 }
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(InsertKeyMsgIdValueToSerdeJsonBufRsp "cf59aa4f-ff67-49ec-a48f-55bada1c4667" {
-    msg_id: AnId,
-    status: InsertKeyMsgIdValueToSerdeJsonBufRspStatus
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "cf59aa4f-ff67-49ec-a48f-55bada1c4667")]
+#[repr(C)]
+pub struct InsertKeyMsgIdValueToSerdeJsonBufRsp {
+    pub header: MsgHeader,
+    pub msg_id: AnId,
+    pub status: InsertKeyMsgIdValueToSerdeJsonBufRspStatus,
+}
 
 impl InsertKeyMsgIdValueToSerdeJsonBufRsp {
     pub fn new(
@@ -0,0 +1,49 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use protocol::Protocol;
+use serde::{Deserialize, Serialize};
+
+// Sent by a forwarder after connecting to a dispatcher, answered with a
+// LinkHelloAck or LinkRefused.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "45249d46-3ff5-4078-aa58-8dd94595ba5a", serde)]
+#[repr(C)]
+pub struct LinkHello {
+    pub header: MsgHeader,
+    pub protocols: Vec<Protocol>, // Protocols the sender forwards over the link
+}
+
+impl LinkHello {
+    pub fn new(dst_id: &AnId, src_id: &AnId, protocols: &[Protocol]) -> Self {
+        Self {
+            header: MsgHeader::new(LINK_HELLO_ID, *dst_id, *src_id),
+            protocols: protocols.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use protocol::{ProtocolMsg, ProtocolRole, Version};
+
+    #[test]
+    fn test_link_hello_serde() {
+        let protocol = Protocol::new(
+            "test_link_hello_serde",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Requestee,
+            vec![ProtocolMsg::receive(LINK_HELLO_ID)],
+        );
+        let hello = Box::new(LinkHello::new(&AnId::nil(), &AnId::new(), &[protocol]));
+        let buf = LinkHello::to_serde_json_buf(hello.clone()).unwrap();
+        let msg_any = LinkHello::from_serde_json_buf(&buf).unwrap();
+        let msg = LinkHello::from_box_msg_any(&msg_any).unwrap();
+        assert_eq!(msg.header, hello.header);
+        assert_eq!(msg.protocols, hello.protocols);
+    }
+}
@@ -0,0 +1,31 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use protocol::Protocol;
+use serde::{Deserialize, Serialize};
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "b0139227-575f-4421-a2b5-8baffa582343", serde)]
+#[repr(C)]
+pub struct LinkHelloAck {
+    pub header: MsgHeader,
+    pub protocols: Vec<Protocol>, // Protocols the sender dispatches from the link
+    #[serde(default = "AnId::nil")]
+    pub con_mgr_instance_id: AnId, // ConMgr of the senders process, nil if unknown
+}
+
+impl LinkHelloAck {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        protocols: &[Protocol],
+        con_mgr_instance_id: &AnId,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(LINK_HELLO_ACK_ID, *dst_id, *src_id),
+            protocols: protocols.to_vec(),
+            con_mgr_instance_id: *con_mgr_instance_id,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+// Sent by a dispatcher that can't accept a link before it closes the
+// connection.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "9c41e7d2-58a3-4b6f-a0d9-3e72c1f86b54", serde)]
+#[repr(C)]
+pub struct LinkRefused {
+    pub header: MsgHeader,
+    pub reason: String,
+}
+
+impl LinkRefused {
+    pub fn new(dst_id: &AnId, src_id: &AnId, reason: &str) -> Self {
+        Self {
+            header: MsgHeader::new(LINK_REFUSED_ID, *dst_id, *src_id),
+            reason: reason.to_owned(),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "5a0e3b5c-7f52-4d8e-9c6a-2b1f4e8d7a31", serde)]
+#[repr(C)]
+pub struct MetricsReq {
+    pub header: MsgHeader,
+}
+
+impl MetricsReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(METRICS_REQ_ID, *dst_id, *src_id),
+        }
+    }
+}
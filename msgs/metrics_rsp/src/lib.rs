@@ -0,0 +1,40 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use runtime_metrics::MetricsSnapshot;
+use serde::{Deserialize, Serialize};
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "c3d9a8e4-1b6f-4a27-8e05-f6b2d7c94e18", serde)]
+#[repr(C)]
+pub struct MetricsRsp {
+    pub header: MsgHeader,
+    pub snapshot: MetricsSnapshot,
+}
+
+impl MetricsRsp {
+    pub fn new(dst_id: &AnId, src_id: &AnId, snapshot: MetricsSnapshot) -> Self {
+        Self {
+            header: MsgHeader::new(METRICS_RSP_ID, *dst_id, *src_id),
+            snapshot,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_metrics_rsp_serde() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let msg = MetricsRsp::new(&dst_id, &src_id, MetricsSnapshot::default());
+        let buf = serde_json::to_vec(&msg).unwrap();
+        let msg_any = MetricsRsp::from_serde_json_buf(&buf).unwrap();
+        let msg2 = MetricsRsp::from_box_msg_any(&msg_any).unwrap();
+        assert_eq!(msg2.header, msg.header);
+        assert!(msg2.snapshot.executors.is_empty());
+    }
+}
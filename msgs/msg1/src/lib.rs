@@ -1,25 +1,18 @@
-use an_id::{anid, paste, AnId};
-use box_msg_any::BoxMsgAny;
-use msg_header::{get_msg_id_str_from_buf, MsgHeader};
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
 use serde::{Deserialize, Serialize};
 
-// From: https://www.uuidgenerator.net/version4
-pub const MSG1_ID_STR: &str = "a88ba7e7-0930-4df6-bb24-240338bf8eb5";
-pub const MSG1_ID: AnId = anid!("a88ba7e7-0930-4df6-bb24-240338bf8eb5");
-pub const MSG1_NAME: &str = "Msg1";
-
 // Message 1
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "a88ba7e7-0930-4df6-bb24-240338bf8eb5", serde)]
 #[repr(C)]
 pub struct Msg1 {
     pub header: MsgHeader,
     pub v: u64,
 }
 
-// Allow `clippy::uninlined_format_args`  because in msg_macro
-// we need to use stringify!($name) which can't be used in a
-// format string. Also this is caught by `cargo +nightly clippy`.
-#[allow(clippy::uninlined_format_args)]
 impl Msg1 {
     pub fn new(dst_id: &AnId, src_id: &AnId, v: u64) -> Self {
         Self {
@@ -27,73 +20,12 @@ impl Msg1 {
             v,
         }
     }
-
-    pub fn msg_id(&self) -> &AnId {
-        &self.header.msg_id
-    }
-
-    pub fn dst_id(&self) -> &AnId {
-        &self.header.dst_id
-    }
-
-    pub fn src_id(&self) -> &AnId {
-        &self.header.src_id
-    }
-
-    pub fn from_box_msg_any(msg: &BoxMsgAny) -> Option<&Msg1> {
-        if let Some(msg1) = msg.downcast_ref::<Self>() {
-            Some(msg1)
-        } else {
-            None
-        }
-    }
-
-    pub fn from_serde_json_buf(buf: &[u8]) -> std::option::Option<BoxMsgAny> {
-        let id = get_msg_id_str_from_buf(buf);
-        //if id == &*MSG1_ID_STRING {
-        if id == MSG1_ID_STR {
-            if let Ok(s) = std::str::from_utf8(buf) {
-                match serde_json::from_str::<Self>(s) {
-                    Ok(msg) => Some(Box::new(msg)),
-                    Err(why) => {
-                        log::error!("{}::from_serde_json_str: {why}", MSG1_NAME);
-                        None
-                    }
-                }
-            } else {
-                log::error!(
-                    "{}::from_serde_json_buf: buf parameter was NOT UTF8",
-                    MSG1_NAME
-                );
-                None
-            }
-        } else {
-            log::error!(
-                "{MSG1_NAME} id: {}, does not match buffer id: {id}",
-                MSG1_ID_STR
-            );
-            None
-        }
-    }
-
-    pub fn to_serde_json_buf(boxed_msg_any: BoxMsgAny) -> std::option::Option<Vec<u8>> {
-        if let Some(m) = boxed_msg_any.downcast_ref::<Self>() {
-            match serde_json::to_vec(m) {
-                Ok(v) => Some(v),
-                Err(why) => {
-                    log::error!("{}.to_serde_json_buf: Error {}", MSG1_NAME, why);
-                    None
-                }
-            }
-        } else {
-            None
-        }
-    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use box_msg_any::BoxMsgAny;
     use msg_header::{get_msg_id_str_from_buf, FromSerdeJsonBuf, ToSerdeJsonBuf};
     use std::{
         any::{Any, TypeId},
@@ -1,9 +1,15 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_serde_macro::{msg_serde_macro, paste};
+use serde::{Deserialize, Serialize};
 
 // From: https://www.uuidgenerator.net/version4
-msg_serde_macro!(Msg2 "4029b3c4-f380-488a-8560-8320cc8fb76e");
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Message)]
+#[msg(id = "4029b3c4-f380-488a-8560-8320cc8fb76e", serde)]
+#[repr(C)]
+pub struct Msg2 {
+    pub header: MsgHeader,
+}
 
 impl Msg2 {
     pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
@@ -1,11 +1,15 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro, paste};
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(MsgRouterForwarderActorSenderReq "a3c8423e-f6be-4005-911e-8d4e6e21d442" {
-    instance_id: AnId // Ignored for now
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "a3c8423e-f6be-4005-911e-8d4e6e21d442")]
+#[repr(C)]
+pub struct MsgRouterForwarderActorSenderReq {
+    pub header: MsgHeader,
+    pub instance_id: AnId, // Ignored for now
+}
 
 impl MsgRouterForwarderActorSenderReq {
     pub fn new(dst_id: &AnId, src_id: &AnId, instance_id: &AnId) -> Self {
@@ -1,12 +1,16 @@
 use actor::Actor;
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro_not_cloneable, paste};
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro_not_cloneable!(ReqAddActor "8cc2afb6-c71f-43ae-a278-affcce76ffdd" {
-    actor: Box<dyn Actor>
-});
+#[derive(Debug, Message)]
+#[msg(id = "8cc2afb6-c71f-43ae-a278-affcce76ffdd")]
+#[repr(C)]
+pub struct ReqAddActor {
+    pub header: MsgHeader,
+    pub actor: Box<dyn Actor>,
+}
 
 impl ReqAddActor {
     pub fn new(dst_id: &AnId, src_id: &AnId, actor: Box<dyn Actor>) -> Self {
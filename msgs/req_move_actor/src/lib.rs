@@ -0,0 +1,59 @@
+use actor::Actor;
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+
+// Moving an actor is a two step operation. The requester sends a ReqMoveActor
+// with `actor: None` to the ActorExecutor currently running the actor. That
+// ActorExecutor removes the actor and forwards a ReqMoveActor with
+// `actor: Some(..)` to the destination ActorExecutor which adds it and
+// responds to `requester_instance_id` with a RspMoveActor. The actors
+// factory `params` are forwarded so it can be restarted by the destination.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Message)]
+#[msg(id = "df6f9b61-a92a-40f8-919a-e0a6069417ff")]
+#[repr(C)]
+pub struct ReqMoveActor {
+    pub header: MsgHeader,
+    pub actor_instance_id: AnId,
+    pub dst_ae_instance_id: AnId,
+    pub requester_instance_id: AnId,
+    pub params: String,
+    pub actor: Option<Box<dyn Actor>>,
+}
+
+impl ReqMoveActor {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        actor_instance_id: &AnId,
+        dst_ae_instance_id: &AnId,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(REQ_MOVE_ACTOR_ID, *dst_id, *src_id),
+            actor_instance_id: *actor_instance_id,
+            dst_ae_instance_id: *dst_ae_instance_id,
+            requester_instance_id: *src_id,
+            params: String::new(),
+            actor: None,
+        }
+    }
+
+    pub fn new_with_actor(
+        dst_id: &AnId,
+        src_id: &AnId,
+        requester_instance_id: &AnId,
+        params: &str,
+        actor: Box<dyn Actor>,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(REQ_MOVE_ACTOR_ID, *dst_id, *src_id),
+            actor_instance_id: *actor.get_instance_id(),
+            dst_ae_instance_id: *dst_id,
+            requester_instance_id: *requester_instance_id,
+            params: params.to_owned(),
+            actor: Some(actor),
+        }
+    }
+}
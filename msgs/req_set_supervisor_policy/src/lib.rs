@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+
+/// Which actors are affected when an actor fails
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SupervisorStrategy {
+    /// Only the failed actor
+    OneForOne,
+
+    /// The failed actor and all of its siblings, i.e. the actors in
+    /// the same ActorExecutor with the same supervisor
+    OneForAll,
+}
+
+/// What is done to the affected actors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SupervisorDirective {
+    /// Replace with a new instance created by the factory registered
+    /// for the actors actor_id, actors without a factory are stopped
+    Restart,
+
+    /// Remove the actors
+    Stop,
+
+    /// Stop all of the supervisor's actors in the ActorExecutor, whatever
+    /// the strategy, leaving the supervisor to handle the failure
+    Escalate,
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisorPolicy {
+    pub strategy: SupervisorStrategy,
+    pub directive: SupervisorDirective,
+
+    /// Maximum number of restarts of an actor `within` the window,
+    /// when exceeded the actor is stopped
+    pub max_restarts: usize,
+    pub within: Duration,
+}
+
+impl Default for SupervisorPolicy {
+    fn default() -> Self {
+        Self {
+            strategy: SupervisorStrategy::OneForOne,
+            directive: SupervisorDirective::Stop,
+            max_restarts: 3,
+            within: Duration::from_secs(5),
+        }
+    }
+}
+
+impl SupervisorPolicy {
+    pub fn new(
+        strategy: SupervisorStrategy,
+        directive: SupervisorDirective,
+        max_restarts: usize,
+        within: Duration,
+    ) -> Self {
+        Self {
+            strategy,
+            directive,
+            max_restarts,
+            within,
+        }
+    }
+}
+
+// Change the SupervisorPolicy an ActorExecutor applies to the actors of
+// the sender, answered with RspSetSupervisorPolicy.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "e8628588-c435-4129-8402-0dbb354e396f")]
+#[repr(C)]
+pub struct ReqSetSupervisorPolicy {
+    pub header: MsgHeader,
+    pub policy: SupervisorPolicy,
+}
+
+impl ReqSetSupervisorPolicy {
+    pub fn new(dst_id: &AnId, src_id: &AnId, policy: SupervisorPolicy) -> Self {
+        Self {
+            header: MsgHeader::new(REQ_SET_SUPERVISOR_POLICY_ID, *dst_id, *src_id),
+            policy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_supervisor_policy_default() {
+        let policy = SupervisorPolicy::default();
+        assert_eq!(policy.strategy, SupervisorStrategy::OneForOne);
+        assert_eq!(policy.directive, SupervisorDirective::Stop);
+    }
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+
+// Request an ActorExecutor shutdown, each actor is sent a CmdShutdown
+// and has until `timeout` expires to respond with CmdShutdownAck. When
+// all actors have acknowledged or the timeout expires the ActorExecutor
+// responds with RspShutdown and exits.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "18f72f21-3880-4e74-84c0-3b548cbcb67d")]
+#[repr(C)]
+pub struct ReqShutdown {
+    pub header: MsgHeader,
+    pub timeout: Duration,
+}
+
+impl ReqShutdown {
+    pub fn new(dst_id: &AnId, src_id: &AnId, timeout: Duration) -> Self {
+        Self {
+            header: MsgHeader::new(REQ_SHUTDOWN_ID, *dst_id, *src_id),
+            timeout,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+// Request an ActorExecutor construct an actor using the factory
+// registered for `actor_id` and `params`, which are serialized init
+// parameters, an empty string means use the defaults.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "0909d1e5-4f18-469d-abb8-7ff7761c3197", serde)]
+#[repr(C)]
+pub struct ReqSpawnActor {
+    pub header: MsgHeader,
+    pub actor_id: AnId,
+    pub name: String,
+    pub params: String,
+}
+
+impl ReqSpawnActor {
+    pub fn new(dst_id: &AnId, src_id: &AnId, actor_id: &AnId, name: &str, params: &str) -> Self {
+        Self {
+            header: MsgHeader::new(REQ_SPAWN_ACTOR_ID, *dst_id, *src_id),
+            actor_id: *actor_id,
+            name: name.to_owned(),
+            params: params.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_req_spawn_actor_to_from_serde_json_buf() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let actor_id = AnId::new();
+        let msg = Box::new(ReqSpawnActor::new(
+            &dst_id,
+            &src_id,
+            &actor_id,
+            "server1",
+            r#"{"count":1}"#,
+        ));
+        let buf = ReqSpawnActor::to_serde_json_buf(msg).unwrap();
+        let msg_any = ReqSpawnActor::from_serde_json_buf(&buf).unwrap();
+        let msg = ReqSpawnActor::from_box_msg_any(&msg_any).unwrap();
+        println!("test_req_spawn_actor_to_from_serde_json_buf msg={msg:?}");
+        assert_eq!(msg.msg_id(), &REQ_SPAWN_ACTOR_ID);
+        assert_eq!(msg.dst_id(), &dst_id);
+        assert_eq!(msg.src_id(), &src_id);
+        assert_eq!(msg.actor_id, actor_id);
+        assert_eq!(msg.name, "server1");
+        assert_eq!(msg.params, r#"{"count":1}"#);
+    }
+}
@@ -1,19 +1,37 @@
 use an_id::AnId;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro, paste};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RspAddActorStatus {
+    Success,
+    ShuttingDown, // The actor was dropped, the ActorExecutor is shutting down
+}
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(RspAddActor "17a6ee73-6a91-42e2-908f-b1887e95d87a" {
-    actor_id: AnId,
-    actor_instance_id: AnId
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "17a6ee73-6a91-42e2-908f-b1887e95d87a")]
+#[repr(C)]
+pub struct RspAddActor {
+    pub header: MsgHeader,
+    pub actor_id: AnId,
+    pub actor_instance_id: AnId,
+    pub status: RspAddActorStatus,
+}
 
 impl RspAddActor {
-    pub fn new(dst_id: &AnId, src_id: &AnId, actor_id: &AnId, actor_instance_id: &AnId) -> Self {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        actor_id: &AnId,
+        actor_instance_id: &AnId,
+        status: RspAddActorStatus,
+    ) -> Self {
         Self {
             header: MsgHeader::new(RSP_ADD_ACTOR_ID, *dst_id, *src_id),
             actor_id: *actor_id,
             actor_instance_id: *actor_instance_id,
+            status,
         }
     }
 }
@@ -0,0 +1,40 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum RspMoveActorStatus {
+    Success,
+    ActorNotFound,
+    DstActorExecutorNotFound,
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "323645c2-a7c9-4b9d-803c-421dda1bb0ae")]
+#[repr(C)]
+pub struct RspMoveActor {
+    pub header: MsgHeader,
+    pub actor_instance_id: AnId,
+    pub ae_instance_id: AnId,
+    pub status: RspMoveActorStatus,
+}
+
+impl RspMoveActor {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        actor_instance_id: &AnId,
+        ae_instance_id: &AnId,
+        status: RspMoveActorStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(RSP_MOVE_ACTOR_ID, *dst_id, *src_id),
+            actor_instance_id: *actor_instance_id,
+            ae_instance_id: *ae_instance_id,
+            status,
+        }
+    }
+}
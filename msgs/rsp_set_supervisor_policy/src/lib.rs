@@ -0,0 +1,19 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "4858dc70-4871-41a3-be33-90ee0c8ef3f3")]
+#[repr(C)]
+pub struct RspSetSupervisorPolicy {
+    pub header: MsgHeader,
+}
+
+impl RspSetSupervisorPolicy {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(RSP_SET_SUPERVISOR_POLICY_ID, *dst_id, *src_id),
+        }
+    }
+}
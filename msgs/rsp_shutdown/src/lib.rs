@@ -0,0 +1,30 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ShutdownSummary {
+    pub acked: Vec<AnId>,     // instance_ids of actors that sent CmdShutdownAck
+    pub timed_out: Vec<AnId>, // instance_ids of actors that didn't
+    pub unregistered: usize,  // Number of actors unregistered from ConMgr
+    pub dropped_msgs: usize, // Messages left after an actor was shutdown, posted to the dead letters
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "9b24b067-54e4-4914-a7e9-90e4e13f67b2")]
+#[repr(C)]
+pub struct RspShutdown {
+    pub header: MsgHeader,
+    pub summary: ShutdownSummary,
+}
+
+impl RspShutdown {
+    pub fn new(dst_id: &AnId, src_id: &AnId, summary: ShutdownSummary) -> Self {
+        Self {
+            header: MsgHeader::new(RSP_SHUTDOWN_ID, *dst_id, *src_id),
+            summary,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum RspSpawnActorStatus {
+    Success,
+    FactoryNotFound,
+    FactoryFailed(String),
+    ShuttingDown, // The ActorExecutor is shutting down
+}
+
+// On failure actor_instance_id is nil.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "05286e3c-efb9-4b2c-a8a2-91b16264ce10", serde)]
+#[repr(C)]
+pub struct RspSpawnActor {
+    pub header: MsgHeader,
+    pub actor_id: AnId,
+    pub actor_instance_id: AnId,
+    pub status: RspSpawnActorStatus,
+}
+
+impl RspSpawnActor {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        actor_id: &AnId,
+        actor_instance_id: &AnId,
+        status: RspSpawnActorStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(RSP_SPAWN_ACTOR_ID, *dst_id, *src_id),
+            actor_id: *actor_id,
+            actor_instance_id: *actor_instance_id,
+            status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rsp_spawn_actor_to_from_serde_json_buf() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let actor_id = AnId::new();
+        let msg = Box::new(RspSpawnActor::new(
+            &dst_id,
+            &src_id,
+            &actor_id,
+            &AnId::nil(),
+            RspSpawnActorStatus::FactoryFailed("bad params".to_owned()),
+        ));
+        let buf = RspSpawnActor::to_serde_json_buf(msg).unwrap();
+        let msg_any = RspSpawnActor::from_serde_json_buf(&buf).unwrap();
+        let msg = RspSpawnActor::from_box_msg_any(&msg_any).unwrap();
+        println!("test_rsp_spawn_actor_to_from_serde_json_buf msg={msg:?}");
+        assert_eq!(msg.msg_id(), &RSP_SPAWN_ACTOR_ID);
+        assert_eq!(msg.actor_id, actor_id);
+        assert_eq!(msg.actor_instance_id, AnId::nil());
+        assert_eq!(
+            msg.status,
+            RspSpawnActorStatus::FactoryFailed("bad params".to_owned())
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "4e9a7c2b-6d1f-48a3-9b5e-c7f20d8a1e65", serde)]
+#[repr(C)]
+pub struct TapDetachReq {
+    pub header: MsgHeader,
+    pub tap_id: AnId,
+}
+
+impl TapDetachReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId, tap_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(TAP_DETACH_REQ_ID, *dst_id, *src_id),
+            tap_id: *tap_id,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TapDetachRspStatus {
+    Success,
+    TapNotFound,
+    RecordError(String),
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "d2a61e8f-5b3c-4790-8e1d-3f6c9b4a2d07", serde)]
+#[repr(C)]
+pub struct TapDetachRsp {
+    pub header: MsgHeader,
+    pub tap_id: AnId,
+    pub status: TapDetachRspStatus,
+}
+
+impl TapDetachRsp {
+    pub fn new(dst_id: &AnId, src_id: &AnId, tap_id: &AnId, status: TapDetachRspStatus) -> Self {
+        Self {
+            header: MsgHeader::new(TAP_DETACH_RSP_ID, *dst_id, *src_id),
+            tap_id: *tap_id,
+            status,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+/// Where a message was tapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TapPoint {
+    /// Dispatched by the ActorExecutor with this instance_id
+    Executor(AnId),
+
+    /// Forwarded or dispatched by the link with this instance_id
+    Link(AnId),
+}
+
+// A summary of a tapped message, sent to the observer of tap_id.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "71c8e3d5-a4f2-4b69-8d07-e5b1c2f94a38", serde)]
+#[repr(C)]
+pub struct TapMsg {
+    pub header: MsgHeader,
+    pub tap_id: AnId,
+    pub point: TapPoint,
+    pub msg_header: MsgHeader, // Header of the tapped message
+    pub type_name: String,     // Empty if the message type isn't serializable
+    pub size: usize,
+    pub timestamp_ns: u128, // Since UNIX_EPOCH
+}
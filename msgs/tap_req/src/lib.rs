@@ -0,0 +1,82 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+use tap_msg::TapPoint;
+
+/// Which messages are tapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TapFilter {
+    /// All messages dispatched by the ActorExecutor with this instance_id
+    Executor(AnId),
+
+    /// All messages passing through the link with this instance_id
+    Link(AnId),
+
+    /// Messages dispatched by any ActorExecutor to or from this instance_id
+    Instance(AnId),
+}
+
+impl TapFilter {
+    pub fn matches(&self, point: &TapPoint, header: &MsgHeader) -> bool {
+        match (self, point) {
+            (TapFilter::Executor(id), TapPoint::Executor(point_id)) => id == point_id,
+            (TapFilter::Link(id), TapPoint::Link(point_id)) => id == point_id,
+            (TapFilter::Instance(id), TapPoint::Executor(_)) => {
+                header.dst_id == *id || header.src_id == *id
+            }
+            _ => false,
+        }
+    }
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "0b6d2f4e-93a1-4c7e-b5d8-6e2f1a9c3b47", serde)]
+#[repr(C)]
+pub struct TapReq {
+    pub header: MsgHeader,
+    pub filter: TapFilter,
+    pub sample_every: u64, // 1 taps every message, n taps every nth message
+    pub record_path: Option<String>, // If Some, summaries and bodies are also appended to this file
+}
+
+impl TapReq {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        filter: TapFilter,
+        sample_every: u64,
+        record_path: Option<&str>,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(TAP_REQ_ID, *dst_id, *src_id),
+            filter,
+            sample_every,
+            record_path: record_path.map(|p| p.to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tap_filter_matches() {
+        let ae_id = AnId::new();
+        let link_id = AnId::new();
+        let actor_id = AnId::new();
+        let header = MsgHeader::new(AnId::new(), actor_id, AnId::new());
+
+        let ae_point = TapPoint::Executor(ae_id);
+        let link_point = TapPoint::Link(link_id);
+        assert!(TapFilter::Executor(ae_id).matches(&ae_point, &header));
+        assert!(!TapFilter::Executor(ae_id).matches(&link_point, &header));
+        assert!(TapFilter::Link(link_id).matches(&link_point, &header));
+        assert!(!TapFilter::Link(link_id).matches(&ae_point, &header));
+        assert!(TapFilter::Instance(actor_id).matches(&ae_point, &header));
+        assert!(!TapFilter::Instance(actor_id).matches(&link_point, &header));
+        assert!(!TapFilter::Instance(AnId::new()).matches(&ae_point, &header));
+    }
+}
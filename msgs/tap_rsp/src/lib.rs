@@ -0,0 +1,30 @@
+use an_id::AnId;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TapRspStatus {
+    Success,
+    RecordError(String),
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[msg(id = "8f3c5a1d-2e7b-4f96-a0c4-d19b6e5f7283", serde)]
+#[repr(C)]
+pub struct TapRsp {
+    pub header: MsgHeader,
+    pub tap_id: AnId,
+    pub status: TapRspStatus,
+}
+
+impl TapRsp {
+    pub fn new(dst_id: &AnId, src_id: &AnId, tap_id: &AnId, status: TapRspStatus) -> Self {
+        Self {
+            header: MsgHeader::new(TAP_RSP_ID, *dst_id, *src_id),
+            tap_id: *tap_id,
+            status,
+        }
+    }
+}
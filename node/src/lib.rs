@@ -0,0 +1,761 @@
+//! A node is a process hosting ActorExecutors, the ConMgr, the
+//! dispatchers and forwarders linking it to other nodes and the actors
+//! it was configured to spawn. The topology is described by a
+//! [`NodeConfig`] read from a TOML or JSON file.
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    net::SocketAddr,
+    path::Path,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use actor::Actor;
+use actor_channel::ActorChannel;
+use actor_executor::{ActorExecutor, ActorExecutorOptions, ProtocolEnforcement};
+use admin_protocol::{AdminExecutorInfo, AdminNodeInfo};
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use cmd_init_issuer_protocol::{CmdReady, CmdReadyStatus};
+use con_mgr::ConMgr;
+use dispatcher_listening_protocol::{DispatcherListening, DispatcherListeningStatus};
+use id_inventory::{id_entries, IdKind};
+use msg_header::MsgHeader;
+use msg_router_dispatcher::MsgRouterDispatcher;
+use msg_router_forwarder::MsgRouterForwarder;
+use req_add_actor::ReqAddActor;
+use req_shutdown::ReqShutdown;
+use req_spawn_actor::ReqSpawnActor;
+use rsp_add_actor::{RspAddActor, RspAddActorStatus};
+use rsp_shutdown::RspShutdown;
+use rsp_spawn_actor::{RspSpawnActor, RspSpawnActorStatus};
+use runtime_metrics::{metrics_http_serve, MetricsServer};
+use sender_map_by_instance_id::{sender_map_get, sender_map_insert};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The static configuration of a node.
+///
+/// The first executor also hosts the ConMgr.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NodeConfig {
+    pub name: String,
+    pub executors: Vec<ExecutorConfig>,
+    #[serde(default)]
+    pub dispatchers: Vec<DispatcherConfig>,
+    #[serde(default)]
+    pub forwarders: Vec<ForwarderConfig>,
+    #[serde(default)]
+    pub actors: Vec<ActorConfig>,
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+    /// Serve the metrics in the Prometheus text format over HTTP on this
+    /// address, port 0 picks any free port, see [`Node::metrics_addr`].
+    #[serde(default)]
+    pub metrics_addr: Option<String>,
+}
+
+fn default_shutdown_timeout_ms() -> u64 {
+    5000
+}
+
+/// How long starting a node waits for each response of an executor,
+/// dispatcher or actor
+const START_RSP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExecutorConfig {
+    pub name: String,
+    /// Reject the messages an actor didn't declare it receives instead of
+    /// delivering them, if absent true in debug builds and false in release
+    #[serde(default)]
+    pub strict_protocols: Option<bool>,
+}
+
+/// A MsgRouterDispatcher accepting links on listen_addr, port 0 picks
+/// any free port, see [`Node::local_addr`]. It dispatches the messages
+/// of federating forwarders. If admin is true it also accepts admin
+/// connections and must listen on a loopback address.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DispatcherConfig {
+    pub name: String,
+    pub executor: String,
+    pub listen_addr: String,
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// A MsgRouterForwarder linking to the dispatcher of a peer at peer_addr.
+/// If federate is true the ConMgr of the node peers with the ConMgr of
+/// the peer while the link is up, the peer must federate with a
+/// forwarder back to this node for each to see the others actors.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForwarderConfig {
+    pub name: String,
+    pub executor: String,
+    pub peer_addr: String,
+    #[serde(default)]
+    pub federate: bool,
+}
+
+/// An actor constructed by the factory registered for `actor`, which is
+/// either the name the actor id was registered with or the id itself.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActorConfig {
+    pub name: String,
+    pub actor: String,
+    pub executor: String,
+    #[serde(default)]
+    pub params: String,
+}
+
+impl NodeConfig {
+    pub fn from_toml_str(s: &str) -> Result<Self, Box<dyn Error>> {
+        let config: Self = toml::from_str(s)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self, Box<dyn Error>> {
+        let config: Self = serde_json::from_str(s)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read a config file, a `.json` extension means JSON otherwise TOML.
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let s = fs::read_to_string(path)
+            .map_err(|why| format!("NodeConfig::from_file {}: {why}", path.display()))?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json_str(&s)
+        } else {
+            Self::from_toml_str(&s)
+        }
+    }
+
+    /// Check there is an executor and that every name is unique and every
+    /// executor referenced exists.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.executors.is_empty() {
+            return Err(format!("node {}: no executors", self.name).into());
+        }
+
+        let mut names = Vec::<&str>::new();
+        let all_names = self
+            .executors
+            .iter()
+            .map(|e| e.name.as_str())
+            .chain(self.dispatchers.iter().map(|d| d.name.as_str()))
+            .chain(self.forwarders.iter().map(|f| f.name.as_str()))
+            .chain(self.actors.iter().map(|a| a.name.as_str()));
+        for name in all_names {
+            if names.contains(&name) {
+                return Err(format!("node {}: duplicate name {name}", self.name).into());
+            }
+            names.push(name);
+        }
+
+        let executor_refs = self
+            .dispatchers
+            .iter()
+            .map(|d| (&d.name, &d.executor))
+            .chain(self.forwarders.iter().map(|f| (&f.name, &f.executor)))
+            .chain(self.actors.iter().map(|a| (&a.name, &a.executor)));
+        for (name, executor) in executor_refs {
+            if !self.executors.iter().any(|e| e.name == *executor) {
+                return Err(format!(
+                    "node {}: {name} references unknown executor {executor}",
+                    self.name
+                )
+                .into());
+            }
+        }
+
+        for dispatcher in self.dispatchers.iter().filter(|d| d.admin) {
+            let is_loopback = dispatcher
+                .listen_addr
+                .parse::<SocketAddr>()
+                .is_ok_and(|addr| addr.ip().is_loopback());
+            if !is_loopback {
+                return Err(format!(
+                    "node {}: admin dispatcher {} must listen on a loopback address",
+                    self.name, dispatcher.name
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve an actor id given its uuid or registered name.
+pub fn resolve_actor_id(actor: &str) -> Option<AnId> {
+    if let Ok(uuid) = Uuid::parse_str(actor) {
+        return Some(AnId::from(uuid));
+    }
+    id_entries()
+        .iter()
+        .find(|entry| entry.kind == IdKind::Actor && entry.name == actor)
+        .map(|entry| entry.id)
+}
+
+struct NodeExecutor {
+    name: String,
+    instance_id: AnId,
+    join_handle: JoinHandle<()>,
+}
+
+/// A running node, see [`Node::start`].
+pub struct Node {
+    pub name: String,
+    pub supervisor_instance_id: AnId,
+    pub con_mgr_instance_id: AnId,
+    supervisor_chnl: ActorChannel,
+    executors: Vec<NodeExecutor>,
+    instance_ids: HashMap<String, AnId>,
+    local_addrs: HashMap<String, SocketAddr>,
+    shutdown_timeout: Duration,
+    metrics_server: Option<MetricsServer>,
+}
+
+impl std::fmt::Debug for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Node")
+            .field("name", &self.name)
+            .field("supervisor_instance_id", &self.supervisor_instance_id)
+            .field("con_mgr_instance_id", &self.con_mgr_instance_id)
+            .field("instance_ids", &self.instance_ids)
+            .field("local_addrs", &self.local_addrs)
+            .field("metrics_addr", &self.metrics_addr())
+            .finish()
+    }
+}
+
+impl Node {
+    /// Bring up the node described by config blocking until every
+    /// actor is ready.
+    ///
+    /// Factories for the configured actors must have been registered
+    /// with actor_factory_insert.
+    ///
+    /// The dispatchers and forwarders serialize the messages they link
+    /// with the MsgCodec of each message, see msg_header::msg_codec_get,
+    /// so the messages needn't be added to them.
+    pub fn start(config: &NodeConfig) -> Result<Self, Box<dyn Error>> {
+        log::info!("Node::start:+ {}", config.name);
+        config.validate()?;
+
+        // Add supervisor to sender_map
+        let supervisor_instance_id = AnId::new();
+        let supervisor_chnl = ActorChannel::new(
+            &format!("{}_supervisor", config.name),
+            &supervisor_instance_id,
+        );
+        sender_map_insert(&supervisor_instance_id, &supervisor_chnl.sender);
+
+        // The first executor hosts the ConMgr
+        let con_mgr = Box::new(ConMgr::new(&format!("{}_con_mgr", config.name)));
+        let con_mgr_instance_id = *con_mgr.get_instance_id();
+
+        let mut node = Self {
+            name: config.name.clone(),
+            supervisor_instance_id,
+            con_mgr_instance_id,
+            supervisor_chnl,
+            executors: Vec::new(),
+            instance_ids: HashMap::new(),
+            local_addrs: HashMap::new(),
+            shutdown_timeout: Duration::from_millis(config.shutdown_timeout_ms),
+            metrics_server: None,
+        };
+
+        for executor in config.executors.iter() {
+            let protocol_enforcement = match executor.strict_protocols {
+                Some(true) => ProtocolEnforcement::Strict,
+                Some(false) => ProtocolEnforcement::Permissive,
+                None => ProtocolEnforcement::default(),
+            };
+            let options = ActorExecutorOptions {
+                protocol_enforcement,
+                supervisor_instance_id: Some(node.supervisor_instance_id),
+                ..Default::default()
+            };
+            let (join_handle, instance_id) =
+                ActorExecutor::start_with_options(&executor.name, &con_mgr_instance_id, options);
+            node.instance_ids.insert(executor.name.clone(), instance_id);
+            node.executors.push(NodeExecutor {
+                name: executor.name.clone(),
+                instance_id,
+                join_handle,
+            });
+        }
+
+        if let Err(why) = node.add_actor(&config.executors[0].name, con_mgr) {
+            log::error!("Node::start: {why}");
+            node.shutdown();
+            return Err(why);
+        }
+
+        // Dispatchers before forwarders so links within a node can connect
+        let admin_node = node.admin_node_info();
+        for dispatcher in config.dispatchers.iter() {
+            let mut mrd = Box::new(MsgRouterDispatcher::new(
+                &dispatcher.name,
+                &dispatcher.listen_addr,
+            ));
+            mrd.enable_federation();
+            if dispatcher.admin {
+                mrd.enable_admin(&admin_node);
+            }
+            mrd.notify_listening(&node.supervisor_instance_id);
+            let result = node
+                .req_add_actor(&dispatcher.executor, mrd)
+                .and_then(|instance_id| {
+                    node.instance_ids
+                        .insert(dispatcher.name.clone(), instance_id);
+                    node.wait_for_listening(&dispatcher.name)
+                });
+            if let Err(why) = result {
+                log::error!("Node::start: {why}");
+                node.shutdown();
+                return Err(why);
+            }
+        }
+        for forwarder in config.forwarders.iter() {
+            let mut mrf = Box::new(MsgRouterForwarder::new(
+                &forwarder.name,
+                &forwarder.peer_addr,
+            ));
+            if forwarder.federate {
+                mrf.enable_federation();
+            }
+            match node.add_actor(&forwarder.executor, mrf) {
+                Ok(instance_id) => {
+                    node.instance_ids
+                        .insert(forwarder.name.clone(), instance_id);
+                }
+                Err(why) => {
+                    log::error!("Node::start: {why}");
+                    node.shutdown();
+                    return Err(why);
+                }
+            }
+        }
+
+        for actor in config.actors.iter() {
+            if let Err(why) = node.spawn_actor(actor) {
+                log::error!("Node::start: {why}");
+                node.shutdown();
+                return Err(why);
+            }
+        }
+
+        if let Some(metrics_addr) = &config.metrics_addr {
+            match metrics_http_serve(metrics_addr) {
+                Ok(server) => node.metrics_server = Some(server),
+                Err(why) => {
+                    let why = format!("Node::start: metrics on {metrics_addr}: {why}");
+                    log::error!("{why}");
+                    node.shutdown();
+                    return Err(why.into());
+                }
+            }
+        }
+
+        log::info!("Node::start:- {node:?}");
+        Ok(node)
+    }
+
+    /// The instance_id of the executor, dispatcher, forwarder or actor
+    /// with name.
+    pub fn instance_id(&self, name: &str) -> Option<&AnId> {
+        self.instance_ids.get(name)
+    }
+
+    /// The address the dispatcher with name is listening on, the port
+    /// is the one chosen when its listen_addr has port 0.
+    pub fn local_addr(&self, name: &str) -> Option<&SocketAddr> {
+        self.local_addrs.get(name)
+    }
+
+    /// The address the metrics are served on, if metrics_addr was
+    /// configured, the port is the one chosen when it has port 0.
+    pub fn metrics_addr(&self) -> Option<SocketAddr> {
+        self.metrics_server
+            .as_ref()
+            .map(|server| server.local_addr())
+    }
+
+    /// Stop serving the metrics then shutdown each executor, the one
+    /// hosting the ConMgr last so the others can unregister their actors,
+    /// and wait for them to exit.
+    pub fn shutdown(mut self) {
+        log::info!("Node::shutdown:+ {}", self.name);
+        if let Some(server) = self.metrics_server.take() {
+            server.stop();
+        }
+        for executor in std::mem::take(&mut self.executors).into_iter().rev() {
+            let msg = Box::new(ReqShutdown::new(
+                &executor.instance_id,
+                &self.supervisor_instance_id,
+                self.shutdown_timeout,
+            ));
+            let sent = match sender_map_get(&executor.instance_id) {
+                Some(sndr) => sndr.send(msg),
+                None => Err("not in the sender_map".into()),
+            };
+            // The executor waits at most shutdown_timeout for its actors
+            let timeout = self.shutdown_timeout + START_RSP_TIMEOUT;
+            match sent.and_then(|_| {
+                self.recv_rsp::<RspShutdown>(&executor.instance_id, timeout, "shutdown")
+            }) {
+                Ok(msg) => log::info!("Node::shutdown: {} {:?}", executor.name, msg.summary),
+                Err(why) => log::warn!("Node::shutdown: {} {why}", executor.name),
+            }
+            if executor.join_handle.join().is_err() {
+                log::warn!("Node::shutdown: {} panicked", executor.name);
+            }
+        }
+        log::info!("Node::shutdown:- {}", self.name);
+    }
+
+    fn admin_node_info(&self) -> AdminNodeInfo {
+        AdminNodeInfo {
+            name: self.name.clone(),
+            con_mgr_instance_id: self.con_mgr_instance_id,
+            executors: self
+                .executors
+                .iter()
+                .map(|executor| AdminExecutorInfo {
+                    name: executor.name.clone(),
+                    instance_id: executor.instance_id,
+                })
+                .collect(),
+        }
+    }
+
+    /// Add actor to executor and wait until it's ready, returns its
+    /// instance_id
+    fn add_actor(&mut self, executor: &str, actor: Box<dyn Actor>) -> Result<AnId, Box<dyn Error>> {
+        let instance_id = self.req_add_actor(executor, actor)?;
+        self.wait_for_ready(&instance_id)?;
+        Ok(instance_id)
+    }
+
+    /// Add actor to executor without waiting until it's ready, returns
+    /// its instance_id
+    fn req_add_actor(&self, executor: &str, actor: Box<dyn Actor>) -> Result<AnId, Box<dyn Error>> {
+        let name = actor.get_name().to_owned();
+        let ae_instance_id = self.instance_ids[executor];
+        sender_map_get(&ae_instance_id)
+            .ok_or_else(|| format!("{name}: no executor {executor}"))?
+            .send(Box::new(ReqAddActor::new(
+                &ae_instance_id,
+                &self.supervisor_instance_id,
+                actor,
+            )))?;
+
+        let msg = self.recv_rsp::<RspAddActor>(&ae_instance_id, START_RSP_TIMEOUT, "add_actor")?;
+        if msg.status != RspAddActorStatus::Success {
+            return Err(format!("{name}: add failed {:?}", msg.status).into());
+        }
+        Ok(msg.actor_instance_id)
+    }
+
+    fn spawn_actor(&mut self, actor: &ActorConfig) -> Result<(), Box<dyn Error>> {
+        let actor_id = resolve_actor_id(&actor.actor)
+            .ok_or_else(|| format!("{}: unknown actor {}", actor.name, actor.actor))?;
+        let ae_instance_id = self.instance_ids[&actor.executor];
+        sender_map_get(&ae_instance_id)
+            .ok_or_else(|| format!("{}: no executor {}", actor.name, actor.executor))?
+            .send(Box::new(ReqSpawnActor::new(
+                &ae_instance_id,
+                &self.supervisor_instance_id,
+                &actor_id,
+                &actor.name,
+                &actor.params,
+            )))?;
+
+        let msg =
+            self.recv_rsp::<RspSpawnActor>(&ae_instance_id, START_RSP_TIMEOUT, "spawn_actor")?;
+        if msg.status != RspSpawnActorStatus::Success {
+            return Err(format!("{}: spawn failed {:?}", actor.name, msg.status).into());
+        }
+        let instance_id = msg.actor_instance_id;
+        self.wait_for_ready(&instance_id)?;
+        self.instance_ids.insert(actor.name.clone(), instance_id);
+
+        Ok(())
+    }
+
+    /// Wait until the dispatcher with name is ready and has sent the
+    /// DispatcherListening it was asked for, in either order as the
+    /// executor passes on its CmdReady
+    fn wait_for_listening(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        let instance_id = self.instance_ids[name];
+        let deadline = Instant::now() + START_RSP_TIMEOUT;
+        let mut ready = false;
+        let mut listening = None;
+        while !ready || listening.is_none() {
+            let msg_any = self.recv_msg(
+                &instance_id,
+                deadline,
+                "wait_for_listening",
+                "CmdReady and DispatcherListening",
+                |msg_any| msg_any.is::<CmdReady>() || msg_any.is::<DispatcherListening>(),
+            )?;
+            match msg_any.downcast::<CmdReady>() {
+                Ok(msg) => {
+                    cmd_ready_result(&instance_id, &msg)?;
+                    ready = true;
+                }
+                Err(msg_any) => listening = msg_any.downcast::<DispatcherListening>().ok(),
+            }
+        }
+
+        let msg = listening.unwrap();
+        match &msg.status {
+            DispatcherListeningStatus::Listening(local_addr) => {
+                self.local_addrs.insert(name.to_owned(), *local_addr);
+                Ok(())
+            }
+            DispatcherListeningStatus::BindFailed(why) => {
+                Err(format!("{name}: unable to listen on {} {why}", msg.addr).into())
+            }
+            DispatcherListeningStatus::NotStarted => {
+                Err(format!("{name}: not listening on {}", msg.addr).into())
+            }
+        }
+    }
+
+    fn wait_for_ready(&self, instance_id: &AnId) -> Result<(), Box<dyn Error>> {
+        let msg = self.recv_rsp::<CmdReady>(instance_id, START_RSP_TIMEOUT, "wait_for_ready")?;
+        cmd_ready_result(instance_id, &msg)
+    }
+
+    /// Receive the next T from src_id on the supervisor channel within
+    /// timeout, other messages are skipped as they aren't the response
+    fn recv_rsp<T: 'static>(
+        &self,
+        src_id: &AnId,
+        timeout: Duration,
+        context: &str,
+    ) -> Result<Box<T>, Box<dyn Error>> {
+        let type_name = std::any::type_name::<T>();
+        let deadline = Instant::now() + timeout;
+        let msg_any = self.recv_msg(src_id, deadline, context, type_name, |msg_any| {
+            msg_any.is::<T>()
+        })?;
+        Ok(msg_any.downcast::<T>().unwrap())
+    }
+
+    /// Receive the next expected message, described by waiting_for, from
+    /// src_id on the supervisor channel before deadline, other messages
+    /// are skipped
+    fn recv_msg(
+        &self,
+        src_id: &AnId,
+        deadline: Instant,
+        context: &str,
+        waiting_for: &str,
+        expected: impl Fn(&BoxMsgAny) -> bool,
+    ) -> Result<BoxMsgAny, Box<dyn Error>> {
+        loop {
+            let msg_any = self
+                .supervisor_chnl
+                .receiver
+                .rx
+                .recv_deadline(deadline)
+                .map_err(|why| format!("Node::{context}: no {waiting_for} from {src_id}, {why}"))?;
+            if MsgHeader::get_src_id_from_boxed_msg_any(&msg_any) == src_id && expected(&msg_any) {
+                return Ok(msg_any);
+            }
+            log::warn!(
+                "Node::{context}: skipping msg_id={} waiting for {waiting_for} from {src_id}",
+                MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any)
+            );
+        }
+    }
+}
+
+fn cmd_ready_result(instance_id: &AnId, msg: &CmdReady) -> Result<(), Box<dyn Error>> {
+    if let CmdReadyStatus::RegistrationFailed(reason) = &msg.status {
+        return Err(format!("{instance_id}: registration failed {reason}").into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actor_factory::actor_factory_insert;
+    use con_mgr_federation_protocol::{
+        CON_MGR_FEDERATION_DELTA_ID, CON_MGR_FEDERATION_SNAPSHOT_ID,
+        CON_MGR_FEDERATION_SNAPSHOT_REQ_ID,
+    };
+    use echo_req::{EchoReq, ECHO_REQ_ID};
+    use echo_rsp::ECHO_RSP_ID;
+    use msg_header::msg_codec_get;
+    use server::{Server, SERVER_ACTOR_ID};
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+    };
+
+    const TOML_CONFIG: &str = r#"
+        name = "node1"
+
+        [[executors]]
+        name = "ae1"
+
+        [[executors]]
+        name = "ae2"
+        strict_protocols = false
+
+        [[dispatchers]]
+        name = "mrd1"
+        executor = "ae1"
+        listen_addr = "127.0.0.1:12350"
+
+        [[forwarders]]
+        name = "mrf1"
+        executor = "ae1"
+        peer_addr = "127.0.0.1:12350"
+        federate = true
+
+        [[actors]]
+        name = "server1"
+        actor = "server"
+        executor = "ae2"
+    "#;
+
+    const JSON_CONFIG: &str = r#"{
+        "name": "node1",
+        "executors": [{ "name": "ae1" }, { "name": "ae2", "strict_protocols": false }],
+        "dispatchers": [{ "name": "mrd1", "executor": "ae1", "listen_addr": "127.0.0.1:12350" }],
+        "forwarders": [{ "name": "mrf1", "executor": "ae1", "peer_addr": "127.0.0.1:12350", "federate": true }],
+        "actors": [{ "name": "server1", "actor": "server", "executor": "ae2" }]
+    }"#;
+
+    #[test]
+    fn test_node_config() {
+        println!("\ntest_node_config:+");
+        let toml_config = NodeConfig::from_toml_str(TOML_CONFIG).unwrap();
+        let json_config = NodeConfig::from_json_str(JSON_CONFIG).unwrap();
+        assert_eq!(toml_config, json_config);
+        assert_eq!(toml_config.shutdown_timeout_ms, 5000);
+        assert_eq!(toml_config.metrics_addr, None);
+        assert_eq!(toml_config.executors[0].strict_protocols, None);
+        assert_eq!(toml_config.executors[1].strict_protocols, Some(false));
+        assert!(toml_config.forwarders[0].federate);
+        assert_eq!(toml_config.actors[0].params, "");
+        assert_eq!(resolve_actor_id("server"), Some(SERVER_ACTOR_ID));
+        assert_eq!(
+            resolve_actor_id(&SERVER_ACTOR_ID.to_string()),
+            Some(SERVER_ACTOR_ID)
+        );
+        assert_eq!(resolve_actor_id("no_such_actor"), None);
+
+        let mut config = toml_config.clone();
+        config.actors[0].executor = "ae3".to_owned();
+        assert!(config.validate().is_err());
+
+        let mut config = toml_config.clone();
+        config.forwarders[0].name = "server1".to_owned();
+        assert!(config.validate().is_err());
+
+        let mut config = toml_config.clone();
+        config.dispatchers[0].admin = true;
+        config.dispatchers[0].listen_addr = "0.0.0.0:12350".to_owned();
+        assert!(config.validate().is_err());
+
+        let mut config = toml_config;
+        config.executors.clear();
+        assert!(config.validate().is_err());
+        println!("test_node_config:-");
+    }
+
+    #[test]
+    fn test_node_start_shutdown() {
+        println!("\ntest_node_start_shutdown:+");
+        actor_factory_insert(&SERVER_ACTOR_ID, Server::factory);
+
+        // Port 0 binds any free port, the forwarder's peer is a mock dispatcher
+        let mock_mrd_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = NodeConfig::from_toml_str(TOML_CONFIG).unwrap();
+        config.dispatchers[0].listen_addr = "127.0.0.1:0".to_owned();
+        config.forwarders[0].peer_addr = mock_mrd_listener.local_addr().unwrap().to_string();
+        config.metrics_addr = Some("127.0.0.1:0".to_owned());
+        let node = Node::start(&config).unwrap();
+        println!("test_node_start_shutdown: node={node:?}");
+        for name in ["ae1", "ae2", "mrd1", "mrf1", "server1"] {
+            let instance_id = node.instance_id(name).unwrap();
+            assert!(sender_map_get(instance_id).is_some());
+        }
+        assert_ne!(node.local_addr("mrd1").unwrap().port(), 0);
+
+        // The metrics of the executors are served on metrics_addr
+        let metrics_addr = node.metrics_addr().unwrap();
+        let mut stream = TcpStream::connect(metrics_addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut rsp = String::new();
+        stream.read_to_string(&mut rsp).unwrap();
+        assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(rsp.contains(r#"executor_active_actors{executor="ae2""#));
+
+        // The EchoRsp is skipped waiting for the RspShutdown's
+        let server1_instance_id = node.instance_id("server1").unwrap();
+        sender_map_get(server1_instance_id)
+            .unwrap()
+            .send(Box::new(EchoReq::new(
+                server1_instance_id,
+                &node.supervisor_instance_id,
+                1,
+            )))
+            .unwrap();
+        node.shutdown();
+        assert!(TcpStream::connect(metrics_addr).is_err());
+
+        println!("test_node_start_shutdown:-");
+    }
+
+    #[test]
+    fn test_node_msg_codecs() {
+        // The messages a node links are serialized by their MsgCodec
+        for msg_id in [
+            ECHO_REQ_ID,
+            ECHO_RSP_ID,
+            CON_MGR_FEDERATION_SNAPSHOT_ID,
+            CON_MGR_FEDERATION_SNAPSHOT_REQ_ID,
+            CON_MGR_FEDERATION_DELTA_ID,
+        ] {
+            assert!(msg_codec_get(&msg_id).is_some(), "no MsgCodec for {msg_id}");
+        }
+    }
+
+    #[test]
+    fn test_node_dispatcher_bind_failed() {
+        println!("\ntest_node_dispatcher_bind_failed:+");
+        let mut config = NodeConfig::from_toml_str(TOML_CONFIG).unwrap();
+        config.forwarders.clear();
+        config.actors.clear();
+
+        // Port 0 binds any free port
+        config.dispatchers[0].listen_addr = "127.0.0.1:0".to_owned();
+        let node = Node::start(&config).unwrap();
+        let local_addr = *node.local_addr("mrd1").unwrap();
+        assert_ne!(local_addr.port(), 0);
+
+        // While node is running its address is in use
+        config.dispatchers[0].listen_addr = local_addr.to_string();
+        let why = Node::start(&config).unwrap_err();
+        println!("test_node_dispatcher_bind_failed: why={why}");
+        assert!(why.to_string().contains("mrd1: unable to listen"));
+        node.shutdown();
+
+        println!("test_node_dispatcher_bind_failed:-");
+    }
+}
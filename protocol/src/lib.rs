@@ -1,21 +1,198 @@
+use std::{fmt::Display, str::FromStr};
+
 use an_id::AnId;
 use serde::{Deserialize, Serialize};
 
+/// A semantic version, `major.minor.patch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Same rules as cargo's default `^` requirement, versions with the
+    /// same major are compatible unless major is 0 in which case the
+    /// minor must also match.
+    pub fn is_compatible_with(&self, other: &Version) -> bool {
+        self.major == other.major && (self.major != 0 || self.minor == other.minor)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('.').collect();
+        let [major, minor, patch] = parts.as_slice() else {
+            return Err(format!("version \"{s}\" isn't major.minor.patch"));
+        };
+        let parse = |p: &str| {
+            p.parse::<u32>()
+                .map_err(|why| format!("version \"{s}\": {why}"))
+        };
+        Ok(Version::new(parse(major)?, parse(minor)?, parse(patch)?))
+    }
+}
+
+/// The part an entity implementing a Protocol plays in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProtocolRole {
+    Requester,
+    Requestee,
+    Issuer,
+    Issuee,
+}
+
+impl ProtocolRole {
+    /// The role of the entity on the other side
+    pub fn complement(&self) -> Self {
+        match self {
+            ProtocolRole::Requester => ProtocolRole::Requestee,
+            ProtocolRole::Requestee => ProtocolRole::Requester,
+            ProtocolRole::Issuer => ProtocolRole::Issuee,
+            ProtocolRole::Issuee => ProtocolRole::Issuer,
+        }
+    }
+}
+
+/// Direction of a message as seen by the entity implementing the Protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MsgDirection {
+    Send,
+    Receive,
+}
+
+impl MsgDirection {
+    pub fn complement(&self) -> Self {
+        match self {
+            MsgDirection::Send => MsgDirection::Receive,
+            MsgDirection::Receive => MsgDirection::Send,
+        }
+    }
+}
+
+/// Version of a message whose shape hasn't changed since it was added
+pub const INITIAL_MSG_VERSION: Version = Version::new(1, 0, 0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProtocolMsg {
+    pub id: AnId,
+    pub direction: MsgDirection,
+    pub version: Version, // Version of the messages shape
+}
+
+impl ProtocolMsg {
+    pub fn new(id: AnId, direction: MsgDirection, version: Version) -> Self {
+        Self {
+            id,
+            direction,
+            version,
+        }
+    }
+
+    pub fn send(id: AnId) -> Self {
+        Self::new(id, MsgDirection::Send, INITIAL_MSG_VERSION)
+    }
+
+    pub fn receive(id: AnId) -> Self {
+        Self::new(id, MsgDirection::Receive, INITIAL_MSG_VERSION)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Protocol {
     pub name: String,
     pub id: AnId,
-    pub messages: Vec<AnId>,
+    pub version: Version,
+    pub role: ProtocolRole,
+    pub messages: Vec<ProtocolMsg>,
 }
 
 impl Protocol {
-    pub fn new(name: &str, id: AnId, messages: Vec<AnId>) -> Self {
+    pub fn new(
+        name: &str,
+        id: AnId,
+        version: Version,
+        role: ProtocolRole,
+        messages: Vec<ProtocolMsg>,
+    ) -> Self {
         Self {
             name: name.to_string(),
             id,
+            version,
+            role,
             messages,
         }
     }
+
+    /// Ids of all messages in the protocol
+    pub fn msg_ids(&self) -> Vec<AnId> {
+        self.messages.iter().map(|m| m.id).collect()
+    }
+
+    pub fn sends(&self, msg_id: &AnId) -> bool {
+        self.messages
+            .iter()
+            .any(|m| m.id == *msg_id && m.direction == MsgDirection::Send)
+    }
+
+    pub fn receives(&self, msg_id: &AnId) -> bool {
+        self.messages
+            .iter()
+            .any(|m| m.id == *msg_id && m.direction == MsgDirection::Receive)
+    }
+
+    /// True if other plays the complementary role and receives
+    /// every message this sends and sends every message this receives.
+    /// Versions aren't considered, see incompatibility.
+    pub fn is_complement_of(&self, other: &Protocol) -> bool {
+        self.role.complement() == other.role
+            && self.messages.len() == other.messages.len()
+            && self.messages.iter().all(|m| {
+                other
+                    .messages
+                    .iter()
+                    .any(|o| o.id == m.id && o.direction == m.direction.complement())
+            })
+    }
+
+    /// Why messages of this protocol can't be exchanged with an entity
+    /// implementing peer, None if the versions are compatible.
+    pub fn incompatibility(&self, peer: &Protocol) -> Option<String> {
+        if !self.version.is_compatible_with(&peer.version) {
+            return Some(format!(
+                "protocol {} version {} is incompatible with the peers version {}",
+                self.name, self.version, peer.version
+            ));
+        }
+        for m in self.messages.iter() {
+            if let Some(p) = peer.messages.iter().find(|p| p.id == m.id) {
+                if !m.version.is_compatible_with(&p.version) {
+                    return Some(format!(
+                        "message {} of protocol {} version {} is incompatible with the peers version {}",
+                        m.id, self.name, m.version, p.version
+                    ));
+                }
+            }
+        }
+        None
+    }
 }
 
 #[cfg(test)]
@@ -27,12 +204,128 @@ mod test {
         println!("test_protocol");
 
         let id = AnId::new();
-        let messages = vec![AnId::new()];
-        let a_protocol = Protocol::new("a_protocol", id.clone(), messages.clone());
+        let messages = vec![ProtocolMsg::send(AnId::new())];
+        let a_protocol = Protocol::new(
+            "a_protocol",
+            id,
+            Version::new(1, 2, 3),
+            ProtocolRole::Issuer,
+            messages.clone(),
+        );
 
         println!("a_protocol={a_protocol:#?}");
         assert_eq!(a_protocol.name, "a_protocol");
         assert_eq!(a_protocol.id, id);
+        assert_eq!(a_protocol.version, Version::new(1, 2, 3));
+        assert_eq!(a_protocol.role, ProtocolRole::Issuer);
         assert_eq!(a_protocol.messages, messages);
+        assert_eq!(a_protocol.msg_ids(), vec![messages[0].id]);
+    }
+
+    #[test]
+    fn test_is_complement_of() {
+        println!("test_is_complement_of");
+
+        let req_id = AnId::new();
+        let rsp_id = AnId::new();
+        let requester = Protocol::new(
+            "requester",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Requester,
+            vec![ProtocolMsg::send(req_id), ProtocolMsg::receive(rsp_id)],
+        );
+        let requestee = Protocol::new(
+            "requestee",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Requestee,
+            vec![ProtocolMsg::receive(req_id), ProtocolMsg::send(rsp_id)],
+        );
+        assert!(requester.sends(&req_id));
+        assert!(requester.receives(&rsp_id));
+        assert!(!requester.receives(&req_id));
+        assert!(requester.is_complement_of(&requestee));
+        assert!(requestee.is_complement_of(&requester));
+
+        // Same role isn't complementary
+        assert!(!requester.is_complement_of(&requester));
+
+        // Right role but wrong directions
+        let bad_requestee = Protocol::new(
+            "bad_requestee",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Requestee,
+            vec![ProtocolMsg::send(req_id), ProtocolMsg::receive(rsp_id)],
+        );
+        assert!(!requester.is_complement_of(&bad_requestee));
+
+        // Missing a message
+        let short_requestee = Protocol::new(
+            "short_requestee",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Requestee,
+            vec![ProtocolMsg::receive(req_id)],
+        );
+        assert!(!requester.is_complement_of(&short_requestee));
+    }
+
+    #[test]
+    fn test_version() {
+        println!("test_version");
+
+        let v: Version = "1.2.3".parse().unwrap();
+        assert_eq!(v, Version::new(1, 2, 3));
+        assert_eq!(v.to_string(), "1.2.3");
+        assert!("1.2".parse::<Version>().is_err());
+        assert!("1.2.x".parse::<Version>().is_err());
+
+        assert!(v.is_compatible_with(&Version::new(1, 0, 0)));
+        assert!(v.is_compatible_with(&Version::new(1, 9, 9)));
+        assert!(!v.is_compatible_with(&Version::new(2, 2, 3)));
+        assert!(Version::new(0, 1, 0).is_compatible_with(&Version::new(0, 1, 7)));
+        assert!(!Version::new(0, 1, 0).is_compatible_with(&Version::new(0, 2, 0)));
+    }
+
+    #[test]
+    fn test_incompatibility() {
+        println!("test_incompatibility");
+
+        let req_id = AnId::new();
+        let rsp_id = AnId::new();
+        let requester = |version: Version, req_version: Version| {
+            Protocol::new(
+                "requester",
+                AnId::new(),
+                version,
+                ProtocolRole::Requester,
+                vec![
+                    ProtocolMsg::new(req_id, MsgDirection::Send, req_version),
+                    ProtocolMsg::receive(rsp_id),
+                ],
+            )
+        };
+        let requestee = Protocol::new(
+            "requestee",
+            AnId::new(),
+            Version::new(1, 1, 0),
+            ProtocolRole::Requestee,
+            vec![ProtocolMsg::receive(req_id), ProtocolMsg::send(rsp_id)],
+        );
+
+        let p = requester(Version::new(1, 0, 0), INITIAL_MSG_VERSION);
+        assert_eq!(p.incompatibility(&requestee), None);
+
+        let p = requester(Version::new(2, 0, 0), INITIAL_MSG_VERSION);
+        let why = p.incompatibility(&requestee).unwrap();
+        println!("test_incompatibility: {why}");
+        assert!(why.contains("protocol requester version 2.0.0"));
+
+        let p = requester(Version::new(1, 0, 0), Version::new(2, 0, 0));
+        let why = p.incompatibility(&requestee).unwrap();
+        println!("test_incompatibility: {why}");
+        assert!(why.contains(&format!("message {req_id}")));
     }
 }
@@ -1,7 +1,7 @@
 use an_id::AnId;
-use protocol::Protocol;
+use protocol::{MsgDirection, Protocol};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ProtocolSet {
@@ -23,38 +23,88 @@ impl ProtocolSet {
             protocols_map,
         }
     }
+
+    /// True if any protocol in this set receives msg_id
+    pub fn receives(&self, msg_id: &AnId) -> bool {
+        self.protocols_map.values().any(|p| p.receives(msg_id))
+    }
+
+    /// The ids of the messages any protocol in this set receives, for
+    /// checking many messages without searching the protocols each time
+    pub fn receive_msg_ids(&self) -> HashSet<AnId> {
+        self.protocols_map
+            .values()
+            .flat_map(|p| p.messages.iter())
+            .filter(|m| m.direction == MsgDirection::Receive)
+            .map(|m| m.id)
+            .collect()
+    }
+
+    /// The protocol in this set that complements protocol
+    pub fn complement_of(&self, protocol: &Protocol) -> Option<&Protocol> {
+        self.protocols_map
+            .values()
+            .find(|p| p.is_complement_of(protocol))
+    }
+
+    /// Pairs of protocol ids, the first from this set and the second
+    /// from other, of the protocols that complement each other.
+    pub fn complementary_protocols(&self, other: &ProtocolSet) -> Vec<(AnId, AnId)> {
+        let mut pairs: Vec<(AnId, AnId)> = self
+            .protocols_map
+            .values()
+            .filter_map(|p| other.complement_of(p).map(|o| (p.id, o.id)))
+            .collect();
+        pairs.sort_by_key(|(id, _)| id.0);
+        pairs
+    }
+
+    /// True if the two sets can communicate using at least one protocol
+    pub fn is_complementary(&self, other: &ProtocolSet) -> bool {
+        !self.complementary_protocols(other).is_empty()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use protocol::{ProtocolMsg, ProtocolRole, Version};
 
     #[test]
     fn test_protocol() {
         println!("test_protocol");
 
         let protocol1_id = AnId::new();
-        let protocol1_msgs = vec![AnId::new()];
-        let protocol1 = Protocol::new("protocol1", protocol1_id.clone(), protocol1_msgs.clone());
+        let protocol1_msgs = vec![ProtocolMsg::send(AnId::new())];
+        let protocol1 = Protocol::new(
+            "protocol1",
+            protocol1_id,
+            Version::new(1, 0, 0),
+            ProtocolRole::Issuer,
+            protocol1_msgs.clone(),
+        );
 
         let protocol2_id = AnId::new();
-        let protocol2_msgs = vec![AnId::new()];
-        let protocol2 = Protocol::new("protocol2", protocol2_id.clone(), protocol2_msgs.clone());
+        let protocol2_msgs = vec![ProtocolMsg::receive(AnId::new())];
+        let protocol2 = Protocol::new(
+            "protocol2",
+            protocol2_id,
+            Version::new(1, 0, 0),
+            ProtocolRole::Issuee,
+            protocol2_msgs.clone(),
+        );
 
         let protocol_set_id = AnId::new();
         let mut protocols_map = HashMap::new();
         assert!(protocols_map
-            .insert(protocol1_id.clone(), protocol1.clone())
+            .insert(protocol1_id, protocol1.clone())
             .is_none());
         assert!(protocols_map
-            .insert(protocol2_id.clone(), protocol2.clone())
+            .insert(protocol2_id, protocol2.clone())
             .is_none());
 
-        let a_protocol_set = ProtocolSet::new(
-            "a_protocol_set",
-            protocol_set_id.clone(),
-            protocols_map.clone(),
-        );
+        let a_protocol_set =
+            ProtocolSet::new("a_protocol_set", protocol_set_id, protocols_map.clone());
         println!("a_protocol_set: {a_protocol_set:#?}");
 
         let protocols = Vec::from_iter(a_protocol_set.protocols_map.values().into_iter());
@@ -62,15 +112,73 @@ mod test {
 
         assert_eq!(a_protocol_set.id, protocol_set_id);
         assert_eq!(a_protocol_set.name, "a_protocol_set");
-        let p = a_protocol_set
-            .protocols_map
-            .get(&protocol1_id.clone())
-            .unwrap();
+        let p = a_protocol_set.protocols_map.get(&protocol1_id).unwrap();
         assert_eq!(p, &protocol1);
-        let p = a_protocol_set
-            .protocols_map
-            .get(&protocol2_id.clone())
-            .unwrap();
+        let p = a_protocol_set.protocols_map.get(&protocol2_id).unwrap();
         assert_eq!(p, &protocol2);
     }
+
+    #[test]
+    fn test_complementary_protocols() {
+        println!("test_complementary_protocols");
+
+        let req_id = AnId::new();
+        let rsp_id = AnId::new();
+        let cmd_id = AnId::new();
+        let requester = Protocol::new(
+            "requester",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Requester,
+            vec![ProtocolMsg::send(req_id), ProtocolMsg::receive(rsp_id)],
+        );
+        let requestee = Protocol::new(
+            "requestee",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Requestee,
+            vec![ProtocolMsg::receive(req_id), ProtocolMsg::send(rsp_id)],
+        );
+        let issuee = Protocol::new(
+            "issuee",
+            AnId::new(),
+            Version::new(1, 0, 0),
+            ProtocolRole::Issuee,
+            vec![ProtocolMsg::receive(cmd_id)],
+        );
+
+        let client_ps = ProtocolSet::new(
+            "client_ps",
+            AnId::new(),
+            HashMap::from([
+                (requester.id, requester.clone()),
+                (issuee.id, issuee.clone()),
+            ]),
+        );
+        let server_ps = ProtocolSet::new(
+            "server_ps",
+            AnId::new(),
+            HashMap::from([(requestee.id, requestee.clone()), (issuee.id, issuee)]),
+        );
+        assert!(client_ps.is_complementary(&server_ps));
+        assert_eq!(
+            client_ps.complementary_protocols(&server_ps),
+            vec![(requester.id, requestee.id)]
+        );
+        assert_eq!(
+            server_ps.complementary_protocols(&client_ps),
+            vec![(requestee.id, requester.id)]
+        );
+        assert_eq!(server_ps.complement_of(&requester), Some(&requestee));
+
+        // Two clients can't communicate
+        assert!(!client_ps.is_complementary(&client_ps));
+
+        assert!(client_ps.receives(&rsp_id));
+        assert!(client_ps.receives(&cmd_id));
+        assert!(!client_ps.receives(&req_id));
+        assert!(server_ps.receives(&req_id));
+        assert_eq!(client_ps.receive_msg_ids(), HashSet::from([rsp_id, cmd_id]));
+        assert_eq!(server_ps.receive_msg_ids(), HashSet::from([req_id, cmd_id]));
+    }
 }
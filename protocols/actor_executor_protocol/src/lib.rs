@@ -1,22 +1,47 @@
 //! Protocol implemented by entities that that receive requests
 //! and send responses.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use req_add_actor::*;
+pub use req_move_actor::*;
+pub use req_shutdown::*;
+pub use req_spawn_actor::*;
 pub use rsp_add_actor::*;
+pub use rsp_move_actor::*;
+pub use rsp_shutdown::*;
+pub use rsp_spawn_actor::*;
 
 const ACTOR_EXECUTOR_PROTOCOL_ID: AnId = anid!("907ee4b7-2819-4211-84b1-e01fc940e2f6");
 const ACTOR_EXECUTOR_PROTOCOL_NAME: &str = "actor_executor_protocol";
-static ACTOR_EXECUTOR_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
-    Lazy::new(|| vec![REQ_ADD_ACTOR_ID, RSP_ADD_ACTOR_ID]);
+const ACTOR_EXECUTOR_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    ACTOR_EXECUTOR_PROTOCOL_ID,
+    ACTOR_EXECUTOR_PROTOCOL_NAME
+);
+static ACTOR_EXECUTOR_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(REQ_ADD_ACTOR_ID),
+        ProtocolMsg::send(RSP_ADD_ACTOR_ID),
+        ProtocolMsg::receive(REQ_MOVE_ACTOR_ID),
+        ProtocolMsg::send(RSP_MOVE_ACTOR_ID),
+        ProtocolMsg::receive(REQ_SPAWN_ACTOR_ID),
+        ProtocolMsg::send(RSP_SPAWN_ACTOR_ID),
+        ProtocolMsg::receive(REQ_SHUTDOWN_ID),
+        ProtocolMsg::send(RSP_SHUTDOWN_ID),
+    ]
+});
 
 static ACTOR_EXECUTOR_PROTOCOL: Lazy<ActorExecutorProtocol> = Lazy::new(|| {
     Protocol::new(
         ACTOR_EXECUTOR_PROTOCOL_NAME,
         ACTOR_EXECUTOR_PROTOCOL_ID,
+        ACTOR_EXECUTOR_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
         ACTOR_EXECUTOR_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -36,6 +61,8 @@ mod test {
         let errp = actor_executor_protocol();
         assert_eq!(errp.id, ACTOR_EXECUTOR_PROTOCOL_ID);
         assert_eq!(errp.name, ACTOR_EXECUTOR_PROTOCOL_NAME);
+        assert_eq!(errp.version, ACTOR_EXECUTOR_PROTOCOL_VERSION);
         assert_eq!(errp.messages, *ACTOR_EXECUTOR_PROTOCOL_MESSAGES);
+        assert_eq!(errp.role, ProtocolRole::Requestee);
     }
 }
@@ -0,0 +1,435 @@
+//! Protocol for administering a running node from another process.
+//!
+//! An admin tool connects to a msg_router_dispatcher that has admin
+//! enabled and sends an AdminHello. The dispatcher answers with an
+//! AdminHelloAck containing a description of the node and the
+//! reply_instance_id of the connection, messages sent to it are written
+//! back over the connection. The tool then sends requests, with
+//! reply_instance_id as their src_id, to the ActorExecutors and ConMgr:
+//!
+//! * AdminListActorsReq, to an ActorExecutor, lists its actors
+//! * AdminRemoveActorReq, to an ActorExecutor, stops one of its actors
+//! * AdminRegistrationsReq, to the ConMgr, lists its registrations
+//! * MetricsReq, to an ActorExecutor, reports the status of the links
+//! * CmdDone, to an ActorExecutor, stops it
+//! * EchoReq, to any actor answering it, checks the actor is alive
+//! * EchoStart, to a Client, runs its echo loop with the partner and
+//!   is answered with EchoComplete
+use an_id::{anid, paste, AnId};
+use box_msg_any::BoxMsgAny;
+use cmd_done::CmdDone;
+use echo_requestee_protocol::{EchoReq, EchoRsp, ECHO_REQ_ID, ECHO_RSP_ID};
+use echo_start_complete_protocol::{EchoComplete, ECHO_COMPLETE_ID, ECHO_START_ID};
+use id_inventory::register_id;
+use metrics_protocol::{MetricsReq, MetricsRsp, METRICS_REQ_ID, METRICS_RSP_ID};
+use msg_derive::Message;
+use msg_header::{get_msg_id_str_from_buf, FromSerdeJsonBuf, MsgHeader};
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminExecutorInfo {
+    pub name: String,
+    pub instance_id: AnId,
+}
+
+/// What an admin tool needs to know to address a node
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminNodeInfo {
+    pub name: String,
+    pub con_mgr_instance_id: AnId,
+    pub executors: Vec<AdminExecutorInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminActorInfo {
+    pub name: String,
+    pub actor_id: AnId,
+    pub instance_id: AnId,
+    pub state: String, // The actors Debug output which includes its current state
+}
+
+/// An actor registered with the ConMgr, link_instance_id is Some for
+/// remote actors and identifies the link they're reachable through.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminRegistration {
+    pub name: String,
+    pub id: AnId,
+    pub instance_id: AnId,
+    pub protocol_set_name: String,
+    pub link_instance_id: Option<AnId>,
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "f5622c03-05c8-4edb-baca-0d7d8ca260a8", serde)]
+#[repr(C)]
+pub struct AdminHello {
+    pub header: MsgHeader,
+}
+
+impl AdminHello {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_HELLO_ID, *dst_id, *src_id),
+        }
+    }
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "82062cde-20ac-457c-a3f6-e7c5eaadd09c", serde)]
+#[repr(C)]
+pub struct AdminHelloAck {
+    pub header: MsgHeader,
+    pub reply_instance_id: AnId,
+    pub node: AdminNodeInfo,
+}
+
+impl AdminHelloAck {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        reply_instance_id: &AnId,
+        node: &AdminNodeInfo,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_HELLO_ACK_ID, *dst_id, *src_id),
+            reply_instance_id: *reply_instance_id,
+            node: node.clone(),
+        }
+    }
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "6e564a6b-a5de-4c6a-b7c8-3e523470dbae", serde)]
+#[repr(C)]
+pub struct AdminListActorsReq {
+    pub header: MsgHeader,
+}
+
+impl AdminListActorsReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_LIST_ACTORS_REQ_ID, *dst_id, *src_id),
+        }
+    }
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "83e538ec-fa9a-4b62-841f-b2dc95b40cdf", serde)]
+#[repr(C)]
+pub struct AdminListActorsRsp {
+    pub header: MsgHeader,
+    pub executor: AdminExecutorInfo,
+    pub actors: Vec<AdminActorInfo>,
+}
+
+impl AdminListActorsRsp {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        executor: &AdminExecutorInfo,
+        actors: &[AdminActorInfo],
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_LIST_ACTORS_RSP_ID, *dst_id, *src_id),
+            executor: executor.clone(),
+            actors: actors.to_vec(),
+        }
+    }
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "e790f65b-2fdd-4114-9e72-27d4e572c747", serde)]
+#[repr(C)]
+pub struct AdminRemoveActorReq {
+    pub header: MsgHeader,
+    pub instance_id: AnId,
+}
+
+impl AdminRemoveActorReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId, instance_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_REMOVE_ACTOR_REQ_ID, *dst_id, *src_id),
+            instance_id: *instance_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AdminRemoveActorStatus {
+    Success,
+    NotFound,
+    NotRemovable, // The ConMgr can't be removed
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "5edb973a-d676-43a4-8e92-3ac7064c141f", serde)]
+#[repr(C)]
+pub struct AdminRemoveActorRsp {
+    pub header: MsgHeader,
+    pub instance_id: AnId,
+    pub status: AdminRemoveActorStatus,
+}
+
+impl AdminRemoveActorRsp {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        instance_id: &AnId,
+        status: AdminRemoveActorStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_REMOVE_ACTOR_RSP_ID, *dst_id, *src_id),
+            instance_id: *instance_id,
+            status,
+        }
+    }
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "d4148fc8-9c1f-40e5-b0e2-0144a29879b4", serde)]
+#[repr(C)]
+pub struct AdminRegistrationsReq {
+    pub header: MsgHeader,
+}
+
+impl AdminRegistrationsReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_REGISTRATIONS_REQ_ID, *dst_id, *src_id),
+        }
+    }
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "3c45fed8-9adb-4c74-a1c5-c94421fb3a5b", serde)]
+#[repr(C)]
+pub struct AdminRegistrationsRsp {
+    pub header: MsgHeader,
+    pub registrations: Vec<AdminRegistration>,
+}
+
+impl AdminRegistrationsRsp {
+    pub fn new(dst_id: &AnId, src_id: &AnId, registrations: &[AdminRegistration]) -> Self {
+        Self {
+            header: MsgHeader::new(ADMIN_REGISTRATIONS_RSP_ID, *dst_id, *src_id),
+            registrations: registrations.to_vec(),
+        }
+    }
+}
+
+/// The requests a dispatcher with admin enabled deserializes
+pub fn admin_req_from_serde_json_bufs() -> Vec<(AnId, FromSerdeJsonBuf)> {
+    vec![
+        (
+            ADMIN_LIST_ACTORS_REQ_ID,
+            AdminListActorsReq::from_serde_json_buf,
+        ),
+        (
+            ADMIN_REMOVE_ACTOR_REQ_ID,
+            AdminRemoveActorReq::from_serde_json_buf,
+        ),
+        (
+            ADMIN_REGISTRATIONS_REQ_ID,
+            AdminRegistrationsReq::from_serde_json_buf,
+        ),
+        (METRICS_REQ_ID, MetricsReq::from_serde_json_buf),
+        (cmd_done::CMD_DONE_ID, CmdDone::from_serde_json_buf),
+        (ECHO_REQ_ID, EchoReq::from_serde_json_buf),
+    ]
+}
+
+/// Serialize a response sent to the reply_instance_id of an admin
+/// connection, None if it isn't one.
+pub fn admin_rsp_to_serde_json_buf(msg: BoxMsgAny) -> Option<Vec<u8>> {
+    let msg_id = *MsgHeader::get_msg_id_from_boxed_msg_any(&msg);
+    match msg_id {
+        ADMIN_LIST_ACTORS_RSP_ID => AdminListActorsRsp::to_serde_json_buf(msg),
+        ADMIN_REMOVE_ACTOR_RSP_ID => AdminRemoveActorRsp::to_serde_json_buf(msg),
+        ADMIN_REGISTRATIONS_RSP_ID => AdminRegistrationsRsp::to_serde_json_buf(msg),
+        METRICS_RSP_ID => MetricsRsp::to_serde_json_buf(msg),
+        ECHO_RSP_ID => EchoRsp::to_serde_json_buf(msg),
+        ECHO_COMPLETE_ID => EchoComplete::to_serde_json_buf(msg),
+        _ => None,
+    }
+}
+
+/// Deserialize a response, or the AdminHelloAck, read by an admin tool
+pub fn admin_rsp_from_serde_json_buf(buf: &[u8]) -> Option<BoxMsgAny> {
+    match get_msg_id_str_from_buf(buf) {
+        ADMIN_HELLO_ACK_ID_STR => AdminHelloAck::from_serde_json_buf(buf),
+        ADMIN_LIST_ACTORS_RSP_ID_STR => AdminListActorsRsp::from_serde_json_buf(buf),
+        ADMIN_REMOVE_ACTOR_RSP_ID_STR => AdminRemoveActorRsp::from_serde_json_buf(buf),
+        ADMIN_REGISTRATIONS_RSP_ID_STR => AdminRegistrationsRsp::from_serde_json_buf(buf),
+        metrics_protocol::METRICS_RSP_ID_STR => MetricsRsp::from_serde_json_buf(buf),
+        echo_requestee_protocol::ECHO_RSP_ID_STR => EchoRsp::from_serde_json_buf(buf),
+        echo_start_complete_protocol::ECHO_COMPLETE_ID_STR => {
+            EchoComplete::from_serde_json_buf(buf)
+        }
+        _ => None,
+    }
+}
+
+static ADMIN_EXECUTOR_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(ADMIN_LIST_ACTORS_REQ_ID),
+        ProtocolMsg::send(ADMIN_LIST_ACTORS_RSP_ID),
+        ProtocolMsg::receive(ADMIN_REMOVE_ACTOR_REQ_ID),
+        ProtocolMsg::send(ADMIN_REMOVE_ACTOR_RSP_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const ADMIN_EXECUTOR_PROTOCOL_ID: AnId = anid!("3063d089-e697-409a-b7e6-287ebfb77384");
+const ADMIN_EXECUTOR_PROTOCOL_NAME: &str = "admin_executor_protocol";
+const ADMIN_EXECUTOR_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    ADMIN_EXECUTOR_PROTOCOL_ID,
+    ADMIN_EXECUTOR_PROTOCOL_NAME
+);
+static ADMIN_EXECUTOR_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        ADMIN_EXECUTOR_PROTOCOL_NAME,
+        ADMIN_EXECUTOR_PROTOCOL_ID,
+        ADMIN_EXECUTOR_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        ADMIN_EXECUTOR_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol of an ActorExecutor answering admin requests
+pub fn admin_executor_protocol() -> &'static Protocol {
+    &ADMIN_EXECUTOR_PROTOCOL
+}
+
+static ADMIN_CON_MGR_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(ADMIN_REGISTRATIONS_REQ_ID),
+        ProtocolMsg::send(ADMIN_REGISTRATIONS_RSP_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const ADMIN_CON_MGR_PROTOCOL_ID: AnId = anid!("554875e1-ac64-47d8-8f54-80db0e20f3a1");
+const ADMIN_CON_MGR_PROTOCOL_NAME: &str = "admin_con_mgr_protocol";
+const ADMIN_CON_MGR_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    ADMIN_CON_MGR_PROTOCOL_ID,
+    ADMIN_CON_MGR_PROTOCOL_NAME
+);
+static ADMIN_CON_MGR_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        ADMIN_CON_MGR_PROTOCOL_NAME,
+        ADMIN_CON_MGR_PROTOCOL_ID,
+        ADMIN_CON_MGR_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        ADMIN_CON_MGR_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol of the ConMgr answering admin requests
+pub fn admin_con_mgr_protocol() -> &'static Protocol {
+    &ADMIN_CON_MGR_PROTOCOL
+}
+
+static ADMIN_REQUESTER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(ADMIN_HELLO_ID),
+        ProtocolMsg::receive(ADMIN_HELLO_ACK_ID),
+        ProtocolMsg::send(ADMIN_LIST_ACTORS_REQ_ID),
+        ProtocolMsg::receive(ADMIN_LIST_ACTORS_RSP_ID),
+        ProtocolMsg::send(ADMIN_REMOVE_ACTOR_REQ_ID),
+        ProtocolMsg::receive(ADMIN_REMOVE_ACTOR_RSP_ID),
+        ProtocolMsg::send(ADMIN_REGISTRATIONS_REQ_ID),
+        ProtocolMsg::receive(ADMIN_REGISTRATIONS_RSP_ID),
+        ProtocolMsg::send(METRICS_REQ_ID),
+        ProtocolMsg::receive(METRICS_RSP_ID),
+        ProtocolMsg::send(cmd_done::CMD_DONE_ID),
+        ProtocolMsg::send(ECHO_REQ_ID),
+        ProtocolMsg::receive(ECHO_RSP_ID),
+        ProtocolMsg::send(ECHO_START_ID),
+        ProtocolMsg::receive(ECHO_COMPLETE_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const ADMIN_REQUESTER_PROTOCOL_ID: AnId = anid!("e186c663-e7c1-4cc9-b385-3961de3182e4");
+const ADMIN_REQUESTER_PROTOCOL_NAME: &str = "admin_requester_protocol";
+const ADMIN_REQUESTER_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    ADMIN_REQUESTER_PROTOCOL_ID,
+    ADMIN_REQUESTER_PROTOCOL_NAME
+);
+static ADMIN_REQUESTER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        ADMIN_REQUESTER_PROTOCOL_NAME,
+        ADMIN_REQUESTER_PROTOCOL_ID,
+        ADMIN_REQUESTER_PROTOCOL_VERSION,
+        ProtocolRole::Requester,
+        ADMIN_REQUESTER_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol of an admin tool
+pub fn admin_requester_protocol() -> &'static Protocol {
+    &ADMIN_REQUESTER_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_admin_protocols() {
+        let aep = admin_executor_protocol();
+        assert_eq!(aep.id, ADMIN_EXECUTOR_PROTOCOL_ID);
+        assert_eq!(aep.role, ProtocolRole::Requestee);
+        let acp = admin_con_mgr_protocol();
+        assert_eq!(acp.id, ADMIN_CON_MGR_PROTOCOL_ID);
+        assert_eq!(acp.messages, *ADMIN_CON_MGR_PROTOCOL_MESSAGES);
+        let arp = admin_requester_protocol();
+        assert_eq!(arp.role, ProtocolRole::Requester);
+        assert_eq!(arp.messages, *ADMIN_REQUESTER_PROTOCOL_MESSAGES);
+    }
+
+    #[test]
+    fn test_admin_rsp_serde() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let executor = AdminExecutorInfo {
+            name: "ae1".to_owned(),
+            instance_id: AnId::new(),
+        };
+        let actor = AdminActorInfo {
+            name: "server1".to_owned(),
+            actor_id: AnId::new(),
+            instance_id: AnId::new(),
+            state: "server1 { state_machine: state0 }".to_owned(),
+        };
+        let msg = AdminListActorsRsp::new(&dst_id, &src_id, &executor, &[actor]);
+        let buf = admin_rsp_to_serde_json_buf(Box::new(msg.clone())).unwrap();
+        let msg_any = admin_rsp_from_serde_json_buf(&buf).unwrap();
+        assert_eq!(AdminListActorsRsp::from_box_msg_any(&msg_any), Some(&msg));
+
+        let msg = EchoComplete::new(&dst_id, &src_id);
+        let buf = admin_rsp_to_serde_json_buf(Box::new(msg.clone())).unwrap();
+        let msg_any = admin_rsp_from_serde_json_buf(&buf).unwrap();
+        assert_eq!(EchoComplete::from_box_msg_any(&msg_any), Some(&msg));
+
+        // Requests aren't responses
+        let req = Box::new(AdminListActorsReq::new(&dst_id, &src_id));
+        assert!(admin_rsp_to_serde_json_buf(req).is_none());
+    }
+}
@@ -1,19 +1,29 @@
 //! Protocol for actors that receives CmdDone from issuer.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use cmd_done::*;
 
 const CMD_DONE_ISSUEE_PROTOCOL_ID: AnId = anid!("3fc97255-f992-48ca-ac8f-e910e63d6f6a");
 const CMD_DONE_ISSUEE_PROTOCOL_NAME: &str = "cmd_done_issuee_protocol";
-static CMD_DONE_ISSUEE_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> = Lazy::new(|| vec![CMD_DONE_ID]);
+const CMD_DONE_ISSUEE_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    CMD_DONE_ISSUEE_PROTOCOL_ID,
+    CMD_DONE_ISSUEE_PROTOCOL_NAME
+);
+static CMD_DONE_ISSUEE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> =
+    Lazy::new(|| vec![ProtocolMsg::receive(CMD_DONE_ID)]);
 
 static CMD_DONE_ISSUEE_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
     Protocol::new(
         CMD_DONE_ISSUEE_PROTOCOL_NAME,
         CMD_DONE_ISSUEE_PROTOCOL_ID,
+        CMD_DONE_ISSUEE_PROTOCOL_VERSION,
+        ProtocolRole::Issuee,
         CMD_DONE_ISSUEE_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -31,6 +41,8 @@ mod test {
         let p = cmd_done_issuee_protocol();
         assert_eq!(p.id, CMD_DONE_ISSUEE_PROTOCOL_ID);
         assert_eq!(p.name, CMD_DONE_ISSUEE_PROTOCOL_NAME);
+        assert_eq!(p.version, CMD_DONE_ISSUEE_PROTOCOL_VERSION);
         assert_eq!(p.messages, *CMD_DONE_ISSUEE_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Issuee);
     }
 }
@@ -1,19 +1,34 @@
 //! Protocol for actors that receives CmdInit from issuer.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use cmd_init::*;
+pub use cmd_ready::*;
 
 const CMD_INIT_ISSUEE_PROTOCOL_ID: AnId = anid!("151ae493-3b66-433d-8797-68d1029ec3e9");
 const CMD_INIT_ISSUEE_PROTOCOL_NAME: &str = "cmd_init_issuee_protocol";
-static CMD_INIT_ISSUEE_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> = Lazy::new(|| vec![CMD_INIT_ID]);
+const CMD_INIT_ISSUEE_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    CMD_INIT_ISSUEE_PROTOCOL_ID,
+    CMD_INIT_ISSUEE_PROTOCOL_NAME
+);
+static CMD_INIT_ISSUEE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(CMD_INIT_ID),
+        ProtocolMsg::send(CMD_READY_ID),
+    ]
+});
 
 static CMD_INIT_ISSUEE_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
     Protocol::new(
         CMD_INIT_ISSUEE_PROTOCOL_NAME,
         CMD_INIT_ISSUEE_PROTOCOL_ID,
+        CMD_INIT_ISSUEE_PROTOCOL_VERSION,
+        ProtocolRole::Issuee,
         CMD_INIT_ISSUEE_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -31,6 +46,8 @@ mod test {
         let p = cmd_init_issuee_protocol();
         assert_eq!(p.id, CMD_INIT_ISSUEE_PROTOCOL_ID);
         assert_eq!(p.name, CMD_INIT_ISSUEE_PROTOCOL_NAME);
+        assert_eq!(p.version, CMD_INIT_ISSUEE_PROTOCOL_VERSION);
         assert_eq!(p.messages, *CMD_INIT_ISSUEE_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Issuee);
     }
 }
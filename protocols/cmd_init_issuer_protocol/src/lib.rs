@@ -2,20 +2,35 @@
 //! Protocol for initialization
 //! and send responses.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use cmd_init::*;
+pub use cmd_ready::*;
 
 const CMD_INIT_ISSUER_PROTOCOL_ID: AnId = anid!("e5a5c3a5-02c1-484b-a72f-4f0e599aed6f");
 const CMD_INIT_ISSUER_PROTOCOL_NAME: &str = "cmd_init_issuer_protocol";
-static CMD_INIT_ISSUER_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> = Lazy::new(|| vec![CMD_INIT_ID]);
+const CMD_INIT_ISSUER_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    CMD_INIT_ISSUER_PROTOCOL_ID,
+    CMD_INIT_ISSUER_PROTOCOL_NAME
+);
+static CMD_INIT_ISSUER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(CMD_INIT_ID),
+        ProtocolMsg::receive(CMD_READY_ID),
+    ]
+});
 
 static CMD_INIT_ISSUER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
     Protocol::new(
         CMD_INIT_ISSUER_PROTOCOL_NAME,
         CMD_INIT_ISSUER_PROTOCOL_ID,
+        CMD_INIT_ISSUER_PROTOCOL_VERSION,
+        ProtocolRole::Issuer,
         CMD_INIT_ISSUER_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -33,6 +48,8 @@ mod test {
         let p = cmd_init_issuer_protocol();
         assert_eq!(p.id, CMD_INIT_ISSUER_PROTOCOL_ID);
         assert_eq!(p.name, CMD_INIT_ISSUER_PROTOCOL_NAME);
+        assert_eq!(p.version, CMD_INIT_ISSUER_PROTOCOL_VERSION);
         assert_eq!(p.messages, *CMD_INIT_ISSUER_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Issuer);
     }
 }
@@ -0,0 +1,54 @@
+//! Protocol for actors that receive CmdShutdown from issuer and
+//! respond with CmdShutdownAck.
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+
+// Re-exports
+pub use cmd_shutdown::*;
+pub use cmd_shutdown_ack::*;
+
+const CMD_SHUTDOWN_ISSUEE_PROTOCOL_ID: AnId = anid!("b92ffe05-185d-4e6e-9158-8b8b2b5bed9e");
+const CMD_SHUTDOWN_ISSUEE_PROTOCOL_NAME: &str = "cmd_shutdown_issuee_protocol";
+const CMD_SHUTDOWN_ISSUEE_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    CMD_SHUTDOWN_ISSUEE_PROTOCOL_ID,
+    CMD_SHUTDOWN_ISSUEE_PROTOCOL_NAME
+);
+static CMD_SHUTDOWN_ISSUEE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(CMD_SHUTDOWN_ID),
+        ProtocolMsg::send(CMD_SHUTDOWN_ACK_ID),
+    ]
+});
+
+static CMD_SHUTDOWN_ISSUEE_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        CMD_SHUTDOWN_ISSUEE_PROTOCOL_NAME,
+        CMD_SHUTDOWN_ISSUEE_PROTOCOL_ID,
+        CMD_SHUTDOWN_ISSUEE_PROTOCOL_VERSION,
+        ProtocolRole::Issuee,
+        CMD_SHUTDOWN_ISSUEE_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+pub fn cmd_shutdown_issuee_protocol() -> &'static Protocol {
+    &CMD_SHUTDOWN_ISSUEE_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cmd_shutdown_issuee_protocol() {
+        let p = cmd_shutdown_issuee_protocol();
+        assert_eq!(p.id, CMD_SHUTDOWN_ISSUEE_PROTOCOL_ID);
+        assert_eq!(p.name, CMD_SHUTDOWN_ISSUEE_PROTOCOL_NAME);
+        assert_eq!(p.version, CMD_SHUTDOWN_ISSUEE_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CMD_SHUTDOWN_ISSUEE_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Issuee);
+    }
+}
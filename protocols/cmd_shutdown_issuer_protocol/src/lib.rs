@@ -0,0 +1,54 @@
+//! Protocol for entities that send CmdShutdown to issuee and
+//! receive CmdShutdownAck.
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+
+// Re-exports
+pub use cmd_shutdown::*;
+pub use cmd_shutdown_ack::*;
+
+const CMD_SHUTDOWN_ISSUER_PROTOCOL_ID: AnId = anid!("ea800811-9687-4add-91c7-e505a619c895");
+const CMD_SHUTDOWN_ISSUER_PROTOCOL_NAME: &str = "cmd_shutdown_issuer_protocol";
+const CMD_SHUTDOWN_ISSUER_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    CMD_SHUTDOWN_ISSUER_PROTOCOL_ID,
+    CMD_SHUTDOWN_ISSUER_PROTOCOL_NAME
+);
+static CMD_SHUTDOWN_ISSUER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(CMD_SHUTDOWN_ID),
+        ProtocolMsg::receive(CMD_SHUTDOWN_ACK_ID),
+    ]
+});
+
+static CMD_SHUTDOWN_ISSUER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        CMD_SHUTDOWN_ISSUER_PROTOCOL_NAME,
+        CMD_SHUTDOWN_ISSUER_PROTOCOL_ID,
+        CMD_SHUTDOWN_ISSUER_PROTOCOL_VERSION,
+        ProtocolRole::Issuer,
+        CMD_SHUTDOWN_ISSUER_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+pub fn cmd_shutdown_issuer_protocol() -> &'static Protocol {
+    &CMD_SHUTDOWN_ISSUER_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cmd_shutdown_issuer_protocol() {
+        let p = cmd_shutdown_issuer_protocol();
+        assert_eq!(p.id, CMD_SHUTDOWN_ISSUER_PROTOCOL_ID);
+        assert_eq!(p.name, CMD_SHUTDOWN_ISSUER_PROTOCOL_NAME);
+        assert_eq!(p.version, CMD_SHUTDOWN_ISSUER_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CMD_SHUTDOWN_ISSUER_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Issuer);
+    }
+}
@@ -0,0 +1,451 @@
+//! Protocols for federating the ConMgrs of several processes.
+//!
+//! A ConMgr is told about a peer ConMgr in another process with a
+//! ConMgrAddPeerReq, it has the instance_id of the peer ConMgr and the
+//! sender of a msg_router_forwarder linked to the peers process. The
+//! ConMgr then sends the peer a ConMgrFederationSnapshot of the actors
+//! registered with it and a ConMgrFederationDelta whenever an actor is
+//! registered or unregistered. The peer marks the actors as remote,
+//! reachable through that link, so they're returned by its queries and
+//! may be connected to.
+//!
+//! Snapshots and deltas from a ConMgr that isn't a peer are ignored, so
+//! each side must peer with the other. As the peer may have ignored our
+//! snapshot, peering also sends a ConMgrFederationSnapshotReq which a
+//! peer answers with its snapshot. A ConMgrRemovePeerReq, sent when the
+//! link is lost, forgets the peer and its actors.
+//!
+//! The snapshots, deltas and requests are sent over the link so the
+//! forwarder must serialize them and the dispatcher in the peers process
+//! deserialize them, the protocols are added to both as link protocols.
+use actor_channel::ActorSender;
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+use protocol_set::ProtocolSet;
+use serde::{Deserialize, Serialize};
+
+/// An actor registered with a ConMgr
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConMgrFederationEntry {
+    pub name: String,
+    pub id: AnId,
+    pub instance_id: AnId,
+    pub protocol_set: ProtocolSet,
+}
+
+impl ConMgrFederationEntry {
+    pub fn new(name: &str, id: &AnId, instance_id: &AnId, protocol_set: &ProtocolSet) -> Self {
+        Self {
+            name: name.to_owned(),
+            id: *id,
+            instance_id: *instance_id,
+            protocol_set: protocol_set.clone(),
+        }
+    }
+
+    /// True if the entry matches all of the criteria that are Some
+    pub fn matches(
+        &self,
+        name: Option<&str>,
+        id: Option<&AnId>,
+        protocol_id: Option<&AnId>,
+        protocol_set_id: Option<&AnId>,
+    ) -> bool {
+        name.is_none_or(|name| self.name == name)
+            && id.is_none_or(|id| self.id == *id)
+            && protocol_id
+                .is_none_or(|protocol_id| self.protocol_set.protocols_map.contains_key(protocol_id))
+            && protocol_set_id
+                .is_none_or(|protocol_set_id| self.protocol_set.id == *protocol_set_id)
+    }
+}
+
+/// All of the actors registered with the sending ConMgr, replaces
+/// whatever the receiver knew about them.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "82379a2b-9ed9-47b9-b90a-9850a374b05c", serde)]
+#[repr(C)]
+pub struct ConMgrFederationSnapshot {
+    pub header: MsgHeader,
+    pub entries: Vec<ConMgrFederationEntry>,
+}
+
+impl ConMgrFederationSnapshot {
+    pub fn new(dst_id: &AnId, src_id: &AnId, entries: &[ConMgrFederationEntry]) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_FEDERATION_SNAPSHOT_ID, *dst_id, *src_id),
+            entries: entries.to_vec(),
+        }
+    }
+}
+
+/// Actors registered with, or unregistered from, the sending ConMgr
+/// since its last snapshot or delta.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "66583966-9775-4d42-bed3-fde53280a17e", serde)]
+#[repr(C)]
+pub struct ConMgrFederationDelta {
+    pub header: MsgHeader,
+    pub added: Vec<ConMgrFederationEntry>,
+    pub removed: Vec<AnId>, // instance_ids
+}
+
+impl ConMgrFederationDelta {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        added: &[ConMgrFederationEntry],
+        removed: &[AnId],
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_FEDERATION_DELTA_ID, *dst_id, *src_id),
+            added: added.to_vec(),
+            removed: removed.to_vec(),
+        }
+    }
+}
+
+/// Ask the receiving ConMgr for a snapshot of its actors
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Message)]
+#[msg(id = "b90fcc94-b70a-4e28-a2b2-d2f3fcefd6d1", serde)]
+#[repr(C)]
+pub struct ConMgrFederationSnapshotReq {
+    pub header: MsgHeader,
+}
+
+impl ConMgrFederationSnapshotReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_FEDERATION_SNAPSHOT_REQ_ID, *dst_id, *src_id),
+        }
+    }
+}
+
+/// Peer with the ConMgr at peer_con_mgr_instance_id, messages for it
+/// and the actors registered with it are sent with sender, the sender
+/// of the msg_router_forwarder whose instance_id is link_instance_id.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "1f8fabdc-eccd-4e2d-95b8-50240b5aa076")]
+#[repr(C)]
+pub struct ConMgrAddPeerReq {
+    pub header: MsgHeader,
+    pub peer_con_mgr_instance_id: AnId,
+    pub link_instance_id: AnId,
+    pub sender: ActorSender,
+}
+
+impl ConMgrAddPeerReq {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        peer_con_mgr_instance_id: &AnId,
+        link_instance_id: &AnId,
+        sender: &ActorSender,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_ADD_PEER_REQ_ID, *dst_id, *src_id),
+            peer_con_mgr_instance_id: *peer_con_mgr_instance_id,
+            link_instance_id: *link_instance_id,
+            sender: sender.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConMgrAddPeerStatus {
+    Success,
+    PeerAlreadyAdded,
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "49b1dc5a-9bfd-49e7-af1d-8d04ebdfa763")]
+#[repr(C)]
+pub struct ConMgrAddPeerRsp {
+    pub header: MsgHeader,
+    pub peer_con_mgr_instance_id: AnId,
+    pub status: ConMgrAddPeerStatus,
+}
+
+impl ConMgrAddPeerRsp {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        peer_con_mgr_instance_id: &AnId,
+        status: ConMgrAddPeerStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_ADD_PEER_RSP_ID, *dst_id, *src_id),
+            peer_con_mgr_instance_id: *peer_con_mgr_instance_id,
+            status,
+        }
+    }
+}
+
+/// Stop peering with the ConMgr at peer_con_mgr_instance_id, the actors
+/// registered with it are no longer reachable.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "54af4fae-1157-4c82-8649-66f6cd27e189")]
+#[repr(C)]
+pub struct ConMgrRemovePeerReq {
+    pub header: MsgHeader,
+    pub peer_con_mgr_instance_id: AnId,
+}
+
+impl ConMgrRemovePeerReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId, peer_con_mgr_instance_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_REMOVE_PEER_REQ_ID, *dst_id, *src_id),
+            peer_con_mgr_instance_id: *peer_con_mgr_instance_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConMgrRemovePeerStatus {
+    Success,
+    PeerNotFound,
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "53975763-2e5f-48cd-9a26-f458218edd09")]
+#[repr(C)]
+pub struct ConMgrRemovePeerRsp {
+    pub header: MsgHeader,
+    pub peer_con_mgr_instance_id: AnId,
+    pub status: ConMgrRemovePeerStatus,
+}
+
+impl ConMgrRemovePeerRsp {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        peer_con_mgr_instance_id: &AnId,
+        status: ConMgrRemovePeerStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_REMOVE_PEER_RSP_ID, *dst_id, *src_id),
+            peer_con_mgr_instance_id: *peer_con_mgr_instance_id,
+            status,
+        }
+    }
+}
+
+static CON_MGR_FEDERATION_ISSUER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(CON_MGR_FEDERATION_SNAPSHOT_ID),
+        ProtocolMsg::send(CON_MGR_FEDERATION_DELTA_ID),
+        ProtocolMsg::receive(CON_MGR_FEDERATION_SNAPSHOT_REQ_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const CON_MGR_FEDERATION_ISSUER_PROTOCOL_ID: AnId = anid!("02be53ab-9f7a-4c2e-a4c9-4dc9177a2f37");
+const CON_MGR_FEDERATION_ISSUER_PROTOCOL_NAME: &str = "con_mgr_federation_issuer_protocol";
+const CON_MGR_FEDERATION_ISSUER_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    CON_MGR_FEDERATION_ISSUER_PROTOCOL_ID,
+    CON_MGR_FEDERATION_ISSUER_PROTOCOL_NAME
+);
+static CON_MGR_FEDERATION_ISSUER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        CON_MGR_FEDERATION_ISSUER_PROTOCOL_NAME,
+        CON_MGR_FEDERATION_ISSUER_PROTOCOL_ID,
+        CON_MGR_FEDERATION_ISSUER_PROTOCOL_VERSION,
+        ProtocolRole::Issuer,
+        CON_MGR_FEDERATION_ISSUER_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol of a ConMgr sending its registrations to its peers, also
+/// the protocol a msg_router_forwarder linking two ConMgrs forwards.
+pub fn con_mgr_federation_issuer_protocol() -> &'static Protocol {
+    &CON_MGR_FEDERATION_ISSUER_PROTOCOL
+}
+
+static CON_MGR_FEDERATION_ISSUEE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(CON_MGR_FEDERATION_SNAPSHOT_ID),
+        ProtocolMsg::receive(CON_MGR_FEDERATION_DELTA_ID),
+        ProtocolMsg::send(CON_MGR_FEDERATION_SNAPSHOT_REQ_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const CON_MGR_FEDERATION_ISSUEE_PROTOCOL_ID: AnId = anid!("3f184e83-7efe-46ef-82e9-004fc9801918");
+const CON_MGR_FEDERATION_ISSUEE_PROTOCOL_NAME: &str = "con_mgr_federation_issuee_protocol";
+const CON_MGR_FEDERATION_ISSUEE_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    CON_MGR_FEDERATION_ISSUEE_PROTOCOL_ID,
+    CON_MGR_FEDERATION_ISSUEE_PROTOCOL_NAME
+);
+static CON_MGR_FEDERATION_ISSUEE_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        CON_MGR_FEDERATION_ISSUEE_PROTOCOL_NAME,
+        CON_MGR_FEDERATION_ISSUEE_PROTOCOL_ID,
+        CON_MGR_FEDERATION_ISSUEE_PROTOCOL_VERSION,
+        ProtocolRole::Issuee,
+        CON_MGR_FEDERATION_ISSUEE_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol of a ConMgr receiving the registrations of its peers, also
+/// the protocol a msg_router_dispatcher linking two ConMgrs dispatches.
+pub fn con_mgr_federation_issuee_protocol() -> &'static Protocol {
+    &CON_MGR_FEDERATION_ISSUEE_PROTOCOL
+}
+
+static CON_MGR_PEER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(CON_MGR_ADD_PEER_REQ_ID),
+        ProtocolMsg::send(CON_MGR_ADD_PEER_RSP_ID),
+        ProtocolMsg::receive(CON_MGR_REMOVE_PEER_REQ_ID),
+        ProtocolMsg::send(CON_MGR_REMOVE_PEER_RSP_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const CON_MGR_PEER_PROTOCOL_ID: AnId = anid!("fb461871-7e2c-4429-98f9-2af233b64a42");
+const CON_MGR_PEER_PROTOCOL_NAME: &str = "con_mgr_peer_protocol";
+const CON_MGR_PEER_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    CON_MGR_PEER_PROTOCOL_ID,
+    CON_MGR_PEER_PROTOCOL_NAME
+);
+static CON_MGR_PEER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        CON_MGR_PEER_PROTOCOL_NAME,
+        CON_MGR_PEER_PROTOCOL_ID,
+        CON_MGR_PEER_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        CON_MGR_PEER_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol implemented by ConMgr for adding and removing peers
+pub fn con_mgr_peer_protocol() -> &'static Protocol {
+    &CON_MGR_PEER_PROTOCOL
+}
+
+static CON_MGR_PEER_REQUESTER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(CON_MGR_ADD_PEER_REQ_ID),
+        ProtocolMsg::receive(CON_MGR_ADD_PEER_RSP_ID),
+        ProtocolMsg::send(CON_MGR_REMOVE_PEER_REQ_ID),
+        ProtocolMsg::receive(CON_MGR_REMOVE_PEER_RSP_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const CON_MGR_PEER_REQUESTER_PROTOCOL_ID: AnId = anid!("1761922a-73d2-40e4-9c0e-919553e79287");
+const CON_MGR_PEER_REQUESTER_PROTOCOL_NAME: &str = "con_mgr_peer_requester_protocol";
+const CON_MGR_PEER_REQUESTER_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    CON_MGR_PEER_REQUESTER_PROTOCOL_ID,
+    CON_MGR_PEER_REQUESTER_PROTOCOL_NAME
+);
+static CON_MGR_PEER_REQUESTER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        CON_MGR_PEER_REQUESTER_PROTOCOL_NAME,
+        CON_MGR_PEER_REQUESTER_PROTOCOL_ID,
+        CON_MGR_PEER_REQUESTER_PROTOCOL_VERSION,
+        ProtocolRole::Requester,
+        CON_MGR_PEER_REQUESTER_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol implemented by entities that add and remove the peers of a
+/// ConMgr
+pub fn con_mgr_peer_requester_protocol() -> &'static Protocol {
+    &CON_MGR_PEER_REQUESTER_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_con_mgr_federation_protocols() {
+        let p = con_mgr_federation_issuer_protocol();
+        assert_eq!(p.id, CON_MGR_FEDERATION_ISSUER_PROTOCOL_ID);
+        assert_eq!(p.name, CON_MGR_FEDERATION_ISSUER_PROTOCOL_NAME);
+        assert_eq!(p.version, CON_MGR_FEDERATION_ISSUER_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CON_MGR_FEDERATION_ISSUER_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Issuer);
+
+        let p = con_mgr_federation_issuee_protocol();
+        assert_eq!(p.id, CON_MGR_FEDERATION_ISSUEE_PROTOCOL_ID);
+        assert_eq!(p.name, CON_MGR_FEDERATION_ISSUEE_PROTOCOL_NAME);
+        assert_eq!(p.version, CON_MGR_FEDERATION_ISSUEE_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CON_MGR_FEDERATION_ISSUEE_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Issuee);
+        assert!(p.is_complement_of(con_mgr_federation_issuer_protocol()));
+    }
+
+    #[test]
+    fn test_con_mgr_peer_protocols() {
+        let p = con_mgr_peer_protocol();
+        assert_eq!(p.id, CON_MGR_PEER_PROTOCOL_ID);
+        assert_eq!(p.name, CON_MGR_PEER_PROTOCOL_NAME);
+        assert_eq!(p.version, CON_MGR_PEER_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CON_MGR_PEER_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Requestee);
+
+        let p = con_mgr_peer_requester_protocol();
+        assert_eq!(p.id, CON_MGR_PEER_REQUESTER_PROTOCOL_ID);
+        assert_eq!(p.name, CON_MGR_PEER_REQUESTER_PROTOCOL_NAME);
+        assert_eq!(p.version, CON_MGR_PEER_REQUESTER_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CON_MGR_PEER_REQUESTER_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Requester);
+        assert!(p.is_complement_of(con_mgr_peer_protocol()));
+    }
+
+    #[test]
+    fn test_con_mgr_federation_snapshot_serde() {
+        let entry = ConMgrFederationEntry::new(
+            "server",
+            &AnId::new(),
+            &AnId::new(),
+            &ProtocolSet::new("server_ps", AnId::new(), HashMap::new()),
+        );
+        assert!(entry.matches(Some("server"), Some(&entry.id), None, None));
+        assert!(!entry.matches(Some("client"), None, None, None));
+        assert!(!entry.matches(None, None, Some(&AnId::new()), None));
+
+        let msg = Box::new(ConMgrFederationSnapshot::new(
+            &AnId::new(),
+            &AnId::new(),
+            &[entry],
+        ));
+        let buf = ConMgrFederationSnapshot::to_serde_json_buf(msg.clone()).unwrap();
+        let msg_any = ConMgrFederationSnapshot::from_serde_json_buf(&buf).unwrap();
+        let msg_rt = ConMgrFederationSnapshot::from_box_msg_any(&msg_any).unwrap();
+        assert_eq!(msg_rt, &*msg);
+
+        let msg = Box::new(ConMgrFederationSnapshotReq::new(&AnId::new(), &AnId::new()));
+        let buf = ConMgrFederationSnapshotReq::to_serde_json_buf(msg.clone()).unwrap();
+        let msg_any = ConMgrFederationSnapshotReq::from_serde_json_buf(&buf).unwrap();
+        let msg_rt = ConMgrFederationSnapshotReq::from_box_msg_any(&msg_any).unwrap();
+        assert_eq!(msg_rt, &*msg);
+    }
+}
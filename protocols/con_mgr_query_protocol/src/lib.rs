@@ -5,19 +5,29 @@
 //! for applicable actors using "name", "id", "protoocol"
 //! and/or "protocol_set" and then use ConMgrConnectReq with an instance_id
 //! retunred by ConMgrQueryRsp.
-use an_id::{anid, AnId};
+//!
+//! ConMgr only lets the actors connect if the requesting actors protocol
+//! is in its ProtocolSet and the ProtocolSet of the other actor has the
+//! complementary protocol.
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro, paste};
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+use serde::{Deserialize, Serialize};
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(ConMgrQueryReq "e5922aa5-9731-4379-813b-8cf5d0319d3d" {
-    name: Option<String>,
-    id: Option<AnId>,
-    protocol_id: Option<AnId>,
-    protocol_set_id: Option<AnId>
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "e5922aa5-9731-4379-813b-8cf5d0319d3d")]
+#[repr(C)]
+pub struct ConMgrQueryReq {
+    pub header: MsgHeader,
+    pub name: Option<String>,
+    pub id: Option<AnId>,
+    pub protocol_id: Option<AnId>,
+    pub protocol_set_id: Option<AnId>,
+}
 
 impl ConMgrQueryReq {
     pub fn new(
@@ -39,9 +49,13 @@ impl ConMgrQueryReq {
 }
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(ConMgrQueryRsp "162306ca-10b5-4bc9-9537-f7d8c53c7d0a" {
-    instance_ids: Vec<AnId>
-});
+#[derive(Debug, Clone, Message)]
+#[msg(id = "162306ca-10b5-4bc9-9537-f7d8c53c7d0a")]
+#[repr(C)]
+pub struct ConMgrQueryRsp {
+    pub header: MsgHeader,
+    pub instance_ids: Vec<AnId>,
+}
 
 impl ConMgrQueryRsp {
     pub fn new(dst_id: &AnId, src_id: &AnId, instance_ids: &[AnId]) -> Self {
@@ -52,16 +66,93 @@ impl ConMgrQueryRsp {
     }
 }
 
-static CON_MGR_QUERY_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
-    Lazy::new(|| vec![CON_MGR_QUERY_REQ_ID, CON_MGR_QUERY_RSP_ID]);
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "a93ff65b-2995-4595-a796-d7455f6f6611")]
+#[repr(C)]
+pub struct ConMgrConnectReq {
+    pub header: MsgHeader,
+    pub instance_id: AnId,
+    pub protocol_id: AnId,
+}
+
+impl ConMgrConnectReq {
+    /// Request to connect the sender, using its protocol_id, to instance_id
+    pub fn new(dst_id: &AnId, src_id: &AnId, instance_id: &AnId, protocol_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_CONNECT_REQ_ID, *dst_id, *src_id),
+            instance_id: *instance_id,
+            protocol_id: *protocol_id,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ConMgrConnectStatus {
+    Success,
+    ActorNotRegistered,
+    PeerNotRegistered,
+    ProtocolNotInProtocolSet,
+    PeerHasNoComplementaryProtocol,
+    PeerUnreachable, // A remote actor whose ConMgr isn't a peer
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "4aadfc85-1e8e-4c13-89dd-e506afad7926")]
+#[repr(C)]
+pub struct ConMgrConnectRsp {
+    pub header: MsgHeader,
+    pub instance_id: AnId,
+    pub protocol_id: AnId,
+    pub peer_protocol_id: Option<AnId>,
+    pub status: ConMgrConnectStatus,
+}
+
+impl ConMgrConnectRsp {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        instance_id: &AnId,
+        protocol_id: &AnId,
+        peer_protocol_id: Option<AnId>,
+        status: ConMgrConnectStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_CONNECT_RSP_ID, *dst_id, *src_id),
+            instance_id: *instance_id,
+            protocol_id: *protocol_id,
+            peer_protocol_id,
+            status,
+        }
+    }
+}
+
+static CON_MGR_QUERY_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(CON_MGR_QUERY_REQ_ID),
+        ProtocolMsg::send(CON_MGR_QUERY_RSP_ID),
+        ProtocolMsg::receive(CON_MGR_CONNECT_REQ_ID),
+        ProtocolMsg::send(CON_MGR_CONNECT_RSP_ID),
+    ]
+});
 
 // From: https://www.uuidgenerator.net/version4
 const CON_MGR_QUERY_PROTOCOL_ID: AnId = anid!("0b22d500-f51f-421f-bf59-2b553f47c459");
 const CON_MGR_QUERY_PROTOCOL_NAME: &str = "con_mgr_query_protocol";
+const CON_MGR_QUERY_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    CON_MGR_QUERY_PROTOCOL_ID,
+    CON_MGR_QUERY_PROTOCOL_NAME
+);
 static CON_MGR_QUERY_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
     Protocol::new(
         CON_MGR_QUERY_PROTOCOL_NAME,
         CON_MGR_QUERY_PROTOCOL_ID,
+        CON_MGR_QUERY_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
         CON_MGR_QUERY_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -70,6 +161,39 @@ pub fn con_mgr_query_protocol() -> &'static Protocol {
     &CON_MGR_QUERY_PROTOCOL
 }
 
+static CON_MGR_QUERY_REQUESTER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(CON_MGR_QUERY_REQ_ID),
+        ProtocolMsg::receive(CON_MGR_QUERY_RSP_ID),
+        ProtocolMsg::send(CON_MGR_CONNECT_REQ_ID),
+        ProtocolMsg::receive(CON_MGR_CONNECT_RSP_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const CON_MGR_QUERY_REQUESTER_PROTOCOL_ID: AnId = anid!("51984109-44d9-4850-b963-f928c8f3b88b");
+const CON_MGR_QUERY_REQUESTER_PROTOCOL_NAME: &str = "con_mgr_query_requester_protocol";
+const CON_MGR_QUERY_REQUESTER_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    CON_MGR_QUERY_REQUESTER_PROTOCOL_ID,
+    CON_MGR_QUERY_REQUESTER_PROTOCOL_NAME
+);
+static CON_MGR_QUERY_REQUESTER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        CON_MGR_QUERY_REQUESTER_PROTOCOL_NAME,
+        CON_MGR_QUERY_REQUESTER_PROTOCOL_ID,
+        CON_MGR_QUERY_REQUESTER_PROTOCOL_VERSION,
+        ProtocolRole::Requester,
+        CON_MGR_QUERY_REQUESTER_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol implemented by actors that query and connect via ConMgr
+pub fn con_mgr_query_requester_protocol() -> &'static Protocol {
+    &CON_MGR_QUERY_REQUESTER_PROTOCOL
+}
+
 #[cfg(test)]
 #[cfg(test)]
 mod test {
@@ -80,6 +204,19 @@ mod test {
         let errp = con_mgr_query_protocol();
         assert_eq!(errp.id, CON_MGR_QUERY_PROTOCOL_ID);
         assert_eq!(errp.name, CON_MGR_QUERY_PROTOCOL_NAME);
+        assert_eq!(errp.version, CON_MGR_QUERY_PROTOCOL_VERSION);
         assert_eq!(errp.messages, *CON_MGR_QUERY_PROTOCOL_MESSAGES);
+        assert_eq!(errp.role, ProtocolRole::Requestee);
+    }
+
+    #[test]
+    fn test_con_mgr_query_requester_protocol() {
+        let p = con_mgr_query_requester_protocol();
+        assert_eq!(p.id, CON_MGR_QUERY_REQUESTER_PROTOCOL_ID);
+        assert_eq!(p.name, CON_MGR_QUERY_REQUESTER_PROTOCOL_NAME);
+        assert_eq!(p.version, CON_MGR_QUERY_REQUESTER_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CON_MGR_QUERY_REQUESTER_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Requester);
+        assert!(p.is_complement_of(con_mgr_query_protocol()));
     }
 }
@@ -10,24 +10,29 @@
 //! The CON_MGR_REGISTEE_ACTOR_PROTOCOL is implemented by
 //! the actors that want to register with the connection manager
 //! they send CON_MGR_REGISTER_ACTOR_RSP_ID messages.
-use an_id::{anid, AnId};
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use msg_derive::Message;
 use msg_header::MsgHeader;
-use msg_local_macro::{msg_local_macro, paste};
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 use protocol_set::ProtocolSet;
 use serde::{Deserialize, Serialize};
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(ConMgrRegisterActorReq "b0e83356-fd22-4389-9f2e-586be8ec9719" {
-    name: String,
-    id: AnId,
-    instance_id: AnId,
+#[derive(Debug, Clone, Message)]
+#[msg(id = "b0e83356-fd22-4389-9f2e-586be8ec9719")]
+#[repr(C)]
+pub struct ConMgrRegisterActorReq {
+    pub header: MsgHeader,
+    pub name: String,
+    pub id: AnId,
+    pub instance_id: AnId,
     //protocol_set_id: AnId,
     // As ProtocolSet is immutable, see is #13
     //   https://github.com/winksaville/exper_inter_process_channel/issues/13
-    protocol_set: ProtocolSet // TODO maybe make Option<ProtocolSet>
-});
+    pub protocol_set: ProtocolSet, // TODO maybe make Option<ProtocolSet>
+}
 
 impl ConMgrRegisterActorReq {
     pub fn new(
@@ -56,10 +61,14 @@ pub enum ConMgrRegisterActorStatus {
 }
 
 // From: https://www.uuidgenerator.net/version4
-msg_local_macro!(ConMgrRegisterActorRsp "db6a401d-cd0a-4585-8ac4-c13ae1ab7a39" {
+#[derive(Debug, Clone, Message)]
+#[msg(id = "db6a401d-cd0a-4585-8ac4-c13ae1ab7a39")]
+#[repr(C)]
+pub struct ConMgrRegisterActorRsp {
+    pub header: MsgHeader,
     // Should we add a transaction id here and in ConMgrReqActor?
-    status: ConMgrRegisterActorStatus
-});
+    pub status: ConMgrRegisterActorStatus,
+}
 
 impl ConMgrRegisterActorRsp {
     pub fn new(dst_id: &AnId, src_id: &AnId, status: ConMgrRegisterActorStatus) -> Self {
@@ -70,16 +79,80 @@ impl ConMgrRegisterActorRsp {
     }
 }
 
-static CON_MGR_REGISTER_ACTOR_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
-    Lazy::new(|| vec![CON_MGR_REGISTER_ACTOR_REQ_ID, CON_MGR_REGISTER_ACTOR_RSP_ID]);
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "8cbf4c1d-1b32-429a-8587-ee50b0ea79e4")]
+#[repr(C)]
+pub struct ConMgrUnregisterActorReq {
+    pub header: MsgHeader,
+    pub instance_id: AnId,
+}
+
+impl ConMgrUnregisterActorReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId, instance_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_UNREGISTER_ACTOR_REQ_ID, *dst_id, *src_id),
+            instance_id: *instance_id,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ConMgrUnregisterActorStatus {
+    Success,
+    ActorNotRegistered,
+}
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "d7aaaa93-855c-4d9e-b670-68b126099518")]
+#[repr(C)]
+pub struct ConMgrUnregisterActorRsp {
+    pub header: MsgHeader,
+    pub instance_id: AnId,
+    pub status: ConMgrUnregisterActorStatus,
+}
+
+impl ConMgrUnregisterActorRsp {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        instance_id: &AnId,
+        status: ConMgrUnregisterActorStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(CON_MGR_UNREGISTER_ACTOR_RSP_ID, *dst_id, *src_id),
+            instance_id: *instance_id,
+            status,
+        }
+    }
+}
+
+static CON_MGR_REGISTER_ACTOR_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(CON_MGR_REGISTER_ACTOR_REQ_ID),
+        ProtocolMsg::send(CON_MGR_REGISTER_ACTOR_RSP_ID),
+        ProtocolMsg::receive(CON_MGR_UNREGISTER_ACTOR_REQ_ID),
+        ProtocolMsg::send(CON_MGR_UNREGISTER_ACTOR_RSP_ID),
+    ]
+});
 
 // From: https://www.uuidgenerator.net/version4
 const CON_MGR_REGISTER_ACTOR_PROTOCOL_ID: AnId = anid!("66fa196c-3871-4139-86b3-f98bc9d2dfe7");
 const CON_MGR_REGISTER_ACTOR_PROTOCOL_NAME: &str = "con_mgr_register_actor_protocol";
+const CON_MGR_REGISTER_ACTOR_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    CON_MGR_REGISTER_ACTOR_PROTOCOL_ID,
+    CON_MGR_REGISTER_ACTOR_PROTOCOL_NAME
+);
 static CON_MGR_REGISTER_ACTOR_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
     Protocol::new(
         CON_MGR_REGISTER_ACTOR_PROTOCOL_NAME,
         CON_MGR_REGISTER_ACTOR_PROTOCOL_ID,
+        CON_MGR_REGISTER_ACTOR_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
         CON_MGR_REGISTER_ACTOR_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -88,14 +161,31 @@ pub fn con_mgr_register_actor_protocol() -> &'static Protocol {
     &CON_MGR_REGISTER_ACTOR_PROTOCOL
 }
 
+static CON_MGR_REGISTEE_ACTOR_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(CON_MGR_REGISTER_ACTOR_REQ_ID),
+        ProtocolMsg::receive(CON_MGR_REGISTER_ACTOR_RSP_ID),
+        ProtocolMsg::send(CON_MGR_UNREGISTER_ACTOR_REQ_ID),
+        ProtocolMsg::receive(CON_MGR_UNREGISTER_ACTOR_RSP_ID),
+    ]
+});
+
 // From: https://www.uuidgenerator.net/version4
 const CON_MGR_REGISTEE_ACTOR_PROTOCOL_ID: AnId = anid!("fcaa554c-6969-42a3-841f-703bd18d93c4");
-const CON_MGR_REGISTEE_ACTOR_PROTOCOL_NAME: &str = "con_mgr_register_actor_protocol";
+const CON_MGR_REGISTEE_ACTOR_PROTOCOL_NAME: &str = "con_mgr_registee_actor_protocol";
+const CON_MGR_REGISTEE_ACTOR_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    CON_MGR_REGISTEE_ACTOR_PROTOCOL_ID,
+    CON_MGR_REGISTEE_ACTOR_PROTOCOL_NAME
+);
 static CON_MGR_REGISTEE_ACTOR_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
     Protocol::new(
         CON_MGR_REGISTEE_ACTOR_PROTOCOL_NAME,
         CON_MGR_REGISTEE_ACTOR_PROTOCOL_ID,
-        CON_MGR_REGISTER_ACTOR_PROTOCOL_MESSAGES.clone(),
+        CON_MGR_REGISTEE_ACTOR_PROTOCOL_VERSION,
+        ProtocolRole::Requester,
+        CON_MGR_REGISTEE_ACTOR_PROTOCOL_MESSAGES.clone(),
     )
 });
 
@@ -118,7 +208,20 @@ mod test {
         let errp = con_mgr_register_actor_protocol();
         assert_eq!(errp.id, CON_MGR_REGISTER_ACTOR_PROTOCOL_ID);
         assert_eq!(errp.name, CON_MGR_REGISTER_ACTOR_PROTOCOL_NAME);
+        assert_eq!(errp.version, CON_MGR_REGISTER_ACTOR_PROTOCOL_VERSION);
         assert_eq!(errp.messages, *CON_MGR_REGISTER_ACTOR_PROTOCOL_MESSAGES);
+        assert_eq!(errp.role, ProtocolRole::Requestee);
+    }
+
+    #[test]
+    fn test_con_mgr_registee_actor_protocol() {
+        let p = con_mgr_registee_actor_protocol();
+        assert_eq!(p.id, CON_MGR_REGISTEE_ACTOR_PROTOCOL_ID);
+        assert_eq!(p.name, CON_MGR_REGISTEE_ACTOR_PROTOCOL_NAME);
+        assert_eq!(p.version, CON_MGR_REGISTEE_ACTOR_PROTOCOL_VERSION);
+        assert_eq!(p.messages, *CON_MGR_REGISTEE_ACTOR_PROTOCOL_MESSAGES);
+        assert_eq!(p.role, ProtocolRole::Requester);
+        assert!(p.is_complement_of(con_mgr_register_actor_protocol()));
     }
 
     #[test]
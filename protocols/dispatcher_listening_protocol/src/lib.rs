@@ -0,0 +1,220 @@
+//! Protocol for asking a msg_router_dispatcher about its listener.
+//!
+//! Once started the dispatcher binds its address, a DispatcherListeningReq
+//! is answered with a DispatcherListeningRsp with either the address
+//! actually bound, useful when the port was 0, or why the bind failed.
+//! A dispatcher asked to notify a requester sends it a DispatcherListening
+//! with the same status as soon as the bind completes.
+use std::net::SocketAddr;
+
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use msg_derive::Message;
+use msg_header::MsgHeader;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "3ebab194-2d40-40b2-8f65-40888859a064")]
+#[repr(C)]
+pub struct DispatcherListeningReq {
+    pub header: MsgHeader,
+}
+
+impl DispatcherListeningReq {
+    pub fn new(dst_id: &AnId, src_id: &AnId) -> Self {
+        Self {
+            header: MsgHeader::new(DISPATCHER_LISTENING_REQ_ID, *dst_id, *src_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispatcherListeningStatus {
+    NotStarted,
+    Listening(SocketAddr),
+    BindFailed(String),
+}
+
+/// The status of the listener of a dispatcher, addr is the address it
+/// was asked to bind.
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "765ec231-2f86-47eb-81ec-9605a5363e4d")]
+#[repr(C)]
+pub struct DispatcherListeningRsp {
+    pub header: MsgHeader,
+    pub addr: String,
+    pub status: DispatcherListeningStatus,
+}
+
+impl DispatcherListeningRsp {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        addr: &str,
+        status: &DispatcherListeningStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(DISPATCHER_LISTENING_RSP_ID, *dst_id, *src_id),
+            addr: addr.to_owned(),
+            status: status.clone(),
+        }
+    }
+
+    /// The address bound, None if the dispatcher isn't listening
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self.status {
+            DispatcherListeningStatus::Listening(local_addr) => Some(local_addr),
+            _ => None,
+        }
+    }
+}
+
+/// Sent by a dispatcher once it has bound addr, or failed to
+//
+// From: https://www.uuidgenerator.net/version4
+#[derive(Debug, Clone, Message)]
+#[msg(id = "c6c5be40-e206-4dc4-9b81-72940608a5e5")]
+#[repr(C)]
+pub struct DispatcherListening {
+    pub header: MsgHeader,
+    pub addr: String,
+    pub status: DispatcherListeningStatus,
+}
+
+impl DispatcherListening {
+    pub fn new(
+        dst_id: &AnId,
+        src_id: &AnId,
+        addr: &str,
+        status: &DispatcherListeningStatus,
+    ) -> Self {
+        Self {
+            header: MsgHeader::new(DISPATCHER_LISTENING_ID, *dst_id, *src_id),
+            addr: addr.to_owned(),
+            status: status.clone(),
+        }
+    }
+}
+
+static DISPATCHER_LISTENING_REQUESTER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(DISPATCHER_LISTENING_REQ_ID),
+        ProtocolMsg::receive(DISPATCHER_LISTENING_RSP_ID),
+        ProtocolMsg::receive(DISPATCHER_LISTENING_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const DISPATCHER_LISTENING_REQUESTER_PROTOCOL_ID: AnId =
+    anid!("69e4b811-be1e-4e1b-90c9-d9171e4326f0");
+const DISPATCHER_LISTENING_REQUESTER_PROTOCOL_NAME: &str =
+    "dispatcher_listening_requester_protocol";
+const DISPATCHER_LISTENING_REQUESTER_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    DISPATCHER_LISTENING_REQUESTER_PROTOCOL_ID,
+    DISPATCHER_LISTENING_REQUESTER_PROTOCOL_NAME
+);
+static DISPATCHER_LISTENING_REQUESTER_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        DISPATCHER_LISTENING_REQUESTER_PROTOCOL_NAME,
+        DISPATCHER_LISTENING_REQUESTER_PROTOCOL_ID,
+        DISPATCHER_LISTENING_REQUESTER_PROTOCOL_VERSION,
+        ProtocolRole::Requester,
+        DISPATCHER_LISTENING_REQUESTER_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol of the supervisor of a msg_router_dispatcher asking about
+/// its listener
+pub fn dispatcher_listening_requester_protocol() -> &'static Protocol {
+    &DISPATCHER_LISTENING_REQUESTER_PROTOCOL
+}
+
+static DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(DISPATCHER_LISTENING_REQ_ID),
+        ProtocolMsg::send(DISPATCHER_LISTENING_RSP_ID),
+        ProtocolMsg::send(DISPATCHER_LISTENING_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_ID: AnId =
+    anid!("fcd13c4c-ba32-4d1e-bd16-6b6915991a11");
+const DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_NAME: &str =
+    "dispatcher_listening_requestee_protocol";
+const DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_VERSION: Version = Version::new(1, 1, 0);
+register_id!(
+    Protocol,
+    DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_ID,
+    DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_NAME
+);
+static DISPATCHER_LISTENING_REQUESTEE_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_NAME,
+        DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_ID,
+        DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+/// Protocol of a msg_router_dispatcher reporting its listener
+pub fn dispatcher_listening_requestee_protocol() -> &'static Protocol {
+    &DISPATCHER_LISTENING_REQUESTEE_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dispatcher_listening_protocols() {
+        let p = dispatcher_listening_requester_protocol();
+        assert_eq!(p.id, DISPATCHER_LISTENING_REQUESTER_PROTOCOL_ID);
+        assert_eq!(p.name, DISPATCHER_LISTENING_REQUESTER_PROTOCOL_NAME);
+        assert_eq!(
+            p.messages,
+            *DISPATCHER_LISTENING_REQUESTER_PROTOCOL_MESSAGES
+        );
+        assert_eq!(p.role, ProtocolRole::Requester);
+
+        let p = dispatcher_listening_requestee_protocol();
+        assert_eq!(p.id, DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_ID);
+        assert_eq!(p.name, DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_NAME);
+        assert_eq!(
+            p.messages,
+            *DISPATCHER_LISTENING_REQUESTEE_PROTOCOL_MESSAGES
+        );
+        assert_eq!(p.role, ProtocolRole::Requestee);
+        assert!(p.is_complement_of(dispatcher_listening_requester_protocol()));
+    }
+
+    #[test]
+    fn test_dispatcher_listening_rsp_local_addr() {
+        let dst_id = AnId::new();
+        let src_id = AnId::new();
+        let local_addr: SocketAddr = "127.0.0.1:43210".parse().unwrap();
+        let msg = DispatcherListeningRsp::new(
+            &dst_id,
+            &src_id,
+            "127.0.0.1:0",
+            &DispatcherListeningStatus::Listening(local_addr),
+        );
+        assert_eq!(msg.header.msg_id, DISPATCHER_LISTENING_RSP_ID);
+        assert_eq!(msg.local_addr(), Some(local_addr));
+
+        let msg = DispatcherListeningRsp::new(
+            &dst_id,
+            &src_id,
+            "127.0.0.1:0",
+            &DispatcherListeningStatus::BindFailed("in use".to_owned()),
+        );
+        assert_eq!(msg.local_addr(), None);
+    }
+}
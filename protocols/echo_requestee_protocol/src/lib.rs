@@ -1,8 +1,9 @@
 //! Protocol implemented by entities that receives requests
 //! and sends responses.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use echo_req::*;
@@ -10,13 +11,25 @@ pub use echo_rsp::*;
 
 const ECHO_REQUESTEE_PROTOCOL_ID: AnId = anid!("16e9c5a6-cf3f-4813-b0e2-1c3c54058183");
 const ECHO_REQUESTEE_PROTOCOL_NAME: &str = "echo_requestee_protocol";
-static ECHO_REQUESTEE_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
-    Lazy::new(|| vec![ECHO_REQ_ID, ECHO_RSP_ID]);
+const ECHO_REQUESTEE_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    ECHO_REQUESTEE_PROTOCOL_ID,
+    ECHO_REQUESTEE_PROTOCOL_NAME
+);
+static ECHO_REQUESTEE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(ECHO_REQ_ID),
+        ProtocolMsg::send(ECHO_RSP_ID),
+    ]
+});
 
 static ECHO_REQ_RSP_PROTOCOL: Lazy<EchoRequesteeProtocol> = Lazy::new(|| {
     Protocol::new(
         ECHO_REQUESTEE_PROTOCOL_NAME,
         ECHO_REQUESTEE_PROTOCOL_ID,
+        ECHO_REQUESTEE_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
         ECHO_REQUESTEE_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -36,6 +49,8 @@ mod test {
         let errp = echo_requestee_protocol();
         assert_eq!(errp.id, ECHO_REQUESTEE_PROTOCOL_ID);
         assert_eq!(errp.name, ECHO_REQUESTEE_PROTOCOL_NAME);
+        assert_eq!(errp.version, ECHO_REQUESTEE_PROTOCOL_VERSION);
         assert_eq!(errp.messages, *ECHO_REQUESTEE_PROTOCOL_MESSAGES);
+        assert_eq!(errp.role, ProtocolRole::Requestee);
     }
 }
@@ -1,8 +1,9 @@
 //! Protocol implemented by entities that that sends requests
 //! and receives responses.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use echo_req::*;
@@ -10,13 +11,25 @@ pub use echo_rsp::*;
 
 const ECHO_REQUESTER_PROTOCOL_ID: AnId = anid!("2084ca39-77f0-4ba0-b3f9-693f529e727b");
 const ECHO_REQUESTER_PROTOCOL_NAME: &str = "echo_requester_protocol";
-static ECHO_REQUESTER_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
-    Lazy::new(|| vec![ECHO_REQ_ID, ECHO_RSP_ID]);
+const ECHO_REQUESTER_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    ECHO_REQUESTER_PROTOCOL_ID,
+    ECHO_REQUESTER_PROTOCOL_NAME
+);
+static ECHO_REQUESTER_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(ECHO_REQ_ID),
+        ProtocolMsg::receive(ECHO_RSP_ID),
+    ]
+});
 
 static ECHO_REQUESTER_PROTOCOL: Lazy<EchoRequesterProtocol> = Lazy::new(|| {
     Protocol::new(
         ECHO_REQUESTER_PROTOCOL_NAME,
         ECHO_REQUESTER_PROTOCOL_ID,
+        ECHO_REQUESTER_PROTOCOL_VERSION,
+        ProtocolRole::Requester,
         ECHO_REQUESTER_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -36,6 +49,8 @@ mod test {
         let errp = echo_requester_protocol();
         assert_eq!(errp.id, ECHO_REQUESTER_PROTOCOL_ID);
         assert_eq!(errp.name, ECHO_REQUESTER_PROTOCOL_NAME);
+        assert_eq!(errp.version, ECHO_REQUESTER_PROTOCOL_VERSION);
         assert_eq!(errp.messages, *ECHO_REQUESTER_PROTOCOL_MESSAGES);
+        assert_eq!(errp.role, ProtocolRole::Requester);
     }
 }
@@ -1,6 +1,7 @@
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use echo_complete::*;
@@ -8,13 +9,25 @@ pub use echo_start::*;
 
 const ECHO_START_COMPLETE_PROTOCOL_ID: AnId = anid!("e46194bc-65a4-4b8e-884a-4272fd8fae99");
 const ECHO_START_COMPLETE_PROTOCOL_NAME: &str = "echo_start_complete_protocol";
-static ECHO_START_COMPLETE_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
-    Lazy::new(|| vec![ECHO_START_ID, ECHO_COMPLETE_ID]);
+const ECHO_START_COMPLETE_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    ECHO_START_COMPLETE_PROTOCOL_ID,
+    ECHO_START_COMPLETE_PROTOCOL_NAME
+);
+static ECHO_START_COMPLETE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(ECHO_START_ID),
+        ProtocolMsg::send(ECHO_COMPLETE_ID),
+    ]
+});
 
 static ECHO_START_COMPLETE_PROTOCOL: Lazy<EchoStartCompleteProtocol> = Lazy::new(|| {
     Protocol::new(
         ECHO_START_COMPLETE_PROTOCOL_NAME,
         ECHO_START_COMPLETE_PROTOCOL_ID,
+        ECHO_START_COMPLETE_PROTOCOL_VERSION,
+        ProtocolRole::Issuee,
         ECHO_START_COMPLETE_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -34,6 +47,8 @@ mod test {
         let ep = echo_start_complete_protocol();
         assert_eq!(&ep.id, &ECHO_START_COMPLETE_PROTOCOL_ID);
         assert_eq!(ep.name, ECHO_START_COMPLETE_PROTOCOL_NAME);
+        assert_eq!(ep.version, ECHO_START_COMPLETE_PROTOCOL_VERSION);
         assert_eq!(&ep.messages, &*ECHO_START_COMPLETE_PROTOCOL_MESSAGES);
+        assert_eq!(ep.role, ProtocolRole::Issuee);
     }
 }
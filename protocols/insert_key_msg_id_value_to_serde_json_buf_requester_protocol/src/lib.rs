@@ -1,8 +1,9 @@
 //! Protocol implemented by entities that sends requests
 //! and receives responses.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use insert_key_msg_id_value_to_serde_json_buf_req::*;
@@ -13,13 +14,21 @@ const INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_ID: AnId =
     anid!("7e5f820a-c545-414a-9a1b-2071dc59453a");
 const INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_NAME: &str =
     "insert_key_msg_id_value_to_serde_json_buf_requester_protocol";
-static INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
-    Lazy::new(|| {
-        vec![
-            INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQ_ID,
-            INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_RSP_ID,
-        ]
-    });
+const INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_VERSION: Version =
+    Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_ID,
+    INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_NAME
+);
+static INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_MESSAGES: Lazy<
+    Vec<ProtocolMsg>,
+> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQ_ID),
+        ProtocolMsg::receive(INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_RSP_ID),
+    ]
+});
 
 static INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL: Lazy<
     InsertKeyMsgIdValueToSerdeJsonBufRequesterProtocol,
@@ -27,6 +36,8 @@ static INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL: Lazy<
     Protocol::new(
         INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_NAME,
         INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_ID,
+        INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_VERSION,
+        ProtocolRole::Requester,
         INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -53,9 +64,14 @@ mod test {
             protocol.name,
             INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_NAME
         );
+        assert_eq!(
+            protocol.version,
+            INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_VERSION
+        );
         assert_eq!(
             protocol.messages,
             *INSERT_KEY_MSG_ID_VALUE_TO_SERDE_JSON_BUF_REQUESTER_PROTOCOL_MESSAGES
         );
+        assert_eq!(protocol.role, ProtocolRole::Requester);
     }
 }
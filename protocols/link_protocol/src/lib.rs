@@ -0,0 +1,296 @@
+//! Protocol for the handshake at the start of a link between a
+//! msg_router_forwarder and a msg_router_dispatcher.
+//!
+//! After connecting the forwarder sends a LinkHello with the protocols,
+//! and their versions, it forwards and the dispatcher answers with a
+//! LinkHelloAck listing the protocols it dispatches and the instance_id
+//! of the ConMgr in its process, see con_mgr_instance_id_from_answer.
+//! Each side then uses
+//! LinkCompatibility to refuse messages the other side can't understand
+//! rather than failing to decode them. A dispatcher that can't accept
+//! the link at all answers with a LinkRefused and closes the connection,
+//! the forwarder then refuses every message.
+use std::collections::HashMap;
+
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use msg_header::get_msg_id_str_from_buf;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+
+// Re-exports
+pub use link_hello::*;
+pub use link_hello_ack::*;
+pub use link_refused::*;
+
+/// The messages that can't be exchanged over a link and why
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinkCompatibility {
+    refused: HashMap<AnId, String>,
+    refused_all: Option<String>, // Some if the link was refused
+}
+
+impl LinkCompatibility {
+    /// Compare the protocols of this side of the link with those the
+    /// peer sent in its LinkHello or LinkHelloAck. A peer protocol
+    /// matches if it has the same id or is the complement. If the peer
+    /// didn't list any protocols the link is refused, there's nothing
+    /// it's known to understand.
+    pub fn new(local: &[Protocol], peer: &[Protocol]) -> Self {
+        let mut refused = HashMap::<AnId, String>::new();
+        if peer.is_empty() {
+            return Self::refuse_all("peer didn't list any protocols");
+        }
+
+        let mut allowed = Vec::<AnId>::new();
+        for l in local {
+            let why = match peer.iter().find(|p| p.id == l.id || p.is_complement_of(l)) {
+                Some(p) => l.incompatibility(p),
+                None => Some(format!("peer doesn't support protocol {}", l.name)),
+            };
+            match why {
+                Some(why) => {
+                    for m in l.messages.iter() {
+                        refused.entry(m.id).or_insert_with(|| why.clone());
+                    }
+                }
+                None => allowed.extend(l.msg_ids()),
+            }
+        }
+
+        // A message is allowed if any protocol it's part of is compatible
+        for id in allowed.iter() {
+            refused.remove(id);
+        }
+
+        Self {
+            refused,
+            refused_all: None,
+        }
+    }
+
+    /// Refuse every message, the link was refused
+    pub fn refuse_all(why: &str) -> Self {
+        Self {
+            refused: HashMap::new(),
+            refused_all: Some(why.to_owned()),
+        }
+    }
+
+    /// The compatibility of the link from the peer's answer to a
+    /// LinkHello offering local. A LinkHelloAck is compared with local,
+    /// a LinkRefused or anything else refuses the link.
+    pub fn from_answer(local: &[Protocol], buf: &[u8]) -> Self {
+        let id_str = get_msg_id_str_from_buf(buf);
+        if id_str == LINK_HELLO_ACK_ID_STR {
+            if let Some(ack) = LinkHelloAck::from_serde_json_buf(buf)
+                .and_then(|msg_any| msg_any.downcast::<LinkHelloAck>().ok())
+            {
+                return Self::new(local, &ack.protocols);
+            }
+        } else if id_str == LINK_REFUSED_ID_STR {
+            if let Some(refused) = LinkRefused::from_serde_json_buf(buf)
+                .and_then(|msg_any| msg_any.downcast::<LinkRefused>().ok())
+            {
+                return Self::refuse_all(&format!("link refused, {}", refused.reason));
+            }
+        }
+        Self::refuse_all(&format!("expected a LinkHelloAck, got msg_id={id_str}"))
+    }
+
+    /// Why msg_id is refused, None if it may be exchanged
+    pub fn refused(&self, msg_id: &AnId) -> Option<&str> {
+        match &self.refused_all {
+            Some(why) => Some(why.as_str()),
+            None => self.refused.get(msg_id).map(|why| why.as_str()),
+        }
+    }
+
+    /// True if the link was refused
+    pub fn is_refused(&self) -> bool {
+        self.refused_all.is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.refused.is_empty() && self.refused_all.is_none()
+    }
+}
+
+/// The instance_id of the ConMgr in the peers process from its answer to
+/// a LinkHello, None if the answer isn't a LinkHelloAck or the peer
+/// didn't know its ConMgr.
+pub fn con_mgr_instance_id_from_answer(buf: &[u8]) -> Option<AnId> {
+    if get_msg_id_str_from_buf(buf) != LINK_HELLO_ACK_ID_STR {
+        return None;
+    }
+    LinkHelloAck::from_serde_json_buf(buf)
+        .and_then(|msg_any| msg_any.downcast::<LinkHelloAck>().ok())
+        .map(|ack| ack.con_mgr_instance_id)
+        .filter(|con_mgr_instance_id| *con_mgr_instance_id != AnId::nil())
+}
+
+static LINK_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(LINK_HELLO_ID),
+        ProtocolMsg::send(LINK_HELLO_ACK_ID),
+        ProtocolMsg::send(LINK_REFUSED_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const LINK_PROTOCOL_ID: AnId = anid!("cf24c031-2758-4771-97a2-30c03982fe6a");
+const LINK_PROTOCOL_NAME: &str = "link_protocol";
+const LINK_PROTOCOL_VERSION: Version = Version::new(1, 2, 0);
+register_id!(Protocol, LINK_PROTOCOL_ID, LINK_PROTOCOL_NAME);
+static LINK_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        LINK_PROTOCOL_NAME,
+        LINK_PROTOCOL_ID,
+        LINK_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        LINK_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+pub fn link_protocol() -> &'static Protocol {
+    &LINK_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use protocol::MsgDirection;
+
+    #[test]
+    fn test_link_protocol() {
+        let lp = link_protocol();
+        assert_eq!(lp.id, LINK_PROTOCOL_ID);
+        assert_eq!(lp.name, LINK_PROTOCOL_NAME);
+        assert_eq!(lp.version, LINK_PROTOCOL_VERSION);
+        assert_eq!(lp.messages, *LINK_PROTOCOL_MESSAGES);
+        assert_eq!(lp.role, ProtocolRole::Requestee);
+    }
+
+    fn requester(req_id: AnId, rsp_id: AnId, version: Version, req_version: Version) -> Protocol {
+        Protocol::new(
+            "requester",
+            AnId::new(),
+            version,
+            ProtocolRole::Requester,
+            vec![
+                ProtocolMsg::new(req_id, MsgDirection::Send, req_version),
+                ProtocolMsg::receive(rsp_id),
+            ],
+        )
+    }
+
+    fn requestee(req_id: AnId, rsp_id: AnId, version: Version) -> Protocol {
+        Protocol::new(
+            "requestee",
+            AnId::new(),
+            version,
+            ProtocolRole::Requestee,
+            vec![ProtocolMsg::receive(req_id), ProtocolMsg::send(rsp_id)],
+        )
+    }
+
+    #[test]
+    fn test_link_compatibility() {
+        println!("test_link_compatibility:+");
+        let v1 = Version::new(1, 0, 0);
+        let v1_1 = Version::new(1, 1, 0);
+        let v2 = Version::new(2, 0, 0);
+        let req_id = AnId::new();
+        let rsp_id = AnId::new();
+        let other_id = AnId::new();
+
+        // Everything is refused if the peer didn't list its protocols
+        let local = [requester(req_id, rsp_id, v2, v1)];
+        let lc = LinkCompatibility::new(&local, &[]);
+        assert!(lc.is_refused());
+        assert!(lc.refused(&other_id).is_some());
+
+        // Compatible versions
+        let peer = [requestee(req_id, rsp_id, v1_1)];
+        let local = [requester(req_id, rsp_id, v1, v1)];
+        let lc = LinkCompatibility::new(&local, &peer);
+        assert!(lc.is_empty());
+        assert_eq!(lc.refused(&req_id), None);
+
+        // Incompatible protocol version refuses all its messages
+        let local = [requester(req_id, rsp_id, v2, v1)];
+        let lc = LinkCompatibility::new(&local, &peer);
+        let why = lc.refused(&req_id).unwrap();
+        println!("test_link_compatibility: {why}");
+        assert!(why.contains("version 2.0.0"));
+        assert!(lc.refused(&rsp_id).is_some());
+        assert_eq!(lc.refused(&other_id), None);
+
+        // Incompatible message version
+        let local = [requester(req_id, rsp_id, v1, v2)];
+        let lc = LinkCompatibility::new(&local, &peer);
+        assert!(lc.refused(&req_id).is_some());
+
+        // Peer doesn't have the protocol
+        let peer = [requestee(other_id, AnId::new(), v1)];
+        let local = [requester(req_id, rsp_id, v1, v1)];
+        let lc = LinkCompatibility::new(&local, &peer);
+        let why = lc.refused(&req_id).unwrap();
+        println!("test_link_compatibility: {why}");
+        assert!(why.contains("doesn't support protocol requester"));
+
+        // Allowed if any protocol containing the message is compatible
+        let peer = [requestee(req_id, rsp_id, v1)];
+        let local = [
+            requester(req_id, rsp_id, v2, v1),
+            requester(req_id, rsp_id, v1, v1),
+        ];
+        let lc = LinkCompatibility::new(&local, &peer);
+        assert!(lc.is_empty());
+
+        // A LinkHelloAck answer is compared with local
+        let con_mgr_instance_id = AnId::new();
+        let ack = Box::new(LinkHelloAck::new(
+            &AnId::new(),
+            &AnId::new(),
+            &peer,
+            &con_mgr_instance_id,
+        ));
+        let buf = LinkHelloAck::to_serde_json_buf(ack).unwrap();
+        assert_eq!(
+            LinkCompatibility::from_answer(&local, &buf),
+            LinkCompatibility::new(&local, &peer)
+        );
+        assert_eq!(
+            con_mgr_instance_id_from_answer(&buf),
+            Some(con_mgr_instance_id)
+        );
+
+        // Without a ConMgr there is no instance_id
+        let ack = Box::new(LinkHelloAck::new(
+            &AnId::new(),
+            &AnId::new(),
+            &peer,
+            &AnId::nil(),
+        ));
+        let buf = LinkHelloAck::to_serde_json_buf(ack).unwrap();
+        assert_eq!(con_mgr_instance_id_from_answer(&buf), None);
+
+        // A LinkRefused, or anything else, refuses every message
+        let refused = Box::new(LinkRefused::new(&AnId::new(), &AnId::new(), "go away"));
+        let buf = LinkRefused::to_serde_json_buf(refused).unwrap();
+        let lc = LinkCompatibility::from_answer(&local, &buf);
+        assert_eq!(con_mgr_instance_id_from_answer(&buf), None);
+        assert!(lc.is_refused());
+        assert!(!lc.is_empty());
+        assert_eq!(lc.refused(&other_id), Some("link refused, go away"));
+        let hello = Box::new(LinkHello::new(&AnId::new(), &AnId::new(), &peer));
+        let buf = LinkHello::to_serde_json_buf(hello).unwrap();
+        let lc = LinkCompatibility::from_answer(&local, &buf);
+        assert!(lc
+            .refused(&req_id)
+            .unwrap()
+            .contains("expected a LinkHelloAck"));
+        println!("test_link_compatibility:-");
+    }
+}
@@ -0,0 +1,54 @@
+//! Protocol for querying the runtime metrics of a process.
+//!
+//! A MetricsReq sent to any ActorExecutor is answered with a MetricsRsp
+//! containing a snapshot of the metrics of all of the ActorExecutors and
+//! links in the process.
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+
+// Re-exports
+pub use metrics_req::*;
+pub use metrics_rsp::*;
+
+static METRICS_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(METRICS_REQ_ID),
+        ProtocolMsg::send(METRICS_RSP_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const METRICS_PROTOCOL_ID: AnId = anid!("9e41f7a2-6c3d-4b58-a0e9-7d25c81b3f64");
+const METRICS_PROTOCOL_NAME: &str = "metrics_protocol";
+const METRICS_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(Protocol, METRICS_PROTOCOL_ID, METRICS_PROTOCOL_NAME);
+static METRICS_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        METRICS_PROTOCOL_NAME,
+        METRICS_PROTOCOL_ID,
+        METRICS_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        METRICS_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+pub fn metrics_protocol() -> &'static Protocol {
+    &METRICS_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_metrics_protocol() {
+        let mp = metrics_protocol();
+        assert_eq!(mp.id, METRICS_PROTOCOL_ID);
+        assert_eq!(mp.name, METRICS_PROTOCOL_NAME);
+        assert_eq!(mp.version, METRICS_PROTOCOL_VERSION);
+        assert_eq!(mp.messages, *METRICS_PROTOCOL_MESSAGES);
+        assert_eq!(mp.role, ProtocolRole::Requestee);
+    }
+}
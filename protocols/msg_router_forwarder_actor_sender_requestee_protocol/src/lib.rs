@@ -1,8 +1,9 @@
 //! Protocol implemented by entities that receives requests
 //! and sends responses.
 use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
 use once_cell::sync::Lazy;
-use protocol::Protocol;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
 
 // Re-exports
 pub use msg_router_forwarder_actor_sender_req::*;
@@ -13,11 +14,17 @@ const MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_ID: AnId =
     anid!("9a86053a-33a6-4e86-bffe-09c03d7f51fc");
 const MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_NAME: &str =
     "msg_router_forwarder_actor_sender_requestee_protocol";
-static MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_MESSAGES: Lazy<Vec<AnId>> =
+const MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(
+    Protocol,
+    MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_ID,
+    MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_NAME
+);
+static MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> =
     Lazy::new(|| {
         vec![
-            MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQ_ID,
-            MSG_ROUTER_FORWARDER_ACTOR_SENDER_RSP_ID,
+            ProtocolMsg::receive(MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQ_ID),
+            ProtocolMsg::send(MSG_ROUTER_FORWARDER_ACTOR_SENDER_RSP_ID),
         ]
     });
 
@@ -27,6 +34,8 @@ static MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL: Lazy<
     Protocol::new(
         MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_NAME,
         MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_ID,
+        MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
         MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_MESSAGES.clone(),
     )
 });
@@ -53,9 +62,14 @@ mod test {
             protocol.name,
             MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_NAME
         );
+        assert_eq!(
+            protocol.version,
+            MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_VERSION
+        );
         assert_eq!(
             protocol.messages,
             *MSG_ROUTER_FORWARDER_ACTOR_SENDER_REQUESTEE_PROTOCOL_MESSAGES
         );
+        assert_eq!(protocol.role, ProtocolRole::Requestee);
     }
 }
@@ -0,0 +1,62 @@
+//! Protocol between an ActorExecutor and the supervisors of its actors.
+//!
+//! The supervisor of an actor is the entity that sent the ReqAddActor.
+//! When an actor panics while processing a message the ActorExecutor
+//! sends the supervisor an ActorFailed and then applies the
+//! SupervisorPolicy, which results in an ActorRestarted or ActorStopped.
+//! The policy is changed with ReqSetSupervisorPolicy.
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+
+// Re-exports
+pub use actor_failed::*;
+pub use actor_restarted::*;
+pub use actor_stopped::*;
+pub use req_set_supervisor_policy::*;
+pub use rsp_set_supervisor_policy::*;
+
+static SUPERVISOR_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::send(ACTOR_FAILED_ID),
+        ProtocolMsg::send(ACTOR_RESTARTED_ID),
+        ProtocolMsg::send(ACTOR_STOPPED_ID),
+        ProtocolMsg::receive(REQ_SET_SUPERVISOR_POLICY_ID),
+        ProtocolMsg::send(RSP_SET_SUPERVISOR_POLICY_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const SUPERVISOR_PROTOCOL_ID: AnId = anid!("f7976b54-831a-45dc-911d-e96523c5b605");
+const SUPERVISOR_PROTOCOL_NAME: &str = "supervisor_protocol";
+const SUPERVISOR_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(Protocol, SUPERVISOR_PROTOCOL_ID, SUPERVISOR_PROTOCOL_NAME);
+static SUPERVISOR_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        SUPERVISOR_PROTOCOL_NAME,
+        SUPERVISOR_PROTOCOL_ID,
+        SUPERVISOR_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        SUPERVISOR_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+pub fn supervisor_protocol() -> &'static Protocol {
+    &SUPERVISOR_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_supervisor_protocol() {
+        let sp = supervisor_protocol();
+        assert_eq!(sp.id, SUPERVISOR_PROTOCOL_ID);
+        assert_eq!(sp.name, SUPERVISOR_PROTOCOL_NAME);
+        assert_eq!(sp.version, SUPERVISOR_PROTOCOL_VERSION);
+        assert_eq!(sp.messages, *SUPERVISOR_PROTOCOL_MESSAGES);
+        assert_eq!(sp.role, ProtocolRole::Requestee);
+    }
+}
@@ -0,0 +1,61 @@
+//! Protocol for tapping the messages passing through a process.
+//!
+//! The sender of a TapReq is the observer, it receives a TapMsg with a
+//! summary of every `sample_every`th message that matches the filter
+//! until it sends a TapDetachReq. TapReq and TapDetachReq may be sent to
+//! any ActorExecutor in the process.
+use an_id::{anid, paste, AnId};
+use id_inventory::register_id;
+use once_cell::sync::Lazy;
+use protocol::{Protocol, ProtocolMsg, ProtocolRole, Version};
+
+// Re-exports
+pub use tap_detach_req::*;
+pub use tap_detach_rsp::*;
+pub use tap_msg::*;
+pub use tap_req::*;
+pub use tap_rsp::*;
+
+static TAP_PROTOCOL_MESSAGES: Lazy<Vec<ProtocolMsg>> = Lazy::new(|| {
+    vec![
+        ProtocolMsg::receive(TAP_REQ_ID),
+        ProtocolMsg::send(TAP_RSP_ID),
+        ProtocolMsg::receive(TAP_DETACH_REQ_ID),
+        ProtocolMsg::send(TAP_DETACH_RSP_ID),
+        ProtocolMsg::send(TAP_MSG_ID),
+    ]
+});
+
+// From: https://www.uuidgenerator.net/version4
+const TAP_PROTOCOL_ID: AnId = anid!("a5f04b7c-3e9d-4c12-b6a8-92d1e7f3c50b");
+const TAP_PROTOCOL_NAME: &str = "tap_protocol";
+const TAP_PROTOCOL_VERSION: Version = Version::new(1, 0, 0);
+register_id!(Protocol, TAP_PROTOCOL_ID, TAP_PROTOCOL_NAME);
+static TAP_PROTOCOL: Lazy<Protocol> = Lazy::new(|| {
+    Protocol::new(
+        TAP_PROTOCOL_NAME,
+        TAP_PROTOCOL_ID,
+        TAP_PROTOCOL_VERSION,
+        ProtocolRole::Requestee,
+        TAP_PROTOCOL_MESSAGES.clone(),
+    )
+});
+
+pub fn tap_protocol() -> &'static Protocol {
+    &TAP_PROTOCOL
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tap_protocol() {
+        let tp = tap_protocol();
+        assert_eq!(tp.id, TAP_PROTOCOL_ID);
+        assert_eq!(tp.name, TAP_PROTOCOL_NAME);
+        assert_eq!(tp.version, TAP_PROTOCOL_VERSION);
+        assert_eq!(tp.messages, *TAP_PROTOCOL_MESSAGES);
+        assert_eq!(tp.role, ProtocolRole::Requestee);
+    }
+}
@@ -0,0 +1,636 @@
+//! Runtime metrics for ActorExecutors, their actors and the links
+//! between processes.
+//!
+//! Each ActorExecutor registers an ExecutorMetrics which it updates as
+//! it dispatches messages and each msg_router forwarder or dispatcher
+//! registers LinkCounters which its threads update. A MetricsSnapshot of
+//! everything registered in the process is returned by metrics_snapshot,
+//! it can be converted to the Prometheus text format and served from a
+//! local HTTP endpoint with metrics_http_serve.
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use an_id::AnId;
+use box_msg_any::BoxMsgAny;
+use crossbeam_channel::Receiver;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds of the processing time histogram buckets in nanoseconds,
+/// there is an additional +Inf bucket.
+pub const PROCESSING_TIME_BOUNDS_NS: [u64; 7] = [
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+    1_000_000_000,
+];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Histogram {
+    pub counts: Vec<u64>, // Not cumulative, the last is the +Inf bucket
+    pub sum_ns: u64,
+    pub count: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; PROCESSING_TIME_BOUNDS_NS.len() + 1],
+            sum_ns: 0,
+            count: 0,
+        }
+    }
+
+    pub fn observe(&mut self, duration: Duration) {
+        let ns = duration.as_nanos().min(u64::MAX as u128) as u64;
+        self.counts[Self::bucket(ns)] += 1;
+        self.sum_ns = self.sum_ns.saturating_add(ns);
+        self.count += 1;
+    }
+
+    // Index in counts of the bucket for ns
+    fn bucket(ns: u64) -> usize {
+        PROCESSING_TIME_BOUNDS_NS
+            .iter()
+            .position(|bound| ns <= *bound)
+            .unwrap_or(PROCESSING_TIME_BOUNDS_NS.len())
+    }
+}
+
+/// Counters updated by an ActorExecutor as it dispatches an actor's
+/// messages, there's no lock so updating them is cheap.
+#[derive(Debug)]
+pub struct ActorCounters {
+    pub name: String,
+    pub instance_id: AnId,
+    pub msgs_processed: AtomicU64,
+    pub unknown_msgs: AtomicU64,
+    processing_time_counts: [AtomicU64; PROCESSING_TIME_BOUNDS_NS.len() + 1],
+    processing_time_sum_ns: AtomicU64,
+    mailbox: Receiver<BoxMsgAny>, // For the mailbox_depth, dropped with the counters
+}
+
+impl ActorCounters {
+    pub fn new(name: &str, instance_id: &AnId, mailbox: &Receiver<BoxMsgAny>) -> Self {
+        Self {
+            name: name.to_owned(),
+            instance_id: *instance_id,
+            msgs_processed: AtomicU64::new(0),
+            unknown_msgs: AtomicU64::new(0),
+            processing_time_counts: Default::default(),
+            processing_time_sum_ns: AtomicU64::new(0),
+            mailbox: mailbox.clone(),
+        }
+    }
+
+    pub fn processed(&self, duration: Duration, unknown_msgs: u64) {
+        self.msgs_processed.fetch_add(1, Ordering::Relaxed);
+        self.unknown_msgs.fetch_add(unknown_msgs, Ordering::Relaxed);
+        let ns = duration.as_nanos().min(u64::MAX as u128) as u64;
+        self.processing_time_counts[Histogram::bucket(ns)].fetch_add(1, Ordering::Relaxed);
+        self.processing_time_sum_ns.fetch_add(ns, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ActorMetrics {
+        let counts: Vec<u64> = self
+            .processing_time_counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect();
+        ActorMetrics {
+            name: self.name.clone(),
+            instance_id: self.instance_id,
+            msgs_processed: self.msgs_processed.load(Ordering::Relaxed),
+            unknown_msgs: self.unknown_msgs.load(Ordering::Relaxed),
+            processing_time: Histogram {
+                count: counts.iter().sum(),
+                counts,
+                sum_ns: self.processing_time_sum_ns.load(Ordering::Relaxed),
+            },
+            mailbox_depth: self.mailbox.len() as u64,
+        }
+    }
+}
+
+/// Counters of an ActorExecutor and those of the actors it's running.
+///
+/// The actors are only locked when one is added or removed and when a
+/// snapshot is taken, dispatching a message updates its ActorCounters.
+#[derive(Debug)]
+pub struct ExecutorCounters {
+    pub name: String,
+    pub instance_id: AnId,
+    pub select_wakeups: AtomicU64,
+    actors: Mutex<Vec<Arc<ActorCounters>>>,
+}
+
+impl ExecutorCounters {
+    pub fn new(name: &str, instance_id: &AnId) -> Self {
+        Self {
+            name: name.to_owned(),
+            instance_id: *instance_id,
+            select_wakeups: AtomicU64::new(0),
+            actors: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add the counters of an actor the executor is running
+    pub fn actor_add(
+        &self,
+        name: &str,
+        instance_id: &AnId,
+        mailbox: &Receiver<BoxMsgAny>,
+    ) -> Arc<ActorCounters> {
+        let counters = Arc::new(ActorCounters::new(name, instance_id, mailbox));
+        let mut locked_actors = self.actors.lock().unwrap(); // TODO: remove unwrap
+        locked_actors.push(Arc::clone(&counters));
+        counters
+    }
+
+    /// Remove the counters of an actor the executor is no longer running
+    pub fn actor_remove(&self, instance_id: &AnId) {
+        let mut locked_actors = self.actors.lock().unwrap(); // TODO: remove unwrap
+        locked_actors.retain(|actor| actor.instance_id != *instance_id);
+    }
+
+    pub fn snapshot(&self) -> ExecutorMetrics {
+        let locked_actors = self.actors.lock().unwrap(); // TODO: remove unwrap
+        ExecutorMetrics {
+            name: self.name.clone(),
+            instance_id: self.instance_id,
+            select_wakeups: self.select_wakeups.load(Ordering::Relaxed),
+            active_actors: locked_actors.len() as u64,
+            actors: locked_actors.iter().map(|actor| actor.snapshot()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ActorMetrics {
+    pub name: String,
+    pub instance_id: AnId,
+    pub msgs_processed: u64,
+    pub unknown_msgs: u64,
+    pub processing_time: Histogram,
+    pub mailbox_depth: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExecutorMetrics {
+    pub name: String,
+    pub instance_id: AnId,
+    pub select_wakeups: u64,
+    pub active_actors: u64,
+    pub actors: Vec<ActorMetrics>, // The actors the executor is running
+}
+
+/// Counters updated by a links threads
+#[derive(Debug, Default)]
+pub struct LinkCounters {
+    pub bytes_in: AtomicU64,
+    pub bytes_out: AtomicU64,
+    pub frames_in: AtomicU64,
+    pub frames_out: AtomicU64,
+    pub decode_errors: AtomicU64,
+    pub reconnects: AtomicU64,
+    pub refused: AtomicU64, // Messages with no compatible protocol version on the peer
+}
+
+impl LinkCounters {
+    pub fn add(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LinkMetrics {
+    pub name: String,
+    pub instance_id: AnId,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub frames_in: u64,
+    pub frames_out: u64,
+    pub decode_errors: u64,
+    pub reconnects: u64,
+    pub refused: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub executors: Vec<ExecutorMetrics>,
+    pub links: Vec<LinkMetrics>,
+}
+
+type LinkEntry = (String, Arc<LinkCounters>);
+
+static EXECUTORS: Lazy<RwLock<HashMap<AnId, Arc<ExecutorCounters>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+static LINKS: Lazy<RwLock<HashMap<AnId, LinkEntry>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+// Register the counters of an ActorExecutor
+pub fn executor_metrics_register(name: &str, instance_id: &AnId) -> Arc<ExecutorCounters> {
+    let counters = Arc::new(ExecutorCounters::new(name, instance_id));
+    let mut wlocked_hashmap = EXECUTORS.write().unwrap(); // TODO: remove unwrap
+    wlocked_hashmap.insert(*instance_id, Arc::clone(&counters));
+    counters
+}
+
+pub fn executor_metrics_unregister(instance_id: &AnId) {
+    let mut wlocked_hashmap = EXECUTORS.write().unwrap(); // TODO: remove unwrap
+    wlocked_hashmap.remove(instance_id);
+}
+
+// Register the counters of a link
+pub fn link_metrics_register(name: &str, instance_id: &AnId) -> Arc<LinkCounters> {
+    let counters = Arc::new(LinkCounters::default());
+    let mut wlocked_hashmap = LINKS.write().unwrap(); // TODO: remove unwrap
+    wlocked_hashmap.insert(*instance_id, (name.to_owned(), Arc::clone(&counters)));
+    counters
+}
+
+pub fn link_metrics_unregister(instance_id: &AnId) {
+    let mut wlocked_hashmap = LINKS.write().unwrap(); // TODO: remove unwrap
+    wlocked_hashmap.remove(instance_id);
+}
+
+/// Snapshot of the metrics of all of the executors and links in this process
+pub fn metrics_snapshot() -> MetricsSnapshot {
+    let mut executors: Vec<ExecutorMetrics> = EXECUTORS
+        .read()
+        .unwrap() // TODO: remove unwrap
+        .values()
+        .map(|counters| counters.snapshot())
+        .collect();
+    executors.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut links: Vec<LinkMetrics> = LINKS
+        .read()
+        .unwrap() // TODO: remove unwrap
+        .iter()
+        .map(|(instance_id, (name, counters))| LinkMetrics {
+            name: name.clone(),
+            instance_id: *instance_id,
+            bytes_in: counters.bytes_in.load(Ordering::Relaxed),
+            bytes_out: counters.bytes_out.load(Ordering::Relaxed),
+            frames_in: counters.frames_in.load(Ordering::Relaxed),
+            frames_out: counters.frames_out.load(Ordering::Relaxed),
+            decode_errors: counters.decode_errors.load(Ordering::Relaxed),
+            reconnects: counters.reconnects.load(Ordering::Relaxed),
+            refused: counters.refused.load(Ordering::Relaxed),
+        })
+        .collect();
+    links.sort_by(|a, b| a.name.cmp(&b.name));
+
+    MetricsSnapshot { executors, links }
+}
+
+// Escape a label value for the Prometheus text exposition format, a
+// backslash, double quote or newline would otherwise end or corrupt it.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '"' => escaped.push_str(r#"\""#),
+            '\n' => escaped.push_str(r"\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Convert a snapshot to the Prometheus text exposition format
+pub fn to_prometheus(snapshot: &MetricsSnapshot) -> String {
+    let mut s = String::new();
+
+    // Writing to a String can't fail so the results are ignored
+    let mut family = |name: &str, kind: &str, help: &str| {
+        let _ = writeln!(s, "# HELP {name} {help}");
+        let _ = writeln!(s, "# TYPE {name} {kind}");
+    };
+    family(
+        "executor_select_wakeups_total",
+        "counter",
+        "Number of times select returned",
+    );
+    family("executor_active_actors", "gauge", "Number of actors");
+    family(
+        "actor_msgs_processed_total",
+        "counter",
+        "Messages processed",
+    );
+    family(
+        "actor_unknown_msgs_total",
+        "counter",
+        "Messages the actor didn't understand",
+    );
+    family("actor_mailbox_depth", "gauge", "Messages waiting");
+    family(
+        "actor_processing_seconds",
+        "histogram",
+        "Time to process a message",
+    );
+    for (name, help) in [
+        ("link_bytes_in_total", "Bytes received"),
+        ("link_bytes_out_total", "Bytes sent"),
+        ("link_frames_in_total", "Frames received"),
+        ("link_frames_out_total", "Frames sent"),
+        (
+            "link_decode_errors_total",
+            "Frames that couldn't be decoded",
+        ),
+        ("link_reconnects_total", "Reconnections"),
+        (
+            "link_refused_total",
+            "Messages refused as the peer has no compatible protocol version",
+        ),
+    ] {
+        family(name, "counter", help);
+    }
+
+    for e in &snapshot.executors {
+        let el = format!(
+            r#"executor="{}",executor_instance_id="{}""#,
+            escape_label_value(&e.name),
+            e.instance_id
+        );
+        let _ = writeln!(
+            s,
+            "executor_select_wakeups_total{{{el}}} {}",
+            e.select_wakeups
+        );
+        let _ = writeln!(s, "executor_active_actors{{{el}}} {}", e.active_actors);
+        for a in e.actors.iter() {
+            let al = format!(
+                r#"{el},actor="{}",instance_id="{}""#,
+                escape_label_value(&a.name),
+                a.instance_id
+            );
+            let _ = writeln!(s, "actor_msgs_processed_total{{{al}}} {}", a.msgs_processed);
+            let _ = writeln!(s, "actor_unknown_msgs_total{{{al}}} {}", a.unknown_msgs);
+            let _ = writeln!(s, "actor_mailbox_depth{{{al}}} {}", a.mailbox_depth);
+            let mut cumulative = 0;
+            for (idx, count) in a.processing_time.counts.iter().enumerate() {
+                cumulative += count;
+                let le = match PROCESSING_TIME_BOUNDS_NS.get(idx) {
+                    Some(bound) => format!("{}", *bound as f64 / 1e9),
+                    None => "+Inf".to_owned(),
+                };
+                let _ = writeln!(
+                    s,
+                    r#"actor_processing_seconds_bucket{{{al},le="{le}"}} {cumulative}"#
+                );
+            }
+            let _ = writeln!(
+                s,
+                "actor_processing_seconds_sum{{{al}}} {}",
+                a.processing_time.sum_ns as f64 / 1e9
+            );
+            let _ = writeln!(
+                s,
+                "actor_processing_seconds_count{{{al}}} {}",
+                a.processing_time.count
+            );
+        }
+    }
+
+    for l in &snapshot.links {
+        let ll = format!(
+            r#"link="{}",instance_id="{}""#,
+            escape_label_value(&l.name),
+            l.instance_id
+        );
+        for (name, value) in [
+            ("link_bytes_in_total", l.bytes_in),
+            ("link_bytes_out_total", l.bytes_out),
+            ("link_frames_in_total", l.frames_in),
+            ("link_frames_out_total", l.frames_out),
+            ("link_decode_errors_total", l.decode_errors),
+            ("link_reconnects_total", l.reconnects),
+            ("link_refused_total", l.refused),
+        ] {
+            let _ = writeln!(s, "{name}{{{ll}}} {value}");
+        }
+    }
+
+    s
+}
+
+/// How long the metrics server waits to read a request or write its
+/// response before dropping the connection
+const RESPOND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The most bytes of a request that are read, the rest is ignored
+const MAX_REQUEST_LEN: u64 = 8 * 1024;
+
+/// A running metrics_http_serve server
+#[derive(Debug)]
+pub struct MetricsServer {
+    local_addr: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    join_handle: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// The address it's listening on, useful when it was started on port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting connections and wait for the accept thread to exit,
+    /// connections already accepted are answered by their own threads.
+    pub fn stop(self) {
+        self.stopping.store(true, Ordering::Relaxed);
+
+        // Wake the accept thread so it sees stopping
+        if let Err(why) = TcpStream::connect(self.local_addr) {
+            log::warn!("MetricsServer::stop: unable to wake the accept thread: {why}");
+            return;
+        }
+        if self.join_handle.join().is_err() {
+            log::warn!("MetricsServer::stop: accept thread panicked");
+        }
+    }
+}
+
+/// Serve metrics_snapshot in the Prometheus text format over HTTP, every
+/// request is answered with the metrics regardless of its path.
+///
+/// Each connection is answered on its own thread so a slow client
+/// doesn't hold up the others, and is dropped if reading the request or
+/// writing the response takes longer than RESPOND_TIMEOUT.
+pub fn metrics_http_serve(addr: &str) -> std::io::Result<MetricsServer> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    log::debug!("metrics_http_serve: listening on {local_addr}");
+
+    let stopping = Arc::new(AtomicBool::new(false));
+    let accept_stopping = Arc::clone(&stopping);
+    let join_handle = thread::spawn(move || {
+        for stream in listener.incoming() {
+            if accept_stopping.load(Ordering::Relaxed) {
+                break;
+            }
+            match stream {
+                Ok(stream) => {
+                    thread::spawn(move || {
+                        if let Err(why) = respond(stream) {
+                            log::warn!("metrics_http_serve: error responding: {why}");
+                        }
+                    });
+                }
+                Err(why) => log::warn!("metrics_http_serve: error accepting: {why}"),
+            }
+        }
+        log::debug!("metrics_http_serve: stopped listening on {local_addr}");
+    });
+
+    Ok(MetricsServer {
+        local_addr,
+        stopping,
+        join_handle,
+    })
+}
+
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(RESPOND_TIMEOUT))?;
+    stream.set_write_timeout(Some(RESPOND_TIMEOUT))?;
+
+    // Read the request line and headers, they're ignored
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST_LEN));
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line != "\r\n" && line != "\n" {
+        line.clear();
+    }
+
+    let body = to_prometheus(&metrics_snapshot());
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use crossbeam_channel::unbounded;
+
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut h = Histogram::new();
+        h.observe(Duration::from_nanos(500));
+        h.observe(Duration::from_nanos(1_000));
+        h.observe(Duration::from_micros(50));
+        h.observe(Duration::from_secs(2));
+        assert_eq!(h.counts, vec![2, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(h.count, 4);
+        assert_eq!(h.sum_ns, 500 + 1_000 + 50_000 + 2_000_000_000);
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("ae1"), "ae1");
+        assert_eq!(escape_label_value("a\\b\"c\nd"), r#"a\\b\"c\nd"#);
+    }
+
+    #[test]
+    fn test_snapshot_and_http() {
+        let e_iid = AnId::new();
+        let metrics = executor_metrics_register("test_snapshot_and_http_ae", &e_iid);
+        let (tx, rx) = unbounded::<BoxMsgAny>();
+        let a_iid = AnId::new();
+        metrics.select_wakeups.fetch_add(3, Ordering::Relaxed);
+        let a1 = metrics.actor_add("a1", &a_iid, &rx);
+        a1.processed(Duration::from_micros(5), 1);
+        drop(rx);
+        tx.send(Box::new(1)).unwrap();
+        tx.send(Box::new(2)).unwrap();
+
+        let l_iid = AnId::new();
+        let counters = link_metrics_register("test_snapshot_and_http_link", &l_iid);
+        LinkCounters::add(&counters.bytes_out, 10);
+        LinkCounters::add(&counters.frames_out, 1);
+
+        let snapshot = metrics_snapshot();
+        let e = snapshot
+            .executors
+            .iter()
+            .find(|e| e.instance_id == e_iid)
+            .unwrap();
+        assert_eq!(e.select_wakeups, 3);
+        assert_eq!(e.active_actors, 1);
+        assert_eq!(e.actors[0].msgs_processed, 1);
+        assert_eq!(e.actors[0].processing_time.count, 1);
+        assert_eq!(e.actors[0].processing_time.sum_ns, 5_000);
+        assert_eq!(e.actors[0].unknown_msgs, 1);
+        assert_eq!(e.actors[0].mailbox_depth, 2);
+        let l = snapshot
+            .links
+            .iter()
+            .find(|l| l.instance_id == l_iid)
+            .unwrap();
+        assert_eq!(l.bytes_out, 10);
+        assert_eq!(l.frames_out, 1);
+
+        let server = metrics_http_serve("127.0.0.1:0").unwrap();
+        let local_addr = server.local_addr();
+
+        // A client that never sends its request doesn't hold up the others
+        let _idle = TcpStream::connect(local_addr).unwrap();
+        let mut stream = TcpStream::connect(local_addr).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut rsp = String::new();
+        stream.read_to_string(&mut rsp).unwrap();
+        println!("test_snapshot_and_http: rsp={rsp}");
+        assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(rsp.contains(&format!(
+            r#"actor_mailbox_depth{{executor="test_snapshot_and_http_ae",executor_instance_id="{e_iid}",actor="a1",instance_id="{a_iid}"}} 2"#
+        )));
+        assert!(rsp.contains(&format!(
+            r#"link_bytes_out_total{{link="test_snapshot_and_http_link",instance_id="{l_iid}"}} 10"#
+        )));
+        assert!(rsp.contains(r#"le="+Inf"} 1"#));
+
+        // Once stopped no connections are accepted
+        server.stop();
+        assert!(TcpStream::connect(local_addr).is_err());
+
+        // The counters of a removed actor are dropped with its mailbox
+        metrics.actor_remove(&a_iid);
+        drop(a1);
+        assert!(tx.send(Box::new(3)).is_err());
+        let snapshot = metrics_snapshot();
+        let e = snapshot
+            .executors
+            .iter()
+            .find(|e| e.instance_id == e_iid)
+            .unwrap();
+        assert_eq!(e.active_actors, 0);
+        assert!(e.actors.is_empty());
+
+        executor_metrics_unregister(&e_iid);
+        link_metrics_unregister(&l_iid);
+        assert!(!metrics_snapshot()
+            .executors
+            .iter()
+            .any(|e| e.instance_id == e_iid));
+    }
+}
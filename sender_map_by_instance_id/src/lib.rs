@@ -1,5 +1,11 @@
 //! This module provides a thread safe hashmap that maps instance_id to a sender.
-use std::sync::RwLock;
+//!
+//! Senders cached from the map are valid until sender_map_generation
+//! changes, it is incremented when a sender is removed or replaced.
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    RwLock,
+};
 
 use actor_channel::ActorSender;
 use an_id::AnId;
@@ -8,17 +14,21 @@ use std::collections::HashMap;
 
 static SENDER_HASHMAP: Lazy<RwLock<HashMap<AnId, ActorSender>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
+static SENDER_MAP_GENERATION: AtomicU64 = AtomicU64::new(0);
 
 // Add the sender to the response channel map.
 //
 // This is thread safe and but only one sender is added per instance_id
-// additional invocations will be ignored.
-pub fn sender_map_insert(instance_id: &AnId, sender: &ActorSender) {
+// additional invocations will be ignored. Returns true if sender was added.
+pub fn sender_map_insert(instance_id: &AnId, sender: &ActorSender) -> bool {
     let mut wlocked_hashmap = SENDER_HASHMAP.write().unwrap(); // TODO: remove unwrap
     if !wlocked_hashmap.contains_key(instance_id) {
-        println!("sender_map_insert: instance_id: {}", instance_id);
+        log::debug!("sender_map_insert: instance_id: {}", instance_id);
         let r = wlocked_hashmap.insert(*instance_id, sender.clone());
         assert!(r.is_none());
+        true
+    } else {
+        false
     }
 }
 
@@ -27,13 +37,52 @@ pub fn sender_map_get(instance_id: &AnId) -> Option<ActorSender> {
     let rlocked_hashmap = SENDER_HASHMAP.read().unwrap(); // TODO: remove unwrap
     let sender = rlocked_hashmap.get(instance_id).cloned();
 
-    println!(
+    log::trace!(
         "sender_map_get: instance_id: {} sender: {:?}",
-        instance_id, sender
+        instance_id,
+        sender
     );
     sender
 }
 
+// Remove the sender from the response channel map, returns the sender
+// or None if there was no sender for instance_id.
+pub fn sender_map_remove(instance_id: &AnId) -> Option<ActorSender> {
+    let mut wlocked_hashmap = SENDER_HASHMAP.write().unwrap(); // TODO: remove unwrap
+    let sender = wlocked_hashmap.remove(instance_id);
+    log::debug!(
+        "sender_map_remove: instance_id: {} removed: {}",
+        instance_id,
+        sender.is_some()
+    );
+    if sender.is_some() {
+        SENDER_MAP_GENERATION.fetch_add(1, Ordering::Release);
+    }
+    sender
+}
+
+// Replace the sender for instance_id, adding it if there was none,
+// returns the previous sender.
+pub fn sender_map_replace(instance_id: &AnId, sender: &ActorSender) -> Option<ActorSender> {
+    let mut wlocked_hashmap = SENDER_HASHMAP.write().unwrap(); // TODO: remove unwrap
+    let prev = wlocked_hashmap.insert(*instance_id, sender.clone());
+    log::debug!(
+        "sender_map_replace: instance_id: {} replaced: {}",
+        instance_id,
+        prev.is_some()
+    );
+    if prev.is_some() {
+        SENDER_MAP_GENERATION.fetch_add(1, Ordering::Release);
+    }
+    prev
+}
+
+// Incremented each time a sender is removed or replaced, a cached
+// sender may be stale if the generation has changed since it was cached.
+pub fn sender_map_generation() -> u64 {
+    SENDER_MAP_GENERATION.load(Ordering::Acquire)
+}
+
 #[cfg(test)]
 mod test {
     use actor_channel::ActorChannel;
@@ -58,5 +107,33 @@ mod test {
         sender2.send(Box::new(2)).unwrap();
         let r = ac.receiver.recv().unwrap();
         assert_eq!(r.downcast_ref::<i32>().unwrap(), &2);
+
+        // Verify that sender_map_remove works
+        assert!(sender_map_remove(&instance_id).is_some());
+        assert!(sender_map_get(&instance_id).is_none());
+        assert!(sender_map_remove(&instance_id).is_none());
+    }
+
+    // Test that removing or replacing a sender changes the generation
+    #[test]
+    fn test_sender_map_generation() {
+        let instance_id = AnId::new();
+        let ac1 = ActorChannel::new("test_sender_map_generation1", &instance_id);
+        let ac2 = ActorChannel::new("test_sender_map_generation2", &instance_id);
+
+        assert!(sender_map_replace(&instance_id, &ac1.sender).is_none());
+        let generation = sender_map_generation();
+        assert!(sender_map_replace(&instance_id, &ac2.sender).is_some());
+        assert!(sender_map_generation() > generation);
+
+        sender_map_get(&instance_id)
+            .unwrap()
+            .send(Box::new(2))
+            .unwrap();
+        assert_eq!(ac2.receiver.recv().unwrap().downcast_ref::<i32>(), Some(&2));
+
+        let generation = sender_map_generation();
+        assert!(sender_map_remove(&instance_id).is_some());
+        assert!(sender_map_generation() > generation);
     }
 }
@@ -1,31 +1,22 @@
-use actor::{Actor, ActorContext, ProcessMsgFn};
+use actor::{Actor, ActorContext};
 use actor_channel::ActorChannel;
+use actor_lifecycle::{add_lifecycle_handlers, ActorLifecycle, Lifecycle};
 use an_id::{anid, paste, AnId};
-use cmd_init_issuee_protocol::{cmd_init_issuee_protocol, CmdInit, CMD_INIT_ID};
-use con_mgr_register_actor_protocol::{
-    ConMgrRegisterActorReq, ConMgrRegisterActorRsp, ConMgrRegisterActorStatus,
-    CON_MGR_REGISTER_ACTOR_RSP_ID,
-};
-use echo_requestee_protocol::{echo_requestee_protocol, EchoReq, EchoRsp, ECHO_REQ_ID};
+use cmd_init_issuee_protocol::cmd_init_issuee_protocol;
+use cmd_shutdown_issuee_protocol::cmd_shutdown_issuee_protocol;
+use con_mgr_register_actor_protocol::con_mgr_registee_actor_protocol;
+use echo_requestee_protocol::echo_requestee_protocol;
+use id_inventory::register_id;
 use protocol::Protocol;
 use protocol_set::ProtocolSet;
 use sender_map_by_instance_id::sender_map_insert;
+use state_machine::{add_base_state, base_state, state_machine, HasStateMachine, StateMachine};
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
 };
 
 use box_msg_any::BoxMsgAny;
-use msg_header::MsgHeader;
-
-// State information
-#[derive(Debug)]
-pub struct StateInfo {
-    pub name: String,
-}
-
-// HashMap that maps address of a ProcessMsgFn to StateInfo
-type StateInfoMap<SM> = HashMap<*const ProcessMsgFn<SM>, StateInfo>;
 
 // State machine for channel to network
 pub struct Server {
@@ -33,8 +24,8 @@ pub struct Server {
     pub actor_id: AnId,
     pub instance_id: AnId,
     pub protocol_set: ProtocolSet,
-    pub current_state: ProcessMsgFn<Self>,
-    pub state_info_hash: StateInfoMap<Self>,
+    pub state_machine: StateMachine<Self>,
+    pub lifecycle: Lifecycle,
     pub chnl: ActorChannel,
 }
 
@@ -62,47 +53,67 @@ impl Actor for Server {
     }
 
     fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
-        (self.current_state)(self, context, msg);
+        self.dispatch(context, msg);
     }
 
     fn done(&self) -> bool {
         false
     }
+
+    fn get_protocol_set(&self) -> Option<&ProtocolSet> {
+        Some(&self.protocol_set)
+    }
+}
+
+impl HasStateMachine for Server {
+    fn state_machine(&self) -> &StateMachine<Self> {
+        &self.state_machine
+    }
+
+    fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+        &mut self.state_machine
+    }
+}
+
+impl ActorLifecycle for Server {
+    fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+        &mut self.lifecycle
+    }
 }
 
 impl Debug for Server {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let fn_ptr = self.current_state as *const ProcessMsgFn<Self>;
-        let fn_ptr_string = format!("{fn_ptr:p}");
-        let state_name = if let Some(n) = self.state_info_hash.get(&fn_ptr) {
-            // State does have a name
-            n.name.as_str()
-        } else {
-            // State does NOT have a name, use address
-            fn_ptr_string.as_str()
-        };
-
         write!(
             f,
-            "{} {{ id: {} instance_id: {} state_info_hash: {:?}; current_state: {state_name}; protocol_set: {:?}}}",
-            self.name, self.actor_id, self.instance_id, self.state_info_hash, self.protocol_set
+            "{} {{ id: {} instance_id: {} state_machine: {:?}; protocol_set: {:?}}}",
+            self.name, self.actor_id, self.instance_id, self.state_machine, self.protocol_set
         )
     }
 }
 
 // From: https://www.uuidgenerator.net/version4
-const SERVER_ACTOR_ID: AnId = anid!("d9a4c51e-c42e-4f2e-ae6c-96f62217d892");
+pub const SERVER_ACTOR_ID: AnId = anid!("d9a4c51e-c42e-4f2e-ae6c-96f62217d892");
 const SERVER_PROTOCOL_SET_ID: AnId = anid!("4c797cb5-08ff-4970-9a6b-17c5d296f69f");
+register_id!(Actor, SERVER_ACTOR_ID, "server");
+register_id!(ProtocolSet, SERVER_PROTOCOL_SET_ID, "server_ps");
 
 impl Server {
     pub fn new(name: &str) -> Self {
         // Create the server ProtocolSet, `server_ps`.
-        println!("Server::new({})", name);
+        log::debug!("Server::new({})", name);
         let mut server_pm = HashMap::<AnId, Protocol>::new();
         let erep = echo_requestee_protocol();
         server_pm.insert(erep.id, erep.clone());
         let ci_iep = cmd_init_issuee_protocol();
         server_pm.insert(ci_iep.id, ci_iep.clone());
+        let cs_iep = cmd_shutdown_issuee_protocol();
+        server_pm.insert(cs_iep.id, cs_iep.clone());
+        let cm_rap = con_mgr_registee_actor_protocol();
+        server_pm.insert(cm_rap.id, cm_rap.clone());
 
         let server_ps = ProtocolSet::new("server_ps", SERVER_PROTOCOL_SET_ID, server_pm);
 
@@ -110,77 +121,38 @@ impl Server {
         let chnl_name = name.to_owned() + "_chnl";
         let chnl = ActorChannel::new(&chnl_name, &server_instance_id);
 
-        let mut this = Self {
+        let mut state_machine = state_machine!(Self, [state0]);
+        let base = add_base_state(&mut state_machine);
+        state_machine.set_parent(Self::state0, base);
+        add_lifecycle_handlers(&mut state_machine, base);
+
+        let this = Self {
             name: name.to_owned(),
             actor_id: SERVER_ACTOR_ID,
             instance_id: server_instance_id,
             protocol_set: server_ps,
-            current_state: Self::state0,
-            state_info_hash: StateInfoMap::<Self>::new(),
+            state_machine,
+            lifecycle: Lifecycle::new(true),
             chnl,
         };
 
         // Add ourself to the sender_map
         sender_map_insert(&this.instance_id, &this.chnl.sender);
 
-        this.add_state(Self::state0, "state0");
         this
     }
 
-    pub fn add_state(&mut self, state: ProcessMsgFn<Self>, name: &str) {
-        let s = StateInfo {
-            name: name.to_owned(),
-        };
-        let k = state as *const ProcessMsgFn<Self>;
-        self.state_info_hash.insert(k, s);
-    }
-
-    #[allow(unused)]
-    fn transition(&mut self, dest: ProcessMsgFn<Self>) {
-        self.current_state = dest;
+    // ActorFactoryFn for Server, there are no init parameters so params is ignored
+    pub fn factory(
+        name: &str,
+        _params: &str,
+    ) -> Result<Box<dyn Actor>, Box<dyn std::error::Error>> {
+        Ok(Box::new(Self::new(name)))
     }
 
     pub fn state0(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
-        if let Some(msg) = msg_any.downcast_ref::<EchoReq>() {
-            assert_eq!(msg.msg_id(), &ECHO_REQ_ID);
-            //println!("{}:State0: msg={msg:?}", self.name);
-            let rsp_msg = Box::new(EchoRsp::new(
-                context.get_dst_instance_id(),
-                &self.instance_id,
-                msg.req_timestamp_ns,
-                msg.counter,
-            ));
-            //println!("{}:State0: sending rsp_msg={rsp_msg:?}", self.name);
-            context.send_dst(rsp_msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<CmdInit>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CMD_INIT_ID);
-
-            // Register ourselves with ConMgr
-            let msg = Box::new(ConMgrRegisterActorReq::new(
-                msg.src_id(),
-                &self.instance_id,
-                &self.name,
-                &self.actor_id,
-                &self.instance_id,
-                &self.protocol_set,
-            ));
-            print!(
-                "{}:State0: sending ConMgrRegisterActorReq={msg:?}",
-                self.name
-            );
-            context.send_con_mgr(msg).unwrap();
-        } else if let Some(msg) = msg_any.downcast_ref::<ConMgrRegisterActorRsp>() {
-            println!("{}:State0: {msg:?}", self.name);
-            assert_eq!(msg.msg_id(), &CON_MGR_REGISTER_ACTOR_RSP_ID);
-            assert_eq!(msg.status, ConMgrRegisterActorStatus::Success);
-        } else {
-            let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
-            println!(
-                "{}:State0: Unknown msg_any={msg_any:?} {msg_id:?}",
-                self.name
-            );
-        }
+        // Messages without a handler are unknown
+        base_state(self, context, msg_any);
     }
 }
 
@@ -188,8 +160,12 @@ impl Server {
 mod test {
     use actor_channel::ActorSender;
     use chrono::Utc;
-    use cmd_init_issuer_protocol::CmdInit;
-    use con_mgr_register_actor_protocol::CON_MGR_REGISTER_ACTOR_REQ_ID;
+    use cmd_init_issuer_protocol::{CmdInit, CmdReady, CmdReadyStatus};
+    use con_mgr_register_actor_protocol::{
+        ConMgrRegisterActorRsp, ConMgrRegisterActorStatus, CON_MGR_REGISTER_ACTOR_REQ_ID,
+    };
+    use echo_requestee_protocol::{EchoReq, EchoRsp};
+    use msg_header::MsgHeader;
 
     use super::*;
 
@@ -224,8 +200,8 @@ mod test {
             self.dst_sndr.get_dst_instance_id()
         }
 
-        fn clone_dst_sndr(&self) -> ActorSender {
-            self.dst_sndr.clone()
+        fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>> {
+            Ok(self.dst_sndr.clone())
         }
     }
 
@@ -392,6 +368,12 @@ mod test {
         ));
         server.process_msg_any(&server_context, msg);
 
+        // Server is ready and sends CmdReady to the issuer of CmdInit
+        let ready_msg_any = supervisor_chnl.receiver.recv().unwrap();
+        let ready_msg = ready_msg_any.downcast_ref::<CmdReady>().unwrap();
+        assert_eq!(ready_msg.src_id(), &server.instance_id);
+        assert_eq!(ready_msg.status, CmdReadyStatus::Ready);
+
         println!("test_cmd_init:-");
     }
 }
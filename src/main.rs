@@ -1,3 +1,60 @@
-fn main() {
-    println!("experiment_inter_process_channel, does nothing!");
+//! Node daemon, brings up the node described by the config file given
+//! as the only argument and runs until signalled.
+use std::{path::Path, process::ExitCode};
+
+use actor_factory::actor_factory_insert;
+use client::{Client, CLIENT_ACTOR_ID};
+use crossbeam_channel::bounded;
+use node::{Node, NodeConfig};
+use server::{Server, SERVER_ACTOR_ID};
+
+fn main() -> ExitCode {
+    // RUST_LOG overrides the default level
+    custom_logger::env_logger_init("info");
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 2 {
+        eprintln!("Usage: {} <config.toml|config.json>", args[0]);
+        return ExitCode::FAILURE;
+    }
+
+    let config = match NodeConfig::from_file(Path::new(&args[1])) {
+        Ok(config) => config,
+        Err(why) => {
+            eprintln!("{why}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // The actors a config may spawn
+    actor_factory_insert(&CLIENT_ACTOR_ID, Client::factory);
+    actor_factory_insert(&SERVER_ACTOR_ID, Server::factory);
+
+    let (signal_tx, signal_rx) = bounded::<()>(1);
+    if let Err(why) = ctrlc::set_handler(move || {
+        let _ = signal_tx.try_send(());
+    }) {
+        eprintln!("Unable to set signal handler: {why}");
+        return ExitCode::FAILURE;
+    }
+
+    let node = match Node::start(&config) {
+        Ok(node) => node,
+        Err(why) => {
+            eprintln!("Node {} failed to start: {why}", config.name);
+            return ExitCode::FAILURE;
+        }
+    };
+    for dispatcher in config.dispatchers.iter() {
+        if let Some(local_addr) = node.local_addr(&dispatcher.name) {
+            println!("Dispatcher {} listening on {local_addr}", dispatcher.name);
+        }
+    }
+    println!("Node {} running", node.name);
+
+    let _ = signal_rx.recv();
+    println!("Node {} shutting down", node.name);
+    node.shutdown();
+
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,845 @@
+//! A state machine shared by actors.
+//!
+//! Each state is a ProcessMsgFn, the actor holds a StateMachine<Self>
+//! and implements HasStateMachine so process_msg_any can simply call
+//! `self.dispatch(context, msg)`. States are named so the Debug output
+//! shows the current state, may have entry and exit hooks and the most
+//! recent transitions are kept in a history for debugging.
+//!
+//! A state requests a transition with `self.state_machine.transition(dest)`,
+//! it takes effect after the state returns: the exit hook of the current
+//! state is called, the transition is recorded and then the entry hook
+//! of dest is called.
+//!
+//! Rather than downcasting a message to each type it handles a state may
+//! have a table of handlers keyed by msg_id, see `msg_handlers!`, and a
+//! parent state whose handlers are used for messages it has no handler
+//! for. A message is dispatched to the first handler found looking up its
+//! msg_id in the current state and then its ancestors, if there is none
+//! the current state's ProcessMsgFn is called.
+//!
+//! Actors share a parent state, see `add_base_state`, which answers
+//! EchoReq and counts the messages nothing handles as unknown.
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Debug},
+};
+
+pub use actor::{Actor, ActorContext, ProcessMsgFn};
+use an_id::AnId;
+pub use box_msg_any::BoxMsgAny;
+use echo_req::{EchoReq, ECHO_REQ_ID};
+use echo_rsp::EchoRsp;
+use msg_header::MsgHeader;
+
+/// Entry or exit hook of a state
+pub type StateHookFn<SM> = fn(&mut SM, context: &dyn ActorContext);
+
+/// Number of transitions kept in the history by default
+pub const STATE_MACHINE_HISTORY_CAPACITY: usize = 16;
+
+// State information
+pub struct StateInfo<SM> {
+    pub name: String,
+    pub state: ProcessMsgFn<SM>,
+    pub parent: Option<ProcessMsgFn<SM>>,
+    pub handlers: HashMap<AnId, ProcessMsgFn<SM>>,
+    pub on_entry: Option<StateHookFn<SM>>,
+    pub on_exit: Option<StateHookFn<SM>>,
+}
+
+impl<SM> StateInfo<SM> {
+    pub fn new(name: &str, state: ProcessMsgFn<SM>) -> Self {
+        Self {
+            name: name.to_owned(),
+            state,
+            parent: None,
+            handlers: HashMap::new(),
+            on_entry: None,
+            on_exit: None,
+        }
+    }
+}
+
+impl<SM> Debug for StateInfo<SM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {{ handlers: {}, on_entry: {}, on_exit: {} }}",
+            self.name,
+            self.handlers.len(),
+            self.on_entry.is_some(),
+            self.on_exit.is_some()
+        )
+    }
+}
+
+/// A transition from one state to another and the id of the message
+/// being processed when it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    pub msg_id: AnId,
+}
+
+// The key of a state is the address of its ProcessMsgFn
+fn state_key<SM>(state: ProcessMsgFn<SM>) -> usize {
+    state as usize
+}
+
+pub struct StateMachine<SM> {
+    current: ProcessMsgFn<SM>,
+    pending: Option<ProcessMsgFn<SM>>,
+    entered: bool,
+    states: HashMap<usize, StateInfo<SM>>,
+    history: VecDeque<Transition>,
+    history_capacity: usize,
+    transition_log: Option<String>,
+}
+
+impl<SM> StateMachine<SM> {
+    /// Create a StateMachine whose current state is initial
+    pub fn new(initial: ProcessMsgFn<SM>, name: &str) -> Self {
+        Self::from_states(vec![StateInfo::new(name, initial)])
+    }
+
+    /// Create a StateMachine from states, the first is the initial state.
+    /// Used by the `state_machine!` macro.
+    ///
+    /// # Panics
+    ///
+    /// If states is empty, has a state twice or their parents aren't
+    /// valid, see set_parent
+    pub fn from_states(states: Vec<StateInfo<SM>>) -> Self {
+        let initial = states.first().expect("at least one state").state;
+        let mut sm = Self {
+            current: initial,
+            pending: None,
+            entered: false,
+            states: HashMap::new(),
+            history: VecDeque::new(),
+            history_capacity: STATE_MACHINE_HISTORY_CAPACITY,
+            transition_log: None,
+        };
+        let mut parents = vec![];
+        for mut si in states {
+            if let Some(parent) = si.parent.take() {
+                parents.push((si.state, parent));
+            }
+            sm.add_state_info(si);
+        }
+        for (state, parent) in parents {
+            sm.set_parent(state, parent);
+        }
+        sm
+    }
+
+    pub fn add_state(&mut self, state: ProcessMsgFn<SM>, name: &str) {
+        self.add_state_info(StateInfo::new(name, state));
+    }
+
+    pub fn add_state_with_hooks(
+        &mut self,
+        state: ProcessMsgFn<SM>,
+        name: &str,
+        on_entry: Option<StateHookFn<SM>>,
+        on_exit: Option<StateHookFn<SM>>,
+    ) {
+        let mut si = StateInfo::new(name, state);
+        si.on_entry = on_entry;
+        si.on_exit = on_exit;
+        self.add_state_info(si);
+    }
+
+    /// # Panics
+    ///
+    /// If state_info's state was already added, whatever its name
+    pub fn add_state_info(&mut self, state_info: StateInfo<SM>) {
+        let key = state_key(state_info.state);
+        if let Some(existing) = self.states.get(&key) {
+            panic!(
+                "state {} was already added as state {}",
+                state_info.name, existing.name
+            );
+        }
+        self.states.insert(key, state_info);
+    }
+
+    fn state_info_mut(&mut self, state: ProcessMsgFn<SM>) -> &mut StateInfo<SM> {
+        let name = self.state_name(state);
+        match self.states.get_mut(&state_key(state)) {
+            Some(si) => si,
+            None => panic!("state {name} must be added before it's used"),
+        }
+    }
+
+    /// Messages state has no handler for are looked up in parent
+    ///
+    /// # Panics
+    ///
+    /// If state or parent haven't been added or parent is state or
+    /// one of its descendants
+    pub fn set_parent(&mut self, state: ProcessMsgFn<SM>, parent: ProcessMsgFn<SM>) {
+        let mut ancestor = Some(parent);
+        while let Some(a) = ancestor {
+            assert!(
+                state_key(a) != state_key(state),
+                "parent of {} can't be a descendant of it",
+                self.state_name(state)
+            );
+            ancestor = self.parent_state(a);
+        }
+        self.state_info_mut(parent);
+        self.state_info_mut(state).parent = Some(parent);
+    }
+
+    pub fn parent_state(&self, state: ProcessMsgFn<SM>) -> Option<ProcessMsgFn<SM>> {
+        self.states.get(&state_key(state)).and_then(|si| si.parent)
+    }
+
+    /// Handle messages with msg_id with handler while in state or one
+    /// of its descendants.
+    ///
+    /// # Panics
+    ///
+    /// If state hasn't been added
+    pub fn add_handler(
+        &mut self,
+        state: ProcessMsgFn<SM>,
+        msg_id: AnId,
+        handler: ProcessMsgFn<SM>,
+    ) {
+        self.state_info_mut(state).handlers.insert(msg_id, handler);
+    }
+
+    /// The handler for msg_id in the current state, None if neither it
+    /// nor its ancestors have one.
+    pub fn handler(&self, msg_id: &AnId) -> Option<ProcessMsgFn<SM>> {
+        let mut state = self.states.get(&state_key(self.current));
+        while let Some(si) = state {
+            if let Some(handler) = si.handlers.get(msg_id) {
+                return Some(*handler);
+            }
+            state = si.parent.and_then(|p| self.states.get(&state_key(p)));
+        }
+        None
+    }
+
+    pub fn current_state(&self) -> ProcessMsgFn<SM> {
+        self.current
+    }
+
+    /// Name of state, if it doesn't have one its address is returned
+    pub fn state_name(&self, state: ProcessMsgFn<SM>) -> String {
+        match self.states.get(&state_key(state)) {
+            Some(si) => si.name.clone(),
+            None => format!("{:p}", state as *const ProcessMsgFn<SM>),
+        }
+    }
+
+    pub fn current_state_name(&self) -> String {
+        self.state_name(self.current)
+    }
+
+    /// Transition to dest after the current state returns
+    pub fn transition(&mut self, dest: ProcessMsgFn<SM>) {
+        self.pending = Some(dest);
+    }
+
+    /// The most recent transitions, oldest first
+    pub fn history(&self) -> &VecDeque<Transition> {
+        &self.history
+    }
+
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// Log each transition with log::debug prefixed by name, None
+    /// disables the transition log.
+    pub fn set_transition_log(&mut self, name: Option<&str>) {
+        self.transition_log = name.map(|n| n.to_owned());
+    }
+
+    fn on_entry(&self, state: ProcessMsgFn<SM>) -> Option<StateHookFn<SM>> {
+        self.states
+            .get(&state_key(state))
+            .and_then(|si| si.on_entry)
+    }
+
+    fn on_exit(&self, state: ProcessMsgFn<SM>) -> Option<StateHookFn<SM>> {
+        self.states.get(&state_key(state)).and_then(|si| si.on_exit)
+    }
+
+    fn record(&mut self, dest: ProcessMsgFn<SM>, msg_id: AnId) {
+        let transition = Transition {
+            from: self.current_state_name(),
+            to: self.state_name(dest),
+            msg_id,
+        };
+        if let Some(name) = &self.transition_log {
+            log::debug!(
+                "{name}: {} -> {} msg_id: {}",
+                transition.from,
+                transition.to,
+                transition.msg_id
+            );
+        }
+        if self.history_capacity > 0 {
+            if self.history.len() == self.history_capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back(transition);
+        }
+        self.current = dest;
+    }
+}
+
+impl<SM> Debug for StateMachine<SM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&str> = self.states.values().map(|si| si.name.as_str()).collect();
+        names.sort();
+        write!(
+            f,
+            "StateMachine {{ current_state: {}; states: {names:?}; history: [",
+            self.current_state_name()
+        )?;
+        for (i, t) in self.history.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} -> {}", t.from, t.to)?;
+        }
+        write!(f, "] }}")
+    }
+}
+
+/// Implemented by the owner of a StateMachine so dispatch can call the
+/// states and hooks, which need `&mut Self`.
+pub trait HasStateMachine: Sized {
+    fn state_machine(&self) -> &StateMachine<Self>;
+    fn state_machine_mut(&mut self) -> &mut StateMachine<Self>;
+
+    /// Process msg with its handler, or the current state if there is
+    /// no handler, then perform the transition, if any, it requested. The
+    /// entry hook of the initial state is called before the first message
+    /// is processed.
+    fn dispatch(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
+        let msg_id = *MsgHeader::get_msg_id_from_boxed_msg_any(&msg);
+
+        if !self.state_machine().entered {
+            self.state_machine_mut().entered = true;
+            let current = self.state_machine().current;
+            if let Some(on_entry) = self.state_machine().on_entry(current) {
+                on_entry(self, context);
+            }
+            self.complete_transitions(context, msg_id);
+        }
+
+        let state = self
+            .state_machine()
+            .handler(&msg_id)
+            .unwrap_or(self.state_machine().current);
+        state(self, context, msg);
+        self.complete_transitions(context, msg_id);
+    }
+
+    /// Perform pending transitions, an entry hook may itself transition
+    fn complete_transitions(&mut self, context: &dyn ActorContext, msg_id: AnId) {
+        while let Some(dest) = self.state_machine_mut().pending.take() {
+            let current = self.state_machine().current;
+            if let Some(on_exit) = self.state_machine().on_exit(current) {
+                on_exit(self, context);
+            }
+            self.state_machine_mut().record(dest, msg_id);
+            if let Some(on_entry) = self.state_machine().on_entry(dest) {
+                on_entry(self, context);
+            }
+        }
+    }
+}
+
+/// Create a StateMachine for the type `$ty` from a list of its state
+/// methods, the first is the initial state. Each state may be followed
+/// by `{ parent: state, entry: method, exit: method }` naming its parent
+/// and hooks, any of which may be omitted. The states are named after
+/// their methods.
+///
+/// ```ignore
+/// state_machine: state_machine!(Self, [
+///     state0,
+///     state1 { parent: state0, entry: enter_state1, exit: exit_state1 },
+/// ]),
+/// ```
+#[macro_export]
+macro_rules! state_machine {
+    ($ty:ty, [ $( $state:ident $( { $( $hook:ident : $hook_fn:ident ),* $(,)? } )? ),+ $(,)? ]) => {{
+        let states: Vec<$crate::StateInfo<$ty>> = vec![ $( {
+            #[allow(unused_mut)]
+            let mut si = $crate::StateInfo::<$ty>::new(stringify!($state), <$ty>::$state);
+            $( $( $crate::state_machine!(@hook si, $ty, $hook, $hook_fn); )* )?
+            si
+        } ),+ ];
+        $crate::StateMachine::<$ty>::from_states(states)
+    }};
+    (@hook $si:ident, $ty:ty, entry, $hook_fn:ident) => {
+        $si.on_entry = Some(<$ty>::$hook_fn);
+    };
+    (@hook $si:ident, $ty:ty, exit, $hook_fn:ident) => {
+        $si.on_exit = Some(<$ty>::$hook_fn);
+    };
+    (@hook $si:ident, $ty:ty, parent, $parent:ident) => {
+        $si.parent = Some(<$ty>::$parent);
+    };
+}
+
+/// Add handlers to `$state` of the StateMachine `$sm` of `$ty`. Each
+/// handler is a method of `$ty` taking a reference to the message type
+/// and is registered with the msg_id of that type:
+///
+/// ```ignore
+/// msg_handlers!(state_machine, Self, base, [
+///     ECHO_REQ_ID: EchoReq => handle_echo_req,
+///     CMD_INIT_ID: CmdInit => handle_cmd_init,
+/// ]);
+///
+/// fn handle_echo_req(&mut self, context: &dyn ActorContext, msg: &EchoReq) {
+/// ```
+#[macro_export]
+macro_rules! msg_handlers {
+    ($sm:expr, $ty:ty, $state:ident, [ $( $msg_id:path : $msg_ty:ty => $handler:ident ),* $(,)? ]) => {
+        $(
+            $sm.add_handler(
+                <$ty>::$state,
+                $msg_id,
+                |sm: &mut $ty, context: &dyn $crate::ActorContext, msg_any: $crate::BoxMsgAny| {
+                    match msg_any.downcast_ref::<$msg_ty>() {
+                        Some(msg) => <$ty>::$handler(sm, context, msg),
+                        None => $crate::mismatched_msg(context, &msg_any, stringify!($msg_ty)),
+                    }
+                },
+            );
+        )*
+    };
+}
+
+/// Called by handlers added with `msg_handlers!` when a message has the
+/// msg_id of the handler but isn't of its type.
+pub fn mismatched_msg(context: &dyn ActorContext, msg_any: &BoxMsgAny, expected: &str) {
+    let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(msg_any);
+    log::error!("mismatched_msg: msg_id {msg_id} isn't a {expected}");
+    context.record_unknown_msg(msg_id);
+}
+
+/// The state actors use as the parent of their states, added by
+/// `add_base_state`. Messages it has no handler for are unknown.
+pub fn base_state<SM: Actor>(actor: &mut SM, context: &dyn ActorContext, msg_any: BoxMsgAny) {
+    let msg_id = MsgHeader::get_msg_id_from_boxed_msg_any(&msg_any);
+    log::warn!(
+        "{}:Base: Unknown msg_any={msg_any:?} {msg_id:?}",
+        actor.get_name()
+    );
+    context.record_unknown_msg(msg_id);
+}
+
+/// Add base_state, named "base", with the handlers every actor has.
+/// Returns it so it can be made the parent of the actor's states:
+///
+/// ```ignore
+/// let mut state_machine = state_machine!(Self, [state0]);
+/// let base = add_base_state(&mut state_machine);
+/// state_machine.set_parent(Self::state0, base);
+/// ```
+pub fn add_base_state<SM: Actor>(state_machine: &mut StateMachine<SM>) -> ProcessMsgFn<SM> {
+    let base: ProcessMsgFn<SM> = base_state::<SM>;
+    state_machine.add_state(base, "base");
+    state_machine.add_handler(base, ECHO_REQ_ID, handle_echo_req::<SM>);
+    base
+}
+
+// Respond to an EchoReq with an EchoRsp
+fn handle_echo_req<SM: Actor>(actor: &mut SM, context: &dyn ActorContext, msg_any: BoxMsgAny) {
+    let Some(msg) = msg_any.downcast_ref::<EchoReq>() else {
+        mismatched_msg(context, &msg_any, "EchoReq");
+        return;
+    };
+    log::trace!("{}:Base: msg={msg:?}", actor.get_name());
+    let rsp_msg = Box::new(EchoRsp::new(
+        context.get_dst_instance_id(),
+        actor.get_instance_id(),
+        msg.req_timestamp_ns,
+        msg.counter,
+    ));
+    context.send_dst(rsp_msg).unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actor_channel::{ActorChannel, ActorSender};
+    use echo_req::{EchoReq, ECHO_REQ_ID};
+    use echo_rsp::{EchoRsp, ECHO_RSP_ID};
+    use msg1::Msg1;
+
+    struct Context {
+        sndr: ActorSender,
+    }
+
+    impl ActorContext for Context {
+        fn actor_executor_sndr(&self) -> &ActorSender {
+            &self.sndr
+        }
+
+        fn send_con_mgr(&self, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+            self.sndr.send(msg)
+        }
+
+        fn get_con_mgr_instance_id(&self) -> &AnId {
+            self.sndr.get_dst_instance_id()
+        }
+
+        fn send_self(&self, _msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn send_dst(&self, msg: BoxMsgAny) -> Result<(), Box<dyn std::error::Error>> {
+            self.sndr.send(msg)
+        }
+
+        fn get_dst_instance_id(&self) -> &AnId {
+            self.sndr.get_dst_instance_id()
+        }
+
+        fn clone_dst_sndr(&self) -> Result<ActorSender, Box<dyn std::error::Error>> {
+            Ok(self.sndr.clone())
+        }
+    }
+
+    struct Toggle {
+        state_machine: StateMachine<Self>,
+        events: Vec<String>,
+    }
+
+    impl HasStateMachine for Toggle {
+        fn state_machine(&self) -> &StateMachine<Self> {
+            &self.state_machine
+        }
+
+        fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+            &mut self.state_machine
+        }
+    }
+
+    impl Toggle {
+        fn new() -> Self {
+            Self {
+                state_machine: state_machine!(
+                    Self,
+                    [
+                        off { entry: enter_off },
+                        on {
+                            entry: enter_on,
+                            exit: exit_on
+                        },
+                    ]
+                ),
+                events: vec![],
+            }
+        }
+
+        fn off(&mut self, _context: &dyn ActorContext, _msg: BoxMsgAny) {
+            self.events.push("off".to_owned());
+            self.state_machine.transition(Self::on);
+        }
+
+        fn on(&mut self, _context: &dyn ActorContext, _msg: BoxMsgAny) {
+            self.events.push("on".to_owned());
+            self.state_machine.transition(Self::off);
+        }
+
+        fn enter_off(&mut self, _context: &dyn ActorContext) {
+            self.events.push("enter_off".to_owned());
+        }
+
+        fn enter_on(&mut self, _context: &dyn ActorContext) {
+            self.events.push("enter_on".to_owned());
+        }
+
+        fn exit_on(&mut self, _context: &dyn ActorContext) {
+            self.events.push("exit_on".to_owned());
+        }
+    }
+
+    fn echo_req() -> BoxMsgAny {
+        Box::new(EchoReq::new(&AnId::new(), &AnId::new(), 1))
+    }
+
+    #[test]
+    fn test_state_machine() {
+        println!("\ntest_state_machine:+");
+        let chnl = ActorChannel::new("test", &AnId::new());
+        let context = Context {
+            sndr: chnl.sender.clone(),
+        };
+
+        let mut toggle = Toggle::new();
+        assert_eq!(toggle.state_machine.current_state_name(), "off");
+
+        toggle.dispatch(&context, echo_req());
+        assert_eq!(toggle.events, vec!["enter_off", "off", "enter_on"]);
+        assert_eq!(toggle.state_machine.current_state_name(), "on");
+
+        toggle.events.clear();
+        toggle.dispatch(&context, echo_req());
+        assert_eq!(toggle.events, vec!["on", "exit_on", "enter_off"]);
+        assert_eq!(toggle.state_machine.current_state_name(), "off");
+
+        let history: Vec<(&str, &str)> = toggle
+            .state_machine
+            .history()
+            .iter()
+            .map(|t| (t.from.as_str(), t.to.as_str()))
+            .collect();
+        assert_eq!(history, vec![("off", "on"), ("on", "off")]);
+        assert_eq!(
+            toggle.state_machine.history()[0].msg_id,
+            echo_req::ECHO_REQ_ID
+        );
+
+        let dbg = format!("{:?}", toggle.state_machine);
+        println!("test_state_machine: {dbg}");
+        assert_eq!(
+            dbg,
+            r#"StateMachine { current_state: off; states: ["off", "on"]; history: [off -> on, on -> off] }"#
+        );
+        println!("test_state_machine:-");
+    }
+
+    #[test]
+    fn test_state_machine_history_capacity() {
+        println!("\ntest_state_machine_history_capacity:+");
+        let chnl = ActorChannel::new("test", &AnId::new());
+        let context = Context {
+            sndr: chnl.sender.clone(),
+        };
+
+        let mut toggle = Toggle::new();
+        toggle.state_machine.set_transition_log(Some("toggle"));
+        toggle.state_machine.set_history_capacity(2);
+        for _ in 0..5 {
+            toggle.dispatch(&context, echo_req());
+        }
+        assert_eq!(toggle.state_machine.history().len(), 2);
+        assert_eq!(toggle.state_machine.history()[1].to, "on");
+
+        toggle.state_machine.set_history_capacity(0);
+        assert!(toggle.state_machine.history().is_empty());
+        toggle.dispatch(&context, echo_req());
+        assert!(toggle.state_machine.history().is_empty());
+        println!("test_state_machine_history_capacity:-");
+    }
+
+    #[test]
+    fn test_state_machine_unnamed_state() {
+        let mut sm = StateMachine::<Toggle>::new(Toggle::off, "off");
+        assert_eq!(sm.state_name(Toggle::off), "off");
+        assert!(sm.state_name(Toggle::on).starts_with("0x"));
+        sm.add_state_with_hooks(Toggle::on, "on", None, Some(Toggle::exit_on));
+        assert_eq!(sm.state_name(Toggle::on), "on");
+        assert!(sm.on_exit(Toggle::on).is_some());
+        assert!(sm.on_entry(Toggle::on).is_none());
+    }
+
+    struct Nested {
+        state_machine: StateMachine<Self>,
+        events: Vec<String>,
+    }
+
+    impl HasStateMachine for Nested {
+        fn state_machine(&self) -> &StateMachine<Self> {
+            &self.state_machine
+        }
+
+        fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+            &mut self.state_machine
+        }
+    }
+
+    impl Nested {
+        fn new() -> Self {
+            let mut state_machine =
+                state_machine!(Self, [idle { parent: base }, base, busy { parent: idle },]);
+            msg_handlers!(state_machine, Self, base, [
+                ECHO_REQ_ID: EchoReq => handle_echo_req,
+            ]);
+            msg_handlers!(state_machine, Self, busy, [
+                ECHO_RSP_ID: EchoRsp => handle_echo_rsp,
+            ]);
+            Self {
+                state_machine,
+                events: vec![],
+            }
+        }
+
+        fn base(&mut self, _context: &dyn ActorContext, _msg: BoxMsgAny) {
+            self.events.push("base".to_owned());
+        }
+
+        fn idle(&mut self, _context: &dyn ActorContext, _msg: BoxMsgAny) {
+            self.events.push("idle".to_owned());
+        }
+
+        fn busy(&mut self, _context: &dyn ActorContext, _msg: BoxMsgAny) {
+            self.events.push("busy".to_owned());
+        }
+
+        fn handle_echo_req(&mut self, _context: &dyn ActorContext, msg: &EchoReq) {
+            self.events.push(format!("echo_req {}", msg.counter));
+            self.state_machine.transition(Self::busy);
+        }
+
+        fn handle_echo_rsp(&mut self, _context: &dyn ActorContext, msg: &EchoRsp) {
+            self.events.push(format!("echo_rsp {}", msg.counter));
+            self.state_machine.transition(Self::idle);
+        }
+    }
+
+    #[test]
+    fn test_state_machine_msg_handlers() {
+        println!("\ntest_state_machine_msg_handlers:+");
+        let chnl = ActorChannel::new("test", &AnId::new());
+        let context = Context {
+            sndr: chnl.sender.clone(),
+        };
+        let echo_rsp = || -> BoxMsgAny { Box::new(EchoRsp::new(&AnId::new(), &AnId::new(), 1, 2)) };
+
+        let mut nested = Nested::new();
+        assert_eq!(nested.state_machine.current_state_name(), "idle");
+        let busy_parent = nested.state_machine.parent_state(Nested::busy).unwrap();
+        assert_eq!(nested.state_machine.state_name(busy_parent), "idle");
+
+        // idle has no EchoRsp handler, nor does base, so idle gets it
+        nested.dispatch(&context, echo_rsp());
+        // EchoReq is handled by base, the parent of idle
+        nested.dispatch(&context, echo_req());
+        assert_eq!(nested.state_machine.current_state_name(), "busy");
+        // EchoReq is handled by base, the grandparent of busy
+        nested.dispatch(&context, echo_req());
+        // EchoRsp is handled by busy
+        nested.dispatch(&context, echo_rsp());
+        assert_eq!(nested.state_machine.current_state_name(), "idle");
+        // Messages without a handler go to the current state
+        nested.dispatch(&context, Box::new(Msg1::new(&AnId::new(), &AnId::new(), 1)));
+        assert_eq!(
+            nested.events,
+            vec!["idle", "echo_req 1", "echo_req 1", "echo_rsp 2", "idle"]
+        );
+        println!("test_state_machine_msg_handlers:-");
+    }
+
+    #[derive(Debug)]
+    struct Echoer {
+        instance_id: AnId,
+        chnl: ActorChannel,
+        state_machine: StateMachine<Self>,
+    }
+
+    impl HasStateMachine for Echoer {
+        fn state_machine(&self) -> &StateMachine<Self> {
+            &self.state_machine
+        }
+
+        fn state_machine_mut(&mut self) -> &mut StateMachine<Self> {
+            &mut self.state_machine
+        }
+    }
+
+    impl Actor for Echoer {
+        fn get_name(&self) -> &str {
+            "echoer"
+        }
+
+        fn get_actor_id(&self) -> &AnId {
+            &self.instance_id
+        }
+
+        fn get_instance_id(&self) -> &AnId {
+            &self.instance_id
+        }
+
+        fn get_chnl(&self) -> &ActorChannel {
+            &self.chnl
+        }
+
+        fn process_msg_any(&mut self, context: &dyn ActorContext, msg: BoxMsgAny) {
+            self.dispatch(context, msg);
+        }
+
+        fn done(&self) -> bool {
+            false
+        }
+    }
+
+    impl Echoer {
+        fn new() -> Self {
+            let instance_id = AnId::new();
+            let mut state_machine = state_machine!(Self, [state0]);
+            let base = add_base_state(&mut state_machine);
+            state_machine.set_parent(Self::state0, base);
+            Self {
+                instance_id,
+                chnl: ActorChannel::new("echoer", &instance_id),
+                state_machine,
+            }
+        }
+
+        fn state0(&mut self, context: &dyn ActorContext, msg_any: BoxMsgAny) {
+            base_state(self, context, msg_any);
+        }
+    }
+
+    #[test]
+    fn test_state_machine_base_state() {
+        println!("\ntest_state_machine_base_state:+");
+        let chnl = ActorChannel::new("test", &AnId::new());
+        let context = Context {
+            sndr: chnl.sender.clone(),
+        };
+
+        let mut echoer = Echoer::new();
+        let base = echoer.state_machine.parent_state(Echoer::state0).unwrap();
+        assert_eq!(echoer.state_machine.state_name(base), "base");
+
+        // EchoReq is answered by the base state
+        echoer.dispatch(&context, echo_req());
+        let msg_any = chnl.receiver.rx.try_recv().unwrap();
+        let rsp = msg_any.downcast_ref::<EchoRsp>().unwrap();
+        assert_eq!(rsp.src_id(), &echoer.instance_id);
+        assert_eq!(rsp.counter, 1);
+
+        // Anything else is unknown and there's no response
+        echoer.dispatch(&context, Box::new(Msg1::new(&AnId::new(), &AnId::new(), 1)));
+        assert!(chnl.receiver.rx.try_recv().is_err());
+        assert_eq!(echoer.state_machine.current_state_name(), "state0");
+        println!("test_state_machine_base_state:-");
+    }
+
+    #[test]
+    #[should_panic(expected = "state on2 was already added as state on")]
+    fn test_state_machine_duplicate_state() {
+        let mut sm = StateMachine::<Toggle>::new(Toggle::off, "off");
+        sm.add_state(Toggle::on, "on");
+        sm.add_state(Toggle::on, "on2");
+    }
+
+    #[test]
+    #[should_panic(expected = "parent of base can't be a descendant of it")]
+    fn test_state_machine_parent_cycle() {
+        let mut sm = state_machine!(Nested, [base, idle { parent: base }]);
+        sm.set_parent(Nested::base, Nested::idle);
+    }
+}
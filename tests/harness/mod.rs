@@ -0,0 +1,273 @@
+//! Harness for integration tests that run nodes as child processes of
+//! the node binary.
+//!
+//! Each NodeProcess writes its config to a temporary file, starts the
+//! binary with it and collects the child's stdout and stderr. The logs
+//! are printed if the test panics so a failing scenario can be
+//! diagnosed, the child is killed when the NodeProcess is dropped.
+//!
+//! Configs should use port 0 for the dispatchers, the addresses bound
+//! are reported by the node and returned by `local_addr`. A peer that
+//! must reach a node across its restart forwards to a Relay, which is
+//! pointed at the restarted node's new address.
+use std::{
+    error::Error,
+    fs,
+    io::{self, BufRead, BufReader, Read},
+    net::{Shutdown, TcpListener, TcpStream},
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{bounded, Receiver, Sender};
+
+/// How long to wait for a node to become ready or to stop
+pub const NODE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const NODE_BIN: &str = env!("CARGO_BIN_EXE_exper_inter_process_channel");
+
+pub struct NodeProcess {
+    pub name: String,
+    config_path: PathBuf,
+    child: Child,
+    logs: Arc<Mutex<Vec<String>>>,
+    log_threads: Vec<JoinHandle<()>>,
+    ready_rx: Receiver<()>,
+}
+
+impl NodeProcess {
+    /// Start a node named name, config is the TOML config of the node
+    /// and must have the same name.
+    pub fn spawn(name: &str, config: &str) -> Result<Self, Box<dyn Error>> {
+        let config_path = std::env::temp_dir().join(format!(
+            "{name}-{}-{}.toml",
+            std::process::id(),
+            an_id::AnId::new()
+        ));
+        fs::write(&config_path, config)?;
+
+        let mut child = Command::new(NODE_BIN)
+            .arg(&config_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let (ready_tx, ready_rx) = bounded(1);
+        let ready_line = format!("Node {name} running");
+        let log_threads = vec![
+            collect_logs(
+                child.stdout.take().unwrap(),
+                "stdout",
+                &logs,
+                Some((ready_line, ready_tx)),
+            ),
+            collect_logs(child.stderr.take().unwrap(), "stderr", &logs, None),
+        ];
+
+        Ok(Self {
+            name: name.to_owned(),
+            config_path,
+            child,
+            logs,
+            log_threads,
+            ready_rx,
+        })
+    }
+
+    /// Start a node and wait for it to be ready
+    pub fn start(name: &str, config: &str) -> Result<Self, Box<dyn Error>> {
+        let mut node = Self::spawn(name, config)?;
+        node.wait_ready(NODE_TIMEOUT)?;
+        Ok(node)
+    }
+
+    /// Wait for the node to report it's running
+    pub fn wait_ready(&mut self, timeout: Duration) -> Result<(), Box<dyn Error>> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if self
+                .ready_rx
+                .recv_timeout(Duration::from_millis(50))
+                .is_ok()
+            {
+                return Ok(());
+            }
+            if let Some(status) = self.child.try_wait()? {
+                return Err(format!("{} exited before ready, {status}", self.name).into());
+            }
+        }
+        Err(format!("{} not ready after {timeout:?}", self.name).into())
+    }
+
+    /// Is the child still running
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Stop the node gracefully with SIGINT, like ctrl-c, and wait for
+    /// it to exit. The logs are printed if it doesn't exit successfully.
+    pub fn stop(mut self) -> Result<ExitStatus, Box<dyn Error>> {
+        let result = self.signal_and_wait();
+        if !matches!(&result, Ok(status) if status.success()) {
+            let _ = self.child.kill();
+            self.print_logs();
+        }
+        result
+    }
+
+    fn signal_and_wait(&mut self) -> Result<ExitStatus, Box<dyn Error>> {
+        let status = Command::new("kill")
+            .arg("-INT")
+            .arg(self.child.id().to_string())
+            .status()?;
+        if !status.success() {
+            return Err(format!("unable to signal {}, {status}", self.name).into());
+        }
+
+        let deadline = Instant::now() + NODE_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(status);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        Err(format!("{} didn't stop after {NODE_TIMEOUT:?}", self.name).into())
+    }
+
+    /// Crash the node with SIGKILL
+    pub fn kill(&mut self) -> Result<(), Box<dyn Error>> {
+        self.child.kill()?;
+        self.child.wait()?;
+        Ok(())
+    }
+
+    /// The address the dispatcher with name is listening on, available
+    /// once the node is ready
+    pub fn local_addr(&self, name: &str) -> Option<String> {
+        let prefix = format!("stdout: Dispatcher {name} listening on ");
+        self.logs()
+            .iter()
+            .find_map(|line| line.strip_prefix(&prefix).map(str::to_owned))
+    }
+
+    /// The lines written by the node so far
+    pub fn logs(&self) -> Vec<String> {
+        self.logs.lock().unwrap().clone()
+    }
+
+    fn print_logs(&mut self) {
+        // The log threads finish once the child has exited
+        for log_thread in self.log_threads.drain(..) {
+            let _ = log_thread.join();
+        }
+        eprintln!("---- {} logs ----", self.name);
+        for line in self.logs() {
+            eprintln!("{line}");
+        }
+        eprintln!("---- {} end ----", self.name);
+    }
+}
+
+impl Drop for NodeProcess {
+    fn drop(&mut self) {
+        if matches!(self.child.try_wait(), Ok(None)) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+        if thread::panicking() {
+            self.print_logs();
+        }
+        let _ = fs::remove_file(&self.config_path);
+    }
+}
+
+/// Relays the connections to an address the test holds for its whole
+/// run to the current target, so a peer configured with the relay's
+/// address reaches a node restarted on a different port.
+pub struct Relay {
+    addr: String,
+    target: Arc<Mutex<String>>,
+}
+
+impl Relay {
+    /// Start relaying connections to target, the accept thread runs
+    /// until the test process exits
+    pub fn start(target: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?.to_string();
+        let target = Arc::new(Mutex::new(target.to_owned()));
+        let accept_target = Arc::clone(&target);
+        thread::spawn(move || {
+            for downstream in listener.incoming() {
+                let Ok(downstream) = downstream else {
+                    continue;
+                };
+                // Until the target is up the connection is dropped and the
+                // peer retries
+                let target = accept_target.lock().unwrap().clone();
+                if let Ok(upstream) = TcpStream::connect(target) {
+                    Self::pump(downstream, upstream);
+                }
+            }
+        });
+        Ok(Self { addr, target })
+    }
+
+    /// The address peers connect to
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Relay the following connections to target, established
+    /// connections are unaffected
+    pub fn set_target(&self, target: &str) {
+        *self.target.lock().unwrap() = target.to_owned();
+    }
+
+    // Copy each direction on its own thread, when either side closes
+    // both are shutdown so the other side sees it
+    fn pump(downstream: TcpStream, upstream: TcpStream) {
+        for (mut from, mut to) in [
+            (downstream.try_clone(), upstream.try_clone()),
+            (upstream.try_clone(), downstream.try_clone()),
+        ]
+        .into_iter()
+        .filter_map(|(from, to)| Some((from.ok()?, to.ok()?)))
+        {
+            thread::spawn(move || {
+                let _ = io::copy(&mut from, &mut to);
+                let _ = from.shutdown(Shutdown::Both);
+                let _ = to.shutdown(Shutdown::Both);
+            });
+        }
+    }
+}
+
+/// Collect the lines of output into logs prefixed with stream, sends
+/// on ready_tx when ready_line is seen
+fn collect_logs(
+    output: impl Read + Send + 'static,
+    stream: &'static str,
+    logs: &Arc<Mutex<Vec<String>>>,
+    mut ready: Option<(String, Sender<()>)>,
+) -> JoinHandle<()> {
+    let logs = Arc::clone(logs);
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Some((ready_line, ready_tx)) = &ready {
+                if line.starts_with(ready_line.as_str()) {
+                    let _ = ready_tx.send(());
+                    ready = None;
+                }
+            }
+            logs.lock().unwrap().push(format!("{stream}: {line}"));
+        }
+    })
+}
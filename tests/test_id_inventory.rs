@@ -0,0 +1,41 @@
+//! Every library in the workspace is linked here so the whole
+//! workspace is checked for duplicate ids and names.
+use id_inventory::{id_entries, validate_ids, IdKind};
+
+// `use <crate> as _;` for every library in the workspace, see build.rs
+include!(concat!(env!("OUT_DIR"), "/workspace_libs.rs"));
+
+#[test]
+fn test_workspace_ids_are_unique() {
+    println!("test_workspace_ids_are_unique:+");
+    let entries = id_entries();
+    for e in entries.iter() {
+        println!(
+            "test_workspace_ids_are_unique: {:?} {} {} {}",
+            e.kind, e.id, e.name, e.module
+        );
+    }
+
+    for kind in [
+        IdKind::Message,
+        IdKind::Protocol,
+        IdKind::ProtocolSet,
+        IdKind::Actor,
+    ] {
+        assert!(
+            entries.iter().any(|e| e.kind == kind),
+            "no {kind:?} ids registered"
+        );
+    }
+    assert!(entries
+        .iter()
+        .any(|e| e.name == "con_mgr_registee_actor_protocol"));
+
+    if let Err(collisions) = validate_ids() {
+        for c in collisions.iter() {
+            println!("test_workspace_ids_are_unique: {c}");
+        }
+        panic!("{} id collisions", collisions.len());
+    }
+    println!("test_workspace_ids_are_unique:-");
+}
@@ -1,9 +1,14 @@
+use actor_channel::ActorChannel;
 use actor_executor::{
     add_actor_to_actor_executor_blocking, initialize_supervisor_con_mgr_actor_executor_blocking,
 };
+use actor_factory::actor_factory_insert;
 use an_id::AnId;
 use cmd_done_issuer_protocol::CmdDone;
-use echo_requestee_protocol::{EchoReq, ECHO_REQ_ID};
+use cmd_init_issuer_protocol::{CmdReady, CmdReadyStatus};
+use dispatcher_listening_protocol::{DispatcherListeningReq, DispatcherListeningRsp};
+use echo_requestee_protocol::{echo_requestee_protocol, EchoReq, EchoRsp, ECHO_REQ_ID};
+use echo_requester_protocol::echo_requester_protocol;
 use insert_key_msg_id_value_from_serde_json_buf_requester_protocol::{
     InsertKeyMsgIdValueFromSerdeJsonBufReq, InsertKeyMsgIdValueFromSerdeJsonBufRsp,
     InsertKeyMsgIdValueFromSerdeJsonBufRspStatus,
@@ -17,7 +22,29 @@ use msg_router_forwarder::MsgRouterForwarder;
 use msg_router_forwarder_actor_sender_requester_protocol::{
     MsgRouterForwarderActorSenderReq, MsgRouterForwarderActorSenderRsp,
 };
+use protocol::Version;
+use req_spawn_actor::ReqSpawnActor;
+use rsp_spawn_actor::{RspSpawnActor, RspSpawnActorStatus};
+use runtime_metrics::metrics_snapshot;
 use sender_map_by_instance_id::sender_map_get;
+use server::{Server, SERVER_ACTOR_ID};
+use std::time::Duration;
+
+/// The address the dispatcher at instance_id is listening on
+fn dispatcher_local_addr(
+    instance_id: &AnId,
+    supervisor_instance_id: &AnId,
+    supervisor_chnl: &ActorChannel,
+) -> String {
+    let msg = Box::new(DispatcherListeningReq::new(
+        instance_id,
+        supervisor_instance_id,
+    ));
+    sender_map_get(instance_id).unwrap().send(msg).unwrap();
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    let msg = DispatcherListeningRsp::from_box_msg_any(&msg_any).unwrap();
+    msg.local_addr().unwrap().to_string()
+}
 
 #[test]
 fn test_msg_router_forwarder_dispatcher() {
@@ -36,18 +63,20 @@ fn test_msg_router_forwarder_dispatcher() {
 
     // Add MsgRouterDispatcher to ActorExecutor
     println!("test_msg_router_forwarder_dispatcher: add MsgRouterDispatcher to ae");
-    let addr = "127.0.0.1:12345";
-    let mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", &addr));
+    let mut mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+    mrd1.add_link_protocol(echo_requestee_protocol());
     let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
         mrd1,
         &ae_instance_id,
         &supervisor_instance_id,
         &supervisor_chnl.receiver,
     );
+    let addr = &dispatcher_local_addr(&mrd1_instance_id, &supervisor_instance_id, &supervisor_chnl);
 
     // Add MsgRouterForwarder to ActorExecutor
     println!("test_msg_router_forwarder_dispatcher: add MsgRouterForward to ae");
-    let mrf1 = Box::new(MsgRouterForwarder::new("mrf1", addr));
+    let mut mrf1 = Box::new(MsgRouterForwarder::new("mrf1", addr));
+    mrf1.add_link_protocol(echo_requester_protocol());
     let (_mrf1_actor_id, mrf1_instance_id) = add_actor_to_actor_executor_blocking(
         mrf1,
         &ae_instance_id,
@@ -145,3 +174,176 @@ fn test_msg_router_forwarder_dispatcher() {
 
     println!("test_msg_router_forwarder_dispatcher:-");
 }
+
+#[test]
+fn test_msg_router_forwarder_dispatcher_incompatible_version() {
+    println!("\ntest_msg_router_forwarder_dispatcher_incompatible_version:+");
+
+    let (
+        supervisor_instance_id,
+        supervisor_chnl,
+        ae_join_handle,
+        ae_instance_id,
+        _con_mgr_instance_id,
+    ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+
+    // The dispatcher only understands version 1 of the echo protocol
+    let mut mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+    mrd1.add_link_protocol(echo_requestee_protocol());
+    let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
+        mrd1,
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+    let addr = &dispatcher_local_addr(&mrd1_instance_id, &supervisor_instance_id, &supervisor_chnl);
+
+    // The forwarder offers version 2
+    let mut echo_requester_v2 = echo_requester_protocol().clone();
+    echo_requester_v2.version = Version::new(2, 0, 0);
+    let mut mrf1 = Box::new(MsgRouterForwarder::new("mrf1", addr));
+    mrf1.add_link_protocol(&echo_requester_v2);
+    let mrf1_link_instance_id = mrf1.forwarder_instance_id;
+    let (_mrf1_actor_id, mrf1_instance_id) = add_actor_to_actor_executor_blocking(
+        mrf1,
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+
+    let msg = Box::new(InsertKeyMsgIdValueToSerdeJsonBufReq::new(
+        &mrf1_instance_id,
+        &supervisor_instance_id,
+        &ECHO_REQ_ID,
+        EchoReq::to_serde_json_buf,
+    ));
+    sender_map_get(&mrf1_instance_id)
+        .unwrap()
+        .send(msg)
+        .unwrap();
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    assert!(InsertKeyMsgIdValueToSerdeJsonBufRsp::from_box_msg_any(&msg_any).is_some());
+
+    let msg = Box::new(InsertKeyMsgIdValueFromSerdeJsonBufReq::new(
+        &mrd1_instance_id,
+        &supervisor_instance_id,
+        &ECHO_REQ_ID,
+        EchoReq::from_serde_json_buf,
+    ));
+    sender_map_get(&mrd1_instance_id)
+        .unwrap()
+        .send(msg)
+        .unwrap();
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    assert!(InsertKeyMsgIdValueFromSerdeJsonBufRsp::from_box_msg_any(&msg_any).is_some());
+
+    let msg = Box::new(MsgRouterForwarderActorSenderReq::new(
+        &mrf1_instance_id,
+        &supervisor_instance_id,
+        &AnId::nil(),
+    ));
+    sender_map_get(&mrf1_instance_id)
+        .unwrap()
+        .send(msg)
+        .unwrap();
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    let msg = MsgRouterForwarderActorSenderRsp::from_box_msg_any(&msg_any).unwrap();
+    let forwarder = &msg.sender;
+
+    println!("test_msg_router_forwarder_dispatcher_incompatible_version: send EchoReq");
+    let msg = Box::new(EchoReq::new(
+        &supervisor_instance_id,
+        &supervisor_instance_id,
+        456,
+    ));
+    forwarder.send(msg).unwrap();
+
+    // The EchoReq is refused by the forwarder and never arrives
+    assert!(supervisor_chnl
+        .receiver
+        .rx
+        .recv_timeout(Duration::from_millis(500))
+        .is_err());
+    let snapshot = metrics_snapshot();
+    let link = snapshot
+        .links
+        .iter()
+        .find(|l| l.instance_id == mrf1_link_instance_id)
+        .unwrap();
+    println!("test_msg_router_forwarder_dispatcher_incompatible_version: link={link:?}");
+    assert_eq!(link.refused, 1);
+    assert_eq!(link.frames_out, 0);
+
+    let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+    sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+    ae_join_handle.join().unwrap();
+
+    println!("test_msg_router_forwarder_dispatcher_incompatible_version:-");
+}
+
+#[test]
+fn test_spawn_actor_across_link() {
+    println!("\ntest_spawn_actor_across_link:+");
+
+    let (
+        supervisor_instance_id,
+        supervisor_chnl,
+        ae_join_handle,
+        ae_instance_id,
+        _con_mgr_instance_id,
+    ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+    actor_factory_insert(&SERVER_ACTOR_ID, Server::factory);
+
+    // No messages are added to the link, they're found in the MsgCodec
+    // inventory. The link needs protocols in common to be accepted.
+    let mut mrd1 = Box::new(MsgRouterDispatcher::new("mrd1", "127.0.0.1:0"));
+    mrd1.add_link_protocol(echo_requestee_protocol());
+    let (_mrd1_actor_id, mrd1_instance_id) = add_actor_to_actor_executor_blocking(
+        mrd1,
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+    let addr = &dispatcher_local_addr(&mrd1_instance_id, &supervisor_instance_id, &supervisor_chnl);
+    let mut mrf1 = Box::new(MsgRouterForwarder::new("mrf1", addr));
+    mrf1.add_link_protocol(echo_requester_protocol());
+    let forwarder = mrf1.forwarder_chnl.sender.clone();
+    add_actor_to_actor_executor_blocking(
+        mrf1,
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+
+    // Spawn a server in the ActorExecutor at the other end of the link
+    let msg = Box::new(ReqSpawnActor::new(
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &SERVER_ACTOR_ID,
+        "server1",
+        "",
+    ));
+    forwarder.send(msg).unwrap();
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    let msg = RspSpawnActor::from_box_msg_any(&msg_any).unwrap();
+    println!("test_spawn_actor_across_link: msg={msg:?}");
+    assert_eq!(msg.status, RspSpawnActorStatus::Success);
+    let s1_instance_id = msg.actor_instance_id;
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    let msg = CmdReady::from_box_msg_any(&msg_any).unwrap();
+    assert_eq!(msg.src_id(), &s1_instance_id);
+    assert_eq!(msg.status, CmdReadyStatus::Ready);
+
+    // The spawned server responds to an EchoReq sent across the link
+    let msg = Box::new(EchoReq::new(&s1_instance_id, &supervisor_instance_id, 1));
+    forwarder.send(msg).unwrap();
+    let msg_any = supervisor_chnl.receiver.recv().unwrap();
+    let msg = EchoRsp::from_box_msg_any(&msg_any).unwrap();
+    assert_eq!(msg.counter, 1);
+
+    let msg = Box::new(CmdDone::new(&ae_instance_id, &supervisor_instance_id));
+    sender_map_get(&ae_instance_id).unwrap().send(msg).unwrap();
+    ae_join_handle.join().unwrap();
+
+    println!("test_spawn_actor_across_link:-");
+}
@@ -65,8 +65,9 @@ impl IpchnlDeserializer {
             .insert(msg_id.to_string(), from_serde_json_buf)
     }
 
-    /// Reads messages from a TcpStream, deserializes them and sends them to an associated channel
-    pub fn deserializer(self) -> Result<Receiver<BoxMsgAny>, Box<dyn Error>> {
+    /// Reads messages from a TcpStream, deserializes them and sends them to an associated channel,
+    /// returns the channel and the address listened on
+    pub fn deserializer(self) -> Result<(Receiver<BoxMsgAny>, String), Box<dyn Error>> {
         println!("{}::deserializer:+", &self.name);
         let (tx, rx) = unbounded::<BoxMsgAny>();
         let (status_tx, status_rx) = bounded::<String>(1);
@@ -77,9 +78,10 @@ impl IpchnlDeserializer {
 
             // Ignore errors for the moment
             let listener = TcpListener::bind(self.ip_address_port).unwrap();
+            let local_addr = listener.local_addr().unwrap().to_string();
 
             // Indicate we're ready
-            status_tx.send("ready".to_owned()).unwrap_or_else(|_| {
+            status_tx.send(local_addr).unwrap_or_else(|_| {
                 panic!(
                     "{}::deserializer_thread: Unable to indicate we're ready",
                     &self_name
@@ -180,12 +182,12 @@ impl IpchnlDeserializer {
             "{}::deserializer: Wait for thread to be running",
             &self.name
         );
-        status_rx
+        let local_addr = status_rx
             .recv()
             .expect("{}::dserializer error, loop must have died");
         println!("{}::deserializer: thread running", &self.name);
 
-        Ok(rx)
+        Ok((rx, local_addr))
     }
 }
 
@@ -282,18 +284,18 @@ fn test_msg_serde_proof_of_concept() {
     let supervisor2_chnl = ActorChannel::new("supervisor2", &supervisor2_instance_id);
     sender_map_insert(&supervisor2_instance_id, &supervisor2_chnl.sender);
 
-    // Create deserializer
-    let mut deserializer = IpchnlDeserializer::new("serializer", "127.0.0.1:12345");
+    // Create deserializer, port 0 binds any free port
+    let mut deserializer = IpchnlDeserializer::new("serializer", "127.0.0.1:0");
 
     // Add the message types that can be deserialized
     deserializer.add_msg_id_from_serde_json_buf(MSG1_ID, Msg1::from_serde_json_buf);
     deserializer.add_msg_id_from_serde_json_buf(MSG2_ID, Msg2::from_serde_json_buf);
 
     // Start the deserializer
-    deserializer.deserializer().unwrap();
+    let (_rx, addr) = deserializer.deserializer().unwrap();
 
     // Create serializer
-    let mut serializer = IpchnlSerializer::new("serializer", "127.0.0.1:12345");
+    let mut serializer = IpchnlSerializer::new("serializer", &addr);
 
     // Add the message types that can be serialized
     serializer.add_msg_id_to_serde_json_buf(MSG1_ID, Msg1::to_serde_json_buf);
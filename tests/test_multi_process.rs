@@ -0,0 +1,257 @@
+//! Scenarios with each node running in its own process
+mod harness;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use admin_cli::AdminClient;
+use an_id::AnId;
+use harness::{NodeProcess, Relay, NODE_TIMEOUT};
+
+/// Config of a node with an admin dispatcher, a dispatcher at
+/// listen_addr and a server
+fn server_node_config(name: &str, listen_addr: &str) -> String {
+    format!(
+        r#"
+        name = "{name}"
+
+        [[executors]]
+        name = "ae1"
+
+        [[dispatchers]]
+        name = "admin"
+        executor = "ae1"
+        listen_addr = "127.0.0.1:0"
+        admin = true
+
+        [[dispatchers]]
+        name = "mrd1"
+        executor = "ae1"
+        listen_addr = "{listen_addr}"
+
+        [[actors]]
+        name = "server1"
+        actor = "server"
+        executor = "ae1"
+        "#
+    )
+}
+
+/// Config of a node with an admin dispatcher and a forwarder to peer_addr
+fn forwarder_node_config(name: &str, peer_addr: &str) -> String {
+    format!(
+        r#"
+        name = "{name}"
+
+        [[executors]]
+        name = "ae1"
+
+        [[dispatchers]]
+        name = "admin"
+        executor = "ae1"
+        listen_addr = "127.0.0.1:0"
+        admin = true
+
+        [[forwarders]]
+        name = "mrf1"
+        executor = "ae1"
+        peer_addr = "{peer_addr}"
+        "#
+    )
+}
+
+/// Config of a node like server_node_config, with a federating
+/// forwarder to peer_addr if it's Some
+fn federated_node_config(name: &str, listen_addr: &str, peer_addr: Option<&str>) -> String {
+    let mut config = server_node_config(name, listen_addr);
+    if let Some(peer_addr) = peer_addr {
+        config += &format!(
+            r#"
+        [[forwarders]]
+        name = "mrf1"
+        executor = "ae1"
+        peer_addr = "{peer_addr}"
+        federate = true
+        "#
+        );
+    }
+    config
+}
+
+/// Wait until the actor at instance_id is, or isn't, a remote actor in
+/// the registrations of the node client is connected to
+fn wait_for_remote(client: &mut AdminClient, instance_id: &AnId, registered: bool) {
+    let deadline = Instant::now() + NODE_TIMEOUT;
+    loop {
+        let is_registered = client.registrations().unwrap().iter().any(|registration| {
+            registration.instance_id == *instance_id && registration.link_instance_id.is_some()
+        });
+        if is_registered == registered {
+            return;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "{instance_id} registered={is_registered} after {NODE_TIMEOUT:?}"
+        );
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// The instance_id of the actor with name
+fn find_actor(client: &mut AdminClient, name: &str) -> AnId {
+    client
+        .list_actors()
+        .unwrap()
+        .iter()
+        .flat_map(|rsp| rsp.actors.iter())
+        .find(|actor| actor.name == name)
+        .unwrap_or_else(|| panic!("no actor named {name}"))
+        .instance_id
+}
+
+#[test]
+fn test_multi_process_echo() {
+    println!("\ntest_multi_process_echo:+");
+    let node1 = NodeProcess::start("node1", &server_node_config("node1", "127.0.0.1:0")).unwrap();
+
+    let mut client = AdminClient::connect(&node1.local_addr("admin").unwrap()).unwrap();
+    assert_eq!(client.node.name, "node1");
+    let server1_instance_id = find_actor(&mut client, "server1");
+    for counter in 1..=10 {
+        let rsp = client.echo(&server1_instance_id, counter).unwrap();
+        assert_eq!(rsp.counter, counter);
+    }
+    drop(client);
+
+    assert!(node1.stop().unwrap().success());
+    println!("test_multi_process_echo:-");
+}
+
+#[test]
+fn test_multi_process_link() {
+    println!("\ntest_multi_process_link:+");
+    let node1 = NodeProcess::start("node1", &server_node_config("node1", "127.0.0.1:0")).unwrap();
+    let listen_addr = node1.local_addr("mrd1").unwrap();
+    let node2 = NodeProcess::start("node2", &forwarder_node_config("node2", &listen_addr)).unwrap();
+
+    let mut client1 = AdminClient::connect(&node1.local_addr("admin").unwrap()).unwrap();
+    let links = client1.link_status().unwrap();
+    assert!(links.iter().any(|link| link.name == "mrd1_deserializer"));
+    let mut client2 = AdminClient::connect(&node2.local_addr("admin").unwrap()).unwrap();
+    let links = client2.link_status().unwrap();
+    assert!(links.iter().any(|link| link.name == "mrf1_forwarder"));
+    drop(client1);
+    drop(client2);
+
+    // Stop the forwarding node first so the dispatcher sees its peer leave
+    assert!(node2.stop().unwrap().success());
+    assert!(node1.stop().unwrap().success());
+    println!("test_multi_process_link:-");
+}
+
+#[test]
+fn test_multi_process_peer_crash_restart() {
+    println!("\ntest_multi_process_peer_crash_restart:+");
+    let mut node1 = NodeProcess::start(
+        "node1",
+        &federated_node_config("node1", "127.0.0.1:0", None),
+    )
+    .unwrap();
+    // node2 forwards to node1 through relay1 so it reaches node1 once
+    // it's restarted
+    let relay1 = Relay::start(&node1.local_addr("mrd1").unwrap()).unwrap();
+    let mut node2_config = federated_node_config("node2", "127.0.0.1:0", Some(relay1.addr()));
+    node2_config += r#"
+        [[actors]]
+        name = "client1"
+        actor = "client"
+        executor = "ae1"
+        "#;
+    let mut node2 = NodeProcess::start("node2", &node2_config).unwrap();
+
+    let mut client1 = AdminClient::connect(&node1.local_addr("admin").unwrap()).unwrap();
+    let server1_instance_id = find_actor(&mut client1, "server1");
+    client1.echo(&server1_instance_id, 1).unwrap();
+
+    // Crash node1, its admin connection is lost but node2 keeps running
+    node1.kill().unwrap();
+    assert!(client1.echo(&server1_instance_id, 2).is_err());
+    assert!(node2.is_running());
+    let mut client2 = AdminClient::connect(&node2.local_addr("admin").unwrap()).unwrap();
+    assert!(!client2.list_actors().unwrap().is_empty());
+
+    // Restart node1 on any free port, with a forwarder back to node2,
+    // and point relay1 at it. server1 has a new instance_id
+    let listen_addr2 = node2.local_addr("mrd1").unwrap();
+    let node1 = NodeProcess::start(
+        "node1",
+        &federated_node_config("node1", "127.0.0.1:0", Some(&listen_addr2)),
+    )
+    .unwrap();
+    relay1.set_target(&node1.local_addr("mrd1").unwrap());
+    let mut client1 = AdminClient::connect(&node1.local_addr("admin").unwrap()).unwrap();
+    let restarted_instance_id = find_actor(&mut client1, "server1");
+    assert_ne!(restarted_instance_id, server1_instance_id);
+    client1.echo(&restarted_instance_id, 3).unwrap();
+
+    // node2's forwarder reconnected, client1 echoes with the restarted
+    // server1 across the links
+    let client1_instance_id = find_actor(&mut client2, "client1");
+    wait_for_remote(&mut client2, &restarted_instance_id, true);
+    wait_for_remote(&mut client1, &client1_instance_id, true);
+    client2
+        .echo_start(&client1_instance_id, &restarted_instance_id, 3)
+        .unwrap();
+    drop(client1);
+    drop(client2);
+
+    assert!(node2.stop().unwrap().success());
+    assert!(node1.stop().unwrap().success());
+    println!("test_multi_process_peer_crash_restart:-");
+}
+
+#[test]
+fn test_multi_process_federation() {
+    println!("\ntest_multi_process_federation:+");
+    // node1 can only forward back to node2 once node2 is listening, so
+    // it's restarted with its forwarder and node2's link reconnects
+    // through relay1
+    let node1 = NodeProcess::start(
+        "node1",
+        &federated_node_config("node1", "127.0.0.1:0", None),
+    )
+    .unwrap();
+    let relay1 = Relay::start(&node1.local_addr("mrd1").unwrap()).unwrap();
+    let node2 = NodeProcess::start(
+        "node2",
+        &federated_node_config("node2", "127.0.0.1:0", Some(relay1.addr())),
+    )
+    .unwrap();
+    let listen_addr2 = node2.local_addr("mrd1").unwrap();
+    assert!(node1.stop().unwrap().success());
+    let node1 = NodeProcess::start(
+        "node1",
+        &federated_node_config("node1", "127.0.0.1:0", Some(&listen_addr2)),
+    )
+    .unwrap();
+    relay1.set_target(&node1.local_addr("mrd1").unwrap());
+
+    // Each node sees the others server as a remote actor
+    let mut client1 = AdminClient::connect(&node1.local_addr("admin").unwrap()).unwrap();
+    let mut client2 = AdminClient::connect(&node2.local_addr("admin").unwrap()).unwrap();
+    let server1_node1 = find_actor(&mut client1, "server1");
+    let server1_node2 = find_actor(&mut client2, "server1");
+    wait_for_remote(&mut client2, &server1_node1, true);
+    wait_for_remote(&mut client1, &server1_node2, true);
+    drop(client1);
+
+    // When node1 goes away node2 loses the peer and forgets its actors
+    assert!(node1.stop().unwrap().success());
+    wait_for_remote(&mut client2, &server1_node1, false);
+    drop(client2);
+
+    assert!(node2.stop().unwrap().success());
+    println!("test_multi_process_federation:-");
+}
@@ -0,0 +1,135 @@
+//! The ProtocolEnforcement is per ActorExecutor, a strict and a
+//! permissive executor run side by side in this test.
+use actor_channel::ActorReceiver;
+use actor_executor::{
+    add_actor_to_actor_executor_blocking, initialize_supervisor_con_mgr_actor_executor_blocking,
+    ActorExecutor, ActorExecutorOptions, ProtocolEnforcement,
+};
+use an_id::AnId;
+use cmd_done_issuer_protocol::CmdDone;
+use dead_letters::dead_letters_take_for;
+use echo_requestee_protocol::{EchoReq, EchoRsp};
+use metrics_protocol::{MetricsReq, MetricsRsp};
+use msg2::{Msg2, MSG2_ID};
+use runtime_metrics::ActorMetrics;
+use sender_map_by_instance_id::sender_map_get;
+use server::Server;
+
+// Send Msg2, which the server didn't declare, followed by an EchoReq and
+// return the servers metrics once the EchoRsp has arrived.
+fn send_msg2(
+    s1_instance_id: &AnId,
+    ae_instance_id: &AnId,
+    supervisor_instance_id: &AnId,
+    supervisor_receiver: &ActorReceiver,
+) -> ActorMetrics {
+    let s1_sender = sender_map_get(s1_instance_id).unwrap();
+    s1_sender
+        .send(Box::new(Msg2::new(s1_instance_id, supervisor_instance_id)))
+        .unwrap();
+    s1_sender
+        .send(Box::new(EchoReq::new(
+            s1_instance_id,
+            supervisor_instance_id,
+            1,
+        )))
+        .unwrap();
+    let msg_any = supervisor_receiver.recv().unwrap();
+    assert!(msg_any.downcast_ref::<EchoRsp>().is_some());
+
+    sender_map_get(ae_instance_id)
+        .unwrap()
+        .send(Box::new(MetricsReq::new(
+            ae_instance_id,
+            supervisor_instance_id,
+        )))
+        .unwrap();
+    let msg_any = supervisor_receiver.recv().unwrap();
+    let msg = msg_any.downcast_ref::<MetricsRsp>().unwrap();
+    msg.snapshot
+        .executors
+        .iter()
+        .find(|e| e.instance_id == *ae_instance_id)
+        .unwrap()
+        .actors
+        .iter()
+        .find(|a| a.instance_id == *s1_instance_id)
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn test_protocol_enforcement() {
+    println!("\ntest_protocol_enforcement:+");
+
+    // The executor started for tests is strict in debug builds
+    let (
+        supervisor_instance_id,
+        supervisor_chnl,
+        ae_join_handle,
+        ae_instance_id,
+        con_mgr_instance_id,
+    ) = initialize_supervisor_con_mgr_actor_executor_blocking();
+    let (_, s1_instance_id) = add_actor_to_actor_executor_blocking(
+        Box::new(Server::new("server1")),
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+
+    let options = ActorExecutorOptions {
+        protocol_enforcement: ProtocolEnforcement::Permissive,
+        ..Default::default()
+    };
+    let (ae2_join_handle, ae2_instance_id) =
+        ActorExecutor::start_with_options("ae2", &con_mgr_instance_id, options);
+    let (_, s2_instance_id) = add_actor_to_actor_executor_blocking(
+        Box::new(Server::new("server2")),
+        &ae2_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+
+    // Strict, Msg2 goes to the dead letters and the server never sees it
+    let s1_metrics = send_msg2(
+        &s1_instance_id,
+        &ae_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+    println!("test_protocol_enforcement: strict {s1_metrics:?}");
+    assert_eq!(s1_metrics.unknown_msgs, 0);
+    let dead_letters = dead_letters_take_for(&s1_instance_id);
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].msg_id, MSG2_ID);
+    assert_eq!(dead_letters[0].src_id, supervisor_instance_id);
+    assert!(dead_letters[0].reason.contains("server_ps"));
+    assert!(dead_letters[0].msg.downcast_ref::<Msg2>().is_some());
+
+    // Permissive, Msg2 is delivered and the server doesn't understand it
+    let s2_metrics = send_msg2(
+        &s2_instance_id,
+        &ae2_instance_id,
+        &supervisor_instance_id,
+        &supervisor_chnl.receiver,
+    );
+    println!("test_protocol_enforcement: permissive {s2_metrics:?}");
+    assert_eq!(s2_metrics.unknown_msgs, 1);
+    assert!(dead_letters_take_for(&s2_instance_id).is_empty());
+
+    for (instance_id, join_handle) in [
+        (ae2_instance_id, ae2_join_handle),
+        (ae_instance_id, ae_join_handle),
+    ] {
+        sender_map_get(&instance_id)
+            .unwrap()
+            .send(Box::new(CmdDone::new(
+                &instance_id,
+                &supervisor_instance_id,
+            )))
+            .unwrap();
+        join_handle.join().unwrap();
+    }
+
+    println!("test_protocol_enforcement:-");
+}
@@ -1,4 +1,8 @@
-use std::{error::Error, io::Write, net::TcpStream};
+use std::{
+    error::Error,
+    io::{Read, Write},
+    net::TcpStream,
+};
 
 pub fn buf_u8_le_to_u16(buf: &[u8; 2]) -> u16 {
     let b0 = buf[0] as u16;
@@ -24,9 +28,50 @@ pub fn write_msg_buf_to_tcp_stream(
     Ok(())
 }
 
+/// Read a msg_buf written by write_msg_buf_to_tcp_stream
+pub fn read_msg_buf_from_tcp_stream(stream: &mut TcpStream) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buf_len_data = [0u8; 2];
+    stream.read_exact(&mut buf_len_data)?;
+
+    let mut msg_buf = vec![0; buf_u8_le_to_u16(&buf_len_data) as usize];
+    stream.read_exact(&mut msg_buf)?;
+
+    Ok(msg_buf)
+}
+
+/// Write a msg_buf to a tokio stream like write_msg_buf_to_tcp_stream
+#[cfg(feature = "tokio")]
+pub async fn write_msg_buf_to_async_stream<W>(stream: &mut W, msg_buf: &[u8]) -> std::io::Result<()>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::AsyncWriteExt;
+
+    let buf_len_data = u16_to_buf_u8_le(msg_buf.len() as u16);
+    stream.write_all(buf_len_data.as_ref()).await?;
+    stream.write_all(msg_buf).await
+}
+
+/// Read a msg_buf written by write_msg_buf_to_tcp_stream from a tokio stream
+#[cfg(feature = "tokio")]
+pub async fn read_msg_buf_from_async_stream<R>(stream: &mut R) -> std::io::Result<Vec<u8>>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut buf_len_data = [0u8; 2];
+    stream.read_exact(&mut buf_len_data).await?;
+
+    let mut msg_buf = vec![0; buf_u8_le_to_u16(&buf_len_data) as usize];
+    stream.read_exact(&mut msg_buf).await?;
+
+    Ok(msg_buf)
+}
+
 #[cfg(test)]
 mod test {
-    use std::{io::Read, net::TcpListener, thread};
+    use std::{net::TcpListener, thread};
 
     use super::*;
 
@@ -55,10 +100,10 @@ mod test {
         //  * wait for main thread to read the data
         thread::spawn(move || {
             println!("test_write_msg_buf_to_tcp_stream thread:+");
-            let listener = TcpListener::bind("127.0.0.1:12345").unwrap();
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
 
-            // Tell the main thread that we are ready.
-            status_tx.send(()).unwrap();
+            // Tell the main thread that we are ready and where.
+            status_tx.send(listener.local_addr().unwrap()).unwrap();
 
             let (mut stream, _) = listener.accept().unwrap();
 
@@ -68,17 +113,17 @@ mod test {
 
             // Wait for the main thread to read the data.
             println!("test_write_msg_buf_to_tcp_stream thread: wait for main thread");
-            _ = rx.recv().unwrap();
+            rx.recv().unwrap();
             println!("test_write_msg_buf_to_tcp_stream thread:-");
         });
 
         // Wait for the thread to be ready.
         println!("test_write_msg_buf_to_tcp_stream: wait for thread");
-        _ = status_rx.recv().unwrap();
+        let addr = status_rx.recv().unwrap();
 
         println!("test_write_msg_buf_to_tcp_stream: connect to thread");
         // Create a TcpStream that is backed by an in memory buffer.
-        let mut stream = TcpStream::connect("127.0.0.1:12345").unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
 
         println!("test_write_msg_buf_to_tcp_stream: read length");
         let mut buf = [0; 2];
@@ -98,4 +143,19 @@ mod test {
         tx.send(()).unwrap();
         println!("test_write_msg_buf_to_tcp_stream:-");
     }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_stream_msg_buf() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+
+        write_msg_buf_to_async_stream(&mut client, &[0x01, 0x02, 0x03])
+            .await
+            .unwrap();
+        let msg_buf = read_msg_buf_from_async_stream(&mut server).await.unwrap();
+        assert_eq!(msg_buf, vec![0x01, 0x02, 0x03]);
+    }
 }